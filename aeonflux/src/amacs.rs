@@ -21,6 +21,11 @@
 //! The only parameter remaining is the second generator `h`, which is only used
 //! for the "issuer parameters" used for anonymous credentials.  It should be
 //! generated securely.
+//!
+//! The `AlgebraicMac` trait abstracts over the choice of algebraic MAC, so that
+//! credentials may be built upon MAC_GGM (implemented here as `MacGgm`),
+//! MAC_DDH (implemented in `amacs_ddh` as `MacDdh`), or the MAC over group
+//! element attributes (implemented in `amacs_points` as `MacPoints`).
 
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::vec::Vec;
@@ -31,9 +36,13 @@ use std::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::string::String;
 
+#[cfg(feature = "std")]
+use std::fmt::Debug;
 #[cfg(feature = "std")]
 use std::ops::{Index, Mul};
 
+#[cfg(not(feature = "std"))]
+use core::fmt::Debug;
 #[cfg(not(feature = "std"))]
 use core::ops::{Index, Mul};
 
@@ -45,6 +54,8 @@ use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
 
+use merlin::Transcript;
+
 use rand_core::RngCore;
 use rand_core::CryptoRng;
 
@@ -53,16 +64,290 @@ use serde::de::Visitor;
 
 use sha2::Sha512;
 
+use credential::CredentialPresentation;
+
+use errors::CredentialError;
 use errors::MacError;
 
+use linear_relations::Proof;
+use linear_relations::Statement;
+
+use nonces::Ephemeral;
+use nonces::Nonces;
+
 use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::SystemParameters;
+
+use pedersen;
+
 
 pub const SIZEOF_TAG: usize = 64;
 
-/// A `Message` is a vector of `Scalar`s in \( \mathbb{Z}/\mathbb{Z}\ell \).
+/// An algebraic message authentication code, over which keyed-verification
+/// anonymous credentials may be constructed.
+///
+/// A tag is computed over a `Message` of scalar attributes and, for those
+/// MACs which support them, group element attributes.  Each implementation
+/// chooses how its tags are presented: `MacGgm` and `MacDdh` are
+/// `RerandomizableMac`s presented as a `CredentialPresentation`, while
+/// `amacs_points::MacPoints` has its own `Presentation`.
+///
+/// Implementors are zero-sized marker types, and are required to implement the
+/// same traits as the types which are generic over them, so that those may be
+/// derived.
+pub trait AlgebraicMac: Clone + Copy + Debug + Default + Eq + PartialEq {
+    /// The issuer's secret key material.
+    type SecretKey: Clone + Debug;
+    /// The issuer's public parameters.
+    type PublicKey: Clone + Debug + Eq + PartialEq;
+    /// A tag on a `Message`.
+    type Tag: Clone + Debug + Eq + PartialEq;
+    /// A zero-knowledge proof that a tag was correctly issued.
+    type IssuanceProof: Clone + Debug + Eq + PartialEq;
+    /// A presentation of a tag, with all of its attributes hidden.
+    type Presentation: Clone + Debug + Eq + PartialEq;
+
+    /// Create a new `SecretKey` for authenticating a message of `n` `Scalar`s.
+    fn keygen<R>(n: usize, csprng: &mut R) -> Self::SecretKey
+    where
+        R: RngCore + CryptoRng;
+
+    /// Create a new `SecretKey` for authenticating a message of `n` `Scalar`s
+    /// and `k` `RistrettoPoint`s.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MacError::MessageLengthError` if `k` is non-zero and this MAC
+    /// doesn't support group element attributes, which is the default.
+    fn keygen_with_points<R>(n: usize, k: usize, csprng: &mut R) -> Result<Self::SecretKey, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if k != 0 {
+            return Err(MacError::MessageLengthError{ length: n });
+        }
+        Ok(Self::keygen(n, csprng))
+    }
+
+    /// Compute public issuer parameters for use with anonymous credentials.
+    ///
+    /// # Inputs
+    ///
+    /// * `h`, a distinguished basepoint orthogonal to the `RISTRETTO_BASEPOINT_POINT`.
+    fn public_parameters(secret: &Self::SecretKey, h: &RistrettoPoint) -> Self::PublicKey;
+
+    /// Compute a `Tag` on a `message`.
+    fn mac<R>(secret: &Self::SecretKey, message: &Message, csprng: &mut R)
+        -> Result<Self::Tag, MacError>
+    where
+        R: RngCore + CryptoRng;
+
+    /// Compute a `Tag` on a `message` with a given `nonce`.
+    fn mac_with_nonce(secret: &Self::SecretKey, message: &Message, nonce: &RistrettoPoint)
+        -> Result<Self::Tag, MacError>;
+
+    /// Compute a `Tag` on a `message` whose nonce is derived from the
+    /// `message` itself, such that no randomness is required.
+    fn mac_deterministic(secret: &Self::SecretKey, message: &Message) -> Result<Self::Tag, MacError> {
+        Self::mac_with_nonce(secret, message, &derive_nonce(message))
+    }
+
+    /// Check that `tag` is a valid tag on `message`.
+    fn verify(secret: &Self::SecretKey, tag: &Self::Tag, message: &Message) -> Result<(), MacError>;
+
+    /// Check that `tag` is a valid tag on `message` and that its nonce was
+    /// derived from the `message`.
+    fn verify_deterministic(secret: &Self::SecretKey, tag: &Self::Tag, message: &Message)
+        -> Result<(), MacError>
+    {
+        if Self::nonce(tag) != derive_nonce(message) {
+            return Err(MacError::AuthenticationError);
+        }
+        Self::verify(secret, tag, message)
+    }
+
+    /// Serialise the issuer's secret key material, e.g. for deriving
+    /// deterministic randomness from it.
+    fn secret_key_to_bytes(secret: &Self::SecretKey) -> Vec<u8>;
+
+    /// Get the nonce of a `tag`, i.e. the group element which is given by
+    /// `mac_with_nonce()`.
+    fn nonce(tag: &Self::Tag) -> RistrettoPoint;
+
+    /// Prove that a `tag` on a `message`, which is revealed to the user, was
+    /// computed correctly.
+    ///
+    /// # Returns
+    ///
+    /// A Pedersen commitment to the issuer's secret key and the proof.
+    fn prove_issuance<R>(
+        secret: &Self::SecretKey,
+        public: &Self::PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Self::Tag,
+        message: &Message,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<(pedersen::Commitment, Self::IssuanceProof), MacError>
+    where
+        R: RngCore + CryptoRng;
+
+    /// Verify a proof that a `tag` on a `message` was computed correctly.
+    fn verify_issuance(
+        public: &Self::PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Self::Tag,
+        message: &Message,
+        secret_key_commitment: &pedersen::Commitment,
+        proof: &Self::IssuanceProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), MacError>;
+
+    /// Prove that a `tag` on a `message` was computed correctly with the
+    /// nonce derived from the `message`, as by `mac_deterministic()`.
+    ///
+    /// The derived nonce is committed to the `transcript`, so that the proof
+    /// only verifies with `verify_issuance_deterministic()`, which checks it.
+    ///
+    /// # Returns
+    ///
+    /// A Pedersen commitment to the issuer's secret key and the proof, or
+    /// `MacError::AuthenticationError` if the nonce of the `tag` wasn't
+    /// derived from the `message`.
+    fn prove_issuance_deterministic<R>(
+        secret: &Self::SecretKey,
+        public: &Self::PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Self::Tag,
+        message: &Message,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<(pedersen::Commitment, Self::IssuanceProof), MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        commit_derived_nonce::<Self>(tag, message, transcript)?;
+
+        Self::prove_issuance(secret, public, system_parameters, tag, message, transcript, csprng)
+    }
+
+    /// Verify a proof from `prove_issuance_deterministic()` that a `tag` on a
+    /// `message` was computed correctly and that its nonce was derived from
+    /// the `message`.
+    fn verify_issuance_deterministic(
+        public: &Self::PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Self::Tag,
+        message: &Message,
+        secret_key_commitment: &pedersen::Commitment,
+        proof: &Self::IssuanceProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), MacError>
+    {
+        commit_derived_nonce::<Self>(tag, message, transcript)?;
+
+        Self::verify_issuance(public, system_parameters, tag, message, secret_key_commitment,
+                              proof, transcript)
+    }
+
+    /// Present a `tag` on a `message`, hiding all of its attributes.
+    ///
+    /// The `nonces` are the blinding factors for the commitments to the
+    /// attributes, which may be reused in further proofs about them.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerParameters` if the `public` parameters
+    ///   are not those of a key for this `message`.
+    /// * `CredentialError::WrongNumberOfAttributes` if there are too few
+    ///   `nonces`.
+    fn present<R>(
+        public: &Self::PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Self::Tag,
+        message: &Message,
+        nonces: &Nonces,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<Self::Presentation, CredentialError>
+    where
+        R: RngCore + CryptoRng;
+
+    /// Verify a `presentation` of a tag made with our `secret` key.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` if the `presentation` is
+    ///   of the wrong shape for our key.
+    /// * `CredentialError::MacVerification` if it doesn't verify.
+    fn verify_presentation(
+        secret: &Self::SecretKey,
+        public: &Self::PublicKey,
+        system_parameters: &SystemParameters,
+        presentation: &Self::Presentation,
+        transcript: &mut Transcript,
+    ) -> Result<(), CredentialError>;
+
+    /// Get the scalar attributes which are revealed by a `presentation`.
+    fn attributes_revealed(presentation: &Self::Presentation) -> &[Scalar];
+
+    /// Get the number of scalar attributes which are hidden by a `presentation`.
+    fn number_of_attributes_blinded(presentation: &Self::Presentation) -> usize;
+}
+
+/// An `AlgebraicMac` whose tags may be rerandomised, and which are presented
+/// as a `CredentialPresentation`.
+///
+/// A tag is viewed as a nonce \\( P \\), one or more MAC components of the
+/// form \\( Q\_j = (x\_{j,0} + \sum\_i x\_{j,i} m\_i) P \\), and possibly some
+/// auxiliary points which the issuer is able to check directly using its
+/// secret key.  Presentations commit to each MAC component and prove, for each
+/// of them, knowledge of the openings of the committed attributes with respect
+/// to the public key points returned by `presentation_keys()`.
+pub trait RerandomizableMac: AlgebraicMac {
+    /// The number of MAC components in a `Tag`.
+    const NUMBER_OF_COMPONENTS: usize;
+
+    /// The number of auxiliary points in a `Tag`.
+    const NUMBER_OF_AUXILIARY_POINTS: usize;
+
+    /// Rerandomise a `tag` so that it is unlinkable to the original.
+    fn rerandomize(tag: &Self::Tag, rerandomization: &Rerandomization) -> Self::Tag;
+
+    /// Get the MAC components, \\( Q\_j \\), of a `tag`.
+    fn components(tag: &Self::Tag) -> Vec<RistrettoPoint>;
+
+    /// Get any auxiliary points of a `tag`, which are revealed upon presentation.
+    fn auxiliary_points(tag: &Self::Tag) -> Vec<RistrettoPoint>;
+
+    /// Get the public key points for the `component`th MAC component.
+    fn presentation_keys(public: &Self::PublicKey, component: usize) -> Option<&[RistrettoPoint]>;
+
+    /// Recompute the `component`th MAC component on a presentation, with
+    /// respect to its (rerandomised) `nonce`, and revealed and blinded
+    /// attributes, prior to the subtraction of the user's commitment to it.
+    fn recompute_component(
+        secret: &Self::SecretKey,
+        component: usize,
+        nonce: &RistrettoPoint,
+        attributes_revealed: &[Scalar],
+        attributes_blinded: &[pedersen::Commitment],
+    ) -> Result<RistrettoPoint, MacError>;
+
+    /// Check the auxiliary `points` of a presented tag with the given `nonce`.
+    fn verify_auxiliary_points(
+        secret: &Self::SecretKey,
+        nonce: &RistrettoPoint,
+        points: &[RistrettoPoint],
+    ) -> Result<(), MacError>;
+}
+
+/// A `Message` is a vector of `Scalar`s in \( \mathbb{Z}/\mathbb{Z}\ell \),
+/// followed by a vector of `RistrettoPoint`s, which is empty unless the
+/// `AlgebraicMac` supports group element attributes.
 #[derive(Clone, Debug)]
 #[repr(C)]
-pub struct Message(pub Vec<Scalar>);
+pub struct Message(pub Vec<Scalar>, pub Vec<RistrettoPoint>);
 
 /// Convert a static `&str` to a `Message`.
 ///
@@ -80,7 +365,7 @@ impl From<String> for Message {
 
         v.push(Scalar::hash_from_bytes::<Sha512>(source.as_bytes()));
 
-        Message( v )
+        Message( v, Vec::new() )
     }
 }
 
@@ -90,13 +375,13 @@ impl From<Scalar> for Message {
 
         v.push(source);
 
-        Message( v )
+        Message( v, Vec::new() )
     }
 }
 
 impl From<Vec<Scalar>> for Message {
     fn from(source: Vec<Scalar>) -> Message {
-        Message( source )
+        Message( source, Vec::new() )
     }
 }
 
@@ -114,6 +399,47 @@ impl Index<usize> for Message {
     }
 }
 
+/// Derive a deterministic nonce for a `Tag` on a `message`, by hashing its
+/// attributes to the group.
+///
+/// Each kind of attribute is prefixed with its number, so that messages which
+/// split the same bytes differently between scalar and group element
+/// attributes don't get the same nonce.
+pub fn derive_nonce(message: &Message) -> RistrettoPoint {
+    let mut bytes: Vec<u8> = Vec::with_capacity(32 * (message.0.len() + message.1.len() + 2));
+
+    bytes.extend(b"aeonflux deterministic nonce".iter());
+
+    bytes.extend((message.0.len() as u64).to_le_bytes().iter());
+    for attribute in message.0.iter() {
+        bytes.extend(attribute.as_bytes().iter());
+    }
+    bytes.extend((message.1.len() as u64).to_le_bytes().iter());
+    for attribute in message.1.iter() {
+        bytes.extend(attribute.compress().as_bytes().iter());
+    }
+
+    RistrettoPoint::hash_from_bytes::<Sha512>(&bytes)
+}
+
+/// Check that the nonce of a `tag` was derived from its `message`, and commit
+/// to it in the `transcript` of an issuance proof.
+fn commit_derived_nonce<M: AlgebraicMac>(
+    tag: &M::Tag,
+    message: &Message,
+    transcript: &mut Transcript,
+) -> Result<(), MacError>
+{
+    let nonce: RistrettoPoint = derive_nonce(message);
+
+    if M::nonce(tag) != nonce {
+        return Err(MacError::AuthenticationError);
+    }
+    transcript.commit_bytes(b"derived nonce", nonce.compress().as_bytes());
+
+    Ok(())
+}
+
 /// A `Tag` for an authenticated `Message`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
@@ -240,8 +566,8 @@ impl SecretKey {
     pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, MacError> {
         let length: usize = bytes.len();
 
-        // The bytes must be a multiple of 32.
-        if length % 32 != 0 {
+        // The bytes must be a non-zero multiple of 32.
+        if length == 0 || length % 32 != 0 {
             return Err(MacError::MessageLengthError{ length });
         }
         let mut x0: Option<Scalar> = None;
@@ -305,11 +631,17 @@ impl SecretKey {
     where
         R: RngCore + CryptoRng,
     {
-        if self.xn.len() != message.0.len() {
+        let nonce: RistrettoPoint = &Scalar::random(csprng) * &RISTRETTO_BASEPOINT_TABLE;
+
+        self.mac_with_nonce(message, &nonce)
+    }
+
+    /// Compute a `Tag` on a `message` with the given `nonce`.
+    pub fn mac_with_nonce(&self, message: &Message, nonce: &RistrettoPoint) -> Result<Tag, MacError> {
+        if self.xn.len() != message.0.len() || message.1.len() != 0 {
             return Err(MacError::MessageLengthError{ length: self.xn.len() });
         }
 
-        let nonce: RistrettoPoint = &Scalar::random(csprng) * &RISTRETTO_BASEPOINT_TABLE;
         let mut exponent: Scalar = self.x0;
 
         for (xi, mi) in self.xn.iter().zip(message.0.iter()) {
@@ -317,11 +649,11 @@ impl SecretKey {
         }
         let mac = nonce * exponent;
 
-        Ok(Tag { nonce: nonce, mac: mac })
+        Ok(Tag { nonce: *nonce, mac: mac })
     }
 
     pub fn verify(&self, mac: &Tag, message: &Message) -> Result<(), MacError> {
-        if mac.nonce == RISTRETTO_BASEPOINT_POINT {
+        if mac.nonce == RISTRETTO_BASEPOINT_POINT || message.1.len() != 0 {
             return Err(MacError::AuthenticationError);
         }
         let mut exponent = self.x0;
@@ -343,15 +675,19 @@ impl_serde_with_to_bytes_and_from_bytes!(SecretKey, "A valid byte sequence repre
 
 #[derive(Clone, Debug)]
 #[repr(C)]
-pub struct Keypair {
-    pub public: PublicKey,
-    pub secret: SecretKey,
+pub struct Keypair<M: AlgebraicMac = MacGgm> {
+    pub public: M::PublicKey,
+    pub secret: M::SecretKey,
 }
 
 impl Keypair {
     pub fn from_bytes(bytes: &[u8]) -> Result<Keypair, MacError> {
         let length: usize = bytes.len();
 
+        if length < 32 {
+            return Err(MacError::KeypairDeserialisation);
+        }
+
         // The public key must always be 32 bytes shorter since the secret key has the extra x0 element.
         let public_key_length: usize = (length - 32) / 2;
         let secret_key_length: usize = length - public_key_length;
@@ -382,16 +718,34 @@ impl Keypair {
 
 impl_serde_with_to_bytes_and_from_bytes!(Keypair, "A valid byte sequence representing an amacs::Keypair");
 
-impl Keypair {
-    pub fn new<R>(h: &RistrettoPoint, csprng: &mut R) -> Keypair
+impl<M: AlgebraicMac> Keypair<M> {
+    pub fn new<R>(h: &RistrettoPoint, csprng: &mut R) -> Keypair<M>
     where
         R: RngCore + CryptoRng,
     {
-        let secret = SecretKey::new(NUMBER_OF_ATTRIBUTES, csprng);
-        let public = secret.get_public_key(&h);
+        let secret = M::keygen(NUMBER_OF_ATTRIBUTES, csprng);
+        let public = M::public_parameters(&secret, &h);
 
         Keypair { public, secret }
     }
+
+    /// Create a `Keypair` for tags on `n` scalar attributes and `k` group
+    /// element attributes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MacError::MessageLengthError` if `k` is non-zero and `M`
+    /// doesn't support group element attributes.
+    pub fn with_attributes<R>(h: &RistrettoPoint, n: usize, k: usize, csprng: &mut R)
+        -> Result<Keypair<M>, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = M::keygen_with_points(n, k, csprng)?;
+        let public = M::public_parameters(&secret, &h);
+
+        Ok(Keypair { public, secret })
+    }
 }
 
 /// A type for generating secret nonces for aMAC rerandomisation and then
@@ -440,6 +794,263 @@ impl Drop for Rerandomization {
     }
 }
 
+/// The statement that a `tag` on a `message` was computed with the secret key
+/// committed to by `Cx0` and corresponding to the `public` key, in the
+/// secrets \\( (x\_0, \tilde{x}\_0, x\_1, \ldots, x\_n) \\).
+fn issuance_statement(
+    public: &PublicKey,
+    system_parameters: &SystemParameters,
+    tag: &Tag,
+    message: &Message,
+    Cx0: &RistrettoPoint,
+) -> Result<Statement, MacError>
+{
+    let n: usize = message.0.len();
+
+    if n == 0 || public.Xn.len() != n || message.1.len() != 0 {
+        return Err(MacError::MessageLengthError{ length: public.Xn.len() });
+    }
+
+    let A: RistrettoPoint = system_parameters.h;
+    let B: RistrettoPoint = system_parameters.g;
+    let mut statement = Statement::new(b"aeonflux issuance", 2 + n);
+    let mut Q_terms: Vec<(usize, RistrettoPoint)> = Vec::with_capacity(1 + n);
+
+    Q_terms.push((0, tag.nonce));
+
+    for (i, (Xi, mi)) in public.Xn.iter().zip(message.0.iter()).enumerate() {
+        statement.equation(*Xi, [(2 + i, A)].to_vec());
+        Q_terms.push((2 + i, tag.nonce * mi));
+    }
+
+    statement.equation(*Cx0, [(0, B), (1, A)].to_vec());
+    statement.equation(tag.mac, Q_terms);
+    Ok(statement)
+}
+
+/// The MAC_GGM scheme from CMZ'13, which is secure in the generic group model.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MacGgm;
+
+impl AlgebraicMac for MacGgm {
+    type SecretKey = SecretKey;
+    type PublicKey = PublicKey;
+    type Tag = Tag;
+    type IssuanceProof = Proof;
+    type Presentation = CredentialPresentation;
+
+    fn keygen<R>(n: usize, csprng: &mut R) -> SecretKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        SecretKey::new(n, csprng)
+    }
+
+    fn public_parameters(secret: &SecretKey, h: &RistrettoPoint) -> PublicKey {
+        secret.get_public_key(h)
+    }
+
+    fn mac<R>(secret: &SecretKey, message: &Message, csprng: &mut R) -> Result<Tag, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        secret.mac(message, csprng)
+    }
+
+    fn mac_with_nonce(secret: &SecretKey, message: &Message, nonce: &RistrettoPoint) -> Result<Tag, MacError> {
+        secret.mac_with_nonce(message, nonce)
+    }
+
+    fn verify(secret: &SecretKey, tag: &Tag, message: &Message) -> Result<(), MacError> {
+        secret.verify(tag, message)
+    }
+
+    fn secret_key_to_bytes(secret: &SecretKey) -> Vec<u8> {
+        secret.to_bytes()
+    }
+
+    fn nonce(tag: &Tag) -> RistrettoPoint {
+        tag.nonce
+    }
+
+    fn prove_issuance<R>(
+        secret: &SecretKey,
+        public: &PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<(pedersen::Commitment, Proof), MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if secret.xn.len() != message.0.len() {
+            return Err(MacError::MessageLengthError{ length: secret.xn.len() });
+        }
+
+        // Choose a blinding factor, x~0
+        let x0_tilde: Ephemeral = Ephemeral::new(csprng);
+
+        // Construct a commitment to the issuer secret key
+        let Cx0 = pedersen::Commitment::to(&(&system_parameters.g * &secret.x0),
+                                           &x0_tilde, &system_parameters.h);
+        // XXX Could speed up the above by multiscalar_mul and generating a basepoint table
+
+        // Construct the NIZK proof of correct issuance
+        let statement: Statement = issuance_statement(public, system_parameters, tag, message, &Cx0.into())?;
+        let mut secrets: Vec<Scalar> = Vec::with_capacity(2 + secret.xn.len());
+
+        secrets.push(secret.x0);
+        secrets.push(*<&Scalar>::from(&x0_tilde));
+        secrets.extend(secret.xn.iter());
+
+        let proof = Proof::create(transcript, &statement, &secrets, csprng);
+
+        for scalar in secrets.iter_mut() {
+            scalar.clear();
+        }
+
+        match proof {
+            Ok(proof) => Ok((Cx0, proof)),
+            Err(_)    => Err(MacError::MessageLengthError{ length: secret.xn.len() }),
+        }
+    }
+
+    fn verify_issuance(
+        public: &PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        secret_key_commitment: &pedersen::Commitment,
+        proof: &Proof,
+        transcript: &mut Transcript,
+    ) -> Result<(), MacError>
+    {
+        let statement: Statement = issuance_statement(public, system_parameters, tag, message,
+                                                      &(*secret_key_commitment).into())?;
+
+        proof.verify(transcript, &statement).or(Err(MacError::AuthenticationError))
+    }
+
+    fn present<R>(
+        public: &PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        nonces: &Nonces,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<CredentialPresentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        CredentialPresentation::create::<MacGgm, R>(public, system_parameters, tag, message,
+                                                    nonces, transcript, csprng)
+    }
+
+    fn verify_presentation(
+        secret: &SecretKey,
+        public: &PublicKey,
+        system_parameters: &SystemParameters,
+        presentation: &CredentialPresentation,
+        transcript: &mut Transcript,
+    ) -> Result<(), CredentialError>
+    {
+        presentation.verify::<MacGgm>(secret, public, system_parameters, transcript)
+    }
+
+    fn attributes_revealed(presentation: &CredentialPresentation) -> &[Scalar] {
+        &presentation.attributes_revealed
+    }
+
+    fn number_of_attributes_blinded(presentation: &CredentialPresentation) -> usize {
+        presentation.attributes_blinded.len()
+    }
+}
+
+impl RerandomizableMac for MacGgm {
+    const NUMBER_OF_COMPONENTS: usize = 1;
+    const NUMBER_OF_AUXILIARY_POINTS: usize = 0;
+
+    fn rerandomize(tag: &Tag, rerandomization: &Rerandomization) -> Tag {
+        rerandomization.apply_to_tag(tag)
+    }
+
+    fn components(tag: &Tag) -> Vec<RistrettoPoint> {
+        let mut v = Vec::with_capacity(1);
+
+        v.push(tag.mac);
+
+        v
+    }
+
+    fn auxiliary_points(_tag: &Tag) -> Vec<RistrettoPoint> {
+        Vec::new()
+    }
+
+    fn presentation_keys(public: &PublicKey, component: usize) -> Option<&[RistrettoPoint]> {
+        match component {
+            0 => Some(&public.Xn[..]),
+            _ => None,
+        }
+    }
+
+    fn recompute_component(
+        secret: &SecretKey,
+        component: usize,
+        nonce: &RistrettoPoint,
+        attributes_revealed: &[Scalar],
+        attributes_blinded: &[pedersen::Commitment],
+    ) -> Result<RistrettoPoint, MacError>
+    {
+        if component != 0 {
+            return Err(MacError::AuthenticationError);
+        }
+        recompute_affine_component(&secret.x0, &secret.xn, nonce, attributes_revealed, attributes_blinded)
+    }
+
+    fn verify_auxiliary_points(
+        _secret: &SecretKey,
+        _nonce: &RistrettoPoint,
+        points: &[RistrettoPoint],
+    ) -> Result<(), MacError>
+    {
+        if points.len() != 0 {
+            return Err(MacError::AuthenticationError);
+        }
+        Ok(())
+    }
+}
+
+/// Recompute \\( (x\_0 + \sum\_i x\_i m\_i) P \\) over the revealed attributes,
+/// plus \\( \sum\_i x\_i C\_{m\_i} \\) over the commitments to the blinded
+/// attributes, where the blinded attributes follow the revealed ones.
+pub(crate) fn recompute_affine_component(
+    x0: &Scalar,
+    xn: &[Scalar],
+    nonce: &RistrettoPoint,
+    attributes_revealed: &[Scalar],
+    attributes_blinded: &[pedersen::Commitment],
+) -> Result<RistrettoPoint, MacError>
+{
+    let offset: usize = attributes_revealed.len();
+
+    if offset + attributes_blinded.len() != xn.len() {
+        return Err(MacError::MessageLengthError{ length: xn.len() });
+    }
+    let mut V: RistrettoPoint = x0 * nonce;
+
+    for (xi, mi) in xn.iter().zip(attributes_revealed.iter()) {
+        V += (xi * mi) * nonce;
+    }
+    for (xi, Cmi) in xn[offset..].iter().zip(attributes_blinded.iter()) {
+        V += xi * Cmi;
+    }
+
+    Ok(V)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -455,10 +1066,10 @@ mod test {
         let s3 = Scalar::random(&mut csprng);
         let mut v1 = Vec::new();
         v1.extend_from_slice(&[s1, s2]);
-        let m1 = Message(v1);
+        let m1 = Message::from(v1);
         let mut v2 = Vec::new();
         v2.extend_from_slice(&[s1, s3]);
-        let m2 = Message(v2);
+        let m2 = Message::from(v2);
         let tagged_m1 = key.mac(&m1, &mut csprng).unwrap();
 
         assert!(key.verify(&tagged_m1, &m1).is_ok());
@@ -474,10 +1085,10 @@ mod test {
         let s3 = Scalar::random(&mut csprng);
         let mut v1 = Vec::new();
         v1.extend_from_slice(&[s1, s2]);
-        let m1 = Message(v1);
+        let m1 = Message::from(v1);
         let mut v2 = Vec::new();
         v2.extend_from_slice(&[s1, s3]);
-        let m2 = Message(v2);
+        let m2 = Message::from(v2);
         let tag = key.mac(&m1, &mut csprng).unwrap();
 
         let rerandomised = Rerandomization::new(&mut csprng).apply_to_tag(&tag);
@@ -485,4 +1096,55 @@ mod test {
         assert!(key.verify(&rerandomised, &m1).is_ok());
         assert!(key.verify(&rerandomised, &m2).is_err());
     }
+
+    #[test]
+    fn test_algebraic_mac_ggm() {
+        let mut csprng = thread_rng();
+        let key = MacGgm::keygen(1, &mut csprng);
+        let m1 = Message::from(Scalar::random(&mut csprng));
+        let m2 = Message::from(Scalar::random(&mut csprng));
+        let tag = MacGgm::mac(&key, &m1, &mut csprng).unwrap();
+        let rerandomised = MacGgm::rerandomize(&tag, &Rerandomization::new(&mut csprng));
+
+        assert!(MacGgm::verify(&key, &rerandomised, &m1).is_ok());
+        assert!(MacGgm::verify(&key, &rerandomised, &m2).is_err());
+    }
+
+    #[test]
+    fn test_deterministic_mac_authentication() {
+        let mut csprng = thread_rng();
+        let key = MacGgm::keygen(1, &mut csprng);
+        let m1 = Message::from(Scalar::random(&mut csprng));
+        let m2 = Message::from(Scalar::random(&mut csprng));
+        let tag1 = MacGgm::mac_deterministic(&key, &m1).unwrap();
+        let tag2 = MacGgm::mac_deterministic(&key, &m1).unwrap();
+        let random = MacGgm::mac(&key, &m1, &mut csprng).unwrap();
+
+        assert!(tag1 == tag2);
+        assert!(MacGgm::verify_deterministic(&key, &tag1, &m1).is_ok());
+        assert!(MacGgm::verify_deterministic(&key, &tag1, &m2).is_err());
+        assert!(MacGgm::verify_deterministic(&key, &random, &m1).is_err());
+        assert!(MacGgm::verify(&key, &random, &m1).is_ok());
+    }
+
+    #[test]
+    fn derived_nonces_separate_scalar_and_point_attributes() {
+        let mut csprng = thread_rng();
+        let point = RistrettoPoint::random(&mut csprng);
+        let scalar = Scalar::from_bits(point.compress().to_bytes());
+
+        // Both messages would hash the same bytes without the counts.
+        let m1 = Message(vec![scalar], Vec::new());
+        let m2 = Message(Vec::new(), vec![point]);
+
+        assert!(derive_nonce(&m1) != derive_nonce(&m2));
+    }
+
+    #[test]
+    fn short_keys_are_rejected() {
+        assert!(SecretKey::from_bytes(&[]).is_err());
+        assert!(SecretKey::from_bytes(&[0u8; 31]).is_err());
+        assert!(Keypair::from_bytes(&[]).is_err());
+        assert!(Keypair::from_bytes(&[0u8; 31]).is_err());
+    }
 }
@@ -0,0 +1,678 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Implementation of the MAC_DDH scheme in [CMZ'13](https://eprint.iacr.org/2013/516.pdf).
+//!
+//! Unlike MAC_GGM, which is only proven secure in the generic group model,
+//! MAC_DDH is secure under the decisional Diffie-Hellman assumption.  A tag on
+//! a message \\( (m\_1, \ldots, m\_n) \\) is
+//!
+//! \\( (\sigma\_w, \sigma\_x, \sigma\_y, \sigma\_z) = (P, (x\_0 + \sum\_i x\_i m\_i) P, (y\_0 + \sum\_i y\_i m\_i) P, z P) \\)
+//!
+//! where \\( P = r G \\) for a random \\( r \\).  The tag is rerandomised by
+//! multiplying every component by the same scalar, and so credentials
+//! presented with it contain two MAC components, \\( \sigma\_x \\) and
+//! \\( \sigma\_y \\), and one auxiliary point, \\( \sigma\_z \\).
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use clear_on_drop::clear::Clear;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
+
+use amacs::recompute_affine_component;
+use amacs::AlgebraicMac;
+use amacs::Message;
+use amacs::RerandomizableMac;
+use amacs::Rerandomization;
+use credential::CredentialPresentation;
+use errors::CredentialError;
+use errors::MacError;
+use linear_relations::Proof;
+use linear_relations::Statement;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use parameters::SystemParameters;
+use pedersen;
+
+pub const SIZEOF_TAG: usize = 128;
+
+/// A `Tag` for a `Message` authenticated with MAC_DDH.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct Tag {
+    /// The nonce, \\( \sigma\_w \\).
+    pub nonce: RistrettoPoint,
+    /// The MAC under the \\( x \\) key, \\( \sigma\_x \\).
+    pub mac_x: RistrettoPoint,
+    /// The MAC under the \\( y \\) key, \\( \sigma\_y \\).
+    pub mac_y: RistrettoPoint,
+    /// The nonce under the \\( z \\) key, \\( \sigma\_z \\).
+    pub mac_z: RistrettoPoint,
+}
+
+impl Tag {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Tag, MacError> {
+        if bytes.len() != SIZEOF_TAG {
+            return Err(MacError::MessageLengthError{ length: SIZEOF_TAG });
+        }
+
+        let nonce: RistrettoPoint = CompressedRistretto::from_slice(&bytes[00..32]).decompress()?;
+        let mac_x: RistrettoPoint = CompressedRistretto::from_slice(&bytes[32..64]).decompress()?;
+        let mac_y: RistrettoPoint = CompressedRistretto::from_slice(&bytes[64..96]).decompress()?;
+        let mac_z: RistrettoPoint = CompressedRistretto::from_slice(&bytes[96..128]).decompress()?;
+
+        Ok(Tag { nonce, mac_x, mac_y, mac_z })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TAG);
+
+        v.extend(self.nonce.compress().to_bytes().iter());
+        v.extend(self.mac_x.compress().to_bytes().iter());
+        v.extend(self.mac_y.compress().to_bytes().iter());
+        v.extend(self.mac_z.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+/// The public issuer parameters for MAC_DDH.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct PublicKey {
+    pub Xn: Vec<RistrettoPoint>,
+    pub Yn: Vec<RistrettoPoint>,
+    pub Z: RistrettoPoint,
+}
+
+impl PublicKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PublicKey, MacError> {
+        let length: usize = bytes.len();
+
+        // The bytes must be 32 * (2n + 1).
+        if length % 64 != 32 {
+            return Err(MacError::MessageLengthError{ length });
+        }
+        let n: usize = (length - 32) / 64;
+        let mut Xn: Vec<RistrettoPoint> = Vec::with_capacity(n);
+        let mut Yn: Vec<RistrettoPoint> = Vec::with_capacity(n);
+
+        for chunk in bytes[..32 * n].chunks(32) {
+            Xn.push(CompressedRistretto::from_slice(chunk).decompress()?);
+        }
+        for chunk in bytes[32 * n..64 * n].chunks(32) {
+            Yn.push(CompressedRistretto::from_slice(chunk).decompress()?);
+        }
+        let Z: RistrettoPoint = CompressedRistretto::from_slice(&bytes[64 * n..]).decompress()?;
+
+        Ok(PublicKey { Xn, Yn, Z })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(32 * (self.Xn.len() + self.Yn.len() + 1));
+
+        for X in self.Xn.iter() {
+            v.extend(X.compress().0.iter());
+        }
+        for Y in self.Yn.iter() {
+            v.extend(Y.compress().0.iter());
+        }
+        v.extend(self.Z.compress().0.iter());
+
+        v
+    }
+}
+
+/// A secret key for authenticating and verifying MAC_DDH `Tag`s.
+#[derive(Clone, Debug, Default)]
+#[repr(C)]
+pub struct SecretKey {
+    pub x0: Scalar,
+    pub xn: Vec<Scalar>,
+    pub y0: Scalar,
+    pub yn: Vec<Scalar>,
+    pub z: Scalar,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.x0.clear();
+        self.y0.clear();
+        self.z.clear();
+
+        for scalar in self.xn.iter_mut() {
+            scalar.clear();
+        }
+        for scalar in self.yn.iter_mut() {
+            scalar.clear();
+        }
+    }
+}
+
+impl SecretKey {
+    /// Create a new `SecretKey` for authenticating a message of `n` `Scalar`s.
+    pub fn new<R>(n: usize, csprng: &mut R) -> SecretKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut xn: Vec<Scalar> = Vec::with_capacity(n);
+        let mut yn: Vec<Scalar> = Vec::with_capacity(n);
+        let x0: Scalar = Scalar::random(csprng);
+        let y0: Scalar = Scalar::random(csprng);
+        let z: Scalar = Scalar::random(csprng);
+
+        for _ in 0..n {
+            xn.push(Scalar::random(csprng));
+            yn.push(Scalar::random(csprng));
+        }
+
+        SecretKey{ x0, xn, y0, yn, z }
+    }
+
+    /// Construct this secret key from some `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, MacError> {
+        let length: usize = bytes.len();
+
+        // The bytes must be 32 * (2n + 3).
+        if length % 64 != 32 || length < 96 {
+            return Err(MacError::MessageLengthError{ length });
+        }
+        let n: usize = (length - 96) / 64;
+        let mut scalars: Vec<Scalar> = Vec::with_capacity(2 * n + 3);
+
+        for chunk in bytes.chunks(32) {
+            let mut tmp = [0u8; 32];
+
+            tmp.copy_from_slice(chunk);
+
+            match Scalar::from_canonical_bytes(tmp) {
+                None    => return Err(MacError::ScalarFormatError),
+                Some(x) => scalars.push(x),
+            }
+        }
+
+        Ok(SecretKey {
+            x0: scalars[0],
+            xn: scalars[1..n+1].to_vec(),
+            y0: scalars[n+1],
+            yn: scalars[n+2..2*n+2].to_vec(),
+            z: scalars[2*n+2],
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(32 * (self.xn.len() + self.yn.len() + 3));
+
+        v.extend(self.x0.to_bytes().iter());
+        for x in self.xn.iter() {
+            v.extend(x.to_bytes().iter());
+        }
+        v.extend(self.y0.to_bytes().iter());
+        for y in self.yn.iter() {
+            v.extend(y.to_bytes().iter());
+        }
+        v.extend(self.z.to_bytes().iter());
+
+        v
+    }
+
+    /// Compute public issuer parameters for use with anonymous credentials.
+    ///
+    /// # Inputs
+    ///
+    /// * `h`, a distinguished basepoint orthogonal to the `RISTRETTO_BASEPOINT_POINT`.
+    pub fn get_public_key(&self, h: &RistrettoPoint) -> PublicKey {
+        let Xn: Vec<RistrettoPoint> = self.xn.iter().map(|xi| h * xi).collect();
+        let Yn: Vec<RistrettoPoint> = self.yn.iter().map(|yi| h * yi).collect();
+
+        PublicKey { Xn, Yn, Z: h * &self.z }
+    }
+
+    /// Compute \\( (x\_0 + \sum\_i x\_i m\_i, y\_0 + \sum\_i y\_i m\_i) \\).
+    fn exponents(&self, message: &Message) -> Result<(Scalar, Scalar), MacError> {
+        if self.xn.len() != message.0.len() || self.yn.len() != message.0.len() || message.1.len() != 0 {
+            return Err(MacError::MessageLengthError{ length: self.xn.len() });
+        }
+        let mut x: Scalar = self.x0;
+        let mut y: Scalar = self.y0;
+
+        for ((xi, yi), mi) in self.xn.iter().zip(self.yn.iter()).zip(message.0.iter()) {
+            x += xi * mi;
+            y += yi * mi;
+        }
+
+        Ok((x, y))
+    }
+
+    pub fn mac<R>(&self, message: &Message, csprng: &mut R) -> Result<Tag, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let nonce: RistrettoPoint = &Scalar::random(csprng) * &RISTRETTO_BASEPOINT_TABLE;
+
+        self.mac_with_nonce(message, &nonce)
+    }
+
+    /// Compute a `Tag` on a `message` with the given `nonce`.
+    pub fn mac_with_nonce(&self, message: &Message, nonce: &RistrettoPoint) -> Result<Tag, MacError> {
+        let (x, y) = self.exponents(message)?;
+
+        Ok(Tag {
+            nonce: *nonce,
+            mac_x: nonce * x,
+            mac_y: nonce * y,
+            mac_z: nonce * self.z,
+        })
+    }
+
+    pub fn verify(&self, mac: &Tag, message: &Message) -> Result<(), MacError> {
+        if mac.nonce == RistrettoPoint::identity() {
+            return Err(MacError::AuthenticationError);
+        }
+        let (x, y) = self.exponents(message)?;
+
+        if mac.mac_x == mac.nonce * x && mac.mac_y == mac.nonce * y && mac.mac_z == mac.nonce * self.z {
+            Ok(())
+        } else {
+            Err(MacError::AuthenticationError)
+        }
+    }
+}
+
+/// A proof of correct issuance of a MAC_DDH tag.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct IssuanceProof {
+    /// A Pedersen commitment to the issuer's secret key `y0`.
+    pub y0_commitment: pedersen::Commitment,
+    pub proof: Proof,
+}
+
+/// The statement that a `tag` on a `message` was computed with the secret key
+/// committed to by `Cx0` and `Cy0` and corresponding to the `public` key, in
+/// the secrets
+/// \\( (x\_0, \tilde{x}\_0, y\_0, \tilde{y}\_0, z, x\_1, \ldots, x\_n, y\_1, \ldots, y\_n) \\).
+fn issuance_statement(
+    public: &PublicKey,
+    system_parameters: &SystemParameters,
+    tag: &Tag,
+    message: &Message,
+    Cx0: &RistrettoPoint,
+    Cy0: &RistrettoPoint,
+) -> Result<Statement, MacError>
+{
+    let n: usize = message.0.len();
+
+    if n == 0 || public.Xn.len() != n || public.Yn.len() != n || message.1.len() != 0 {
+        return Err(MacError::MessageLengthError{ length: public.Xn.len() });
+    }
+
+    let A: RistrettoPoint = system_parameters.h;
+    let B: RistrettoPoint = system_parameters.g;
+    let mut statement = Statement::new(b"aeonflux issuance ddh", 5 + 2 * n);
+    let mut Qx_terms: Vec<(usize, RistrettoPoint)> = Vec::with_capacity(1 + n);
+    let mut Qy_terms: Vec<(usize, RistrettoPoint)> = Vec::with_capacity(1 + n);
+
+    Qx_terms.push((0, tag.nonce));
+    Qy_terms.push((2, tag.nonce));
+
+    for (i, ((Xi, Yi), mi)) in public.Xn.iter().zip(public.Yn.iter()).zip(message.0.iter()).enumerate() {
+        statement.equation(*Xi, [(5 + i, A)].to_vec());
+        statement.equation(*Yi, [(5 + n + i, A)].to_vec());
+        Qx_terms.push((5 + i, tag.nonce * mi));
+        Qy_terms.push((5 + n + i, tag.nonce * mi));
+    }
+
+    statement.equation(public.Z, [(4, A)].to_vec());
+    statement.equation(*Cx0, [(0, B), (1, A)].to_vec());
+    statement.equation(*Cy0, [(2, B), (3, A)].to_vec());
+    statement.equation(tag.mac_x, Qx_terms);
+    statement.equation(tag.mac_y, Qy_terms);
+    statement.equation(tag.mac_z, [(4, tag.nonce)].to_vec());
+    Ok(statement)
+}
+
+/// The MAC_DDH scheme from CMZ'13, which is secure under the DDH assumption.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MacDdh;
+
+impl AlgebraicMac for MacDdh {
+    type SecretKey = SecretKey;
+    type PublicKey = PublicKey;
+    type Tag = Tag;
+    type IssuanceProof = IssuanceProof;
+    type Presentation = CredentialPresentation;
+
+    fn keygen<R>(n: usize, csprng: &mut R) -> SecretKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        SecretKey::new(n, csprng)
+    }
+
+    fn public_parameters(secret: &SecretKey, h: &RistrettoPoint) -> PublicKey {
+        secret.get_public_key(h)
+    }
+
+    fn mac<R>(secret: &SecretKey, message: &Message, csprng: &mut R) -> Result<Tag, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        secret.mac(message, csprng)
+    }
+
+    fn mac_with_nonce(secret: &SecretKey, message: &Message, nonce: &RistrettoPoint) -> Result<Tag, MacError> {
+        secret.mac_with_nonce(message, nonce)
+    }
+
+    fn verify(secret: &SecretKey, tag: &Tag, message: &Message) -> Result<(), MacError> {
+        secret.verify(tag, message)
+    }
+
+    fn secret_key_to_bytes(secret: &SecretKey) -> Vec<u8> {
+        secret.to_bytes()
+    }
+
+    fn nonce(tag: &Tag) -> RistrettoPoint {
+        tag.nonce
+    }
+
+    fn prove_issuance<R>(
+        secret: &SecretKey,
+        public: &PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<(pedersen::Commitment, IssuanceProof), MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if secret.xn.len() != message.0.len() || secret.yn.len() != message.0.len() {
+            return Err(MacError::MessageLengthError{ length: secret.xn.len() });
+        }
+
+        // Choose blinding factors, x~0 and y~0
+        let x0_tilde: Ephemeral = Ephemeral::new(csprng);
+        let y0_tilde: Ephemeral = Ephemeral::new(csprng);
+
+        // Construct commitments to the issuer secret keys
+        let Cx0 = pedersen::Commitment::to(&(&system_parameters.g * &secret.x0),
+                                           &x0_tilde, &system_parameters.h);
+        let Cy0 = pedersen::Commitment::to(&(&system_parameters.g * &secret.y0),
+                                           &y0_tilde, &system_parameters.h);
+
+        // Construct the NIZK proof of correct issuance
+        let statement: Statement = issuance_statement(public, system_parameters, tag, message,
+                                                      &Cx0.into(), &Cy0.into())?;
+        let mut secrets: Vec<Scalar> = Vec::with_capacity(5 + 2 * secret.xn.len());
+
+        secrets.push(secret.x0);
+        secrets.push(*<&Scalar>::from(&x0_tilde));
+        secrets.push(secret.y0);
+        secrets.push(*<&Scalar>::from(&y0_tilde));
+        secrets.push(secret.z);
+        secrets.extend(secret.xn.iter());
+        secrets.extend(secret.yn.iter());
+
+        let proof = Proof::create(transcript, &statement, &secrets, csprng);
+
+        for scalar in secrets.iter_mut() {
+            scalar.clear();
+        }
+
+        match proof {
+            Ok(proof) => Ok((Cx0, IssuanceProof { y0_commitment: Cy0, proof })),
+            Err(_)    => Err(MacError::MessageLengthError{ length: secret.xn.len() }),
+        }
+    }
+
+    fn verify_issuance(
+        public: &PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        secret_key_commitment: &pedersen::Commitment,
+        proof: &IssuanceProof,
+        transcript: &mut Transcript,
+    ) -> Result<(), MacError>
+    {
+        let statement: Statement = issuance_statement(public, system_parameters, tag, message,
+                                                      &(*secret_key_commitment).into(),
+                                                      &proof.y0_commitment.into())?;
+
+        proof.proof.verify(transcript, &statement).or(Err(MacError::AuthenticationError))
+    }
+
+    fn present<R>(
+        public: &PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        nonces: &Nonces,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<CredentialPresentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        CredentialPresentation::create::<MacDdh, R>(public, system_parameters, tag, message,
+                                                    nonces, transcript, csprng)
+    }
+
+    fn verify_presentation(
+        secret: &SecretKey,
+        public: &PublicKey,
+        system_parameters: &SystemParameters,
+        presentation: &CredentialPresentation,
+        transcript: &mut Transcript,
+    ) -> Result<(), CredentialError>
+    {
+        presentation.verify::<MacDdh>(secret, public, system_parameters, transcript)
+    }
+
+    fn attributes_revealed(presentation: &CredentialPresentation) -> &[Scalar] {
+        &presentation.attributes_revealed
+    }
+
+    fn number_of_attributes_blinded(presentation: &CredentialPresentation) -> usize {
+        presentation.attributes_blinded.len()
+    }
+}
+
+impl RerandomizableMac for MacDdh {
+    const NUMBER_OF_COMPONENTS: usize = 2;
+    const NUMBER_OF_AUXILIARY_POINTS: usize = 1;
+
+    fn rerandomize(tag: &Tag, rerandomization: &Rerandomization) -> Tag {
+        Tag {
+            nonce: rerandomization.0 * tag.nonce,
+            mac_x: rerandomization.0 * tag.mac_x,
+            mac_y: rerandomization.0 * tag.mac_y,
+            mac_z: rerandomization.0 * tag.mac_z,
+        }
+    }
+
+    fn components(tag: &Tag) -> Vec<RistrettoPoint> {
+        let mut v = Vec::with_capacity(2);
+
+        v.push(tag.mac_x);
+        v.push(tag.mac_y);
+
+        v
+    }
+
+    fn auxiliary_points(tag: &Tag) -> Vec<RistrettoPoint> {
+        let mut v = Vec::with_capacity(1);
+
+        v.push(tag.mac_z);
+
+        v
+    }
+
+    fn presentation_keys(public: &PublicKey, component: usize) -> Option<&[RistrettoPoint]> {
+        match component {
+            0 => Some(&public.Xn[..]),
+            1 => Some(&public.Yn[..]),
+            _ => None,
+        }
+    }
+
+    fn recompute_component(
+        secret: &SecretKey,
+        component: usize,
+        nonce: &RistrettoPoint,
+        attributes_revealed: &[Scalar],
+        attributes_blinded: &[pedersen::Commitment],
+    ) -> Result<RistrettoPoint, MacError>
+    {
+        match component {
+            0 => recompute_affine_component(&secret.x0, &secret.xn, nonce,
+                                            attributes_revealed, attributes_blinded),
+            1 => recompute_affine_component(&secret.y0, &secret.yn, nonce,
+                                            attributes_revealed, attributes_blinded),
+            _ => Err(MacError::AuthenticationError),
+        }
+    }
+
+    fn verify_auxiliary_points(
+        secret: &SecretKey,
+        nonce: &RistrettoPoint,
+        points: &[RistrettoPoint],
+    ) -> Result<(), MacError>
+    {
+        if points.len() != 1 || points[0] != nonce * &secret.z {
+            return Err(MacError::AuthenticationError);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn test_mac_authentication() {
+        let mut csprng = thread_rng();
+        let key = SecretKey::new(2, &mut csprng);
+        let s1 = Scalar::random(&mut csprng);
+        let s2 = Scalar::random(&mut csprng);
+        let s3 = Scalar::random(&mut csprng);
+        let mut v1 = Vec::new();
+        v1.extend_from_slice(&[s1, s2]);
+        let m1 = Message::from(v1);
+        let mut v2 = Vec::new();
+        v2.extend_from_slice(&[s1, s3]);
+        let m2 = Message::from(v2);
+        let tagged_m1 = key.mac(&m1, &mut csprng).unwrap();
+
+        assert!(key.verify(&tagged_m1, &m1).is_ok());
+        assert!(key.verify(&tagged_m1, &m2).is_err());
+    }
+
+    #[test]
+    fn test_rerandomised_mac_authentication() {
+        let mut csprng = thread_rng();
+        let key = MacDdh::keygen(2, &mut csprng);
+        let s1 = Scalar::random(&mut csprng);
+        let s2 = Scalar::random(&mut csprng);
+        let s3 = Scalar::random(&mut csprng);
+        let mut v1 = Vec::new();
+        v1.extend_from_slice(&[s1, s2]);
+        let m1 = Message::from(v1);
+        let mut v2 = Vec::new();
+        v2.extend_from_slice(&[s1, s3]);
+        let m2 = Message::from(v2);
+        let tag = MacDdh::mac(&key, &m1, &mut csprng).unwrap();
+
+        let rerandomised = MacDdh::rerandomize(&tag, &Rerandomization::new(&mut csprng));
+
+        assert!(rerandomised != tag);
+        assert!(MacDdh::verify(&key, &rerandomised, &m1).is_ok());
+        assert!(MacDdh::verify(&key, &rerandomised, &m2).is_err());
+    }
+
+    #[test]
+    fn test_keys_serialize_deserialize() {
+        let mut csprng = thread_rng();
+        let secret = SecretKey::new(2, &mut csprng);
+        let h: RistrettoPoint = RistrettoPoint::random(&mut csprng);
+        let public = secret.get_public_key(&h);
+
+        let secret_deserialized = SecretKey::from_bytes(&secret.to_bytes()).unwrap();
+        let public_deserialized = PublicKey::from_bytes(&public.to_bytes()).unwrap();
+
+        assert!(secret_deserialized.to_bytes() == secret.to_bytes());
+        assert!(public_deserialized == public);
+    }
+
+    #[test]
+    fn issuance_proof_with_many_attributes() {
+        let mut csprng = thread_rng();
+        let system_parameters = SystemParameters::hunt_and_peck(&mut csprng);
+        let secret = MacDdh::keygen(3, &mut csprng);
+        let public = MacDdh::public_parameters(&secret, &system_parameters.h);
+        let message = Message::from((0..3).map(|_| Scalar::random(&mut csprng)).collect::<Vec<Scalar>>());
+        let tag = MacDdh::mac(&secret, &message, &mut csprng).unwrap();
+
+        let (Cx0, proof) = MacDdh::prove_issuance(&secret, &public, &system_parameters, &tag, &message,
+                                                  &mut Transcript::new(b"test"), &mut csprng).unwrap();
+
+        assert!(MacDdh::verify_issuance(&public, &system_parameters, &tag, &message, &Cx0, &proof,
+                                        &mut Transcript::new(b"test")).is_ok());
+
+        // The proof must bind the tag to every attribute.
+        let mut other = message.clone();
+
+        other.0[2] = Scalar::random(&mut csprng);
+
+        assert!(MacDdh::verify_issuance(&public, &system_parameters, &tag, &other, &Cx0, &proof,
+                                        &mut Transcript::new(b"test")).is_err());
+
+        // It must bind the tag to the published key, and not some other one.
+        let other_secret = MacDdh::keygen(3, &mut csprng);
+        let other_public = MacDdh::public_parameters(&other_secret, &system_parameters.h);
+
+        assert!(MacDdh::verify_issuance(&other_public, &system_parameters, &tag, &message, &Cx0, &proof,
+                                        &mut Transcript::new(b"test")).is_err());
+
+        let other_tag = MacDdh::mac(&other_secret, &message, &mut csprng).unwrap();
+
+        assert!(MacDdh::prove_issuance(&other_secret, &public, &system_parameters, &other_tag, &message,
+                                       &mut Transcript::new(b"test"), &mut csprng)
+                .and_then(|(Cx0, proof)| MacDdh::verify_issuance(&public, &system_parameters, &other_tag,
+                                                                 &message, &Cx0, &proof,
+                                                                 &mut Transcript::new(b"test"))).is_err());
+
+        // Messages of the wrong length must be refused.
+        let short = Message::from(message.0[..2].to_vec());
+
+        assert!(MacDdh::prove_issuance(&secret, &public, &system_parameters, &tag, &short,
+                                       &mut Transcript::new(b"test"), &mut csprng).is_err());
+        assert!(MacDdh::verify_issuance(&public, &system_parameters, &tag, &short, &Cx0, &proof,
+                                        &mut Transcript::new(b"test")).is_err());
+    }
+}
@@ -0,0 +1,931 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Algebraic MACs over group element attributes, as in the extension of
+//! CMZ'13 given in [CPZ'19](https://eprint.iacr.org/2019/1416.pdf).
+//!
+//! A tag on scalar attributes \\( (m\_1, \ldots, m\_n) \\) and group element
+//! attributes \\( (M\_1, \ldots, M\_k) \\) is \\( (t, U, V) \\) where \\( t \\)
+//! is a random scalar, \\( U \\) is a random group element, and
+//!
+//! \\( V = W + (x\_0 + x\_t t + \sum\_i x\_i m\_i) U + \sum\_j y\_j M\_j \\).
+//!
+//! Since the group element attributes are not multiples of \\( U \\), such a
+//! tag cannot be rerandomised.  Instead, presentations blind every component
+//! of the tag with a Pedersen commitment under the same blinding factor
+//! \\( z \\), and prove that the issuer is able to recompute \\( z I \\) from
+//! them, where \\( I \\) is the issuer's public key.
+//!
+//! This allows a credential to certify a group element which is also
+//! elGamal-encrypted elsewhere, and for the user to prove that the committed
+//! and encrypted group elements are the same.
+//!
+//! `MacPoints` implements `AlgebraicMac` for these tags, so that they may be
+//! used for `Credential`s, `Issuer`s, and `User`s on any number of scalar and
+//! group element attributes.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::{deserialize, serialize};
+
+use clear_on_drop::clear::Clear;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use sha2::Sha512;
+
+use amacs::AlgebraicMac;
+use amacs::Message;
+use elgamal;
+use errors::CredentialError;
+use errors::MacError;
+use linear_relations::Proof;
+use linear_relations::Statement;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use parameters::SystemParameters;
+use pedersen;
+
+pub const SIZEOF_TAG: usize = 96;
+
+/// Derive a generator of the group, whose discrete log with respect to any
+/// other generator is unknown, from a `label` and an `index`.
+fn generator(label: &[u8], index: u64) -> RistrettoPoint {
+    let mut bytes: Vec<u8> = Vec::with_capacity(label.len() + 32 + 8);
+
+    bytes.extend(b"aeonflux point attributes ".iter());
+    bytes.extend(label.iter());
+
+    for i in 0..8 {
+        bytes.push((index >> (8 * i)) as u8);
+    }
+
+    RistrettoPoint::hash_from_bytes::<Sha512>(&bytes)
+}
+
+/// The generators used for tags on `n` scalar attributes and `k` group element
+/// attributes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Generators {
+    pub G_w: RistrettoPoint,
+    pub G_w_prime: RistrettoPoint,
+    pub G_x0: RistrettoPoint,
+    pub G_xt: RistrettoPoint,
+    pub G_xn: Vec<RistrettoPoint>,
+    pub G_yn: Vec<RistrettoPoint>,
+    pub G_V: RistrettoPoint,
+}
+
+impl Generators {
+    /// Derive the generators for `n` scalar attributes and `k` group element
+    /// attributes.
+    pub fn new(n: usize, k: usize) -> Generators {
+        let G_xn: Vec<RistrettoPoint> = (0..n).map(|i| generator(b"G_x", i as u64)).collect();
+        let G_yn: Vec<RistrettoPoint> = (0..k).map(|i| generator(b"G_y", i as u64)).collect();
+
+        Generators {
+            G_w: generator(b"G_w", 0),
+            G_w_prime: generator(b"G_w_prime", 0),
+            G_x0: generator(b"G_x0", 0),
+            G_xt: generator(b"G_xt", 0),
+            G_xn: G_xn,
+            G_yn: G_yn,
+            G_V: generator(b"G_V", 0),
+        }
+    }
+}
+
+/// A `Tag` on some scalar and group element attributes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct Tag {
+    pub t: Scalar,
+    pub U: RistrettoPoint,
+    pub V: RistrettoPoint,
+}
+
+impl Tag {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Tag, MacError> {
+        if bytes.len() != SIZEOF_TAG {
+            return Err(MacError::MessageLengthError{ length: SIZEOF_TAG });
+        }
+
+        let mut t_bytes: [u8; 32] = [0u8; 32];
+
+        t_bytes.copy_from_slice(&bytes[00..32]);
+
+        let t: Scalar = match Scalar::from_canonical_bytes(t_bytes) {
+            None    => return Err(MacError::ScalarFormatError),
+            Some(x) => x,
+        };
+        let U: RistrettoPoint = CompressedRistretto::from_slice(&bytes[32..64]).decompress()?;
+        let V: RistrettoPoint = CompressedRistretto::from_slice(&bytes[64..96]).decompress()?;
+
+        Ok(Tag { t, U, V })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TAG);
+
+        v.extend(self.t.to_bytes().iter());
+        v.extend(self.U.compress().to_bytes().iter());
+        v.extend(self.V.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Tag, "A valid byte sequence representing an amacs_points::Tag");
+
+
+/// The public issuer parameters for tags on group element attributes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct PublicKey {
+    /// A commitment to the secret key `w`.
+    pub CW: RistrettoPoint,
+    /// \\( G\_V - (x\_0 G\_{x\_0} + x\_t G\_{x\_t} + \sum\_i x\_i G\_{x\_i} + \sum\_j y\_j G\_{y\_j}) \\).
+    pub I: RistrettoPoint,
+}
+
+impl PublicKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PublicKey, MacError> {
+        if bytes.len() != 64 {
+            return Err(MacError::MessageLengthError{ length: 64 });
+        }
+
+        let CW: RistrettoPoint = CompressedRistretto::from_slice(&bytes[00..32]).decompress()?;
+        let I: RistrettoPoint = CompressedRistretto::from_slice(&bytes[32..64]).decompress()?;
+
+        Ok(PublicKey { CW, I })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(64);
+
+        v.extend(self.CW.compress().to_bytes().iter());
+        v.extend(self.I.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PublicKey, "A valid byte sequence representing an amacs_points::PublicKey");
+
+/// A secret key for authenticating and verifying `Tag`s.
+#[derive(Clone, Debug, Default)]
+#[repr(C)]
+pub struct SecretKey {
+    pub w: Scalar,
+    pub w_prime: Scalar,
+    pub x0: Scalar,
+    pub xt: Scalar,
+    pub xn: Vec<Scalar>,
+    pub yn: Vec<Scalar>,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.w.clear();
+        self.w_prime.clear();
+        self.x0.clear();
+        self.xt.clear();
+
+        for scalar in self.xn.iter_mut() {
+            scalar.clear();
+        }
+        for scalar in self.yn.iter_mut() {
+            scalar.clear();
+        }
+    }
+}
+
+impl SecretKey {
+    /// Create a new `SecretKey` for authenticating `n` scalar attributes and
+    /// `k` group element attributes.
+    pub fn new<R>(n: usize, k: usize, csprng: &mut R) -> SecretKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        let w: Scalar = Scalar::random(csprng);
+        let w_prime: Scalar = Scalar::random(csprng);
+        let x0: Scalar = Scalar::random(csprng);
+        let xt: Scalar = Scalar::random(csprng);
+        let xn: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+        let yn: Vec<Scalar> = (0..k).map(|_| Scalar::random(csprng)).collect();
+
+        SecretKey { w, w_prime, x0, xt, xn, yn }
+    }
+
+    /// Serialise this key as the number of scalar attributes and the number
+    /// of group element attributes, as 8-byte little-endian integers, followed
+    /// by \\( w, w', x\_0, x\_t, x\_1, \ldots, x\_n, y\_1, \ldots, y\_k \\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(16 + 32 * (4 + self.xn.len() + self.yn.len()));
+
+        v.extend((self.xn.len() as u64).to_le_bytes().iter());
+        v.extend((self.yn.len() as u64).to_le_bytes().iter());
+        v.extend(self.w.to_bytes().iter());
+        v.extend(self.w_prime.to_bytes().iter());
+        v.extend(self.x0.to_bytes().iter());
+        v.extend(self.xt.to_bytes().iter());
+
+        for scalar in self.xn.iter().chain(self.yn.iter()) {
+            v.extend(scalar.to_bytes().iter());
+        }
+
+        v
+    }
+
+    /// Get the generators for tags on the attributes this key authenticates.
+    pub fn generators(&self) -> Generators {
+        Generators::new(self.xn.len(), self.yn.len())
+    }
+
+    /// Compute public issuer parameters with respect to some `generators`.
+    pub fn get_public_key(&self, generators: &Generators) -> PublicKey {
+        let CW: RistrettoPoint = &generators.G_w * &self.w + &generators.G_w_prime * &self.w_prime;
+        let mut I: RistrettoPoint = generators.G_V;
+
+        I -= &generators.G_x0 * &self.x0;
+        I -= &generators.G_xt * &self.xt;
+
+        for (xi, G_xi) in self.xn.iter().zip(generators.G_xn.iter()) {
+            I -= G_xi * xi;
+        }
+        for (yi, G_yi) in self.yn.iter().zip(generators.G_yn.iter()) {
+            I -= G_yi * yi;
+        }
+
+        PublicKey { CW, I }
+    }
+
+    /// Compute \\( V \\) for a tag with the given `t` and `U`.
+    fn compute_V(
+        &self,
+        generators: &Generators,
+        t: &Scalar,
+        U: &RistrettoPoint,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+    ) -> Result<RistrettoPoint, MacError>
+    {
+        if self.xn.len() != scalars.len() || self.yn.len() != points.len() {
+            return Err(MacError::MessageLengthError{ length: self.xn.len() + self.yn.len() });
+        }
+        let mut exponent: Scalar = self.x0 + self.xt * t;
+
+        for (xi, mi) in self.xn.iter().zip(scalars.iter()) {
+            exponent += xi * mi;
+        }
+        let mut V: RistrettoPoint = &generators.G_w * &self.w + U * exponent;
+
+        for (yi, Mi) in self.yn.iter().zip(points.iter()) {
+            V += Mi * yi;
+        }
+
+        Ok(V)
+    }
+
+    pub fn mac<R>(
+        &self,
+        generators: &Generators,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+        csprng: &mut R,
+    ) -> Result<Tag, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let t: Scalar = Scalar::random(csprng);
+        let U: RistrettoPoint = RistrettoPoint::random(csprng);
+        let V: RistrettoPoint = self.compute_V(generators, &t, &U, scalars, points)?;
+
+        Ok(Tag { t, U, V })
+    }
+
+    /// Compute a tag whose \\( U \\) is the given `nonce`, and whose \\( t \\)
+    /// is derived from it.
+    pub fn mac_with_nonce(
+        &self,
+        generators: &Generators,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+        nonce: &RistrettoPoint,
+    ) -> Result<Tag, MacError>
+    {
+        let t: Scalar = derive_t(nonce);
+        let V: RistrettoPoint = self.compute_V(generators, &t, nonce, scalars, points)?;
+
+        Ok(Tag { t, U: *nonce, V })
+    }
+
+    pub fn verify(
+        &self,
+        generators: &Generators,
+        tag: &Tag,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+    ) -> Result<(), MacError>
+    {
+        if tag.U == RistrettoPoint::identity() {
+            return Err(MacError::AuthenticationError);
+        }
+
+        if tag.V == self.compute_V(generators, &tag.t, &tag.U, scalars, points)? {
+            Ok(())
+        } else {
+            Err(MacError::AuthenticationError)
+        }
+    }
+
+    /// Recompute \\( Z = z I \\) from the commitments in a `presentation`.
+    fn recompute_Z(&self, generators: &Generators, presentation: &Presentation) -> Result<RistrettoPoint, MacError> {
+        if presentation.Cxn.len() != self.xn.len() || presentation.Cyn.len() != self.yn.len() {
+            return Err(MacError::MessageLengthError{ length: self.xn.len() + self.yn.len() });
+        }
+        let mut Z: RistrettoPoint = presentation.CV;
+
+        Z -= &generators.G_w * &self.w;
+        Z -= presentation.Cx0 * self.x0;
+        Z -= presentation.Cxt * self.xt;
+
+        for (xi, Cxi) in self.xn.iter().zip(presentation.Cxn.iter()) {
+            Z -= Cxi * xi;
+        }
+        for (yi, Cyi) in self.yn.iter().zip(presentation.Cyn.iter()) {
+            Z -= Cyi * yi;
+        }
+
+        Ok(Z)
+    }
+
+    /// Verify a `presentation` of a `Tag` issued with this key, whose public
+    /// issuer parameters are `public`.
+    pub fn verify_presentation(
+        &self,
+        public: &PublicKey,
+        presentation: &Presentation,
+        transcript: &mut Transcript,
+    ) -> Result<(), MacError>
+    {
+        let generators: Generators = self.generators();
+        let Z: RistrettoPoint = self.recompute_Z(&generators, presentation)?;
+        let statement: Statement = presentation_statement(public, &generators, &Z, &presentation.Cx0,
+                                                          &presentation.Cxt, &presentation.Cxn);
+
+        presentation.proof.verify(transcript, &statement).or(Err(MacError::AuthenticationError))
+    }
+
+    /// Verify a `proof` that the `index`th group element attribute committed
+    /// to in a `presentation` is the plaintext of an elGamal `encryption`
+    /// under `key`.
+    pub fn verify_encrypted_point(
+        &self,
+        public: &PublicKey,
+        presentation: &Presentation,
+        index: usize,
+        encryption: &elgamal::Encryption,
+        key: &elgamal::PublicKey,
+        proof: &Proof,
+        transcript: &mut Transcript,
+    ) -> Result<(), MacError>
+    {
+        let Z: RistrettoPoint = self.recompute_Z(&self.generators(), presentation)?;
+        let Cyj: &RistrettoPoint = match presentation.Cyn.get(index) {
+            Some(x) => x,
+            None    => return Err(MacError::MessageLengthError{ length: self.yn.len() }),
+        };
+        let statement: Statement = encrypted_point_statement(public, index, &Z, Cyj, encryption, key);
+
+        proof.verify(transcript, &statement).or(Err(MacError::AuthenticationError))
+    }
+}
+
+/// Derive the scalar \\( t \\) of a deterministic tag from its `nonce`.
+fn derive_t(nonce: &RistrettoPoint) -> Scalar {
+    let mut bytes: Vec<u8> = Vec::with_capacity(64);
+
+    bytes.extend(b"aeonflux point attributes t".iter());
+    bytes.extend(nonce.compress().as_bytes().iter());
+
+    Scalar::hash_from_bytes::<Sha512>(&bytes)
+}
+
+/// The statement that a `tag` on a `message` was computed correctly, in the
+/// secrets \\( (w, w', x\_0, x\_t, x\_1, \ldots, x\_n, y\_1, \ldots, y\_k) \\).
+fn issuance_statement(
+    public: &PublicKey,
+    generators: &Generators,
+    tag: &Tag,
+    message: &Message,
+) -> Statement
+{
+    let n: usize = message.0.len();
+    let k: usize = message.1.len();
+    let mut statement = Statement::new(b"amacs_points issuance", 4 + n + k);
+    let mut I_terms: Vec<(usize, RistrettoPoint)> = Vec::with_capacity(2 + n + k);
+    let mut V_terms: Vec<(usize, RistrettoPoint)> = Vec::with_capacity(3 + n + k);
+
+    I_terms.push((2, generators.G_x0));
+    I_terms.push((3, generators.G_xt));
+    V_terms.push((0, generators.G_w));
+    V_terms.push((2, tag.U));
+    V_terms.push((3, tag.U * tag.t));
+
+    for (i, (G_xi, mi)) in generators.G_xn.iter().zip(message.0.iter()).enumerate() {
+        I_terms.push((4 + i, *G_xi));
+        V_terms.push((4 + i, tag.U * mi));
+    }
+    for (j, (G_yj, Mj)) in generators.G_yn.iter().zip(message.1.iter()).enumerate() {
+        I_terms.push((4 + n + j, *G_yj));
+        V_terms.push((4 + n + j, *Mj));
+    }
+
+    statement.equation(public.CW, [(0, generators.G_w), (1, generators.G_w_prime)].to_vec());
+    statement.equation(generators.G_V - public.I, I_terms);
+    statement.equation(tag.V, V_terms);
+    statement
+}
+
+/// The statement that the commitments in a presentation are to a valid tag,
+/// in the secrets \\( (z, t, m\_1, \ldots, m\_n, -tz, -m\_1 z, \ldots, -m\_n z) \\),
+/// of which there are \\( 2n + 3 \\).
+fn presentation_statement(
+    public: &PublicKey,
+    generators: &Generators,
+    Z: &RistrettoPoint,
+    Cx0: &RistrettoPoint,
+    Cxt: &RistrettoPoint,
+    Cxn: &[RistrettoPoint],
+) -> Statement
+{
+    let n: usize = Cxn.len();
+    let mut statement = Statement::new(b"amacs_points presentation", 3 + 2 * n);
+
+    statement.equation(*Z, [(0, public.I)].to_vec());
+    statement.equation(*Cxt, [(1, *Cx0), (2 + n, generators.G_x0), (0, generators.G_xt)].to_vec());
+
+    for (i, (Cxi, G_xi)) in Cxn.iter().zip(generators.G_xn.iter()).enumerate() {
+        statement.equation(*Cxi, [(2 + i, *Cx0), (3 + n + i, generators.G_x0), (0, *G_xi)].to_vec());
+    }
+    statement
+}
+
+/// The statement that the commitment `Cyj` to the `index`th group element
+/// attribute is to the plaintext of an elGamal `encryption` under `key`, in
+/// the secrets \\( (z, r) \\).
+fn encrypted_point_statement(
+    public: &PublicKey,
+    index: usize,
+    Z: &RistrettoPoint,
+    Cyj: &RistrettoPoint,
+    encryption: &elgamal::Encryption,
+    key: &elgamal::PublicKey,
+) -> Statement
+{
+    let G_yj: RistrettoPoint = generator(b"G_y", index as u64);
+    let mut statement = Statement::new(b"amacs_points encrypted point", 2);
+
+    statement.equation(*Z, [(0, public.I)].to_vec());
+    statement.equation(encryption.commitment, [(1, RISTRETTO_BASEPOINT_POINT)].to_vec());
+    statement.equation(encryption.encryption - Cyj, [(1, key.0), (0, -G_yj)].to_vec());
+    statement
+}
+
+/// A presentation of a `Tag` on some scalar and group element attributes, all
+/// of which are hidden from the issuer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct Presentation {
+    /// A commitment to the tag's nonce, \\( U \\).
+    pub Cx0: RistrettoPoint,
+    /// A commitment to \\( t U \\).
+    pub Cxt: RistrettoPoint,
+    /// Commitments to \\( m\_i U \\) for each scalar attribute.
+    pub Cxn: Vec<RistrettoPoint>,
+    /// A commitment to the tag, \\( V \\).
+    pub CV: RistrettoPoint,
+    /// Commitments to each group element attribute.
+    pub Cyn: Vec<RistrettoPoint>,
+    /// A zero-knowledge proof that the commitments are to a valid tag.
+    pub proof: Proof,
+}
+
+impl Presentation {
+    /// Present a `tag` on some `scalars` and `points`.
+    ///
+    /// # Inputs
+    ///
+    /// * `public` is the issuer's `PublicKey`.
+    /// * `z` is the blinding factor for the commitments.  It must be kept in
+    ///   order to later prove statements about the committed attributes,
+    ///   e.g. with `Presentation::prove_encrypted_point()`.
+    pub fn create<R>(
+        public: &PublicKey,
+        tag: &Tag,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+        z: &Ephemeral,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<Presentation, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let g = Generators::new(scalars.len(), points.len());
+        let z: &Scalar = z.into();
+
+        let Cx0: RistrettoPoint = &g.G_x0 * z + tag.U;
+        let Cxt: RistrettoPoint = &g.G_xt * z + tag.U * tag.t;
+        let Cxn: Vec<RistrettoPoint> = g.G_xn.iter().zip(scalars.iter()).map(|(G_xi, mi)| G_xi * z + tag.U * mi).collect();
+        let CV: RistrettoPoint = &g.G_V * z + tag.V;
+        let Cyn: Vec<RistrettoPoint> = g.G_yn.iter().zip(points.iter()).map(|(G_yj, Mj)| G_yj * z + Mj).collect();
+        let Z: RistrettoPoint = &public.I * z;
+
+        let mut secrets: Vec<Scalar> = Vec::with_capacity(3 + 2 * scalars.len());
+
+        secrets.push(*z);
+        secrets.push(tag.t);
+        secrets.extend(scalars.iter());
+        secrets.push(-(&tag.t * z));
+        secrets.extend(scalars.iter().map(|mi| -(mi * z)));
+
+        let statement: Statement = presentation_statement(public, &g, &Z, &Cx0, &Cxt, &Cxn);
+        let proof = Proof::create(transcript, &statement, &secrets, csprng);
+
+        for secret in secrets.iter_mut() {
+            secret.clear();
+        }
+
+        match proof {
+            Ok(proof) => Ok(Presentation { Cx0, Cxt, Cxn, CV, Cyn, proof }),
+            Err(_)    => Err(MacError::MessageLengthError{ length: scalars.len() + points.len() }),
+        }
+    }
+
+    /// Prove that the `index`th group element attribute committed to in this
+    /// presentation is the plaintext of an elGamal `encryption` under `key`,
+    /// which was created with the `nonce`.
+    pub fn prove_encrypted_point<R>(
+        &self,
+        public: &PublicKey,
+        z: &Ephemeral,
+        index: usize,
+        encryption: &elgamal::Encryption,
+        nonce: &Ephemeral,
+        key: &elgamal::PublicKey,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<Proof, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let Cyj: &RistrettoPoint = match self.Cyn.get(index) {
+            Some(x) => x,
+            None    => return Err(MacError::MessageLengthError{ length: self.Cyn.len() }),
+        };
+        let statement: Statement = encrypted_point_statement(public, index, &(&public.I * z), Cyj,
+                                                             encryption, key);
+        let mut secrets: [Scalar; 2] = [*<&Scalar>::from(z), *<&Scalar>::from(nonce)];
+        let proof = Proof::create(transcript, &statement, &secrets, csprng);
+
+        secrets[0].clear();
+        secrets[1].clear();
+
+        proof.or(Err(MacError::MessageLengthError{ length: self.Cyn.len() }))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Presentation, MacError> {
+        if bytes.len() < 96 {
+            return Err(MacError::MessageLengthError{ length: 96 });
+        }
+
+        let Cx0: RistrettoPoint = CompressedRistretto::from_slice(&bytes[00..32]).decompress()?;
+        let Cxt: RistrettoPoint = CompressedRistretto::from_slice(&bytes[32..64]).decompress()?;
+        let CV:  RistrettoPoint = CompressedRistretto::from_slice(&bytes[64..96]).decompress()?;
+
+        let (Cxn, Cyn, proof): (Vec<RistrettoPoint>, Vec<RistrettoPoint>, Proof) = match deserialize(&bytes[96..]) {
+            Ok(x)  => x,
+            Err(_) => return Err(MacError::MessageLengthError{ length: 96 }),
+        };
+
+        Ok(Presentation { Cx0, Cxt, Cxn, CV, Cyn, proof })
+    }
+
+    /// Serialise this presentation as \\( C\_{x\_0}, C\_{x\_t}, C\_V \\),
+    /// followed by the bincode encoding of the commitments to the attributes
+    /// and the proof.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(96);
+
+        v.extend(self.Cx0.compress().to_bytes().iter());
+        v.extend(self.Cxt.compress().to_bytes().iter());
+        v.extend(self.CV.compress().to_bytes().iter());
+
+        match serialize(&(&self.Cxn, &self.Cyn, &self.proof)) {
+            Ok(x)   => v.extend(x),
+            Err(_x) => panic!(),  // XXX clean this up
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Presentation,
+                                         "A valid byte sequence representing an amacs_points::Presentation");
+
+/// The algebraic MAC over scalar and group element attributes from CPZ'19.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MacPoints;
+
+impl AlgebraicMac for MacPoints {
+    type SecretKey = SecretKey;
+    type PublicKey = PublicKey;
+    type Tag = Tag;
+    type IssuanceProof = Proof;
+    type Presentation = Presentation;
+
+    fn keygen<R>(n: usize, csprng: &mut R) -> SecretKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        SecretKey::new(n, 0, csprng)
+    }
+
+    fn keygen_with_points<R>(n: usize, k: usize, csprng: &mut R) -> Result<SecretKey, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(SecretKey::new(n, k, csprng))
+    }
+
+    /// Our generators are derived by hashing, so the second generator, `h`,
+    /// of the system parameters is unused.
+    fn public_parameters(secret: &SecretKey, _h: &RistrettoPoint) -> PublicKey {
+        secret.get_public_key(&secret.generators())
+    }
+
+    fn mac<R>(secret: &SecretKey, message: &Message, csprng: &mut R) -> Result<Tag, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        secret.mac(&secret.generators(), &message.0, &message.1, csprng)
+    }
+
+    fn mac_with_nonce(secret: &SecretKey, message: &Message, nonce: &RistrettoPoint) -> Result<Tag, MacError> {
+        secret.mac_with_nonce(&secret.generators(), &message.0, &message.1, nonce)
+    }
+
+    fn verify(secret: &SecretKey, tag: &Tag, message: &Message) -> Result<(), MacError> {
+        secret.verify(&secret.generators(), tag, &message.0, &message.1)
+    }
+
+    fn secret_key_to_bytes(secret: &SecretKey) -> Vec<u8> {
+        secret.to_bytes()
+    }
+
+    fn nonce(tag: &Tag) -> RistrettoPoint {
+        tag.U
+    }
+
+    /// The commitment to the secret key is our public \\( C\_W \\).
+    fn prove_issuance<R>(
+        secret: &SecretKey,
+        public: &PublicKey,
+        _system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<(pedersen::Commitment, Proof), MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if secret.xn.len() != message.0.len() || secret.yn.len() != message.1.len() {
+            return Err(MacError::MessageLengthError{ length: secret.xn.len() + secret.yn.len() });
+        }
+        let statement: Statement = issuance_statement(public, &secret.generators(), tag, message);
+        let mut secrets: Vec<Scalar> = Vec::with_capacity(4 + secret.xn.len() + secret.yn.len());
+
+        secrets.push(secret.w);
+        secrets.push(secret.w_prime);
+        secrets.push(secret.x0);
+        secrets.push(secret.xt);
+        secrets.extend(secret.xn.iter());
+        secrets.extend(secret.yn.iter());
+
+        let proof = Proof::create(transcript, &statement, &secrets, csprng);
+
+        for scalar in secrets.iter_mut() {
+            scalar.clear();
+        }
+
+        match proof {
+            Ok(proof) => Ok((pedersen::Commitment::from(public.CW), proof)),
+            Err(_)    => Err(MacError::MessageLengthError{ length: secret.xn.len() + secret.yn.len() }),
+        }
+    }
+
+    fn verify_issuance(
+        public: &PublicKey,
+        _system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        secret_key_commitment: &pedersen::Commitment,
+        proof: &Proof,
+        transcript: &mut Transcript,
+    ) -> Result<(), MacError>
+    {
+        if RistrettoPoint::from(*secret_key_commitment) != public.CW || tag.U == RistrettoPoint::identity() {
+            return Err(MacError::AuthenticationError);
+        }
+        let generators = Generators::new(message.0.len(), message.1.len());
+        let statement: Statement = issuance_statement(public, &generators, tag, message);
+
+        proof.verify(transcript, &statement).or(Err(MacError::AuthenticationError))
+    }
+
+    /// Only the first of the `nonces` is used, as the blinding factor
+    /// \\( z \\) for every commitment.
+    fn present<R>(
+        public: &PublicKey,
+        _system_parameters: &SystemParameters,
+        tag: &Tag,
+        message: &Message,
+        nonces: &Nonces,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<Presentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if nonces.len() == 0 {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+        Ok(Presentation::create(public, tag, &message.0, &message.1, &nonces[0], transcript, csprng)?)
+    }
+
+    fn verify_presentation(
+        secret: &SecretKey,
+        public: &PublicKey,
+        _system_parameters: &SystemParameters,
+        presentation: &Presentation,
+        transcript: &mut Transcript,
+    ) -> Result<(), CredentialError>
+    {
+        if presentation.Cxn.len() != secret.xn.len() || presentation.Cyn.len() != secret.yn.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+        secret.verify_presentation(public, presentation, transcript).or(Err(CredentialError::MacVerification))
+    }
+
+    /// All attributes are hidden upon presentation.
+    fn attributes_revealed(_presentation: &Presentation) -> &[Scalar] {
+        &[]
+    }
+
+    fn number_of_attributes_blinded(presentation: &Presentation) -> usize {
+        presentation.Cxn.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use credential::CredentialIssuance;
+    use credential::CredentialRequest;
+    use credential::VerifiedCredential;
+    use issuer::Issuer;
+    use user::User;
+
+    const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                          127,   4, 178,  70, 212, 141, 119, 112,
+                          153, 154, 135,  11, 227, 132, 247,  47,
+                           68, 192,  72, 200,  23,  88,  51,  82, ];
+
+    #[test]
+    fn test_mac_authentication() {
+        let mut csprng = thread_rng();
+        let generators = Generators::new(2, 2);
+        let key = SecretKey::new(2, 2, &mut csprng);
+        let scalars = [Scalar::random(&mut csprng), Scalar::random(&mut csprng)];
+        let points = [RistrettoPoint::random(&mut csprng), RistrettoPoint::random(&mut csprng)];
+        let other = [points[0], RistrettoPoint::random(&mut csprng)];
+        let tag = key.mac(&generators, &scalars, &points, &mut csprng).unwrap();
+
+        assert!(key.verify(&generators, &tag, &scalars, &points).is_ok());
+        assert!(key.verify(&generators, &tag, &scalars, &other).is_err());
+        assert!(Tag::from_bytes(&tag.to_bytes()).unwrap() == tag);
+    }
+
+    #[test]
+    fn test_issuance_and_presentation() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters = SystemParameters::from(H);
+        let issuer: Issuer<MacPoints> = Issuer::create_with_attributes(system_parameters, 2, 2,
+                                                                       &mut issuer_rng).unwrap();
+        let mut alice: User<MacPoints> = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+
+        let scalars = vec![Scalar::random(&mut alice_rng), Scalar::random(&mut alice_rng)];
+        let points = vec![RistrettoPoint::random(&mut alice_rng), RistrettoPoint::random(&mut alice_rng)];
+
+        let request: CredentialRequest = alice.obtain_with_points(scalars.clone(), points.clone());
+        let issuance: CredentialIssuance<MacPoints> = issuer.issue(&request, &mut issuer_rng).unwrap();
+
+        alice.obtain_finish(Some(&issuance)).unwrap();
+
+        let nonces = Nonces::new(&mut alice_rng, 1);
+        let presentation: Presentation = alice.show(&nonces, &mut alice_rng).unwrap();
+        let deserialized = Presentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+        assert!(deserialized == presentation);
+
+        let _verified: VerifiedCredential<MacPoints> = issuer.verify(&deserialized).unwrap();
+
+        // A request on the wrong number of attributes must not be issued.
+        let request: CredentialRequest = alice.obtain_with_points(scalars.clone(), points[..1].to_vec());
+
+        assert!(issuer.issue(&request, &mut issuer_rng).is_err());
+
+        // A presentation of a tag on different attributes must not verify.
+        let other = [points[0], RistrettoPoint::random(&mut alice_rng)];
+        let mut transcript = Transcript::new(b"AEONFLUX SHOW");
+        let forged = Presentation::create(&issuer.keypair.public, &issuance.credential.mac, &scalars,
+                                          &other, &nonces[0], &mut transcript, &mut alice_rng).unwrap();
+
+        assert!(issuer.verify(&forged).is_err());
+    }
+
+    #[test]
+    fn test_presentation_of_encrypted_point() {
+        let mut csprng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer: Issuer<MacPoints> = Issuer::create_with_attributes(system_parameters, 1, 2,
+                                                                       &mut csprng).unwrap();
+        let mut alice: User<MacPoints> = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let elgamal_keypair = elgamal::Keypair::generate(&mut csprng);
+        let message = elgamal::Message::from(&Scalar::random(&mut csprng));
+        let points = vec![RistrettoPoint::random(&mut csprng), message.0];
+
+        let request = alice.obtain_with_points(vec![Scalar::random(&mut csprng)], points);
+        let issuance = issuer.issue(&request, &mut csprng).unwrap();
+
+        alice.obtain_finish(Some(&issuance)).unwrap();
+
+        let r = Ephemeral::new(&mut csprng);
+        let encryption = elgamal_keypair.public.encrypt(&message, &r);
+        let other = elgamal_keypair.public.encrypt(&elgamal::Message::from(&Scalar::random(&mut csprng)), &r);
+
+        let nonces = Nonces::new(&mut csprng, 1);
+        let presentation = alice.show(&nonces, &mut csprng).unwrap();
+        let public = issuer.get_issuer_parameters();
+
+        let mut transcript = Transcript::new(b"AEONFLUX ENCRYPTED POINT");
+        let proof = presentation.prove_encrypted_point(&public, &nonces[0], 1, &encryption, &r,
+                                                       &elgamal_keypair.public, &mut transcript,
+                                                       &mut csprng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+
+        let secret = &issuer.keypair.secret;
+        let mut transcript = Transcript::new(b"AEONFLUX ENCRYPTED POINT");
+        assert!(secret.verify_encrypted_point(&public, &presentation, 1, &encryption,
+                                              &elgamal_keypair.public, &proof, &mut transcript).is_ok());
+
+        let mut transcript = Transcript::new(b"AEONFLUX ENCRYPTED POINT");
+        assert!(secret.verify_encrypted_point(&public, &presentation, 1, &other,
+                                              &elgamal_keypair.public, &proof, &mut transcript).is_err());
+
+        // The proof is only for the attribute it was made for.
+        let mut transcript = Transcript::new(b"AEONFLUX ENCRYPTED POINT");
+        assert!(secret.verify_encrypted_point(&public, &presentation, 0, &encryption,
+                                              &elgamal_keypair.public, &proof, &mut transcript).is_err());
+    }
+}
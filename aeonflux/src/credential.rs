@@ -7,28 +7,51 @@
 // Authors:
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 
-//! An implementation of CMZ'13 MAC_GGM based anonymous credentials with one attribute.
+//! An implementation of CMZ'13 algebraic MAC based anonymous credentials with one attribute.
+//!
+//! Credentials are generic over the `AlgebraicMac` in use, and default to
+//! MAC_GGM.  Credentials using `amacs_points::MacPoints` may also have group
+//! element attributes, and may have any number of either.
 
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
+use amacs;
+use amacs::AlgebraicMac;
+use amacs::MacGgm;
+use amacs::RerandomizableMac;
 use amacs::SIZEOF_TAG;
 use amacs::Tag;
 
-use bincode::{deserialize, serialize};
+use bincode::{deserialize, serialize, serialized_size};
+
+use clear_on_drop::clear::Clear;
 
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
 
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
 use elgamal;
 
+use linear_relations::Proof;
+use linear_relations::Statement;
+
+use nonces::Ephemeral;
+use nonces::Nonces;
+
 use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::SystemParameters;
 
 use pedersen;
 
@@ -36,11 +59,15 @@ use errors::CredentialError;
 
 use proofs::attributes_blinded;
 use proofs::issuance_blinded;
-use proofs::issuance_revealed;
-use proofs::valid_credential;
 
+/// The size of a serialised `Credential` on `NUMBER_OF_ATTRIBUTES` attributes,
+/// which is the smallest a `Credential` may be.
 pub const SIZEOF_CREDENTIAL: usize = SIZEOF_TAG + NUMBER_OF_ATTRIBUTES * 32;
-pub const SIZEOF_CREDENTIAL_PRESENTATION: usize = 256;
+
+/// The size of a serialised `CredentialPresentation` of a `Credential` on
+/// `NUMBER_OF_ATTRIBUTES` attributes using `MacGgm`, which is the smallest a
+/// `CredentialPresentation` may be.
+pub const SIZEOF_CREDENTIAL_PRESENTATION: usize = 288;
 
 /// The number of revealed attributes on a `Credential` during issuance.
 pub const ISSUANCE_NUMBER_OF_REVEALED_ATTRIBUTES: usize = 1;
@@ -58,6 +85,10 @@ pub const PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES: usize = 1;
 /// credential.
 pub type RevealedAttribute = Scalar;
 
+/// A plaintext group element attribute, for credentials using
+/// `amacs_points`.
+pub type RevealedPointAttribute = RistrettoPoint;
+
 /// An elGamal-encrypted attribute that is hidden to the issuer when requesting
 /// a credential.
 pub type EncryptedAttribute = elgamal::Encryption;
@@ -66,21 +97,33 @@ pub type EncryptedAttribute = elgamal::Encryption;
 /// by an issuer.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
-pub struct Credential {
+pub struct Credential<M: AlgebraicMac = MacGgm> {
     /// The non-interactive zero knowledge proof that this credential is
     /// well-formed.
-    pub mac: Tag,
+    pub mac: M::Tag,
     /// A vector of unencrypted attributes, which may later be hidden upon
     /// presentation.
     pub attributes: Vec<RevealedAttribute>,
+    /// A vector of unencrypted group element attributes, which are always
+    /// hidden upon presentation.  This is empty unless `M` supports them.
+    pub point_attributes: Vec<RevealedPointAttribute>,
+}
+
+impl<M: AlgebraicMac> Credential<M> {
+    /// Get the `amacs::Message` which this credential's tag is on.
+    pub fn message(&self) -> amacs::Message {
+        amacs::Message(self.attributes.clone(), self.point_attributes.clone())
+    }
 }
 
 impl Credential {
+    /// Deserialise a credential from its tag, followed by each of its
+    /// attributes.
     pub fn from_bytes(bytes: &[u8]) -> Result<Credential, CredentialError> {
         let length: usize = bytes.len();
 
         // The bytes must be a multiple of 32 and at least 96 bytes.
-        if length % 32 != 0 || length < 96 || length != SIZEOF_CREDENTIAL {
+        if length % 32 != 0 || length < 96 {
             return Err(CredentialError::WrongNumberOfBytes);
         }
         let mac: Tag = Tag::from_bytes(&bytes[00..64])?;
@@ -98,7 +141,7 @@ impl Credential {
             }
         }
 
-        Ok(Credential { mac, attributes })
+        Ok(Credential { mac, attributes, point_attributes: Vec::new() })
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -157,21 +200,54 @@ pub struct CredentialBlindIssuance {
 #[repr(C)]
 pub struct CredentialRequest {
     pub attributes_revealed: Vec<RevealedAttribute>,
+    /// Any group element attributes, for credentials using `amacs_points`.
+    pub point_attributes_revealed: Vec<RevealedPointAttribute>,
 }
 
 impl CredentialRequest {
+    /// Get the `amacs::Message` which the requested credential is to be on.
+    pub fn message(&self) -> amacs::Message {
+        amacs::Message(self.attributes_revealed.clone(), self.point_attributes_revealed.clone())
+    }
+
+    /// Deserialise a request from the scalar attributes laid end to end, or,
+    /// if there are group element attributes, from their number as an 8-byte
+    /// little-endian integer, followed by them and then the scalar attributes.
     pub fn from_bytes(bytes: &[u8]) -> Result<CredentialRequest, CredentialError> {
-        let length: usize = bytes.len();
+        let mut length: usize = bytes.len();
+        let mut offset: usize = 0;
+        let mut point_attributes_revealed: Vec<RevealedPointAttribute> = Vec::new();
 
-        // The bytes must be a multiple of 32.
+        if length % 32 == 8 {
+            let mut tmp: [u8; 8] = [0u8; 8];
+
+            tmp.copy_from_slice(&bytes[..8]);
+
+            let k: u64 = u64::from_le_bytes(tmp);
+
+            if k == 0 || k > ((length - 8) / 32) as u64 {
+                return Err(CredentialError::WrongNumberOfBytes);
+            }
+            offset = 8 + 32 * k as usize;
+
+            for chunk in bytes[8..offset].chunks(32) {
+                match CompressedRistretto::from_slice(chunk).decompress() {
+                    Some(x) => point_attributes_revealed.push(x),
+                    None    => return Err(CredentialError::PointDecompressionError),
+                }
+            }
+            length -= offset;
+        }
+
+        // The rest of the bytes must be a multiple of 32.
         if length % 32 != 0 {
             return Err(CredentialError::WrongNumberOfBytes);
         }
 
-        let mut attributes_revealed: Vec<RevealedAttribute> = Vec::with_capacity(length % 32);
+        let mut attributes_revealed: Vec<RevealedAttribute> = Vec::with_capacity(length / 32);
 
         // TODO When #![feature(chunk_exact)] stabilises we should use that instead
-        for chunk in bytes.chunks(32) {
+        for chunk in bytes[offset..].chunks(32) {
             let mut tmp: [u8; 32] = [0u8;32];
 
             tmp.copy_from_slice(chunk);
@@ -182,12 +258,20 @@ impl CredentialRequest {
             }
         }
 
-        Ok(CredentialRequest { attributes_revealed })
+        Ok(CredentialRequest { attributes_revealed, point_attributes_revealed })
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut v: Vec<u8> = Vec::with_capacity(32 * self.attributes_revealed.len());
+        let k: usize = self.point_attributes_revealed.len();
+        let mut v: Vec<u8> = Vec::with_capacity(8 + 32 * (k + self.attributes_revealed.len()));
 
+        if k > 0 {
+            v.extend((k as u64).to_le_bytes().iter());
+
+            for attribute in self.point_attributes_revealed.iter() {
+                v.extend(attribute.compress().as_bytes().iter());
+            }
+        }
         for attribute in self.attributes_revealed.iter() {
             v.extend(attribute.to_bytes().iter());
         }
@@ -201,18 +285,23 @@ impl_serde_with_to_bytes_and_from_bytes!(CredentialRequest,
 
 #[derive(Debug, Eq, PartialEq)]
 #[repr(C)]
-pub struct CredentialIssuance {
+pub struct CredentialIssuance<M: AlgebraicMac = MacGgm> {
     pub secret_key_commitment: pedersen::Commitment,
-    pub credential: Credential,
-    pub proof: issuance_revealed::Proof,
+    pub credential: Credential<M>,
+    pub proof: M::IssuanceProof,
 }
 
 impl CredentialIssuance {
+    /// Deserialise an issuance from the commitment to the issuer's secret key,
+    /// followed by the credential and the proof of its correct issuance.
     pub fn from_bytes(bytes: &[u8]) -> Result<CredentialIssuance, CredentialError> {
+        if bytes.len() < 32 + SIZEOF_CREDENTIAL {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
         let secret_key_commitment = pedersen::Commitment::from_bytes(&bytes[00..32])?;
-        let credential = Credential::from_bytes(&bytes[32..32+SIZEOF_CREDENTIAL])?;
-        
-        let proof: issuance_revealed::Proof = match deserialize(&bytes[32+SIZEOF_CREDENTIAL..]) {
+
+        let (credential, proof) = match deserialize(&bytes[32..]) {
             Ok(x)   => x,
             Err(_x) => {
                 // println!("Error while deserializing CredentialIssuance: {}", _x);
@@ -227,9 +316,8 @@ impl CredentialIssuance {
         let mut v: Vec<u8> = Vec::with_capacity(32 + SIZEOF_CREDENTIAL); // XXX what size is the proof?
 
         v.extend(self.secret_key_commitment.to_bytes());
-        v.extend(self.credential.to_bytes());
 
-        let serialized = match serialize(&self.proof) {
+        let serialized = match serialize(&(&self.credential, &self.proof)) {
             Ok(x)   => x,
             Err(_x) => {
                 // println!("Error while serializing CredentialIssuance: {}", _x);
@@ -245,6 +333,33 @@ impl CredentialIssuance {
 impl_serde_with_to_bytes_and_from_bytes!(CredentialIssuance,
                                          "A valid byte sequence representing a CredentialIssuance");
 
+/// The statement that the `commitments` to the attributes in a presentation
+/// are to those of a valid tag component, for which \\( V \\) is the error
+/// factor and the `keys` are the issuer's, in the secrets
+/// \\( (-z\_Q, m\_1, \ldots, m\_n, z\_1, \ldots, z\_n) \\).
+fn presentation_statement(
+    A: &RistrettoPoint,
+    keys: &[RistrettoPoint],
+    P: &RistrettoPoint,
+    V: &RistrettoPoint,
+    commitments: &[pedersen::Commitment],
+) -> Statement
+{
+    let n: usize = commitments.len();
+    let mut statement = Statement::new(b"aeonflux presentation", 1 + 2 * n);
+    let mut V_terms: Vec<(usize, RistrettoPoint)> = Vec::with_capacity(1 + n);
+
+    V_terms.push((0, *A));
+
+    for (i, (Xi, Cmi)) in keys.iter().zip(commitments.iter()).enumerate() {
+        statement.equation((*Cmi).into(), [(1 + i, *P), (1 + n + i, *A)].to_vec());
+        V_terms.push((1 + n + i, *Xi));
+    }
+
+    statement.equation(*V, V_terms);
+    statement
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
 pub struct CredentialPresentation {
@@ -260,15 +375,179 @@ pub struct CredentialPresentation {
     /// A zero-knowledge proof showing that the user knows a valid rerandomised
     /// algebraic MAC over the `attributes_revealed` and `attributes_blinded`
     /// which was created by the `Issuer`.
-    pub proof: valid_credential::Proof,
+    pub proof: Proof,
+    /// Pedersen commitments to any further rerandomised MAC components, for
+    /// algebraic MACs with more than one (e.g. MAC_DDH).
+    pub auxiliary_mac_commitments: Vec<pedersen::Commitment>,
+    /// Any rerandomised auxiliary points of the algebraic MAC, which the
+    /// issuer checks directly.
+    pub rerandomized_auxiliary_points: Vec<RistrettoPoint>,
+    /// A zero-knowledge proof, like `proof`, for each of the
+    /// `auxiliary_mac_commitments`.
+    pub auxiliary_proofs: Vec<Proof>,
 }
 
 impl CredentialPresentation {
+    /// Present a `tag` on a `message` by rerandomising it and committing to
+    /// each of its MAC components, with all of the attributes hidden.
+    ///
+    /// This is `AlgebraicMac::present()` for `RerandomizableMac`s.
+    pub(crate) fn create<M, R>(
+        public: &M::PublicKey,
+        system_parameters: &SystemParameters,
+        tag: &M::Tag,
+        message: &amacs::Message,
+        nonces: &Nonces,
+        transcript: &mut Transcript,
+        csprng: &mut R,
+    ) -> Result<CredentialPresentation, CredentialError>
+    where
+        M: RerandomizableMac,
+        R: RngCore + CryptoRng,
+    {
+        let attributes: &[RevealedAttribute] = &message.0;
+
+        if attributes.len() == 0 || message.1.len() != 0 || nonces.len() < attributes.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        // Rerandomise the aMAC to prevent trivial linkages.
+        let rerandomized_mac: M::Tag = M::rerandomize(tag, &amacs::Rerandomization::new(csprng));
+
+        let A = system_parameters.h;
+        let P = M::nonce(&rerandomized_mac);
+
+        // Commit to the hidden attributes.
+        let mut commitments: Vec<pedersen::Commitment> = Vec::with_capacity(attributes.len());
+
+        for (zi, mi) in nonces.iter().zip(attributes.iter()) {
+            let Cmi: pedersen::Commitment = pedersen::Commitment::to(&(mi * P), zi, &A);
+
+            commitments.push(Cmi);
+        }
+
+        let mut mac_commitments: Vec<pedersen::Commitment> = Vec::with_capacity(M::NUMBER_OF_COMPONENTS);
+        let mut proofs: Vec<Proof> = Vec::with_capacity(M::NUMBER_OF_COMPONENTS);
+
+        for (component, Q) in M::components(&rerandomized_mac).iter().enumerate() {
+            let keys: &[RistrettoPoint] = match M::presentation_keys(public, component) {
+                Some(x) if x.len() == attributes.len() => x,
+                _ => return Err(CredentialError::NoIssuerParameters),
+            };
+
+            // Commit to the rerandomised aMAC.
+            let zQ: Ephemeral = Ephemeral::new(csprng);
+            let CQ: pedersen::Commitment = pedersen::Commitment::to(Q, &zQ, &A);
+
+            // Calculate the error factor.
+            let mut V: RistrettoPoint = RistrettoPoint::identity();
+
+            for (zi, Xi) in nonces.iter().zip(keys.iter()) {
+                V += &(zi * Xi);
+            }
+            V -= &zQ * &A;
+
+            let minus_zQ = -zQ;
+
+            let statement: Statement = presentation_statement(&A, keys, &P, &V, &commitments);
+            let mut secrets: Vec<Scalar> = Vec::with_capacity(1 + 2 * attributes.len());
+
+            secrets.push(*<&Scalar>::from(&minus_zQ));
+            secrets.extend(attributes.iter());
+            secrets.extend(nonces.iter().take(attributes.len()).map(|zi| *<&Scalar>::from(zi)));
+
+            let proof = Proof::create(transcript, &statement, &secrets, csprng);
+
+            for scalar in secrets.iter_mut() {
+                scalar.clear();
+            }
+
+            mac_commitments.push(CQ);
+            proofs.push(proof.or(Err(CredentialError::MacCreation))?);
+        }
+
+        if mac_commitments.len() == 0 {
+            return Err(CredentialError::MacCreation);
+        }
+        let rerandomized_mac_commitment = mac_commitments.remove(0);
+        let proof = proofs.remove(0);
+
+        Ok(CredentialPresentation {
+            proof: proof,
+            rerandomized_mac_commitment: rerandomized_mac_commitment,
+            rerandomized_nonce: P,
+            attributes_revealed: Vec::with_capacity(0),
+            attributes_blinded: commitments,
+            auxiliary_mac_commitments: mac_commitments,
+            rerandomized_auxiliary_points: M::auxiliary_points(&rerandomized_mac),
+            auxiliary_proofs: proofs,
+        })
+    }
+
+    /// Verify this presentation of a tag made with the `secret` key.
+    ///
+    /// This is `AlgebraicMac::verify_presentation()` for `RerandomizableMac`s.
+    pub(crate) fn verify<M>(
+        &self,
+        secret: &M::SecretKey,
+        public: &M::PublicKey,
+        system_parameters: &SystemParameters,
+        transcript: &mut Transcript,
+    ) -> Result<(), CredentialError>
+    where
+        M: RerandomizableMac,
+    {
+        let P = self.rerandomized_nonce;
+
+        if self.auxiliary_mac_commitments.len() != M::NUMBER_OF_COMPONENTS - 1 ||
+            self.auxiliary_proofs.len() != M::NUMBER_OF_COMPONENTS - 1 ||
+            self.attributes_blinded.len() == 0
+        {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        // Check any auxiliary points of the aMAC directly
+        M::verify_auxiliary_points(secret, &P, &self.rerandomized_auxiliary_points)
+            .or(Err(CredentialError::MacVerification))?;
+
+        for component in 0..M::NUMBER_OF_COMPONENTS {
+            let (commitment, proof) = match component {
+                0 => (&self.rerandomized_mac_commitment, &self.proof),
+                j => (&self.auxiliary_mac_commitments[j - 1], &self.auxiliary_proofs[j - 1]),
+            };
+
+            // Recompute the MAC
+            let mut V_prime: RistrettoPoint = M::recompute_component(secret, component, &P,
+                                                                     &self.attributes_revealed,
+                                                                     &self.attributes_blinded)
+                .or(Err(CredentialError::WrongNumberOfAttributes))?;
+
+            V_prime -= *commitment;
+
+            let keys: &[RistrettoPoint] = match M::presentation_keys(public, component) {
+                Some(x) if x.len() == self.attributes_blinded.len() => x,
+                _ => return Err(CredentialError::NoIssuerParameters),
+            };
+            let statement: Statement = presentation_statement(&system_parameters.h, keys, &P, &V_prime,
+                                                              &self.attributes_blinded);
+
+            if proof.verify(transcript, &statement).is_err() {
+                return Err(CredentialError::MacVerification);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deserialise a presentation from the commitment to its MAC and its
+    /// rerandomised nonce, followed by its attributes and proof, and then a
+    /// trailer for any further MAC components.
     pub fn from_bytes(bytes: &[u8]) -> Result<CredentialPresentation, CredentialError> {
         let length: usize = bytes.len();
 
-        // The bytes must be a multiple of 32.
-        if length % 32 != 0 {
+        // The bytes must contain at least a presentation of one MAC component,
+        // optionally followed by a trailer for any further components.
+        if length < SIZEOF_CREDENTIAL_PRESENTATION {
             return Err(CredentialError::WrongNumberOfBytes);
         }
 
@@ -278,38 +557,32 @@ impl CredentialPresentation {
         tmp.copy_from_slice(&bytes[32..64]);
         let rerandomized_nonce = CompressedRistretto(tmp).decompress()?;
 
-        let attributes_offset: usize = 32 * (PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES + PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES);
-        let mut attributes_revealed: Vec<RevealedAttribute> = Vec::with_capacity(PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES);
-        let mut attributes_blinded: Vec<pedersen::Commitment> = Vec::with_capacity(PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES);
-        let mut attributes_processed: usize = 0;
-
-        // TODO When #![feature(chunk_exact)] stabilises we should use that instead
-        for chunk in (&bytes[64..64 + attributes_offset]).chunks(32) {
-            if attributes_processed < PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES {
-                let mut tmp: [u8; 32] = [0u8;32];
-
-                tmp.copy_from_slice(chunk);
-
-                match Scalar::from_canonical_bytes(tmp) {
-                    Some(x) => attributes_revealed.push(x),
-                    None    => return Err(CredentialError::ScalarFormatError),
-                }
-            } else {
-                match pedersen::Commitment::from_bytes(chunk) {
-                    Ok(x)  => attributes_blinded.push(x),
-                    Err(_) => return Err(CredentialError::PointDecompressionError),
-                }
-            }
-            attributes_processed += 1;
-        }
-
-        let proof: valid_credential::Proof = match deserialize(&bytes[64+attributes_offset..]) {
+        let (attributes_revealed, attributes_blinded, proof): (Vec<RevealedAttribute>, Vec<pedersen::Commitment>, Proof) =
+            match deserialize(&bytes[64..])
+        {
             Ok(x)   => x,
             Err(_x) => {
                 // println!("Error while deserializing CredentialPresentation: {}", _x);
                 return Err(CredentialError::MissingData);
             },
         };
+        let attributes_length: usize = match serialized_size(&(&attributes_revealed, &attributes_blinded, &proof)) {
+            Ok(x)  => x as usize,
+            Err(_) => return Err(CredentialError::MissingData),
+        };
+        let trailer_offset: usize = 64 + attributes_length;
+
+        // Presentations of credentials using algebraic MACs with more than one
+        // component have a trailer containing the extra commitments and proofs.
+        let (auxiliary_mac_commitments, rerandomized_auxiliary_points, auxiliary_proofs) =
+            if bytes.len() > trailer_offset {
+                match deserialize(&bytes[trailer_offset..]) {
+                    Ok(x)  => x,
+                    Err(_) => return Err(CredentialError::MissingData),
+                }
+            } else {
+                (Vec::new(), Vec::new(), Vec::new())
+            };
 
         Ok(CredentialPresentation {
             rerandomized_mac_commitment,
@@ -317,6 +590,9 @@ impl CredentialPresentation {
             attributes_revealed,
             attributes_blinded,
             proof,
+            auxiliary_mac_commitments,
+            rerandomized_auxiliary_points,
+            auxiliary_proofs,
         })
     }
 
@@ -326,22 +602,26 @@ impl CredentialPresentation {
         v.extend(self.rerandomized_mac_commitment.to_bytes());          // 32 bytes
         v.extend(self.rerandomized_nonce.compress().to_bytes().iter()); // 32 bytes
 
-        for attribute in self.attributes_revealed.iter() {              // 32 * 0 bytes
-            v.extend(attribute.to_bytes().iter());
-        }
-        for attribute in self.attributes_blinded.iter() {               // 32 * 1 bytes
-            v.extend(attribute.to_bytes());
-        }
+        let attributes = (&self.attributes_revealed, &self.attributes_blinded, &self.proof);
 
-        let serialized = match serialize(&self.proof) {
-            Ok(x)   => x,
+        match serialize(&attributes) {
+            Ok(x)   => v.extend(x),
             Err(_x) => {
                 // println!("Error while serializing CredentialPresentation: {}", _x);
                 panic!();  // XXX clean this up
             },
-        };
+        }
 
-        v.extend(serialized);
+        if self.auxiliary_mac_commitments.len() > 0 || self.rerandomized_auxiliary_points.len() > 0 {
+            let trailer = (&self.auxiliary_mac_commitments,
+                           &self.rerandomized_auxiliary_points,
+                           &self.auxiliary_proofs);
+
+            match serialize(&trailer) {
+                Ok(x)   => v.extend(x),
+                Err(_x) => panic!(),  // XXX clean this up
+            }
+        }
         v
     }
 }
@@ -358,7 +638,7 @@ impl_serde_with_to_bytes_and_from_bytes!(CredentialPresentation,
 /// `Issuer.verify()`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
-pub struct VerifiedCredential(pub CredentialPresentation);
+pub struct VerifiedCredential<M: AlgebraicMac = MacGgm>(pub M::Presentation);
 
 impl VerifiedCredential {
     pub fn from_bytes(bytes: &[u8]) -> Result<VerifiedCredential, CredentialError> {
@@ -447,4 +727,17 @@ mod test {
         assert!(deserialized.is_ok());
         assert!(deserialized.unwrap() == verified);
     }
+
+    #[test]
+    fn truncated_bytes_are_rejected() {
+        let bytes: [u8; 32 + SIZEOF_CREDENTIAL] = [0u8; 32 + SIZEOF_CREDENTIAL];
+
+        for length in 0..bytes.len() {
+            if length != SIZEOF_CREDENTIAL {
+                assert!(Credential::from_bytes(&bytes[..length]).is_err());
+            }
+            assert!(CredentialIssuance::from_bytes(&bytes[..length]).is_err());
+            assert!(CredentialPresentation::from_bytes(&bytes[..length]).is_err());
+        }
+    }
 }
@@ -77,7 +77,13 @@ impl<'a> From<&'a Scalar> for Message {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+impl From<Message> for RistrettoPoint {
+    fn from(source: Message) -> RistrettoPoint {
+        source.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub struct Encryption {
     pub commitment: RistrettoPoint,
     pub encryption: RistrettoPoint,
@@ -96,7 +102,9 @@ impl<'a, 'b> Add<&'b Encryption> for &'a Encryption {
 
 impl PublicKey {
     pub fn from_bytes(bytes: &[u8]) -> Result<PublicKey, CredentialError> {
-        assert!(bytes.len() == 32);
+        if bytes.len() != SIZEOF_PUBLIC_KEY {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
 
         let mut tmp = [0u8; 32];
 
@@ -144,7 +152,9 @@ impl<'a> From<&'a SecretKey> for PublicKey {
 
 impl SecretKey {
     pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, CredentialError> {
-        assert!(bytes.len() == 32);
+        if bytes.len() != SIZEOF_SECRET_KEY {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
 
         let mut tmp = [0u8; 32];
 
@@ -196,10 +206,12 @@ impl Drop for SecretKey {
 
 impl Keypair {
     pub fn from_bytes(bytes: &[u8]) -> Result<Keypair, CredentialError> {
-        assert!(bytes.len() == 64);
+        if bytes.len() != SIZEOF_KEYPAIR {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
 
-        let secret = SecretKey::from_bytes(&bytes[00..32])?;
-        let public = PublicKey::from_bytes(&bytes[32..64])?;
+        let secret = SecretKey::from_bytes(&bytes[..SIZEOF_SECRET_KEY])?;
+        let public = PublicKey::from_bytes(&bytes[SIZEOF_SECRET_KEY..])?;
 
         Ok(Keypair{ secret, public })
     }
@@ -249,4 +261,13 @@ mod test {
 
         assert!(keypair.secret.decrypt(&enc) == msg.0);
     }
+
+    #[test]
+    fn wrong_number_of_bytes() {
+        assert!(PublicKey::from_bytes(&[]).is_err());
+        assert!(PublicKey::from_bytes(&[0u8; 33]).is_err());
+        assert!(SecretKey::from_bytes(&[0u8; 31]).is_err());
+        assert!(Keypair::from_bytes(&[0u8; 32]).is_err());
+        assert!(Keypair::from_bytes(&[0u8; 65]).is_err());
+    }
 }
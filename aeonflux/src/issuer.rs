@@ -13,42 +13,56 @@ use alloc::vec::Vec;
 use std::vec::Vec;
 
 use amacs;
+use amacs::AlgebraicMac;
+use amacs::MacGgm;
 pub use amacs::PublicKey as IssuerParameters;
 pub use amacs::SecretKey as IssuerSecretKey;
 
-use curve25519_dalek::ristretto::RistrettoPoint;
-use curve25519_dalek::scalar::Scalar;
-
 use merlin::Transcript;
 
+use rand::ChaChaRng;
+use rand::SeedableRng;
+
 use rand_core::RngCore;
 use rand_core::CryptoRng;
 
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
+use sha2::Digest;
+use sha2::Sha512;
+
 use credential::Credential;
 use credential::CredentialIssuance;
 use credential::CredentialRequest;
-use credential::CredentialPresentation;
+use credential::RevealedAttribute;
 use credential::VerifiedCredential;
 use errors::CredentialError;
-use nonces::Ephemeral;
+use issuer_hiding::dummy_attribute;
+use issuer_hiding::IssuerSetMember;
+use parameters::NUMBER_OF_ATTRIBUTES;
 use parameters::SystemParameters;
-use pedersen::{self};
-use proofs::issuance_revealed;
-use proofs::valid_credential;
+use pedersen;
+use schema::CredentialSchema;
+use schema::SIZEOF_FINGERPRINT;
 
 /// An issuer and honest verifier of `Credential`s.
 #[repr(C)]
-pub struct Issuer {
+pub struct Issuer<M: AlgebraicMac = MacGgm> {
     /// The system parameters.  Users and issuers must agree on parameters.
     pub system_parameters: SystemParameters,
     /// The issuer's aMAC key material.
-    pub keypair: amacs::Keypair,
+    pub keypair: amacs::Keypair<M>,
+    /// The schema, if any, which issued and presented credentials must follow.
+    pub schema: Option<CredentialSchema>,
 }
 
 impl Issuer {
+    /// Deserialise an `Issuer` which isn't bound to a `CredentialSchema`.
+    ///
+    /// An `Issuer` which is bound to one is serialised with the schema's
+    /// fingerprint appended, which isn't a valid keypair, and must instead be
+    /// deserialised with `Issuer::from_bytes_with_schema()`.
     pub fn from_bytes(bytes: &[u8]) -> Result<Issuer, CredentialError> {
         if bytes.len() < 64 + 96 {
             return Err(CredentialError::MissingData);
@@ -57,22 +71,39 @@ impl Issuer {
         let system_parameters = SystemParameters::from_bytes(&bytes[..64])?;
         let keypair = amacs::Keypair::from_bytes(&bytes[64..])?;
 
-        Ok(Issuer{ system_parameters, keypair })
+        Ok(Issuer{ system_parameters, keypair, schema: None })
     }
 
+    /// Deserialise an `Issuer` which is bound to a `schema`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CredentialError::BadAttribute` if the `Issuer` wasn't
+    /// serialised with this `schema`, and otherwise as for
+    /// `Issuer::from_bytes()`.
+    pub fn from_bytes_with_schema(bytes: &[u8], schema: CredentialSchema) -> Result<Issuer, CredentialError> {
+        Ok(Issuer::from_bytes(schema.strip_fingerprint(bytes)?)?.with_schema(schema))
+    }
+
+    /// Serialise this `Issuer`, followed by the fingerprint of its schema,
+    /// if it has one.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut v: Vec<u8> = Vec::with_capacity(64 + self.keypair.len() * 32);
+        let mut v: Vec<u8> = Vec::with_capacity(64 + self.keypair.len() * 32 + SIZEOF_FINGERPRINT);
 
         v.extend(self.system_parameters.to_bytes().iter());
         v.extend(self.keypair.to_bytes().iter());
 
+        if let Some(ref schema) = self.schema {
+            v.extend(schema.fingerprint().iter());
+        }
+
         v
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(Issuer, "A valid byte sequence representing an Issuer");
 
-impl Issuer {
+impl<M: AlgebraicMac> Issuer<M> {
     /// Create a new `Issuer` from some agreed upon `system_parameters`.
     ///
     /// This will create an entirely new issuer with new key material.  For
@@ -92,23 +123,73 @@ impl Issuer {
     {
         let keypair = amacs::Keypair::new(&system_parameters.h, csprng);
 
-        Issuer { system_parameters, keypair }
+        Issuer { system_parameters, keypair, schema: None }
+    }
+
+    /// Create a new `Issuer`, with new key material, for credentials on `n`
+    /// scalar attributes and `k` group element attributes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CredentialError::WrongNumberOfAttributes` if `k` is non-zero
+    /// and `M` doesn't support group element attributes.
+    pub fn create_with_attributes<R>(
+        system_parameters: SystemParameters,
+        n: usize,
+        k: usize,
+        csprng: &mut R,
+    ) -> Result<Self, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let keypair = amacs::Keypair::with_attributes(&system_parameters.h, n, k, csprng)
+            .or(Err(CredentialError::WrongNumberOfAttributes))?;
+
+        Ok(Issuer { system_parameters, keypair, schema: None })
     }
 
     /// Initialize an `Issuer`.
     pub fn new(
         system_parameters: SystemParameters,
-        keypair: amacs::Keypair,
+        keypair: amacs::Keypair<M>,
     ) -> Self
     {
-        Issuer { system_parameters, keypair }
+        Issuer { system_parameters, keypair, schema: None }
+    }
+
+    /// Require that all credentials issued and verified by this `Issuer`
+    /// follow some `schema`.
+    pub fn with_schema(mut self, schema: CredentialSchema) -> Self {
+        self.schema = Some(schema);
+        self
     }
 
     /// Get this `Issuer`s parameters for publishing to users.
-    pub fn get_issuer_parameters(&self) -> IssuerParameters {
+    pub fn get_issuer_parameters(&self) -> M::PublicKey {
         self.keypair.public.clone()
     }
 
+    /// Get this `Issuer`s entry for a verifier-published set of issuers, for
+    /// use in issuer-hiding presentations.
+    ///
+    /// This contains a freshly issued credential on the `dummy_attribute()`.
+    pub fn issuer_set_member<R>(&self, csprng: &mut R) -> Result<IssuerSetMember<M>, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        attributes.push(dummy_attribute());
+
+        let mac: M::Tag = M::mac(&self.keypair.secret, &attributes.clone().into(), csprng)
+            .or(Err(CredentialError::MacCreation))?;
+
+        Ok(IssuerSetMember {
+            issuer_parameters: self.get_issuer_parameters(),
+            dummy_credential: Credential { mac, attributes, point_attributes: Vec::new() },
+        })
+    }
+
     /// Unblinded credential issuance.
     ///
     /// # Note
@@ -119,112 +200,183 @@ impl Issuer {
     ///
     /// # Inputs
     ///
-    /// * `request` is a `CredentialRequest` containing some revealed_attributes.
+    /// * `request` is a `CredentialRequest` containing some revealed_attributes,
+    ///   and any group element attributes.
     ///
     /// # Errors
     ///
     /// This method may return the following errors:
     ///
-    /// * `CredentialError::NoIssuerParameters` if this `Issuer`'s
-    ///   `keypair.public` didn't contain the correct length of public key.
-    /// * `CredentialError::NoIssuerKey` if this `Issuer`'s secret `key`
-    ///   was not the correct length.
+    /// * `CredentialError::MacCreation` if the number of attributes in the
+    ///   `request` didn't match this `Issuer`'s key material, including if it
+    ///   has group element attributes which `M` doesn't support.
+    /// * `CredentialError::CredentialIssuance` if the proof of correct
+    ///   issuance couldn't be created, e.g. because this `Issuer`'s
+    ///   `keypair` was not the correct length.
+    /// * `CredentialError::WrongNumberOfAttributes` or
+    ///   `CredentialError::BadAttribute` if the `request` doesn't follow this
+    ///   `Issuer`'s `schema`.
     ///
     /// # Returns
     ///
     /// A `CredentialIssuance` upon successful issuance.
     pub fn issue<R>(&self, request: &CredentialRequest, rng: &mut R)
-        -> Result<CredentialIssuance, CredentialError>
+        -> Result<CredentialIssuance<M>, CredentialError>
     where
         R: RngCore + CryptoRng,
     {
-        // Obtain our needed public and secret key material.
-        let X1: RistrettoPoint = match self.keypair.public.Xn.get(0) {
-            Some(x) => *x,
-            None => return Err(CredentialError::NoIssuerParameters),
-        };
-        let x1: Scalar = match self.keypair.secret.xn.get(0) {
-            Some(x) => *x,
-            None => return Err(CredentialError::NoIssuerKey),
-        };
-
         let attributes = &request.attributes_revealed;
 
+        if let Some(ref schema) = self.schema {
+            schema.validate_issuance(attributes)?;
+        }
+
         // Create a transcript and feed the context into it
         let mut transcript = Transcript::new(b"AEONFLUX ISSUANCE");
+
+        if let Some(ref schema) = self.schema {
+            schema.commit(&mut transcript);
+        }
         let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
 
-        // Calculate (u, u'), i.e. (nonce, mac)
-        let tag: amacs::Tag = self.keypair.secret.mac(&attributes.clone().into(), &mut csprng)
+        // Calculate the aMAC over the attributes
+        let message: amacs::Message = request.message();
+        let tag: M::Tag = M::mac(&self.keypair.secret, &message, &mut csprng)
             .or(Err(CredentialError::MacCreation))?;
 
-        // Choose a blinding factor, x~0
-        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let (secret_key_commitment, proof) = M::prove_issuance(&self.keypair.secret,
+                                                               &self.keypair.public,
+                                                               &self.system_parameters,
+                                                               &tag, &message,
+                                                               &mut transcript, &mut csprng)
+            .or(Err(CredentialError::CredentialIssuance))?;
 
-        // Construct a commitment to the issuer secret key
-        let Cx0 = pedersen::Commitment::to(&(&self.system_parameters.g * &self.keypair.secret.x0),
-                                           &x0_tilde, &self.system_parameters.h);
-        // XXX Could speed up the above by multiscalar_mul and generating a basepoint table
+        Ok(Self::credential_issuance(tag, message, secret_key_commitment, proof))
+    }
 
-        // Construct the NIZK proof of correct issuance
-        let secrets = issuance_revealed::Secrets {
-            x0: &self.keypair.secret.x0,
-            x1: &x1,
-            x0_tilde: (&x0_tilde).into(),
-            m1x1: &(&attributes[0] * &x1),
-        };
-        let publics = issuance_revealed::Publics {
-            P: &tag.nonce,
-            Q: &tag.mac,
-            Cx0: &Cx0.into(),
-            B: &self.system_parameters.g,
-            A: &self.system_parameters.h,
-            X1: &X1,
-        };
-        let proof = issuance_revealed::Proof::create(&mut transcript, publics, secrets);
+    /// Unblinded credential issuance with a deterministic nonce.
+    ///
+    /// The nonce of the `amacs::Tag` is derived by hashing the requested
+    /// attributes to the group, so that issuing a credential on the same
+    /// attributes twice produces the same tag.  The blinding factor for the
+    /// commitment to the issuer's secret key, and the randomness for the
+    /// issuance proof, are drawn from a `ChaChaRng` seeded by hashing the
+    /// secret key and the attributes, so that the whole `CredentialIssuance`
+    /// is a function of the request and no randomness is required.
+    ///
+    /// The derived nonce is bound into the issuance proof, which therefore
+    /// only verifies if the nonce is the hash of the attributes, and the
+    /// resulting `CredentialIssuance` must be processed with
+    /// `User::obtain_finish_deterministic()`.
+    ///
+    /// # Errors
+    ///
+    /// The same as for `Issuer::issue()`.
+    pub fn issue_deterministic(&self, request: &CredentialRequest)
+        -> Result<CredentialIssuance<M>, CredentialError>
+    {
+        let attributes = &request.attributes_revealed;
+
+        if let Some(ref schema) = self.schema {
+            schema.validate_issuance(attributes)?;
+        }
+
+        let mut transcript = Transcript::new(b"AEONFLUX ISSUANCE");
+
+        if let Some(ref schema) = self.schema {
+            schema.commit(&mut transcript);
+        }
+        let message: amacs::Message = request.message();
+        let tag: M::Tag = M::mac_deterministic(&self.keypair.secret, &message)
+            .or(Err(CredentialError::MacCreation))?;
+
+        // Derive a seed from our secret key and the attributes.
+        let mut h = Sha512::default();
+        let mut seed: [u8; 32] = [0u8; 32];
+
+        h.input(b"AEONFLUX DETERMINISTIC ISSUANCE");
+        h.input(&M::secret_key_to_bytes(&self.keypair.secret));
+
+        for attribute in message.0.iter() {
+            h.input(attribute.as_bytes());
+        }
+        for attribute in message.1.iter() {
+            h.input(attribute.compress().as_bytes());
+        }
+        seed.copy_from_slice(&h.result()[..32]);
+
+        let mut csprng = ChaChaRng::from_seed(seed);
+        let (secret_key_commitment, proof) = M::prove_issuance_deterministic(&self.keypair.secret,
+                                                                             &self.keypair.public,
+                                                                             &self.system_parameters,
+                                                                             &tag, &message,
+                                                                             &mut transcript, &mut csprng)
+            .or(Err(CredentialError::CredentialIssuance))?;
+
+        Ok(Self::credential_issuance(tag, message, secret_key_commitment, proof))
+    }
+
+    /// Package a `tag` on a `message` with the commitment to the issuer
+    /// secret key and the NIZK proof of correct issuance.
+    fn credential_issuance(
+        tag: M::Tag,
+        message: amacs::Message,
+        secret_key_commitment: pedersen::Commitment,
+        proof: M::IssuanceProof,
+    ) -> CredentialIssuance<M>
+    {
         let cred = Credential {
-            mac: tag.clone(),
-            attributes: attributes.clone(),
+            mac: tag,
+            attributes: message.0,
+            point_attributes: message.1,
         };
 
-        Ok(CredentialIssuance{
+        CredentialIssuance{
             proof: proof,
             credential: cred,
-            secret_key_commitment: Cx0,
-        })
+            secret_key_commitment: secret_key_commitment,
+        }
     }
 
-    pub fn verify(&self, presentation: &CredentialPresentation)
-        -> Result<VerifiedCredential, CredentialError>
-    {
-        let P = presentation.rerandomized_nonce;
-
-        // Recompute the MAC
-        let mut V_prime: RistrettoPoint = &self.keypair.secret.x0 * &P;
-
-        for (index, attribute) in presentation.attributes_revealed.iter().enumerate() {
-            V_prime += (&self.keypair.secret.xn[index] * attribute) * &P;
-        }
+    /// Verify a `credential` issued with `Issuer::issue_deterministic()`,
+    /// checking both its aMAC and that its nonce was derived from its
+    /// attributes.
+    ///
+    /// # Note
+    ///
+    /// This is for credentials which are shown to the issuer in the clear,
+    /// e.g. those returned to it by a user.  Presentations of such credentials
+    /// are rerandomised, and so are verified with `Issuer::verify()` as usual.
+    pub fn verify_deterministic(&self, credential: &Credential<M>) -> Result<(), CredentialError> {
+        M::verify_deterministic(&self.keypair.secret, &credential.mac, &credential.message())
+            .or(Err(CredentialError::MacVerification))
+    }
 
-        for (index, attribute) in presentation.attributes_blinded.iter().enumerate() {
-            V_prime += &self.keypair.secret.xn[index] * attribute;
+    /// Verify a user's `presentation` of a credential issued by us.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` or
+    ///   `CredentialError::BadAttribute` if the `presentation` doesn't follow
+    ///   this `Issuer`'s `schema`, or is of the wrong shape for our key.
+    /// * `CredentialError::MacVerification` if it doesn't verify.
+    pub fn verify(&self, presentation: &M::Presentation)
+        -> Result<VerifiedCredential<M>, CredentialError>
+    {
+        if let Some(ref schema) = self.schema {
+            schema.validate_presented_attributes(M::attributes_revealed(presentation),
+                                                 M::number_of_attributes_blinded(presentation))?;
         }
-        V_prime -= presentation.rerandomized_mac_commitment;
 
         let mut transcript = Transcript::new(b"AEONFLUX SHOW");
-        let publics = valid_credential::Publics {
-            B: &self.system_parameters.g,
-            A: &self.system_parameters.h,
-            X0: &self.keypair.public.Xn[0],
-            P: &P,
-            V: &V_prime,
-            Cm0: &presentation.attributes_blinded[0].into(),
-        };
 
-        if presentation.proof.verify(&mut transcript, publics).is_err() {
-            return Err(CredentialError::MacVerification);
+        if let Some(ref schema) = self.schema {
+            schema.commit(&mut transcript);
         }
 
+        M::verify_presentation(&self.keypair.secret, &self.keypair.public, &self.system_parameters,
+                               presentation, &mut transcript)?;
+
         Ok(VerifiedCredential(presentation.clone()))
     }
 }
@@ -233,12 +385,22 @@ impl Issuer {
 mod test {
     use super::*;
 
+    use std::string::String;
+
     use rand::thread_rng;
 
+    use curve25519_dalek::scalar::Scalar;
+
+    use amacs_ddh::MacDdh;
+    use credential::CredentialPresentation;
     use credential::RevealedAttribute;
     use parameters::NUMBER_OF_ATTRIBUTES;
     use parameters::SystemParameters;
     use nonces::Nonces;
+    use schema::AttributeDescriptor;
+    use schema::AttributeEncoding;
+    use schema::AttributeValue;
+    use schema::Visibility;
     use user::User;
 
     const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
@@ -281,4 +443,216 @@ mod test {
         let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut alice_rng).unwrap();
         let _verified_credential: VerifiedCredential = issuer.verify(&alice_presentation).unwrap();
     }
+
+    #[test]
+    fn credential_issuance_and_presentation_with_many_attributes() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create_with_attributes(system_parameters, 3, 0, &mut issuer_rng).unwrap();
+        let issuer_parameters: IssuerParameters = issuer.get_issuer_parameters();
+        let mut alice: User = User::new(system_parameters, issuer_parameters.clone(), None);
+
+        // A user of an issuer on more attributes must survive a round trip
+        // both before and after obtaining a credential.
+        assert!(User::from_bytes(&alice.to_bytes()).unwrap() == alice);
+
+        let alice_attributes: Vec<RevealedAttribute> = (0..3).map(|_| Scalar::random(&mut alice_rng)).collect();
+        let alice_request: CredentialRequest = alice.obtain(alice_attributes);
+
+        // Requests with the wrong number of attributes must be refused.
+        assert!(issuer.issue(&alice.obtain(vec![Scalar::random(&mut alice_rng)]), &mut issuer_rng).is_err());
+
+        let alice_issuance: CredentialIssuance = issuer.issue(&alice_request, &mut issuer_rng).unwrap();
+        let alice_issuance: CredentialIssuance = CredentialIssuance::from_bytes(&alice_issuance.to_bytes()).unwrap();
+
+        alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+        assert!(User::from_bytes(&alice.to_bytes()).unwrap() == alice);
+
+        let alice_nonces: Nonces = Nonces::new(&mut alice_rng, 3);
+        let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut alice_rng).unwrap();
+        let alice_presentation: CredentialPresentation =
+            CredentialPresentation::from_bytes(&alice_presentation.to_bytes()).unwrap();
+        let issuer: Issuer = Issuer::from_bytes(&issuer.to_bytes()).unwrap();
+
+        assert!(issuer.verify(&alice_presentation).is_ok());
+
+        // A presentation to an issuer on a different number of attributes
+        // must not verify.
+        let other_issuer: Issuer = Issuer::create(system_parameters, &mut issuer_rng);
+
+        assert!(other_issuer.verify(&alice_presentation).is_err());
+    }
+
+    #[test]
+    fn credential_issuance_deterministic() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut issuer_rng);
+        let issuer_parameters: IssuerParameters = issuer.get_issuer_parameters();
+        let mut alice: User = User::new(system_parameters, issuer_parameters.clone(), None);
+
+        let mut alice_attributes: Vec<RevealedAttribute> = Vec::new();
+
+        alice_attributes.push(Scalar::random(&mut alice_rng));
+
+        let alice_request: CredentialRequest = alice.obtain(alice_attributes);
+        let issuance1: CredentialIssuance = issuer.issue_deterministic(&alice_request).unwrap();
+        let issuance2: CredentialIssuance = issuer.issue_deterministic(&alice_request).unwrap();
+
+        // Issuing twice on the same attributes must produce the same tag,
+        // and the same proof.
+        assert!(issuance1 == issuance2);
+        assert!(issuer.verify_deterministic(&issuance1.credential).is_ok());
+
+        // A randomly-nonced issuance must not pass as a deterministic one.
+        let random: CredentialIssuance = issuer.issue(&alice_request, &mut issuer_rng).unwrap();
+
+        assert!(issuer.verify_deterministic(&random.credential).is_err());
+        assert!(alice.obtain_finish_deterministic(Some(&random)).is_err());
+
+        // The proof covers the derived nonce, so it only verifies along with
+        // the check that the nonce is the hash of the attributes.
+        assert!(alice.obtain_finish(Some(&issuance1)).is_err());
+
+        alice.obtain_finish_deterministic(Some(&issuance1)).unwrap();
+
+        let alice_nonces: Nonces = Nonces::new(&mut alice_rng, NUMBER_OF_ATTRIBUTES);
+        let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut alice_rng).unwrap();
+
+        assert!(issuer.verify(&alice_presentation).is_ok());
+    }
+
+    #[test]
+    fn credential_issuance_and_presentation_mac_ddh() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer<MacDdh> = Issuer::create(system_parameters, &mut issuer_rng);
+        let issuer_parameters = issuer.get_issuer_parameters();
+        let mut alice: User<MacDdh> = User::new(system_parameters, issuer_parameters.clone(), None);
+
+        let mut alice_attributes: Vec<RevealedAttribute> = Vec::new();
+
+        alice_attributes.push(Scalar::random(&mut alice_rng));
+
+        let alice_request: CredentialRequest = alice.obtain(alice_attributes);
+        let alice_issuance: CredentialIssuance<MacDdh> = issuer.issue(&alice_request, &mut issuer_rng).unwrap();
+
+        alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+        let alice_nonces: Nonces = Nonces::new(&mut alice_rng, NUMBER_OF_ATTRIBUTES);
+        let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut alice_rng).unwrap();
+
+        // The presentation must survive a round trip through its wire format.
+        let deserialized = CredentialPresentation::from_bytes(&alice_presentation.to_bytes()).unwrap();
+
+        assert!(deserialized == alice_presentation);
+        assert!(issuer.verify(&deserialized).is_ok());
+
+        // A MAC_GGM issuer must not accept a MAC_DDH presentation.
+        let ggm_issuer: Issuer = Issuer::create(system_parameters, &mut issuer_rng);
+
+        assert!(ggm_issuer.verify(&alice_presentation).is_err());
+    }
+
+    #[test]
+    fn credential_issuance_and_presentation_mac_ddh_with_many_attributes() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer<MacDdh> = Issuer::create_with_attributes(system_parameters, 3, 0, &mut issuer_rng).unwrap();
+        let issuer_parameters = issuer.get_issuer_parameters();
+        let mut alice: User<MacDdh> = User::new(system_parameters, issuer_parameters.clone(), None);
+
+        let alice_attributes: Vec<RevealedAttribute> = (0..3).map(|_| Scalar::random(&mut alice_rng)).collect();
+        let alice_request: CredentialRequest = alice.obtain(alice_attributes);
+
+        // Requests with the wrong number of attributes must be refused.
+        assert!(issuer.issue(&alice.obtain(vec![Scalar::random(&mut alice_rng)]), &mut issuer_rng).is_err());
+
+        let alice_issuance: CredentialIssuance<MacDdh> = issuer.issue(&alice_request, &mut issuer_rng).unwrap();
+
+        alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+        let alice_nonces: Nonces = Nonces::new(&mut alice_rng, 3);
+        let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut alice_rng).unwrap();
+
+        assert!(issuer.verify(&alice_presentation).is_ok());
+    }
+
+    #[test]
+    fn credential_issuance_and_presentation_with_schema() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let schema = CredentialSchema {
+            name: String::from("test"),
+            attributes: vec![
+                AttributeDescriptor {
+                    name: String::from("phone number"),
+                    encoding: AttributeEncoding::HashedString { domain_separator: String::from("phone") },
+                    issuance: Visibility::Revealed,
+                    presentation: Visibility::Hidden,
+                },
+            ],
+        };
+        let mut other_schema = schema.clone();
+
+        other_schema.name = String::from("other");
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut issuer_rng).with_schema(schema.clone());
+        let issuer_parameters: IssuerParameters = issuer.get_issuer_parameters();
+        let mut alice: User = User::new(system_parameters, issuer_parameters.clone(), None)
+            .with_schema(schema.clone());
+
+        let alice_attributes = schema.encode(&[AttributeValue::String("+14155550101")]).unwrap();
+        let alice_request: CredentialRequest = alice.obtain(alice_attributes);
+
+        // Requests with the wrong number of attributes must be refused.
+        assert!(issuer.issue(&alice.obtain(Vec::new()), &mut issuer_rng).is_err());
+
+        let alice_issuance: CredentialIssuance = issuer.issue(&alice_request, &mut issuer_rng).unwrap();
+
+        alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+        let alice_nonces: Nonces = Nonces::new(&mut alice_rng, NUMBER_OF_ATTRIBUTES);
+        let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut alice_rng).unwrap();
+
+        assert!(issuer.verify(&alice_presentation).is_ok());
+
+        // The same key material under a different schema must not verify.
+        let other_issuer: Issuer = Issuer::new(system_parameters, issuer.keypair.clone())
+            .with_schema(other_schema);
+
+        assert!(other_issuer.verify(&alice_presentation).is_err());
+
+        // The schema must survive a round trip of the issuer and the user.
+        let other_schema = other_issuer.schema.clone().unwrap();
+        let serialized = issuer.to_bytes();
+
+        assert!(Issuer::from_bytes(&serialized).is_err());
+        assert!(Issuer::from_bytes_with_schema(&serialized, other_schema.clone()).is_err());
+
+        let deserialized = Issuer::from_bytes_with_schema(&serialized, schema.clone()).unwrap();
+
+        assert!(deserialized.schema == issuer.schema);
+        assert!(deserialized.verify(&alice_presentation).is_ok());
+
+        let serialized = alice.to_bytes();
+
+        assert!(User::from_bytes(&serialized).is_err());
+        assert!(User::from_bytes_with_schema(&serialized, other_schema).is_err());
+
+        let deserialized = User::from_bytes_with_schema(&serialized, schema).unwrap();
+
+        assert!(deserialized == alice);
+    }
 }
@@ -0,0 +1,456 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Issuer-hiding credential presentations.
+//!
+//! A user holding a credential from one issuer in a set of issuers, all of
+//! whose secret keys are held by the verifier, may present it without
+//! revealing which issuer created it.
+//!
+//! Since each issuer's verification equation depends upon its secret key, the
+//! user is unable to simulate a presentation to any issuer other than its own.
+//! Instead, every issuer in the set publishes, alongside its
+//! `IssuerParameters`, a *dummy credential* on a fixed, publicly-known
+//! attribute.  The user then presents one credential to every issuer in the
+//! set: its own credential to its issuer, and the rerandomised dummy
+//! credentials to all others.  Each of these presentations is verified as
+//! usual.  Additionally, the user commits to its attribute and proves, using
+//! an OR-composition, that one of the presented credentials is on the
+//! committed attribute, and that the committed attribute is not the dummy
+//! attribute.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::{deserialize, serialize};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use sha2::Sha512;
+
+use amacs::AlgebraicMac;
+use amacs::MacGgm;
+use credential::Credential;
+use credential::CredentialPresentation;
+use errors::CredentialError;
+use issuer::Issuer;
+use parameters::SystemParameters;
+use proofs::attribute_not_dummy;
+
+/// Get the dummy attribute, on which every issuer in an issuer-hiding set
+/// publishes a credential.
+pub fn dummy_attribute() -> Scalar {
+    Scalar::hash_from_bytes::<Sha512>(b"aeonflux issuer hiding dummy attribute")
+}
+
+/// An issuer's entry in a verifier-published set of issuers, for use in
+/// issuer-hiding presentations.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IssuerSetMember<M: AlgebraicMac = MacGgm> {
+    /// The issuer's public parameters.
+    pub issuer_parameters: M::PublicKey,
+    /// A credential issued by this issuer on the `dummy_attribute()`.
+    pub dummy_credential: Credential<M>,
+}
+
+/// The responses for one branch of an `AttributeEqualityProof`.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+struct BranchResponses {
+    challenge: Scalar,
+    m: Scalar,
+    z_k: Scalar,
+    z: Scalar,
+}
+
+/// A zero-knowledge proof that, for some branch \\( k \\), the attribute
+/// committed to in the \\( k \\)th presentation, \\( C\_{m,k} = m P\_k + z\_k A \\),
+/// is the same as the attribute in \\( C\_m = m A + z B \\).
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AttributeEqualityProof {
+    branches: Vec<BranchResponses>,
+}
+
+impl AttributeEqualityProof {
+    fn commit_publics(
+        transcript: &mut Transcript,
+        system_parameters: &SystemParameters,
+        presentations: &[CredentialPresentation],
+        Cm: &RistrettoPoint,
+    ) {
+        transcript.commit_bytes(b"domain-sep", b"issuer_hiding_attribute_equality");
+        transcript.commit_bytes(b"B", system_parameters.g.compress().as_bytes());
+        transcript.commit_bytes(b"A", system_parameters.h.compress().as_bytes());
+        transcript.commit_bytes(b"Cm", Cm.compress().as_bytes());
+
+        for presentation in presentations.iter() {
+            transcript.commit_bytes(b"P", presentation.rerandomized_nonce.compress().as_bytes());
+            transcript.commit_bytes(b"Cm_k", RistrettoPoint::from(presentation.attributes_blinded[0])
+                                    .compress().as_bytes());
+        }
+    }
+
+    fn challenge(transcript: &mut Transcript) -> Scalar {
+        let mut bytes = [0u8; 64];
+
+        transcript.challenge_bytes(b"chal", &mut bytes);
+
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+
+    /// Create an `AttributeEqualityProof`, where the `index`th presentation is
+    /// the one whose commitment \\( C\_{m,k} \\) opens to `m` with `z_k`.
+    fn create<R>(
+        transcript: &mut Transcript,
+        system_parameters: &SystemParameters,
+        presentations: &[CredentialPresentation],
+        Cm: &RistrettoPoint,
+        index: usize,
+        m: &Scalar,
+        z_k: &Scalar,
+        z: &Scalar,
+        csprng: &mut R,
+    ) -> AttributeEqualityProof
+    where
+        R: RngCore + CryptoRng,
+    {
+        let A = &system_parameters.h;
+        let B = &system_parameters.g;
+
+        AttributeEqualityProof::commit_publics(transcript, system_parameters, presentations, Cm);
+
+        let r_m: Scalar = Scalar::random(csprng);
+        let r_z_k: Scalar = Scalar::random(csprng);
+        let r_z: Scalar = Scalar::random(csprng);
+        let mut branches: Vec<BranchResponses> = Vec::with_capacity(presentations.len());
+
+        for (k, presentation) in presentations.iter().enumerate() {
+            let P: &RistrettoPoint = &presentation.rerandomized_nonce;
+            let Cm_k: RistrettoPoint = presentation.attributes_blinded[0].into();
+
+            // For the real branch, commit to our randomness, and otherwise
+            // simulate the branch with a random challenge and responses.
+            let branch = if k == index {
+                BranchResponses { challenge: Scalar::zero(), m: r_m, z_k: r_z_k, z: r_z }
+            } else {
+                BranchResponses {
+                    challenge: Scalar::random(csprng),
+                    m: Scalar::random(csprng),
+                    z_k: Scalar::random(csprng),
+                    z: Scalar::random(csprng),
+                }
+            };
+            let T_k: RistrettoPoint = P * branch.m + A * branch.z_k - Cm_k * branch.challenge;
+            let T: RistrettoPoint = A * branch.m + B * branch.z - Cm * branch.challenge;
+
+            transcript.commit_bytes(b"com Cm_k", T_k.compress().as_bytes());
+            transcript.commit_bytes(b"com Cm", T.compress().as_bytes());
+
+            branches.push(branch);
+        }
+
+        let mut challenge: Scalar = AttributeEqualityProof::challenge(transcript);
+
+        for (k, branch) in branches.iter().enumerate() {
+            if k != index {
+                challenge -= branch.challenge;
+            }
+        }
+        branches[index] = BranchResponses {
+            challenge: challenge,
+            m: &r_m + &(&challenge * m),
+            z_k: &r_z_k + &(&challenge * z_k),
+            z: &r_z + &(&challenge * z),
+        };
+
+        AttributeEqualityProof { branches }
+    }
+
+    fn verify(
+        &self,
+        transcript: &mut Transcript,
+        system_parameters: &SystemParameters,
+        presentations: &[CredentialPresentation],
+        Cm: &RistrettoPoint,
+    ) -> Result<(), ()>
+    {
+        if self.branches.len() != presentations.len() {
+            return Err(());
+        }
+        let A = &system_parameters.h;
+        let B = &system_parameters.g;
+
+        AttributeEqualityProof::commit_publics(transcript, system_parameters, presentations, Cm);
+
+        let mut challenge_sum: Scalar = Scalar::zero();
+
+        for (branch, presentation) in self.branches.iter().zip(presentations.iter()) {
+            let P: &RistrettoPoint = &presentation.rerandomized_nonce;
+            let Cm_k: RistrettoPoint = presentation.attributes_blinded[0].into();
+
+            let T_k: RistrettoPoint = P * branch.m + A * branch.z_k - Cm_k * branch.challenge;
+            let T: RistrettoPoint = A * branch.m + B * branch.z - Cm * branch.challenge;
+
+            transcript.commit_bytes(b"com Cm_k", T_k.compress().as_bytes());
+            transcript.commit_bytes(b"com Cm", T.compress().as_bytes());
+
+            challenge_sum += branch.challenge;
+        }
+
+        if AttributeEqualityProof::challenge(transcript) == challenge_sum {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// A presentation of a credential from one of a set of issuers, which does not
+/// reveal which.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IssuerHidingPresentation {
+    /// A presentation to each issuer in the set, in order.
+    pub presentations: Vec<CredentialPresentation>,
+    /// A commitment, \\( C\_m = m A + z B \\), to the user's attribute.
+    pub attribute_commitment: RistrettoPoint,
+    /// A proof that one of the `presentations` is on the attribute in the
+    /// `attribute_commitment`.
+    pub equality_proof: AttributeEqualityProof,
+    /// A proof that the attribute in the `attribute_commitment` is not the
+    /// `dummy_attribute()`.
+    pub not_dummy_proof: attribute_not_dummy::Proof,
+}
+
+impl IssuerHidingPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<IssuerHidingPresentation, CredentialError> {
+        let (presentations, attribute_commitment, equality_proof, not_dummy_proof) =
+            match deserialize(bytes) {
+                Ok(x)  => x,
+                Err(_) => return Err(CredentialError::MissingData),
+            };
+
+        Ok(IssuerHidingPresentation { presentations, attribute_commitment, equality_proof, not_dummy_proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.presentations, &self.attribute_commitment,
+                      &self.equality_proof, &self.not_dummy_proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => panic!(),  // XXX clean this up
+        }
+    }
+
+    /// Create the proofs for an issuer-hiding presentation, given a
+    /// presentation to each issuer, where the `index`th presentation is of
+    /// the user's own credential on the attribute `m` with blinding factor
+    /// `z_k`.
+    pub(crate) fn create<R>(
+        system_parameters: &SystemParameters,
+        presentations: Vec<CredentialPresentation>,
+        index: usize,
+        m: &Scalar,
+        z_k: &Scalar,
+        csprng: &mut R,
+    ) -> Result<IssuerHidingPresentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let A = &system_parameters.h;
+        let B = &system_parameters.g;
+        let m_minus_dummy: Scalar = m - &dummy_attribute();
+
+        if m_minus_dummy == Scalar::zero() {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let z: Scalar = Scalar::random(csprng);
+        let Cm: RistrettoPoint = A * m + B * z;
+
+        let mut transcript = Transcript::new(b"AEONFLUX ISSUER HIDING SHOW");
+        let equality_proof = AttributeEqualityProof::create(&mut transcript, system_parameters,
+                                                            &presentations, &Cm, index,
+                                                            m, z_k, &z, csprng);
+
+        // D = Cm - m_d A = (m - m_d) A + z B, so A = D / (m - m_d) - z B / (m - m_d)
+        let a: Scalar = m_minus_dummy.invert();
+        let secrets = attribute_not_dummy::Secrets {
+            a: &a,
+            b: &-(&z * &a),
+        };
+        let publics = attribute_not_dummy::Publics {
+            A: A,
+            D: &(Cm - A * dummy_attribute()),
+            B: B,
+        };
+        let not_dummy_proof = attribute_not_dummy::Proof::create(&mut transcript, publics, secrets, csprng);
+
+        Ok(IssuerHidingPresentation {
+            presentations,
+            attribute_commitment: Cm,
+            equality_proof,
+            not_dummy_proof,
+        })
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(IssuerHidingPresentation,
+                                         "A valid byte sequence representing an IssuerHidingPresentation");
+
+/// Verify an issuer-hiding `presentation` with respect to a set of `issuers`,
+/// which must be in the same order as the set of `IssuerSetMember`s given to
+/// the user.
+///
+/// # Note
+///
+/// This requires holding the secret keys of every issuer in the set.
+pub fn verify<M>(
+    issuers: &[&Issuer<M>],
+    presentation: &IssuerHidingPresentation,
+) -> Result<(), CredentialError>
+where
+    M: AlgebraicMac<Presentation = CredentialPresentation>,
+{
+    if issuers.len() == 0 || issuers.len() != presentation.presentations.len() {
+        return Err(CredentialError::NoIssuerParameters);
+    }
+    let system_parameters: &SystemParameters = &issuers[0].system_parameters;
+
+    // Every presentation must verify for its respective issuer.
+    for (issuer, p) in issuers.iter().zip(presentation.presentations.iter()) {
+        if issuer.system_parameters != *system_parameters {
+            return Err(CredentialError::NoSystemParameters);
+        }
+        issuer.verify(p)?;
+    }
+
+    let A = &system_parameters.h;
+    let B = &system_parameters.g;
+    let Cm = &presentation.attribute_commitment;
+
+    let mut transcript = Transcript::new(b"AEONFLUX ISSUER HIDING SHOW");
+
+    if presentation.equality_proof.verify(&mut transcript, system_parameters,
+                                          &presentation.presentations, Cm).is_err()
+    {
+        return Err(CredentialError::VerificationFailure);
+    }
+
+    let publics = attribute_not_dummy::Publics {
+        A: A,
+        D: &(Cm - A * dummy_attribute()),
+        B: B,
+    };
+
+    if presentation.not_dummy_proof.verify(&mut transcript, publics).is_err() {
+        return Err(CredentialError::VerificationFailure);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use credential::CredentialIssuance;
+    use credential::CredentialRequest;
+    use credential::RevealedAttribute;
+    use user::User;
+
+    const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                          127,   4, 178,  70, 212, 141, 119, 112,
+                          153, 154, 135,  11, 227, 132, 247,  47,
+                           68, 192,  72, 200,  23,  88,  51,  82, ];
+
+    fn user_with_credential(issuer: &Issuer, attribute: Scalar) -> User {
+        let mut rng = thread_rng();
+        let mut user: User = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+        let mut attributes: Vec<RevealedAttribute> = Vec::new();
+
+        attributes.push(attribute);
+
+        let request: CredentialRequest = user.obtain(attributes);
+        let issuance: CredentialIssuance = issuer.issue(&request, &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+        user
+    }
+
+    #[test]
+    fn issuer_hiding_presentation() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer0: Issuer = Issuer::create(system_parameters, &mut rng);
+        let issuer1: Issuer = Issuer::create(system_parameters, &mut rng);
+        let issuer2: Issuer = Issuer::create(system_parameters, &mut rng);
+
+        let set: Vec<IssuerSetMember> = vec![issuer0.issuer_set_member(&mut rng).unwrap(),
+                                             issuer1.issuer_set_member(&mut rng).unwrap(),
+                                             issuer2.issuer_set_member(&mut rng).unwrap()];
+
+        let alice = user_with_credential(&issuer1, Scalar::random(&mut rng));
+        let presentation = alice.show_issuer_hiding(&set, &mut rng).unwrap();
+        let deserialized = IssuerHidingPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+        assert!(deserialized == presentation);
+        assert!(verify(&[&issuer0, &issuer1, &issuer2], &deserialized).is_ok());
+
+        // The issuers must be given in the same order as the set.
+        assert!(verify(&[&issuer1, &issuer0, &issuer2], &presentation).is_err());
+        assert!(verify(&[&issuer0, &issuer1], &presentation).is_err());
+    }
+
+    #[test]
+    fn issuer_hiding_presentation_not_in_set() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer0: Issuer = Issuer::create(system_parameters, &mut rng);
+        let issuer1: Issuer = Issuer::create(system_parameters, &mut rng);
+        let outsider: Issuer = Issuer::create(system_parameters, &mut rng);
+
+        let set: Vec<IssuerSetMember> = vec![issuer0.issuer_set_member(&mut rng).unwrap(),
+                                             issuer1.issuer_set_member(&mut rng).unwrap()];
+
+        let mallory = user_with_credential(&outsider, Scalar::random(&mut rng));
+
+        assert!(mallory.show_issuer_hiding(&set, &mut rng).is_err());
+    }
+
+    #[test]
+    fn issuer_hiding_presentation_of_dummy_attribute() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer0: Issuer = Issuer::create(system_parameters, &mut rng);
+        let issuer1: Issuer = Issuer::create(system_parameters, &mut rng);
+
+        let set: Vec<IssuerSetMember> = vec![issuer0.issuer_set_member(&mut rng).unwrap(),
+                                             issuer1.issuer_set_member(&mut rng).unwrap()];
+
+        // Mallory only has the published dummy credential.
+        let mut mallory: User = User::new(system_parameters, issuer0.get_issuer_parameters(), None);
+
+        mallory.credential = Some(set[0].dummy_credential.clone());
+
+        assert!(mallory.show_issuer_hiding(&set, &mut rng).is_err());
+    }
+}
@@ -40,13 +40,19 @@ extern crate subtle;
 pub mod macros;
 
 pub mod amacs;
+pub mod amacs_ddh;
+pub mod amacs_points;
 pub mod credential;
 pub mod elgamal;
 pub mod errors;
 pub mod issuer;
+pub mod issuer_hiding;
+pub mod linear_relations;
 pub mod nonces;
+pub mod one_out_of_many;
 pub mod parameters;
 pub mod pedersen;
 pub mod prelude;
 pub mod proofs;
+pub mod schema;
 pub mod user;
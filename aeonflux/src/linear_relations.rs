@@ -0,0 +1,226 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Zero-knowledge proofs of knowledge of secrets satisfying linear relations.
+//!
+//! A `Statement` is a system of equations of the form
+//! \\( Y\_k = \sum\_i x\_i G\_{k,i} \\), in some secret scalars
+//! \\( x\_0, \ldots, x\_{n-1} \\) and public group elements.  Unlike the proofs
+//! in `proofs`, whose statements are fixed when the module is generated,
+//! a `Statement` may be built at runtime, e.g. for credentials with any
+//! number of attributes.
+//!
+//! The proofs are the usual Schnorr-style sigma protocols, made
+//! non-interactive with a merlin `Transcript`.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::MultiscalarMul;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+use merlin::Transcript;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
+
+/// One equation, \\( Y = \sum\_i x\_i G\_i \\), in a `Statement`.
+#[derive(Clone, Debug)]
+pub struct Equation {
+    /// The left-hand side, \\( Y \\).
+    pub lhs: RistrettoPoint,
+    /// The terms of the right-hand side, as the index of the secret
+    /// \\( x\_i \\) and the group element \\( G\_i \\) it multiplies.
+    pub terms: Vec<(usize, RistrettoPoint)>,
+}
+
+/// A system of linear equations in some secret scalars.
+#[derive(Clone, Debug)]
+pub struct Statement {
+    label: &'static [u8],
+    number_of_secrets: usize,
+    equations: Vec<Equation>,
+}
+
+impl Statement {
+    /// Create an empty statement about `number_of_secrets` secrets.  The
+    /// `label` separates the transcripts of different kinds of statement.
+    pub fn new(label: &'static [u8], number_of_secrets: usize) -> Statement {
+        Statement { label, number_of_secrets, equations: Vec::new() }
+    }
+
+    /// Add the equation \\( Y = \sum\_i x\_i G\_i \\), where the `lhs` is
+    /// \\( Y \\) and the `terms` are the pairs \\( (i, G\_i) \\).
+    pub fn equation(&mut self, lhs: RistrettoPoint, terms: Vec<(usize, RistrettoPoint)>) {
+        self.equations.push(Equation { lhs, terms });
+    }
+
+    /// Check that every term refers to one of our secrets.
+    fn is_well_formed(&self) -> bool {
+        self.equations.iter().all(|e| e.terms.iter().all(|&(i, _)| i < self.number_of_secrets))
+    }
+
+    fn commit(&self, transcript: &mut Transcript) {
+        transcript.commit_bytes(b"domain-sep", b"linear_relations");
+        transcript.commit_bytes(b"label", self.label);
+        transcript.commit_bytes(b"n", &(self.number_of_secrets as u64).to_le_bytes());
+
+        for equation in self.equations.iter() {
+            transcript.commit_bytes(b"Y", equation.lhs.compress().as_bytes());
+
+            for &(i, ref G) in equation.terms.iter() {
+                transcript.commit_bytes(b"i", &(i as u64).to_le_bytes());
+                transcript.commit_bytes(b"G", G.compress().as_bytes());
+            }
+        }
+    }
+
+    fn challenge(transcript: &mut Transcript, commitments: &[RistrettoPoint]) -> Scalar {
+        let mut bytes = [0u8; 64];
+
+        for T in commitments.iter() {
+            transcript.commit_bytes(b"com", T.compress().as_bytes());
+        }
+        transcript.challenge_bytes(b"chal", &mut bytes);
+
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+}
+
+/// A zero-knowledge proof of knowledge of secrets satisfying a `Statement`.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Proof {
+    challenge: Scalar,
+    responses: Vec<Scalar>,
+}
+
+impl Proof {
+    /// Create a proof that the `secrets` satisfy a `statement`.
+    ///
+    /// # Returns
+    ///
+    /// A `Proof`, or `Err(())` if the number of `secrets` is not that of the
+    /// `statement`, or if the `statement` refers to a secret it doesn't have.
+    /// The `secrets` are not checked to satisfy the `statement`.
+    pub fn create<R>(
+        transcript: &mut Transcript,
+        statement: &Statement,
+        secrets: &[Scalar],
+        csprng: &mut R,
+    ) -> Result<Proof, ()>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if secrets.len() != statement.number_of_secrets || !statement.is_well_formed() {
+            return Err(());
+        }
+
+        statement.commit(transcript);
+
+        let mut rng_ctor = transcript.fork_transcript();
+
+        for x in secrets.iter() {
+            rng_ctor = rng_ctor.commit_witness_bytes(b"x", x.as_bytes());
+        }
+        let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+
+        let rand: Vec<Scalar> = secrets.iter().map(|_| Scalar::random(&mut transcript_rng)).collect();
+        let commitments: Vec<RistrettoPoint> = statement.equations.iter().map(|e| {
+            RistrettoPoint::multiscalar_mul(e.terms.iter().map(|&(i, _)| rand[i]),
+                                            e.terms.iter().map(|&(_, G)| G))
+        }).collect();
+
+        let challenge: Scalar = Statement::challenge(transcript, &commitments);
+        let responses: Vec<Scalar> = secrets.iter().zip(rand.iter()).map(|(x, r)| &challenge * x + r).collect();
+
+        Ok(Proof { challenge, responses })
+    }
+
+    /// Verify this proof of a `statement`.
+    pub fn verify(&self, transcript: &mut Transcript, statement: &Statement) -> Result<(), ()> {
+        if self.responses.len() != statement.number_of_secrets || !statement.is_well_formed() {
+            return Err(());
+        }
+
+        let minus_c: Scalar = -self.challenge;
+        let commitments: Vec<RistrettoPoint> = statement.equations.iter().map(|e| {
+            RistrettoPoint::vartime_multiscalar_mul(
+                e.terms.iter().map(|&(i, _)| self.responses[i]).chain(Some(minus_c)),
+                e.terms.iter().map(|&(_, G)| G).chain(Some(e.lhs)))
+        }).collect();
+
+        statement.commit(transcript);
+
+        if Statement::challenge(transcript, &commitments) == self.challenge {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn dleq() {
+        let mut csprng = thread_rng();
+        let G = RistrettoPoint::random(&mut csprng);
+        let H = RistrettoPoint::random(&mut csprng);
+        let x = Scalar::random(&mut csprng);
+
+        let mut statement = Statement::new(b"dleq", 1);
+
+        statement.equation(G * x, vec![(0, G)]);
+        statement.equation(H * x, vec![(0, H)]);
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = Proof::create(&mut transcript, &statement, &[x], &mut csprng).unwrap();
+
+        let mut transcript = Transcript::new(b"test");
+        assert!(proof.verify(&mut transcript, &statement).is_ok());
+
+        // A proof for one statement must not verify for another.
+        let mut other = Statement::new(b"dleq", 1);
+
+        other.equation(G * x, vec![(0, G)]);
+        other.equation(H * Scalar::random(&mut csprng), vec![(0, H)]);
+
+        let mut transcript = Transcript::new(b"test");
+        assert!(proof.verify(&mut transcript, &other).is_err());
+    }
+
+    #[test]
+    fn malformed_statements_are_rejected() {
+        let mut csprng = thread_rng();
+        let G = RistrettoPoint::random(&mut csprng);
+
+        let mut statement = Statement::new(b"test", 1);
+
+        statement.equation(G, vec![(1, G)]);
+
+        let mut transcript = Transcript::new(b"test");
+        assert!(Proof::create(&mut transcript, &statement, &[Scalar::one()], &mut csprng).is_err());
+
+        let statement = Statement::new(b"test", 2);
+
+        let mut transcript = Transcript::new(b"test");
+        assert!(Proof::create(&mut transcript, &statement, &[Scalar::one()], &mut csprng).is_err());
+    }
+}
@@ -193,4 +193,8 @@ impl Nonces {
     pub fn iter(&self) -> impl Iterator<Item = &Ephemeral> {
         self.0.iter()
     }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
 }
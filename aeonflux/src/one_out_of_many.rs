@@ -0,0 +1,341 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! One-out-of-many proofs, as in "One-out-of-Many Proofs: Or How to Leak a
+//! Secret and Spend a Coin" by Groth and Kohlweiss.
+//!
+//! Given a list of Pedersen commitments, \\( D\_0, \ldots, D\_{N-1} \\), of
+//! the form \\( D\_i = v\_i A + \rho\_i B \\), a prover who knows the opening
+//! \\( \rho\_l \\) of some \\( D\_l = \rho\_l B \\) which is a commitment to
+//! zero may prove so without revealing \\( l \\).  The proof is of size
+//! logarithmic in \\( N \\).
+//!
+//! The list of commitments is padded, by repeating its last element, up to the
+//! next power of two.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
+
+/// Get the number of bits, \\( k \\), needed to index into a padded list of
+/// `n` commitments.
+fn number_of_bits(n: usize) -> usize {
+    let mut k: usize = 1;
+
+    while (1usize << k) < n {
+        k += 1;
+    }
+    k
+}
+
+/// Get the `i`th commitment, padding the list by repeating its last element.
+fn padded<'a>(commitments: &'a [RistrettoPoint], i: usize) -> &'a RistrettoPoint {
+    if i < commitments.len() {
+        &commitments[i]
+    } else {
+        &commitments[commitments.len() - 1]
+    }
+}
+
+/// A zero-knowledge proof that one of a list of commitments is a commitment to
+/// zero.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Proof {
+    C_l: Vec<RistrettoPoint>,
+    C_a: Vec<RistrettoPoint>,
+    C_b: Vec<RistrettoPoint>,
+    C_d: Vec<RistrettoPoint>,
+    f: Vec<Scalar>,
+    z_a: Vec<Scalar>,
+    z_b: Vec<Scalar>,
+    z_d: Scalar,
+}
+
+impl Proof {
+    fn commit_publics(
+        transcript: &mut Transcript,
+        A: &RistrettoPoint,
+        B: &RistrettoPoint,
+        commitments: &[RistrettoPoint],
+    ) {
+        transcript.commit_bytes(b"domain-sep", b"one_out_of_many");
+        transcript.commit_bytes(b"A", A.compress().as_bytes());
+        transcript.commit_bytes(b"B", B.compress().as_bytes());
+
+        for D in commitments.iter() {
+            transcript.commit_bytes(b"D", D.compress().as_bytes());
+        }
+    }
+
+    fn challenge(
+        transcript: &mut Transcript,
+        C_l: &[RistrettoPoint],
+        C_a: &[RistrettoPoint],
+        C_b: &[RistrettoPoint],
+        C_d: &[RistrettoPoint],
+    ) -> Scalar {
+        let mut bytes = [0u8; 64];
+
+        for j in 0..C_l.len() {
+            transcript.commit_bytes(b"C_l", C_l[j].compress().as_bytes());
+            transcript.commit_bytes(b"C_a", C_a[j].compress().as_bytes());
+            transcript.commit_bytes(b"C_b", C_b[j].compress().as_bytes());
+            transcript.commit_bytes(b"C_d", C_d[j].compress().as_bytes());
+        }
+        transcript.challenge_bytes(b"chal", &mut bytes);
+
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+
+    /// Create a proof that the `index`th of some `commitments` is of the form
+    /// \\( D\_l = \rho B \\), where \\( \rho \\) is the `opening`.
+    ///
+    /// # Returns
+    ///
+    /// A `Proof`, or `Err(())` if the `commitments` are empty or the `index`
+    /// is out of bounds.
+    pub fn create<R>(
+        transcript: &mut Transcript,
+        A: &RistrettoPoint,
+        B: &RistrettoPoint,
+        commitments: &[RistrettoPoint],
+        index: usize,
+        opening: &Scalar,
+        csprng: &mut R,
+    ) -> Result<Proof, ()>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if index >= commitments.len() {
+            return Err(());
+        }
+
+        Proof::commit_publics(transcript, A, B, commitments);
+
+        let k: usize = number_of_bits(commitments.len());
+        let n: usize = 1 << k;
+
+        let mut l: Vec<Scalar> = Vec::with_capacity(k);
+        let mut r: Vec<Scalar> = Vec::with_capacity(k);
+        let mut a: Vec<Scalar> = Vec::with_capacity(k);
+        let mut s: Vec<Scalar> = Vec::with_capacity(k);
+        let mut t: Vec<Scalar> = Vec::with_capacity(k);
+        let mut rho: Vec<Scalar> = Vec::with_capacity(k);
+
+        let mut C_l: Vec<RistrettoPoint> = Vec::with_capacity(k);
+        let mut C_a: Vec<RistrettoPoint> = Vec::with_capacity(k);
+        let mut C_b: Vec<RistrettoPoint> = Vec::with_capacity(k);
+        let mut C_d: Vec<RistrettoPoint> = Vec::with_capacity(k);
+
+        for j in 0..k {
+            l.push(Scalar::from(((index >> j) & 1) as u64));
+            r.push(Scalar::random(csprng));
+            a.push(Scalar::random(csprng));
+            s.push(Scalar::random(csprng));
+            t.push(Scalar::random(csprng));
+            rho.push(Scalar::random(csprng));
+
+            C_l.push(A * l[j] + B * r[j]);
+            C_a.push(A * a[j] + B * s[j]);
+            C_b.push(A * (l[j] * a[j]) + B * t[j]);
+        }
+
+        // Compute the coefficients of the polynomials
+        //
+        //     p_i(x) = \prod_j f_{j,i_j}(x)
+        //
+        // where f_{j,1}(x) = l_j x + a_j and f_{j,0}(x) = (1 - l_j) x - a_j.
+        let mut coefficients: Vec<Vec<Scalar>> = Vec::with_capacity(n);
+
+        for i in 0..n {
+            let mut p: Vec<Scalar> = Vec::with_capacity(k + 1);
+
+            p.push(Scalar::one());
+
+            for j in 0..k {
+                let (c0, c1): (Scalar, Scalar) = match (i >> j) & 1 {
+                    1 => (a[j], l[j]),
+                    _ => (-a[j], Scalar::one() - l[j]),
+                };
+                let mut q: Vec<Scalar> = Vec::with_capacity(p.len() + 1);
+
+                q.resize(p.len() + 1, Scalar::zero());
+
+                for (d, pd) in p.iter().enumerate() {
+                    q[d] += pd * c0;
+                    q[d + 1] += pd * c1;
+                }
+                p = q;
+            }
+            coefficients.push(p);
+        }
+
+        for j in 0..k {
+            let mut C_dj: RistrettoPoint = B * rho[j];
+
+            for i in 0..n {
+                C_dj += padded(commitments, i) * coefficients[i][j];
+            }
+            C_d.push(C_dj);
+        }
+
+        let x: Scalar = Proof::challenge(transcript, &C_l, &C_a, &C_b, &C_d);
+
+        let mut f: Vec<Scalar> = Vec::with_capacity(k);
+        let mut z_a: Vec<Scalar> = Vec::with_capacity(k);
+        let mut z_b: Vec<Scalar> = Vec::with_capacity(k);
+        let mut z_d: Scalar = Scalar::zero();
+        let mut x_j: Scalar = Scalar::one();
+
+        for j in 0..k {
+            f.push(l[j] * x + a[j]);
+            z_a.push(r[j] * x + s[j]);
+            z_b.push(r[j] * (x - f[j]) + t[j]);
+            z_d -= rho[j] * x_j;
+            x_j *= x;
+        }
+        // x_j is now x^k.
+        z_d += opening * x_j;
+
+        Ok(Proof { C_l, C_a, C_b, C_d, f, z_a, z_b, z_d })
+    }
+
+    /// Verify a proof that one of some `commitments` is a commitment to zero.
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        A: &RistrettoPoint,
+        B: &RistrettoPoint,
+        commitments: &[RistrettoPoint],
+    ) -> Result<(), ()>
+    {
+        if commitments.len() == 0 {
+            return Err(());
+        }
+
+        let k: usize = number_of_bits(commitments.len());
+        let n: usize = 1 << k;
+
+        if self.C_l.len() != k || self.C_a.len() != k || self.C_b.len() != k || self.C_d.len() != k ||
+            self.f.len() != k || self.z_a.len() != k || self.z_b.len() != k
+        {
+            return Err(());
+        }
+
+        Proof::commit_publics(transcript, A, B, commitments);
+
+        let x: Scalar = Proof::challenge(transcript, &self.C_l, &self.C_a, &self.C_b, &self.C_d);
+
+        for j in 0..k {
+            if self.C_l[j] * x + self.C_a[j] != A * self.f[j] + B * self.z_a[j] {
+                return Err(());
+            }
+            if self.C_l[j] * (x - self.f[j]) + self.C_b[j] != B * self.z_b[j] {
+                return Err(());
+            }
+        }
+
+        let mut lhs: RistrettoPoint = RistrettoPoint::identity();
+
+        for i in 0..n {
+            let mut p: Scalar = Scalar::one();
+
+            for j in 0..k {
+                p *= match (i >> j) & 1 {
+                    1 => self.f[j],
+                    _ => x - self.f[j],
+                };
+            }
+            lhs += padded(commitments, i) * p;
+        }
+
+        let mut x_j: Scalar = Scalar::one();
+
+        for j in 0..k {
+            lhs -= self.C_d[j] * x_j;
+            x_j *= x;
+        }
+
+        if lhs == B * self.z_d {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    fn commitments(n: usize, index: usize, opening: &Scalar) -> (RistrettoPoint, RistrettoPoint, Vec<RistrettoPoint>) {
+        let mut rng = thread_rng();
+        let A: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let B: RistrettoPoint = RISTRETTO_BASEPOINT_POINT;
+        let mut D: Vec<RistrettoPoint> = Vec::with_capacity(n);
+
+        for i in 0..n {
+            if i == index {
+                D.push(B * opening);
+            } else {
+                D.push(A * Scalar::random(&mut rng) + B * Scalar::random(&mut rng));
+            }
+        }
+        (A, B, D)
+    }
+
+    #[test]
+    fn one_out_of_many() {
+        let mut rng = thread_rng();
+
+        for &(n, index) in [(1, 0), (2, 1), (5, 3), (8, 0), (13, 12)].iter() {
+            let opening: Scalar = Scalar::random(&mut rng);
+            let (A, B, D) = commitments(n, index, &opening);
+
+            let mut prover_transcript = Transcript::new(b"ONE OUT OF MANY TEST");
+            let mut verifier_transcript = Transcript::new(b"ONE OUT OF MANY TEST");
+
+            let proof = Proof::create(&mut prover_transcript, &A, &B, &D, index, &opening, &mut rng).unwrap();
+
+            assert!(proof.verify(&mut verifier_transcript, &A, &B, &D).is_ok());
+        }
+    }
+
+    #[test]
+    fn one_out_of_many_wrong_opening() {
+        let mut rng = thread_rng();
+        let opening: Scalar = Scalar::random(&mut rng);
+        let (A, B, D) = commitments(6, 2, &opening);
+
+        let mut prover_transcript = Transcript::new(b"ONE OUT OF MANY TEST");
+        let mut verifier_transcript = Transcript::new(b"ONE OUT OF MANY TEST");
+
+        // Claim that a commitment which isn't to zero is.
+        let proof = Proof::create(&mut prover_transcript, &A, &B, &D, 4, &opening, &mut rng).unwrap();
+
+        assert!(proof.verify(&mut verifier_transcript, &A, &B, &D).is_err());
+    }
+}
@@ -41,6 +41,12 @@ impl From<Commitment> for RistrettoPoint {
     }
 }
 
+impl From<RistrettoPoint> for Commitment {
+    fn from(source: RistrettoPoint) -> Commitment {
+        Commitment(source)
+    }
+}
+
 impl<'a, 'b> Mul<&'b Scalar> for &'a Commitment {
     type Output = RistrettoPoint;
 
@@ -65,9 +71,13 @@ impl SubAssign<Commitment> for RistrettoPoint {
 
 impl Commitment {
     pub fn from_bytes(bytes: &[u8]) -> Result<Commitment, CredentialError> {
+        if bytes.len() != 32 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
         let mut tmp: [u8; 32] = [0u8; 32];
 
-        tmp.copy_from_slice(&bytes[0..32]);
+        tmp.copy_from_slice(bytes);
 
         Ok(Commitment(CompressedRistretto(tmp).decompress()?))
     }
@@ -148,4 +158,11 @@ mod test {
 
         assert!(cmt.open(&other_value, &nonce, &basepoint).is_err());
     }
+
+    #[test]
+    fn commitment_wrong_number_of_bytes() {
+        assert!(Commitment::from_bytes(&[]).is_err());
+        assert!(Commitment::from_bytes(&H.to_bytes()[..31]).is_err());
+        assert!(Commitment::from_bytes(&[0u8; 64]).is_err());
+    }
 }
@@ -8,6 +8,7 @@
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 
 pub use amacs::{self};
+pub use amacs::AlgebraicMac;
 pub use elgamal::{self};
 pub use issuer::IssuerParameters;
 pub use parameters::SystemParameters;
@@ -15,6 +15,12 @@
 //! [LdV'17](https://patternsinthevoid.net/hyphae/hyphae.pdf), not those of
 //! [CMZ'13](https://eprint.iacr.org/2013/516.pdf) because the latter was
 //! missing signification details of the construction.
+//!
+//! Unlike the `create_nipk!` output from `zkp-expand`, which reseeds the
+//! prover's transcript RNG from `thread_rng()`, each `Proof::create()` here
+//! takes the caller's `csprng`, so that e.g. `Issuer::issue_deterministic()`
+//! produces the same proof each time.  This must be kept when regenerating
+//! this module.
 
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
@@ -26,7 +32,8 @@ use curve25519_dalek::traits::{MultiscalarMul, VartimeMultiscalarMul};
 
 use merlin::Transcript;
 
-use rand::thread_rng;
+use rand_core::CryptoRng;
+use rand_core::RngCore;
 
 #[cfg(not(feature = "std"))]
 use core::iter;
@@ -84,7 +91,15 @@ pub mod issuance_revealed {
     impl Proof {
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
             transcript.commit_bytes(b"domain-sep", "issuance_revealed".as_bytes());
             transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
             transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
@@ -99,7 +114,7 @@ pub mod issuance_revealed {
                 rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.as_bytes());
             let rng_ctor =
                 rng_ctor.commit_witness_bytes("m1x1".as_bytes(), secrets.m1x1.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
             let rand = Randomnesses {
                 x0: Scalar::random(&mut transcript_rng),
                 x1: Scalar::random(&mut transcript_rng),
@@ -227,7 +242,15 @@ pub mod attributes_blinded {
     impl Proof {
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
             transcript.commit_bytes(b"domain-sep", "attributes_blinded".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
@@ -246,7 +269,7 @@ pub mod attributes_blinded {
             let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
             let rng_ctor =
                 rng_ctor.commit_witness_bytes("nonce".as_bytes(), secrets.nonce.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
             let rand = Randomnesses {
                 d: Scalar::random(&mut transcript_rng),
                 e0: Scalar::random(&mut transcript_rng),
@@ -411,7 +434,15 @@ pub mod issuance_blinded {
     impl Proof {
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
             transcript.commit_bytes(b"domain-sep", "issuance_blinded".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
@@ -445,7 +476,7 @@ pub mod issuance_blinded {
             let rng_ctor = rng_ctor.commit_witness_bytes("s".as_bytes(), secrets.s.as_bytes());
             let rng_ctor = rng_ctor.commit_witness_bytes("b".as_bytes(), secrets.b.as_bytes());
             let rng_ctor = rng_ctor.commit_witness_bytes("t0".as_bytes(), secrets.t0.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
             let rand = Randomnesses {
                 x0_tilde: Scalar::random(&mut transcript_rng),
                 x0: Scalar::random(&mut transcript_rng),
@@ -639,7 +670,15 @@ pub mod valid_credential {
     impl Proof {
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
             transcript.commit_bytes(b"domain-sep", "valid_credential".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
@@ -652,7 +691,7 @@ pub mod valid_credential {
             let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.as_bytes());
             let rng_ctor =
                 rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
             let rand = Randomnesses {
                 m0: Scalar::random(&mut transcript_rng),
                 z0: Scalar::random(&mut transcript_rng),
@@ -767,7 +806,15 @@ pub mod committed_values_equal {
     impl Proof {
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
             transcript.commit_bytes(b"domain-sep", "committed_values_equal".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
@@ -778,7 +825,7 @@ pub mod committed_values_equal {
             let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
             let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.as_bytes());
             let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
             let rand = Randomnesses {
                 m0: Scalar::random(&mut transcript_rng),
                 z0: Scalar::random(&mut transcript_rng),
@@ -845,3 +892,1576 @@ pub mod committed_values_equal {
         }
     }
 }
+
+pub mod attribute_not_dummy {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub a: &'a Scalar,
+        pub b: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub A: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub B: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        A: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        a: Scalar,
+        b: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        a: Scalar,
+        b: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "attribute_not_dummy".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("a".as_bytes(), secrets.a.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("b".as_bytes(), secrets.b.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                a: Scalar::random(&mut transcript_rng),
+                b: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                A: RistrettoPoint::multiscalar_mul(
+                    &[rand.a, rand.b],
+                    &[*(publics.D), *(publics.B)],
+                ),
+            };
+            transcript.commit_bytes("com A".as_bytes(), commitments.A.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                a: &(&challenge * secrets.a) + &rand.a,
+                b: &(&challenge * secrets.b) + &rand.b,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                A: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.a, responses.b]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.D), *(publics.B)]).into_iter().chain(iter::once(publics.A)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "attribute_not_dummy".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("com A".as_bytes(), commitments.A.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+pub mod issuance_revealed_role {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub x0: &'a Scalar,
+        pub x1: &'a Scalar,
+        pub x2: &'a Scalar,
+        pub x0_tilde: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub P: &'a RistrettoPoint,
+        pub Q: &'a RistrettoPoint,
+        pub Cx0: &'a RistrettoPoint,
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub M1: &'a RistrettoPoint,
+        pub M2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Q: RistrettoPoint,
+        Cx0: RistrettoPoint,
+        X1: RistrettoPoint,
+        X2: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        x0: Scalar,
+        x1: Scalar,
+        x2: Scalar,
+        x0_tilde: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        x0: Scalar,
+        x1: Scalar,
+        x2: Scalar,
+        x0_tilde: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "issuance_revealed_role".as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("M1".as_bytes(), publics.M1.compress().as_bytes());
+            transcript.commit_bytes("M2".as_bytes(), publics.M2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("x0".as_bytes(), secrets.x0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x1".as_bytes(), secrets.x1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x2".as_bytes(), secrets.x2.as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                x0: Scalar::random(&mut transcript_rng),
+                x1: Scalar::random(&mut transcript_rng),
+                x2: Scalar::random(&mut transcript_rng),
+                x0_tilde: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Q: RistrettoPoint::multiscalar_mul(
+                    &[rand.x0, rand.x1, rand.x2],
+                    &[*(publics.P), *(publics.M1), *(publics.M2)],
+                ),
+                Cx0: RistrettoPoint::multiscalar_mul(
+                    &[rand.x0, rand.x0_tilde],
+                    &[*(publics.B), *(publics.A)],
+                ),
+                X1: RistrettoPoint::multiscalar_mul(&[rand.x1], &[*(publics.A)]),
+                X2: RistrettoPoint::multiscalar_mul(&[rand.x2], &[*(publics.A)]),
+            };
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com X2".as_bytes(), commitments.X2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                x0: &(&challenge * secrets.x0) + &rand.x0,
+                x1: &(&challenge * secrets.x1) + &rand.x1,
+                x2: &(&challenge * secrets.x2) + &rand.x2,
+                x0_tilde: &(&challenge * secrets.x0_tilde) + &rand.x0_tilde,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Q: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x0, responses.x1, responses.x2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.M1), *(publics.M2)])
+                        .into_iter()
+                        .chain(iter::once(publics.Q)),
+                ),
+                Cx0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x0, responses.x0_tilde]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.Cx0)),
+                ),
+                X1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X1)),
+                ),
+                X2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X2)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "issuance_revealed_role".as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("M1".as_bytes(), publics.M1.compress().as_bytes());
+            transcript.commit_bytes("M2".as_bytes(), publics.M2.compress().as_bytes());
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com X2".as_bytes(), commitments.X2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+pub mod valid_role_credential {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m1: &'a Scalar,
+        pub m2: &'a Scalar,
+        pub z1: &'a Scalar,
+        pub z2: &'a Scalar,
+        pub minus_zQ: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub V: &'a RistrettoPoint,
+        pub Cm1: &'a RistrettoPoint,
+        pub Cm2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm1: RistrettoPoint,
+        Cm2: RistrettoPoint,
+        V: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m1: Scalar,
+        m2: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        minus_zQ: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m1: Scalar,
+        m2: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        minus_zQ: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "valid_role_credential".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m1".as_bytes(), secrets.m1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("m2".as_bytes(), secrets.m2.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z2".as_bytes(), secrets.z2.as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                m1: Scalar::random(&mut transcript_rng),
+                m2: Scalar::random(&mut transcript_rng),
+                z1: Scalar::random(&mut transcript_rng),
+                z2: Scalar::random(&mut transcript_rng),
+                minus_zQ: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m1, rand.z1],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                Cm2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m2, rand.z2],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                V: RistrettoPoint::multiscalar_mul(
+                    &[rand.z1, rand.z2, rand.minus_zQ],
+                    &[*(publics.X1), *(publics.X2), *(publics.A)],
+                ),
+            };
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m1: &(&challenge * secrets.m1) + &rand.m1,
+                m2: &(&challenge * secrets.m2) + &rand.m2,
+                z1: &(&challenge * secrets.z1) + &rand.z1,
+                z2: &(&challenge * secrets.z2) + &rand.z2,
+                minus_zQ: &(&challenge * secrets.minus_zQ) + &rand.minus_zQ,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m1, responses.z1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm1)),
+                ),
+                Cm2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m2, responses.z2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm2)),
+                ),
+                V: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.z1, responses.z2, responses.minus_zQ]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X1), *(publics.X2), *(publics.A)])
+                        .into_iter()
+                        .chain(iter::once(publics.V)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "valid_role_credential".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+pub mod committed_value_encrypted {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m0: &'a Scalar,
+        pub z0: &'a Scalar,
+        pub e: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub Cm0: &'a RistrettoPoint,
+        pub E1: &'a RistrettoPoint,
+        pub E2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm0: RistrettoPoint,
+        E1: RistrettoPoint,
+        E2: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m0: Scalar,
+        z0: Scalar,
+        e: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m0: Scalar,
+        z0: Scalar,
+        e: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "committed_value_encrypted".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("e".as_bytes(), secrets.e.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                m0: Scalar::random(&mut transcript_rng),
+                z0: Scalar::random(&mut transcript_rng),
+                e: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.z0],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                E1: RistrettoPoint::multiscalar_mul(&[rand.e], &[*(publics.B)]),
+                E2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.e],
+                    &[*(publics.B), *(publics.D)],
+                ),
+            };
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com E1".as_bytes(), commitments.E1.compress().as_bytes());
+            transcript.commit_bytes("com E2".as_bytes(), commitments.E2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m0: &(&challenge * secrets.m0) + &rand.m0,
+                z0: &(&challenge * secrets.z0) + &rand.z0,
+                e: &(&challenge * secrets.e) + &rand.e,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.z0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm0)),
+                ),
+                E1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.e]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.E1)),
+                ),
+                E2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.e]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.D)]).into_iter().chain(iter::once(publics.E2)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "committed_value_encrypted".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com E1".as_bytes(), commitments.E1.compress().as_bytes());
+            transcript.commit_bytes("com E2".as_bytes(), commitments.E2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+pub mod encrypted_attribute {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub d: &'a Scalar,
+        pub e: &'a Scalar,
+        pub m: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub E1: &'a RistrettoPoint,
+        pub E2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        D: RistrettoPoint,
+        E1: RistrettoPoint,
+        E2: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        d: Scalar,
+        e: Scalar,
+        m: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        d: Scalar,
+        e: Scalar,
+        m: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "encrypted_attribute".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("d".as_bytes(), secrets.d.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("e".as_bytes(), secrets.e.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("m".as_bytes(), secrets.m.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                d: Scalar::random(&mut transcript_rng),
+                e: Scalar::random(&mut transcript_rng),
+                m: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                D: RistrettoPoint::multiscalar_mul(&[rand.d], &[*(publics.B)]),
+                E1: RistrettoPoint::multiscalar_mul(&[rand.e], &[*(publics.B)]),
+                E2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m, rand.e],
+                    &[*(publics.B), *(publics.D)],
+                ),
+            };
+            transcript.commit_bytes("com D".as_bytes(), commitments.D.compress().as_bytes());
+            transcript.commit_bytes("com E1".as_bytes(), commitments.E1.compress().as_bytes());
+            transcript.commit_bytes("com E2".as_bytes(), commitments.E2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                d: &(&challenge * secrets.d) + &rand.d,
+                e: &(&challenge * secrets.e) + &rand.e,
+                m: &(&challenge * secrets.m) + &rand.m,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                D: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.d]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.D)),
+                ),
+                E1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.e]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.E1)),
+                ),
+                E2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m, responses.e]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.D)]).into_iter().chain(iter::once(publics.E2)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "encrypted_attribute".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            transcript.commit_bytes("com D".as_bytes(), commitments.D.compress().as_bytes());
+            transcript.commit_bytes("com E1".as_bytes(), commitments.E1.compress().as_bytes());
+            transcript.commit_bytes("com E2".as_bytes(), commitments.E2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+pub mod issuance_partially_blinded {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub x0: &'a Scalar,
+        pub x1: &'a Scalar,
+        pub x2: &'a Scalar,
+        pub x0_tilde: &'a Scalar,
+        pub b: &'a Scalar,
+        pub s: &'a Scalar,
+        pub t: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub Cx0: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub T0: &'a RistrettoPoint,
+        pub T1: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub E1: &'a RistrettoPoint,
+        pub E2: &'a RistrettoPoint,
+        pub M1: &'a RistrettoPoint,
+        pub EQ1: &'a RistrettoPoint,
+        pub EQ2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cx0: RistrettoPoint,
+        X1: RistrettoPoint,
+        X2: RistrettoPoint,
+        P: RistrettoPoint,
+        T0: RistrettoPoint,
+        T1: RistrettoPoint,
+        EQ1: RistrettoPoint,
+        EQ2: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        x0: Scalar,
+        x1: Scalar,
+        x2: Scalar,
+        x0_tilde: Scalar,
+        b: Scalar,
+        s: Scalar,
+        t: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        x0: Scalar,
+        x1: Scalar,
+        x2: Scalar,
+        x0_tilde: Scalar,
+        b: Scalar,
+        s: Scalar,
+        t: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "issuance_partially_blinded".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("T0".as_bytes(), publics.T0.compress().as_bytes());
+            transcript.commit_bytes("T1".as_bytes(), publics.T1.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            transcript.commit_bytes("M1".as_bytes(), publics.M1.compress().as_bytes());
+            transcript.commit_bytes("EQ1".as_bytes(), publics.EQ1.compress().as_bytes());
+            transcript.commit_bytes("EQ2".as_bytes(), publics.EQ2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("x0".as_bytes(), secrets.x0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x1".as_bytes(), secrets.x1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x2".as_bytes(), secrets.x2.as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("b".as_bytes(), secrets.b.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("s".as_bytes(), secrets.s.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("t".as_bytes(), secrets.t.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                x0: Scalar::random(&mut transcript_rng),
+                x1: Scalar::random(&mut transcript_rng),
+                x2: Scalar::random(&mut transcript_rng),
+                x0_tilde: Scalar::random(&mut transcript_rng),
+                b: Scalar::random(&mut transcript_rng),
+                s: Scalar::random(&mut transcript_rng),
+                t: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cx0: RistrettoPoint::multiscalar_mul(
+                    &[rand.x0, rand.x0_tilde],
+                    &[*(publics.B), *(publics.A)],
+                ),
+                X1: RistrettoPoint::multiscalar_mul(&[rand.x1], &[*(publics.A)]),
+                X2: RistrettoPoint::multiscalar_mul(&[rand.x2], &[*(publics.A)]),
+                P: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.B)]),
+                T0: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.X2)]),
+                T1: RistrettoPoint::multiscalar_mul(&[rand.t], &[*(publics.A)]),
+                EQ1: RistrettoPoint::multiscalar_mul(
+                    &[rand.s, rand.t],
+                    &[*(publics.B), *(publics.E1)],
+                ),
+                EQ2: RistrettoPoint::multiscalar_mul(
+                    &[rand.s, rand.t, rand.x0, rand.x1],
+                    &[*(publics.D), *(publics.E2), *(publics.P), *(publics.M1)],
+                ),
+            };
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com X2".as_bytes(), commitments.X2.compress().as_bytes());
+            transcript.commit_bytes("com P".as_bytes(), commitments.P.compress().as_bytes());
+            transcript.commit_bytes("com T0".as_bytes(), commitments.T0.compress().as_bytes());
+            transcript.commit_bytes("com T1".as_bytes(), commitments.T1.compress().as_bytes());
+            transcript.commit_bytes("com EQ1".as_bytes(), commitments.EQ1.compress().as_bytes());
+            transcript.commit_bytes("com EQ2".as_bytes(), commitments.EQ2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                x0: &(&challenge * secrets.x0) + &rand.x0,
+                x1: &(&challenge * secrets.x1) + &rand.x1,
+                x2: &(&challenge * secrets.x2) + &rand.x2,
+                x0_tilde: &(&challenge * secrets.x0_tilde) + &rand.x0_tilde,
+                b: &(&challenge * secrets.b) + &rand.b,
+                s: &(&challenge * secrets.s) + &rand.s,
+                t: &(&challenge * secrets.t) + &rand.t,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cx0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x0, responses.x0_tilde]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.Cx0)),
+                ),
+                X1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X1)),
+                ),
+                X2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X2)),
+                ),
+                P: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.P)),
+                ),
+                T0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X2)]).into_iter().chain(iter::once(publics.T0)),
+                ),
+                T1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.t]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.T1)),
+                ),
+                EQ1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.s, responses.t]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.E1)]).into_iter().chain(iter::once(publics.EQ1)),
+                ),
+                EQ2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.s, responses.t, responses.x0, responses.x1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.D), *(publics.E2), *(publics.P), *(publics.M1)])
+                        .into_iter()
+                        .chain(iter::once(publics.EQ2)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "issuance_partially_blinded".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("T0".as_bytes(), publics.T0.compress().as_bytes());
+            transcript.commit_bytes("T1".as_bytes(), publics.T1.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            transcript.commit_bytes("M1".as_bytes(), publics.M1.compress().as_bytes());
+            transcript.commit_bytes("EQ1".as_bytes(), publics.EQ1.compress().as_bytes());
+            transcript.commit_bytes("EQ2".as_bytes(), publics.EQ2.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com X2".as_bytes(), commitments.X2.compress().as_bytes());
+            transcript.commit_bytes("com P".as_bytes(), commitments.P.compress().as_bytes());
+            transcript.commit_bytes("com T0".as_bytes(), commitments.T0.compress().as_bytes());
+            transcript.commit_bytes("com T1".as_bytes(), commitments.T1.compress().as_bytes());
+            transcript.commit_bytes("com EQ1".as_bytes(), commitments.EQ1.compress().as_bytes());
+            transcript.commit_bytes("com EQ2".as_bytes(), commitments.EQ2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+pub mod valid_credential_with_encryption {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m1: &'a Scalar,
+        pub m2: &'a Scalar,
+        pub z1: &'a Scalar,
+        pub z2: &'a Scalar,
+        pub minus_zQ: &'a Scalar,
+        pub k: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub V: &'a RistrettoPoint,
+        pub Cm1: &'a RistrettoPoint,
+        pub Cm2: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub K1: &'a RistrettoPoint,
+        pub K2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm1: RistrettoPoint,
+        Cm2: RistrettoPoint,
+        V: RistrettoPoint,
+        K1: RistrettoPoint,
+        K2: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m1: Scalar,
+        m2: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        minus_zQ: Scalar,
+        k: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m1: Scalar,
+        m2: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        minus_zQ: Scalar,
+        k: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "valid_credential_with_encryption".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("K1".as_bytes(), publics.K1.compress().as_bytes());
+            transcript.commit_bytes("K2".as_bytes(), publics.K2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m1".as_bytes(), secrets.m1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("m2".as_bytes(), secrets.m2.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z2".as_bytes(), secrets.z2.as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("k".as_bytes(), secrets.k.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                m1: Scalar::random(&mut transcript_rng),
+                m2: Scalar::random(&mut transcript_rng),
+                z1: Scalar::random(&mut transcript_rng),
+                z2: Scalar::random(&mut transcript_rng),
+                minus_zQ: Scalar::random(&mut transcript_rng),
+                k: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m1, rand.z1],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                Cm2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m2, rand.z2],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                V: RistrettoPoint::multiscalar_mul(
+                    &[rand.z1, rand.z2, rand.minus_zQ],
+                    &[*(publics.X1), *(publics.X2), *(publics.A)],
+                ),
+                K1: RistrettoPoint::multiscalar_mul(&[rand.k], &[*(publics.B)]),
+                K2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m2, rand.k],
+                    &[*(publics.B), *(publics.D)],
+                ),
+            };
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            transcript.commit_bytes("com K1".as_bytes(), commitments.K1.compress().as_bytes());
+            transcript.commit_bytes("com K2".as_bytes(), commitments.K2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m1: &(&challenge * secrets.m1) + &rand.m1,
+                m2: &(&challenge * secrets.m2) + &rand.m2,
+                z1: &(&challenge * secrets.z1) + &rand.z1,
+                z2: &(&challenge * secrets.z2) + &rand.z2,
+                minus_zQ: &(&challenge * secrets.minus_zQ) + &rand.minus_zQ,
+                k: &(&challenge * secrets.k) + &rand.k,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m1, responses.z1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm1)),
+                ),
+                Cm2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m2, responses.z2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm2)),
+                ),
+                V: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.z1, responses.z2, responses.minus_zQ]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X1), *(publics.X2), *(publics.A)])
+                        .into_iter()
+                        .chain(iter::once(publics.V)),
+                ),
+                K1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.k]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.K1)),
+                ),
+                K2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m2, responses.k]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.D)]).into_iter().chain(iter::once(publics.K2)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "valid_credential_with_encryption".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("K1".as_bytes(), publics.K1.compress().as_bytes());
+            transcript.commit_bytes("K2".as_bytes(), publics.K2.compress().as_bytes());
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            transcript.commit_bytes("com K1".as_bytes(), commitments.K1.compress().as_bytes());
+            transcript.commit_bytes("com K2".as_bytes(), commitments.K2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+pub mod valid_credential_renewal {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m0: &'a Scalar,
+        pub z0: &'a Scalar,
+        pub minus_zQ: &'a Scalar,
+        pub d: &'a Scalar,
+        pub e: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub X0: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub V: &'a RistrettoPoint,
+        pub Cm0: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub E1: &'a RistrettoPoint,
+        pub E2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm0: RistrettoPoint,
+        V: RistrettoPoint,
+        D: RistrettoPoint,
+        E1: RistrettoPoint,
+        E2: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m0: Scalar,
+        z0: Scalar,
+        minus_zQ: Scalar,
+        d: Scalar,
+        e: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m0: Scalar,
+        z0: Scalar,
+        minus_zQ: Scalar,
+        d: Scalar,
+        e: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "valid_credential_renewal".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X0".as_bytes(), publics.X0.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("d".as_bytes(), secrets.d.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("e".as_bytes(), secrets.e.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                m0: Scalar::random(&mut transcript_rng),
+                z0: Scalar::random(&mut transcript_rng),
+                minus_zQ: Scalar::random(&mut transcript_rng),
+                d: Scalar::random(&mut transcript_rng),
+                e: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.z0],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                V: RistrettoPoint::multiscalar_mul(
+                    &[rand.z0, rand.minus_zQ],
+                    &[*(publics.X0), *(publics.A)],
+                ),
+                D: RistrettoPoint::multiscalar_mul(&[rand.d], &[*(publics.B)]),
+                E1: RistrettoPoint::multiscalar_mul(&[rand.e], &[*(publics.B)]),
+                E2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.e],
+                    &[*(publics.B), *(publics.D)],
+                ),
+            };
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            transcript.commit_bytes("com D".as_bytes(), commitments.D.compress().as_bytes());
+            transcript.commit_bytes("com E1".as_bytes(), commitments.E1.compress().as_bytes());
+            transcript.commit_bytes("com E2".as_bytes(), commitments.E2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m0: &(&challenge * secrets.m0) + &rand.m0,
+                z0: &(&challenge * secrets.z0) + &rand.z0,
+                minus_zQ: &(&challenge * secrets.minus_zQ) + &rand.minus_zQ,
+                d: &(&challenge * secrets.d) + &rand.d,
+                e: &(&challenge * secrets.e) + &rand.e,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.z0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm0)),
+                ),
+                V: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.z0, responses.minus_zQ]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X0), *(publics.A)]).into_iter().chain(iter::once(publics.V)),
+                ),
+                D: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.d]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.D)),
+                ),
+                E1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.e]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.E1)),
+                ),
+                E2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.e]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.D)]).into_iter().chain(iter::once(publics.E2)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "valid_credential_renewal".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X0".as_bytes(), publics.X0.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            transcript.commit_bytes("com D".as_bytes(), commitments.D.compress().as_bytes());
+            transcript.commit_bytes("com E1".as_bytes(), commitments.E1.compress().as_bytes());
+            transcript.commit_bytes("com E2".as_bytes(), commitments.E2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+pub mod issuance_blinded_renewal {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub x0: &'a Scalar,
+        pub x1: &'a Scalar,
+        pub x0_tilde: &'a Scalar,
+        pub b: &'a Scalar,
+        pub s: &'a Scalar,
+        pub t: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub Cx0: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub T0: &'a RistrettoPoint,
+        pub T1: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub E1: &'a RistrettoPoint,
+        pub E2: &'a RistrettoPoint,
+        pub EQ1: &'a RistrettoPoint,
+        pub EQ2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cx0: RistrettoPoint,
+        X1: RistrettoPoint,
+        P: RistrettoPoint,
+        T0: RistrettoPoint,
+        T1: RistrettoPoint,
+        EQ1: RistrettoPoint,
+        EQ2: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        x0: Scalar,
+        x1: Scalar,
+        x0_tilde: Scalar,
+        b: Scalar,
+        s: Scalar,
+        t: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        x0: Scalar,
+        x1: Scalar,
+        x0_tilde: Scalar,
+        b: Scalar,
+        s: Scalar,
+        t: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<R>(
+            transcript: &mut Transcript,
+            publics: Publics,
+            secrets: Secrets,
+            csprng: &mut R,
+        ) -> Proof
+        where
+            R: RngCore + CryptoRng,
+        {
+            transcript.commit_bytes(b"domain-sep", "issuance_blinded_renewal".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("T0".as_bytes(), publics.T0.compress().as_bytes());
+            transcript.commit_bytes("T1".as_bytes(), publics.T1.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            transcript.commit_bytes("EQ1".as_bytes(), publics.EQ1.compress().as_bytes());
+            transcript.commit_bytes("EQ2".as_bytes(), publics.EQ2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("x0".as_bytes(), secrets.x0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x1".as_bytes(), secrets.x1.as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("b".as_bytes(), secrets.b.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("s".as_bytes(), secrets.s.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("t".as_bytes(), secrets.t.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(csprng);
+            let rand = Randomnesses {
+                x0: Scalar::random(&mut transcript_rng),
+                x1: Scalar::random(&mut transcript_rng),
+                x0_tilde: Scalar::random(&mut transcript_rng),
+                b: Scalar::random(&mut transcript_rng),
+                s: Scalar::random(&mut transcript_rng),
+                t: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cx0: RistrettoPoint::multiscalar_mul(
+                    &[rand.x0, rand.x0_tilde],
+                    &[*(publics.B), *(publics.A)],
+                ),
+                X1: RistrettoPoint::multiscalar_mul(&[rand.x1], &[*(publics.A)]),
+                P: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.B)]),
+                T0: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.X1)]),
+                T1: RistrettoPoint::multiscalar_mul(&[rand.t], &[*(publics.A)]),
+                EQ1: RistrettoPoint::multiscalar_mul(
+                    &[rand.s, rand.t],
+                    &[*(publics.B), *(publics.E1)],
+                ),
+                EQ2: RistrettoPoint::multiscalar_mul(
+                    &[rand.s, rand.t, rand.x0],
+                    &[*(publics.D), *(publics.E2), *(publics.P)],
+                ),
+            };
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com P".as_bytes(), commitments.P.compress().as_bytes());
+            transcript.commit_bytes("com T0".as_bytes(), commitments.T0.compress().as_bytes());
+            transcript.commit_bytes("com T1".as_bytes(), commitments.T1.compress().as_bytes());
+            transcript.commit_bytes("com EQ1".as_bytes(), commitments.EQ1.compress().as_bytes());
+            transcript.commit_bytes("com EQ2".as_bytes(), commitments.EQ2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                x0: &(&challenge * secrets.x0) + &rand.x0,
+                x1: &(&challenge * secrets.x1) + &rand.x1,
+                x0_tilde: &(&challenge * secrets.x0_tilde) + &rand.x0_tilde,
+                b: &(&challenge * secrets.b) + &rand.b,
+                s: &(&challenge * secrets.s) + &rand.s,
+                t: &(&challenge * secrets.t) + &rand.t,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cx0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x0, responses.x0_tilde]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.Cx0)),
+                ),
+                X1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X1)),
+                ),
+                P: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.P)),
+                ),
+                T0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X1)]).into_iter().chain(iter::once(publics.T0)),
+                ),
+                T1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.t]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.T1)),
+                ),
+                EQ1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.s, responses.t]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.E1)]).into_iter().chain(iter::once(publics.EQ1)),
+                ),
+                EQ2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.s, responses.t, responses.x0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.D), *(publics.E2), *(publics.P)])
+                        .into_iter()
+                        .chain(iter::once(publics.EQ2)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "issuance_blinded_renewal".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("T0".as_bytes(), publics.T0.compress().as_bytes());
+            transcript.commit_bytes("T1".as_bytes(), publics.T1.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("E2".as_bytes(), publics.E2.compress().as_bytes());
+            transcript.commit_bytes("EQ1".as_bytes(), publics.EQ1.compress().as_bytes());
+            transcript.commit_bytes("EQ2".as_bytes(), publics.EQ2.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com P".as_bytes(), commitments.P.compress().as_bytes());
+            transcript.commit_bytes("com T0".as_bytes(), commitments.T0.compress().as_bytes());
+            transcript.commit_bytes("com T1".as_bytes(), commitments.T1.compress().as_bytes());
+            transcript.commit_bytes("com EQ1".as_bytes(), commitments.EQ1.compress().as_bytes());
+            transcript.commit_bytes("com EQ2".as_bytes(), commitments.EQ2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
@@ -119,3 +119,169 @@ create_nipk!(committed_values_equal,
              Cm0 = (P * m0 + A * z0),
              Cm1 = (A * m0 + B * z1)
 );
+
+/// A NIPK showing that the value committed to in `D` is non-zero, i.e. that
+/// the user knows \\( a = m^{-1} \\) and \\( b = -z m^{-1} \\) for a
+/// commitment \\( D = m A + z B \\).
+///
+/// This is used for issuer-hiding presentations, in order to show that a
+/// committed attribute minus the dummy attribute is non-zero.
+create_nipk!(attribute_not_dummy,
+             (a, b),
+             (A, D, B)
+             :
+             A = (D * a + B * b)
+);
+
+/// A NIPK showing correct issuance of a credential with two revealed
+/// attributes, such as a Signal account identifier and a group role.
+///
+/// # Inputs
+///
+/// Secrets:
+///
+/// * `x0`, `x1`, `x2` are the issuer's secret key.
+/// * `x0_tilde` is the blinding factor for the commitment to `x0`.
+///
+/// Publics:
+///
+/// * `P` and `Q` are the nonce and MAC of the tag.
+/// * `Cx0` is the commitment to the issuer's secret key, `x0`.
+/// * `B` and `A` are the distinguished basepoints.
+/// * `X1` and `X2` are the issuer's public parameters.
+/// * `M1` and `M2` are the revealed attributes multiplied by the nonce, `P`.
+create_nipk!(issuance_revealed_role,
+             (x0, x1, x2, x0_tilde),
+             (P, Q, Cx0, B, A, X1, X2, M1, M2)
+             :
+             Q = (P * x0 + M1 * x1 + M2 * x2),
+             Cx0 = (B * x0 + A * x0_tilde),
+             X1 = (A * x1),
+             X2 = (A * x2)
+);
+
+/// A NIPK showing that a presented credential with two blinded attributes,
+/// `Cm1` and `Cm2`, is valid.  This is the same as `valid_credential`, with
+/// an additional attribute.
+create_nipk!(valid_role_credential,
+             (m1, m2, z1, z2, minus_zQ),
+             (B, A, X1, X2, P, V, Cm1, Cm2)
+             :
+             Cm1 = (P * m1 + A * z1),
+             Cm2 = (P * m2 + A * z2),
+             V = (X1 * z1 + X2 * z2 + A * minus_zQ)
+);
+
+/// Prove that the committed attribute in a credential, `Cm0`, is the same
+/// value as is encrypted in an elGamal encryption, `(E1, E2)`, to the public
+/// key `D`.
+create_nipk!(committed_value_encrypted,
+             (m0, z0, e),
+             (B, A, D, P, Cm0, E1, E2)
+             :
+             Cm0 = (P * m0 + A * z0),
+             E1 = (B * e),
+             E2 = (B * m0 + D * e)
+);
+
+/// A NIPK showing that `(E1, E2)` is an elGamal encryption of `m * B` to the
+/// public key `D`, and that the prover knows the corresponding secret key `d`.
+///
+/// This is used by a user to blind an attribute before requesting a
+/// credential on it.
+create_nipk!(encrypted_attribute,
+             (d, e, m),
+             (B, D, E1, E2)
+             :
+             D = (B * d),
+             E1 = (B * e),
+             E2 = (B * m + D * e)
+);
+
+/// A NIPK showing correct issuance of a credential on one revealed attribute,
+/// `m1`, and one blinded attribute, `m2`, which is given as an elGamal
+/// encryption `(E1, E2)` to the user's public key `D`.
+///
+/// # Inputs
+///
+/// Secrets:
+///
+/// * `x0`, `x1`, `x2` are the issuer's secret key.
+/// * `x0_tilde` is the blinding factor for the commitment to `x0`.
+/// * `b` is the discrete log of the tag's nonce, `P`.
+/// * `s` is the nonce for the encryption of the tag's MAC.
+/// * `t` is `x2 * b`.
+///
+/// Publics:
+///
+/// * `B` and `A` are the distinguished basepoints.
+/// * `Cx0` is the commitment to the issuer's secret key, `x0`.
+/// * `X1` and `X2` are the issuer's public parameters.
+/// * `P` is the tag's nonce.
+/// * `T0` and `T1` are the same point, \\( t A \\).
+/// * `M1` is the revealed attribute multiplied by the nonce, `P`.
+/// * `EQ1` and `EQ2` are the encryption of the tag's MAC to `D`.
+//
+// XXX T0 and T1 should be the same point, but the macro won't let us write two
+//     statements with the same left-hand side.
+create_nipk!(issuance_partially_blinded,
+             (x0, x1, x2, x0_tilde, b, s, t),
+             (B, A, Cx0, X1, X2, P, T0, T1, D, E1, E2, M1, EQ1, EQ2)
+             :
+             Cx0 = (B * x0 + A * x0_tilde),
+             X1 = (A * x1),
+             X2 = (A * x2),
+             P = (B * b),
+             T0 = (X2 * b),
+             T1 = (A * t),
+             EQ1 = (B * s + E1 * t),
+             EQ2 = (D * s + E2 * t + P * x0 + M1 * x1)
+);
+
+/// A NIPK showing that a presented credential with two blinded attributes,
+/// `Cm1` and `Cm2`, is valid, and that `(K1, K2)` is an elGamal encryption of
+/// the second attribute, `m2 * B`, to the public key `D`.
+create_nipk!(valid_credential_with_encryption,
+             (m1, m2, z1, z2, minus_zQ, k),
+             (B, A, X1, X2, P, V, Cm1, Cm2, D, K1, K2)
+             :
+             Cm1 = (P * m1 + A * z1),
+             Cm2 = (P * m2 + A * z2),
+             V = (X1 * z1 + X2 * z2 + A * minus_zQ),
+             K1 = (B * k),
+             K2 = (B * m2 + D * k)
+);
+
+/// A NIPK showing that a presented credential on one hidden attribute, `Cm0`,
+/// is valid, and that `(E1, E2)` is an elGamal encryption of the same
+/// attribute, `m0 * B`, to the user's own public key `D`, so that the issuer
+/// may blindly issue a fresh credential on it.
+create_nipk!(valid_credential_renewal,
+             (m0, z0, minus_zQ, d, e),
+             (B, A, X0, P, V, Cm0, D, E1, E2)
+             :
+             Cm0 = (P * m0 + A * z0),
+             V = (X0 * z0 + A * minus_zQ),
+             D = (B * d),
+             E1 = (B * e),
+             E2 = (B * m0 + D * e)
+);
+
+/// A NIPK showing correct issuance of a credential on one blinded attribute,
+/// `m0`, which is given as an elGamal encryption `(E1, E2)` to the user's
+/// public key `D`.
+///
+/// As for `issuance_partially_blinded`, `t` is `x1 * b`, and `T0` and `T1`
+/// are the same point, \\( t A \\).
+create_nipk!(issuance_blinded_renewal,
+             (x0, x1, x0_tilde, b, s, t),
+             (B, A, Cx0, X1, P, T0, T1, D, E1, E2, EQ1, EQ2)
+             :
+             Cx0 = (B * x0 + A * x0_tilde),
+             X1 = (A * x1),
+             P = (B * b),
+             T0 = (X1 * b),
+             T1 = (A * t),
+             EQ1 = (B * s + E1 * t),
+             EQ2 = (D * s + E2 * t + P * x0)
+);
@@ -0,0 +1,362 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Credential schemas.
+//!
+//! A `CredentialSchema` describes, in order, each attribute in a credential:
+//! its name, how a value is encoded into a `Scalar`, and whether it may be
+//! revealed or must be hidden at issuance and at presentation.
+//!
+//! An `Issuer` or `User` which has been given a schema validates every
+//! issuance, presentation, and verification against it, and binds the
+//! schema's `fingerprint()` into the proof transcripts, so that a credential
+//! issued under one schema cannot be presented under another.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::string::String;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::string::String;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::{deserialize, serialize};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use credential::CredentialPresentation;
+use credential::RevealedAttribute;
+use errors::CredentialError;
+
+/// The size of a schema's `fingerprint()`.
+pub const SIZEOF_FINGERPRINT: usize = 32;
+
+/// How a value is encoded into an attribute.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub enum AttributeEncoding {
+    /// The attribute is an arbitrary `Scalar`, used as is.
+    RawScalar,
+    /// The attribute is a string, hashed to a `Scalar` together with a
+    /// domain separator unique to this field.
+    HashedString { domain_separator: String },
+    /// The attribute is an unsigned 64-bit integer.
+    Integer,
+    /// The attribute is a `RistrettoPoint`.  These are only supported by the
+    /// credentials in `amacs_points`.
+    Point,
+}
+
+/// Whether an attribute may be revealed, hidden, or either.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub enum Visibility {
+    Revealed,
+    Hidden,
+    Either,
+}
+
+impl Visibility {
+    /// Whether an attribute with this `Visibility` may be revealed.
+    pub fn allows_revealed(&self) -> bool {
+        *self != Visibility::Hidden
+    }
+
+    /// Whether an attribute with this `Visibility` may be hidden.
+    pub fn allows_hidden(&self) -> bool {
+        *self != Visibility::Revealed
+    }
+}
+
+/// A value to be encoded into an attribute.
+#[derive(Clone, Copy, Debug)]
+pub enum AttributeValue<'a> {
+    Scalar(Scalar),
+    String(&'a str),
+    Integer(u64),
+    Point(RistrettoPoint),
+}
+
+/// A description of a single attribute in a `CredentialSchema`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct AttributeDescriptor {
+    /// The attribute's name.
+    pub name: String,
+    /// How values are encoded into this attribute.
+    pub encoding: AttributeEncoding,
+    /// The allowed visibility of this attribute when it is issued.
+    pub issuance: Visibility,
+    /// The allowed visibility of this attribute when it is presented.
+    pub presentation: Visibility,
+}
+
+impl AttributeDescriptor {
+    /// Encode a `value` into a scalar attribute.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CredentialError::BadAttribute` if the `value` does not match
+    /// this attribute's `encoding`, or if the encoding is
+    /// `AttributeEncoding::Point`.
+    pub fn encode(&self, value: &AttributeValue) -> Result<RevealedAttribute, CredentialError> {
+        match (&self.encoding, value) {
+            (AttributeEncoding::RawScalar, AttributeValue::Scalar(x)) => Ok(*x),
+            (AttributeEncoding::HashedString { domain_separator }, AttributeValue::String(x)) => {
+                let mut h = Sha512::default();
+
+                h.input(b"aeonflux schema hashed string");
+                h.input(&(domain_separator.len() as u64).to_le_bytes());
+                h.input(domain_separator.as_bytes());
+                h.input(x.as_bytes());
+
+                Ok(Scalar::from_hash(h))
+            },
+            (AttributeEncoding::Integer, AttributeValue::Integer(x)) => Ok(Scalar::from(*x)),
+            _ => Err(CredentialError::BadAttribute),
+        }
+    }
+
+    /// Check that an encoded scalar `attribute` is well-formed for this
+    /// attribute's `encoding`.
+    fn check(&self, attribute: &RevealedAttribute) -> Result<(), CredentialError> {
+        match self.encoding {
+            AttributeEncoding::Point => Err(CredentialError::BadAttribute),
+            AttributeEncoding::Integer if attribute.as_bytes()[8..] != [0u8; 24] => {
+                Err(CredentialError::BadAttribute)
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A description of every attribute in a credential, in order.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct CredentialSchema {
+    /// The schema's name.
+    pub name: String,
+    /// The credential's attributes, in order.
+    pub attributes: Vec<AttributeDescriptor>,
+}
+
+impl CredentialSchema {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CredentialSchema, CredentialError> {
+        match deserialize(bytes) {
+            Ok(x)  => Ok(x),
+            Err(_) => Err(CredentialError::MissingData),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match serialize(self) {
+            Ok(x)   => x,
+            Err(_x) => panic!(),  // XXX clean this up
+        }
+    }
+
+    /// The number of attributes described by this schema.
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    /// Get a stable, 32-byte fingerprint of this schema.
+    pub fn fingerprint(&self) -> [u8; SIZEOF_FINGERPRINT] {
+        let mut h = Sha512::default();
+        let mut fingerprint: [u8; SIZEOF_FINGERPRINT] = [0u8; SIZEOF_FINGERPRINT];
+
+        h.input(b"aeonflux credential schema");
+        h.input(&self.to_bytes());
+
+        fingerprint.copy_from_slice(&h.result()[..SIZEOF_FINGERPRINT]);
+        fingerprint
+    }
+
+    /// Split this schema's `fingerprint()` off the end of some `bytes`, as
+    /// appended when serialising an `Issuer` or `User` which is bound to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CredentialError::BadAttribute` if the `bytes` don't end with
+    /// our fingerprint.
+    pub(crate) fn strip_fingerprint<'a>(&self, bytes: &'a [u8]) -> Result<&'a [u8], CredentialError> {
+        if bytes.len() < SIZEOF_FINGERPRINT {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+        let (rest, fingerprint) = bytes.split_at(bytes.len() - SIZEOF_FINGERPRINT);
+
+        if fingerprint != &self.fingerprint()[..] {
+            return Err(CredentialError::BadAttribute);
+        }
+        Ok(rest)
+    }
+
+    /// Bind this schema into a proof `transcript`.
+    pub(crate) fn commit(&self, transcript: &mut Transcript) {
+        transcript.commit_bytes(b"schema", &self.fingerprint());
+    }
+
+    /// Encode some `values`, one for each attribute, into the attributes for
+    /// a `CredentialRequest`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` if there isn't exactly
+    ///   one value per attribute.
+    /// * `CredentialError::BadAttribute` if any value doesn't match its
+    ///   attribute's encoding.
+    pub fn encode(&self, values: &[AttributeValue]) -> Result<Vec<RevealedAttribute>, CredentialError> {
+        if values.len() != self.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        self.attributes.iter().zip(values.iter()).map(|(a, v)| a.encode(v)).collect()
+    }
+
+    /// Check that some `attributes`, which are revealed to the issuer, may be
+    /// issued under this schema.
+    pub fn validate_issuance(&self, attributes: &[RevealedAttribute]) -> Result<(), CredentialError> {
+        if attributes.len() != self.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        for (descriptor, attribute) in self.attributes.iter().zip(attributes.iter()) {
+            if !descriptor.issuance.allows_revealed() {
+                return Err(CredentialError::BadAttribute);
+            }
+            descriptor.check(attribute)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that a presentation revealing the first `revealed` attributes
+    /// and hiding the next `hidden` attributes is allowed by this schema.
+    pub fn validate_presentation(&self, revealed: usize, hidden: usize) -> Result<(), CredentialError> {
+        if revealed + hidden != self.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        for (i, descriptor) in self.attributes.iter().enumerate() {
+            let allowed: bool = match i < revealed {
+                true  => descriptor.presentation.allows_revealed(),
+                false => descriptor.presentation.allows_hidden(),
+            };
+
+            if !allowed {
+                return Err(CredentialError::BadAttribute);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that a `presentation`, as received by a verifier, is allowed by
+    /// this schema, including that its revealed attributes are well-formed.
+    pub fn validate_credential_presentation(
+        &self,
+        presentation: &CredentialPresentation,
+    ) -> Result<(), CredentialError>
+    {
+        self.validate_presented_attributes(&presentation.attributes_revealed,
+                                           presentation.attributes_blinded.len())
+    }
+
+    /// Check that a presentation revealing some `attributes` and hiding the
+    /// next `hidden` attributes is allowed by this schema, including that the
+    /// revealed attributes are well-formed.
+    pub fn validate_presented_attributes(
+        &self,
+        attributes: &[RevealedAttribute],
+        hidden: usize,
+    ) -> Result<(), CredentialError>
+    {
+        self.validate_presentation(attributes.len(), hidden)?;
+
+        for (descriptor, attribute) in self.attributes.iter().zip(attributes.iter()) {
+            descriptor.check(attribute)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn schema() -> CredentialSchema {
+        CredentialSchema {
+            name: String::from("test"),
+            attributes: vec![
+                AttributeDescriptor {
+                    name: String::from("phone number"),
+                    encoding: AttributeEncoding::HashedString { domain_separator: String::from("phone") },
+                    issuance: Visibility::Revealed,
+                    presentation: Visibility::Hidden,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn schema_serialize_deserialize() {
+        let schema = schema();
+        let deserialized = CredentialSchema::from_bytes(&schema.to_bytes()).unwrap();
+
+        assert!(schema == deserialized);
+        assert!(schema.fingerprint() == deserialized.fingerprint());
+    }
+
+    #[test]
+    fn schema_fingerprint_changes() {
+        let schema = schema();
+        let mut other = schema.clone();
+
+        other.attributes[0].presentation = Visibility::Either;
+
+        assert!(schema.fingerprint() != other.fingerprint());
+    }
+
+    #[test]
+    fn hashed_strings_are_domain_separated() {
+        let schema = schema();
+        let mut other = schema.clone();
+
+        other.attributes[0].encoding = AttributeEncoding::HashedString { domain_separator: String::from("email") };
+
+        let a = schema.encode(&[AttributeValue::String("+14155550101")]).unwrap();
+        let b = other.encode(&[AttributeValue::String("+14155550101")]).unwrap();
+
+        assert!(a != b);
+        assert!(schema.encode(&[AttributeValue::Integer(7)]).is_err());
+    }
+
+    #[test]
+    fn schema_visibility() {
+        let schema = schema();
+
+        assert!(schema.validate_presentation(0, 1).is_ok());
+        assert!(schema.validate_presentation(1, 0).is_err());
+        assert!(schema.validate_presentation(0, 2).is_err());
+    }
+
+    #[test]
+    fn integer_attributes_must_be_small() {
+        let mut schema = schema();
+
+        schema.attributes[0].encoding = AttributeEncoding::Integer;
+
+        assert!(schema.validate_issuance(&[Scalar::from(42u64)]).is_ok());
+        assert!(schema.validate_issuance(&[-Scalar::one()]).is_err());
+    }
+}
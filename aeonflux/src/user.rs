@@ -15,9 +15,6 @@ use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
-use curve25519_dalek::ristretto::RistrettoPoint;
-use curve25519_dalek::traits::Identity;
-
 use merlin::Transcript;
 
 use rand_core::RngCore;
@@ -26,54 +23,80 @@ use rand_core::CryptoRng;
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
-use amacs;
+use amacs::AlgebraicMac;
+use amacs::MacGgm;
+use amacs::SIZEOF_TAG;
 use credential::Credential;
 use credential::CredentialIssuance;
 use credential::CredentialPresentation;
 use credential::CredentialRequest;
 use credential::RevealedAttribute;
+use credential::RevealedPointAttribute;
+use credential::SIZEOF_CREDENTIAL;
 use elgamal;
 use errors::CredentialError;
 use issuer::IssuerParameters;
-use nonces::Ephemeral;
+use issuer_hiding::IssuerHidingPresentation;
+use issuer_hiding::IssuerSetMember;
 use nonces::Nonces;
+use parameters::NUMBER_OF_ATTRIBUTES;
 use parameters::SystemParameters;
-use pedersen;
-use proofs::issuance_revealed;
-use proofs::valid_credential;
+use parameters::SIZEOF_SYSTEM_PARAMETERS;
+use schema::CredentialSchema;
+
+/// The size of a serialised `User`, which isn't bound to a schema, of an
+/// issuer of credentials on `NUMBER_OF_ATTRIBUTES` attributes.  A `User` of an
+/// issuer of credentials on more attributes is 64 bytes longer for each.
+pub const SIZEOF_USER: usize = SIZEOF_SYSTEM_PARAMETERS + 32 * NUMBER_OF_ATTRIBUTES +
+    elgamal::SIZEOF_KEYPAIR + SIZEOF_CREDENTIAL;
 
 /// DOCDOC
 #[derive(Debug, Eq, PartialEq)]
-pub struct User {
+pub struct User<M: AlgebraicMac = MacGgm> {
     pub system_parameters: SystemParameters,
-    pub issuer_parameters: IssuerParameters,
+    pub issuer_parameters: M::PublicKey,
     pub key: Option<elgamal::Keypair>,
-    pub credential: Option<Credential>,
+    pub credential: Option<Credential<M>>,
+    /// The schema, if any, which our credential must follow.
+    pub schema: Option<CredentialSchema>,
 }
 
 impl User {
+    /// Deserialise a `User` which isn't bound to a `CredentialSchema`.
+    ///
+    /// A `User` which is bound to one is serialised with the schema's
+    /// fingerprint appended, and must instead be deserialised with
+    /// `User::from_bytes_with_schema()`.
     pub fn from_bytes(bytes: &[u8]) -> Result<User, CredentialError> {
-        if bytes.len() != 256 {
+        let length: usize = bytes.len();
+
+        // The issuer parameters and credential each grow by 32 bytes with
+        // every attribute, and there must be at least one.
+        if length < SIZEOF_USER || (length - SIZEOF_USER) % 64 != 0 {
             return Err(CredentialError::MissingData);
         }
 
-        let system_parameters = SystemParameters::from_bytes(&bytes[00..64])?;
-        let issuer_parameters = IssuerParameters::from_bytes(&bytes[64..96])?;
+        let n: usize = (length - SIZEOF_USER) / 64 + 1;
+        let key_offset: usize = SIZEOF_SYSTEM_PARAMETERS + 32 * n;
+        let credential_offset: usize = key_offset + elgamal::SIZEOF_KEYPAIR;
+
+        let system_parameters = SystemParameters::from_bytes(&bytes[..SIZEOF_SYSTEM_PARAMETERS])?;
+        let issuer_parameters = IssuerParameters::from_bytes(&bytes[SIZEOF_SYSTEM_PARAMETERS..key_offset])?;
 
         let key: Option<elgamal::Keypair>;
 
-        if &bytes[96..160] == &[0u8; 64][..] {
+        if bytes[key_offset..credential_offset].iter().all(|x| *x == 0) {
             key = None;
         } else {
-            key = Some(elgamal::Keypair::from_bytes(&bytes[96..160])?);
+            key = Some(elgamal::Keypair::from_bytes(&bytes[key_offset..credential_offset])?);
         }
 
         let credential: Option<Credential>;
 
-        if &bytes[160..256] == &[0u8; 96][..] {
+        if bytes[credential_offset..].iter().all(|x| *x == 0) {
             credential = None;
         } else {
-            credential = Some(Credential::from_bytes(&bytes[160..])?);
+            credential = Some(Credential::from_bytes(&bytes[credential_offset..])?);
         }
 
         Ok(User {
@@ -81,9 +104,23 @@ impl User {
             issuer_parameters,
             key,
             credential,
+            schema: None,
         })
     }
 
+    /// Deserialise a `User` which is bound to a `schema`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CredentialError::BadAttribute` if the `User` wasn't
+    /// serialised with this `schema`, and otherwise as for
+    /// `User::from_bytes()`.
+    pub fn from_bytes_with_schema(bytes: &[u8], schema: CredentialSchema) -> Result<User, CredentialError> {
+        Ok(User::from_bytes(schema.strip_fingerprint(bytes)?)?.with_schema(schema))
+    }
+
+    /// Serialise this `User`, followed by the fingerprint of its schema, if
+    /// it has one.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut v: Vec<u8> = Vec::new();
 
@@ -91,37 +128,54 @@ impl User {
         v.extend(self.issuer_parameters.to_bytes());
 
         match self.key {
-            None        => v.extend([0u8; 64].iter()),
+            None        => v.extend([0u8; elgamal::SIZEOF_KEYPAIR].iter()),
             Some(ref x) => v.extend(x.to_bytes().iter()),
         }
 
+        // A missing credential is as long as one on every attribute.
         match self.credential {
-            None        => v.extend([0u8; 96].iter()),
+            None        => {
+                let length: usize = v.len() + SIZEOF_TAG + 32 * self.issuer_parameters.Xn.len();
+
+                v.resize(length, 0u8);
+            },
             Some(ref x) => v.extend(x.to_bytes().iter()),
         }
 
+        if let Some(ref schema) = self.schema {
+            v.extend(schema.fingerprint().iter());
+        }
+
         v
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(User, "A valid byte sequence representing a User");
 
-impl User {
+impl<M: AlgebraicMac> User<M> {
     /// DOCDOC
     pub fn new(
         system_parameters: SystemParameters,
-        issuer_parameters: IssuerParameters,
+        issuer_parameters: M::PublicKey,
         key: Option<elgamal::Keypair>,
-    ) -> User
+    ) -> User<M>
     {
         User {
             system_parameters: system_parameters,
             issuer_parameters: issuer_parameters,
             key: key,
             credential: None,
+            schema: None,
         }
     }
 
+    /// Require that our credential follow some `schema`, which must be the
+    /// same as the issuer's.
+    pub fn with_schema(mut self, schema: CredentialSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
     /// DOCDOC
     ///
     /// # Returns
@@ -133,36 +187,81 @@ impl User {
     {
         CredentialRequest {
             attributes_revealed,
+            point_attributes_revealed: Vec::new(),
+        }
+    }
+
+    /// Request a credential on some scalar `attributes_revealed` and group
+    /// element `point_attributes_revealed`, which requires an issuer whose
+    /// `AlgebraicMac` supports the latter.
+    pub fn obtain_with_points(
+        &self,
+        attributes_revealed: Vec<RevealedAttribute>,
+        point_attributes_revealed: Vec<RevealedPointAttribute>,
+    ) -> CredentialRequest
+    {
+        CredentialRequest {
+            attributes_revealed,
+            point_attributes_revealed,
         }
     }
 
     /// DOCDOC
     pub fn obtain_finish(
         &mut self,
-        issuance: Option<&CredentialIssuance>,
+        issuance: Option<&CredentialIssuance<M>>,
     ) -> Result<(), CredentialError>
     {
         let mut transcript = Transcript::new(b"AEONFLUX ISSUANCE");
 
-        let issue: &CredentialIssuance = match issuance {
+        if let Some(ref schema) = self.schema {
+            schema.commit(&mut transcript);
+        }
+
+        let issue: &CredentialIssuance<M> = match issuance {
             Some(i) => i,
             None    => return Err(CredentialError::CredentialIssuance),
         };
-        let X1: RistrettoPoint = match self.issuer_parameters.Xn.get(0) {
-            None => return Err(CredentialError::NoIssuerParameters),
-            Some(x) => *x,
-        };
 
-        let publics = issuance_revealed::Publics {
-            P: &issue.credential.mac.nonce,
-            Q: &issue.credential.mac.mac,
-            Cx0: &issue.secret_key_commitment.into(),
-            B: &self.system_parameters.g,
-            A: &self.system_parameters.h,
-            X1: &X1,
+        if M::verify_issuance(&self.issuer_parameters, &self.system_parameters,
+                              &issue.credential.mac, &issue.credential.message(),
+                              &issue.secret_key_commitment, &issue.proof, &mut transcript).is_err()
+        {
+            Err(CredentialError::CredentialIssuance)
+        } else {
+            self.credential = Some(issue.credential.clone());
+
+            Ok(())
+        }
+    }
+
+    /// Finish obtaining a credential issued with
+    /// `Issuer::issue_deterministic()`.
+    ///
+    /// The issuance proof is bound to the tag's nonce being the hash of the
+    /// attributes, and is verified with
+    /// `AlgebraicMac::verify_issuance_deterministic()`, which checks this.
+    pub fn obtain_finish_deterministic(
+        &mut self,
+        issuance: Option<&CredentialIssuance<M>>,
+    ) -> Result<(), CredentialError>
+    {
+        let mut transcript = Transcript::new(b"AEONFLUX ISSUANCE");
+
+        if let Some(ref schema) = self.schema {
+            schema.commit(&mut transcript);
+        }
+
+        let issue: &CredentialIssuance<M> = match issuance {
+            Some(i) => i,
+            None    => return Err(CredentialError::CredentialIssuance),
         };
 
-        if issue.proof.verify(&mut transcript, publics).is_err() {
+        if M::verify_issuance_deterministic(&self.issuer_parameters, &self.system_parameters,
+                                            &issue.credential.mac, &issue.credential.message(),
+                                            &issue.secret_key_commitment, &issue.proof,
+                                            &mut transcript).is_err()
+        {
             Err(CredentialError::CredentialIssuance)
         } else {
             self.credential = Some(issue.credential.clone());
@@ -177,77 +276,95 @@ impl User {
         &self,
         nonces: &Nonces,
         rng: &mut R,
-    ) -> Result<CredentialPresentation, CredentialError>
+    ) -> Result<M::Presentation, CredentialError>
     where
         R: RngCore + CryptoRng,
     {
-        let credential: &Credential = match self.credential {
+        let credential: &Credential<M> = match self.credential {
             Some(ref x) => x,
             None        => return Err(CredentialError::MissingData),
         };
 
-        let mut transcript = Transcript::new(b"AEONFLUX SHOW");
-        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
-
-        const N_ATTRIBUTES: usize = 1;
-
-        // Rerandomise the aMAC to prevent trivial linkages.
-        //
-        // XXX do we want to pass in a merlin transcript instead of using the rng here?
-        let rerandomized_mac: amacs::Tag = amacs::Rerandomization::new(&mut csprng).apply_to_tag(&credential.mac);
-
-        let A = self.system_parameters.h;
-        let B = self.system_parameters.g;
-        let P = rerandomized_mac.nonce;
-        let Q = rerandomized_mac.mac;
+        // We hide all attributes upon presentation.
+        if let Some(ref schema) = self.schema {
+            schema.validate_presentation(0, credential.attributes.len())?;
+        }
 
-        // Commit to the rerandomised aMAC.
-        let zQ: Ephemeral = Ephemeral::new(&mut csprng);
-        let CQ: pedersen::Commitment = pedersen::Commitment::to(&Q, &zQ, &A);
+        let mut transcript = Transcript::new(b"AEONFLUX SHOW");
 
-        // Commit to the hidden attributes.
-        let mut commitments: Vec<pedersen::Commitment> = Vec::with_capacity(N_ATTRIBUTES);
+        if let Some(ref schema) = self.schema {
+            schema.commit(&mut transcript);
+        }
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
 
-        for (zi, mi) in nonces.iter().zip(credential.attributes.iter()) {
-            let Cmi: pedersen::Commitment = pedersen::Commitment::to(&(mi * P), zi, &A);
+        M::present(&self.issuer_parameters, &self.system_parameters, &credential.mac,
+                   &credential.message(), nonces, &mut transcript, &mut csprng)
+    }
+}
 
-            commitments.push(Cmi);
-        }
+impl<M> User<M>
+where
+    M: AlgebraicMac<Presentation = CredentialPresentation>,
+{
+    /// Present our credential without revealing which of a verifier-published
+    /// set of `issuers` issued it.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no credential.
+    /// * `CredentialError::NoIssuerParameters` if our issuer is not in the set.
+    /// * `CredentialError::BadAttribute` if our attribute is the
+    ///   `issuer_hiding::dummy_attribute()`.
+    pub fn show_issuer_hiding<R>(
+        &self,
+        issuers: &[IssuerSetMember<M>],
+        rng: &mut R,
+    ) -> Result<IssuerHidingPresentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &Credential<M> = match self.credential {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        let index: usize = match issuers.iter().position(|x| x.issuer_parameters == self.issuer_parameters) {
+            Some(x) => x,
+            None    => return Err(CredentialError::NoIssuerParameters),
+        };
 
-        // Calculate the error factor.
-        let mut V: RistrettoPoint = RistrettoPoint::identity();
+        let transcript = Transcript::new(b"AEONFLUX ISSUER HIDING SHOW");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
 
-        for (index, zi) in nonces.iter().enumerate() {
-            V += &(zi * self.issuer_parameters.Xn[index]);
+        let mut presentations: Vec<CredentialPresentation> = Vec::with_capacity(issuers.len());
+        let mut our_nonces: Option<Nonces> = None;
+
+        // Present our credential to our issuer, and the dummy credentials to
+        // every other issuer.
+        for (k, member) in issuers.iter().enumerate() {
+            let user: User<M> = User {
+                system_parameters: self.system_parameters,
+                issuer_parameters: member.issuer_parameters.clone(),
+                key: None,
+                credential: Some(if k == index { credential.clone() } else { member.dummy_credential.clone() }),
+                schema: self.schema.clone(),
+            };
+            let nonces: Nonces = Nonces::new(&mut csprng, NUMBER_OF_ATTRIBUTES);
+
+            presentations.push(user.show(&nonces, &mut csprng)?);
+
+            if k == index {
+                our_nonces = Some(nonces);
+            }
         }
-        V -= &zQ * &A;
 
-        let minus_zQ = -zQ;
-
-        let valid_credential_secrets = valid_credential::Secrets {
-            m0: &credential.attributes[0],
-            z0: (&nonces[0]).into(),
-            minus_zQ: (&minus_zQ).into(),
-        };
-        let valid_credential_publics = valid_credential::Publics {
-            B: &B,
-            A: &A,
-            X0: &self.issuer_parameters.Xn[0],
-            P: &rerandomized_mac.nonce,
-            V: &V,
-            Cm0: &commitments[0].into(),
+        let nonces: Nonces = match our_nonces {
+            Some(x) => x,
+            None    => return Err(CredentialError::NoIssuerParameters),
         };
-        let valid_credential_proof = valid_credential::Proof::create(&mut transcript,
-                                                                     valid_credential_publics,
-                                                                     valid_credential_secrets);
-
-        Ok(CredentialPresentation {
-            proof: valid_credential_proof,
-            rerandomized_mac_commitment: CQ,
-            rerandomized_nonce: rerandomized_mac.nonce,
-            attributes_revealed: Vec::with_capacity(0),
-            attributes_blinded: commitments,
-        })
+
+        IssuerHidingPresentation::create(&self.system_parameters, presentations, index,
+                                         &credential.attributes[0], (&nonces[0]).into(),
+                                         &mut csprng)
     }
 }
 
@@ -0,0 +1,56 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Generate `aeonflux.h` for the `aeonflux_api` feature.
+//!
+//! The header for the Signal API, `src/include/credential.h`, is written by
+//! hand.  The one for the generic API is generated from `src/generic.rs` by
+//! cbindgen into `OUT_DIR`, whose path is given to the crate's tests as
+//! `AEONFLUX_HEADER`.  A copy is checked in as `src/include/aeonflux.h`, so
+//! that building the library is not needed to read it.  The copy is only
+//! updated when `AEONFLUX_UPDATE_HEADER` is set, as by `make aeonflux.h`, and
+//! the tests check that it is up to date.
+
+#[cfg(feature = "aeonflux_api")]
+extern crate cbindgen;
+
+#[cfg(feature = "aeonflux_api")]
+fn main() {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use cbindgen::Config;
+
+    let crate_dir: PathBuf = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir: PathBuf = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let header: PathBuf = out_dir.join("aeonflux.h");
+    let config: Config = Config::from_file(crate_dir.join("cbindgen.toml").to_str().unwrap())
+        .expect("could not read cbindgen.toml");
+
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(&crate_dir.join("src").join("generic.rs"))
+        .generate()
+        .expect("could not generate aeonflux.h")
+        .write_to_file(&header);
+
+    if env::var_os("AEONFLUX_UPDATE_HEADER").is_some() {
+        fs::copy(&header, crate_dir.join("src").join("include").join("aeonflux.h"))
+            .expect("could not update src/include/aeonflux.h");
+    }
+
+    println!("cargo:rustc-env=AEONFLUX_HEADER={}", header.display());
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src/generic.rs");
+    println!("cargo:rerun-if-env-changed=AEONFLUX_UPDATE_HEADER");
+}
+
+#[cfg(not(feature = "aeonflux_api"))]
+fn main() {}
@@ -0,0 +1,150 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Generate the known-answer test vectors in `test-vectors/credential.json`.
+//!
+//! The whole protocol is run once, with every randomised step drawing from a
+//! `ChaChaRng` seeded with a fixed `seed`, and every seed, input, and output
+//! is written out as hex.  The seeded functions of every binding (the C
+//! `*_deterministic` functions, and so on) use the same `ChaChaRng`, so each
+//! binding can replay the file and must reproduce it byte for byte.
+//! `tests/test-vectors.rs` does this for aeonflux, signal-credential, and
+//! the C API.
+//!
+//! Run it from the ffi directory with
+//!
+//!     cargo run --example generate-test-vectors
+//!
+//! which overwrites the file, or else pass another path to write to.  The
+//! file should only ever need regenerating when the wire format or a proof
+//! changes, and any such change breaks the vectors for every binding.
+
+extern crate aeonflux;
+extern crate hex;
+extern crate rand;
+#[macro_use]
+extern crate serde_json;
+extern crate signal_credential;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use aeonflux::elgamal;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::parameters::SystemParameters;
+
+use rand::ChaChaRng;
+use rand::SeedableRng;
+
+use signal_credential::account_identifier::ToAccountIdentifier;
+use signal_credential::credential::SignalCredentialIssuance;
+use signal_credential::credential::SignalCredentialPresentation;
+use signal_credential::credential::VerifiedSignalCredential;
+use signal_credential::issuer::SignalIssuer;
+use signal_credential::phone_number::CommittedPhoneNumber;
+use signal_credential::phone_number::RosterEntryCommitment;
+use signal_credential::user::SignalUser;
+
+/// The phone number +1 415 555 1234, as the digits the API takes.
+const PHONE_NUMBER: [u8; 11] = [1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+
+const SEED_SYSTEM_PARAMETERS: [u8; 32] = [0x01; 32];
+const SEED_ISSUER: [u8; 32] = [0x02; 32];
+const SEED_ELGAMAL_KEYPAIR: [u8; 32] = [0x03; 32];
+const SEED_ISSUANCE: [u8; 32] = [0x04; 32];
+const SEED_ROSTER_ENTRY_COMMITMENT: [u8; 32] = [0x05; 32];
+const SEED_PRESENTATION: [u8; 32] = [0x06; 32];
+
+fn main() {
+    let path: PathBuf = match env::args().nth(1) {
+        Some(x) => PathBuf::from(x),
+        None    => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("test-vectors").join("credential.json"),
+    };
+
+    let attribute = PHONE_NUMBER[..].to_account_identifier().unwrap().to_scalar();
+
+    let system_parameters: SystemParameters =
+        SystemParameters::hunt_and_peck(&mut ChaChaRng::from_seed(SEED_SYSTEM_PARAMETERS));
+
+    let keypair = SignalIssuer::create(system_parameters, &mut ChaChaRng::from_seed(SEED_ISSUER)).issuer.keypair;
+    let issuer: SignalIssuer = SignalIssuer::new(system_parameters, keypair.clone());
+    let issuer_parameters: IssuerParameters = issuer.get_issuer_parameters();
+
+    let elgamal_keypair: elgamal::Keypair = elgamal::Keypair::generate(&mut ChaChaRng::from_seed(SEED_ELGAMAL_KEYPAIR));
+
+    let mut user: SignalUser = SignalUser::new(system_parameters,
+                                               issuer_parameters.clone(),
+                                               Some(elgamal_keypair.clone()),
+                                               &PHONE_NUMBER[..]).unwrap();
+    let user_bytes: Vec<u8> = user.to_bytes();
+
+    let issuance: SignalCredentialIssuance =
+        issuer.issue(&PHONE_NUMBER[..], &mut ChaChaRng::from_seed(SEED_ISSUANCE)).unwrap();
+
+    user.obtain_finish(Some(&issuance)).unwrap();
+
+    let entry: RosterEntryCommitment =
+        RosterEntryCommitment::create(&PHONE_NUMBER[..], &system_parameters,
+                                      &mut ChaChaRng::from_seed(SEED_ROSTER_ENTRY_COMMITMENT)).unwrap();
+    let presentation: SignalCredentialPresentation =
+        user.show(&mut ChaChaRng::from_seed(SEED_PRESENTATION), &entry).unwrap();
+    let verified: VerifiedSignalCredential = issuer.verify(presentation.clone()).unwrap();
+    let roster_membership: CommittedPhoneNumber = issuer.verify_roster_membership(&verified).unwrap();
+
+    let vectors = json!({
+        "comment": "Generated by ffi/examples/generate-test-vectors.rs.  Every value is hex, and every seed seeds a ChaChaRng.",
+        "phone_number": hex::encode(&PHONE_NUMBER[..]),
+        "attribute": hex::encode(attribute.to_bytes()),
+        "system_parameters": {
+            "seed": hex::encode(SEED_SYSTEM_PARAMETERS),
+            "system_parameters": hex::encode(system_parameters.to_bytes()),
+        },
+        "issuer": {
+            "seed": hex::encode(SEED_ISSUER),
+            "keypair": hex::encode(keypair.to_bytes()),
+            "issuer": hex::encode(issuer.to_bytes()),
+            "issuer_parameters": hex::encode(issuer_parameters.to_bytes()),
+        },
+        "elgamal_keypair": {
+            "seed": hex::encode(SEED_ELGAMAL_KEYPAIR),
+            "keypair": hex::encode(elgamal_keypair.to_bytes()),
+            "public_key": hex::encode(elgamal_keypair.public.to_bytes()),
+        },
+        "issuance": {
+            "seed": hex::encode(SEED_ISSUANCE),
+            "credential_issuance": hex::encode(issuance.to_bytes()),
+        },
+        "user": {
+            "user": hex::encode(user_bytes),
+            "user_with_credential": hex::encode(user.to_bytes()),
+        },
+        "roster_entry_commitment": {
+            "seed": hex::encode(SEED_ROSTER_ENTRY_COMMITMENT),
+            "roster_entry_commitment": hex::encode(entry.to_bytes()),
+            "roster_entry_commitment_sans_opening": hex::encode(entry.commitment.to_bytes()),
+        },
+        "presentation": {
+            "seed": hex::encode(SEED_PRESENTATION),
+            "credential_presentation": hex::encode(presentation.to_bytes()),
+        },
+        "verification": {
+            "verified_credential": hex::encode(verified.to_bytes()),
+            "roster_membership": hex::encode(roster_membership.to_bytes()),
+        },
+    });
+
+    let mut file: File = File::create(&path).unwrap();
+
+    file.write_all(serde_json::to_string_pretty(&vectors).unwrap().as_bytes()).unwrap();
+    file.write_all(b"\n").unwrap();
+
+    println!("Wrote {}", path.display());
+}
@@ -8,18 +8,38 @@
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 
 //! C-like language FFI API.
+//!
+//! Every function, other than `buffer_free()`, returns a `status_t`, which is
+//! `STATUS_OK` on success and otherwise the code of a
+//! `signal_credential::status::Status` describing what went wrong.  Any data
+//! produced is written to the `buf_t` pointed to by the final `out`
+//! parameter, which is always cleared first, so that on failure it holds a
+//! NULL `ptr` and a `len` of zero.
+//!
+//! The work of each function is done by `signal_credential::bindings`, which
+//! the Javascript API also wraps; the functions here only convert pointers
+//! and lengths to and from byte slices.
+//!
+//! Every randomised function other than `system_parameters_create()` takes a
+//! `seed`, which should be NULL, so that the randomness is drawn from the
+//! operating system.  A host which passes its own 32-byte `seed` must never
+//! pass the same one twice, and a seed reused within a process is refused
+//! with `STATUS_SEED_REUSED`; see the `entropy` module.  Seeded functions
+//! which may be called repeatedly, for tests, are in `deterministic`.
 
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::boxed::Box;
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::boxed::Box;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
 cfg_if! {
     if #[cfg(feature = "std")] {
-        use std::ptr;
         use std::slice;
     } else {
-        use core::ptr;
         use core::slice;
     }
 }
@@ -29,10 +49,12 @@ cfg_if! {
     if #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))] {
         #[allow(non_camel_case_types)] pub type size_t = usize;
         #[allow(non_camel_case_types)] pub type uint8_t = u8;
+        #[allow(non_camel_case_types)] pub type uint32_t = u32;
         #[allow(non_camel_case_types)] pub type uint64_t = u64;
     } else {
         pub use libc::size_t;
         pub use libc::uint8_t;
+        pub use libc::uint32_t;
         pub use libc::uint64_t;
     }
 }
@@ -40,60 +62,111 @@ cfg_if! {
 pub use rand::ChaChaRng;
 pub use rand::SeedableRng;
 
-use signal_credential::amacs::Keypair as AmacsKeypair; // The $t:tt in the macro can't munch ::
-use signal_credential::credential::SignalCredentialIssuance;
-use signal_credential::credential::SignalCredentialPresentation;
-use signal_credential::credential::VerifiedSignalCredential;
-use signal_credential::issuer::IssuerParameters;
-use signal_credential::issuer::SignalIssuer;
-use signal_credential::parameters::SystemParameters;
-use signal_credential::phone_number::RosterEntryCommitment;
-use signal_credential::user::SignalUser;
+use signal_credential::bindings;
+use signal_credential::status::Status;
 
-pub type SignalRng = ChaChaRng;
+pub use signal_credential::bindings::SignalRng;
 
-pub const LENGTH_SEED: usize = 32;
+pub const LENGTH_SEED: usize = bindings::SIZEOF_SEED;
 pub const LENGTH_SYSTEM_PARAMETERS: u64 = 64;
 pub const LENGTH_ISSUER: u64 = 160;
 pub const LENGTH_ISSUER_PARAMETERS: u64 = 32;
 pub const LENGTH_ISSUER_KEYPAIR: u64 = 96;
-pub const LENGTH_USER: u64 = 288;
-pub const LENGTH_CREDENTIAL_ISSUANCE: u64 = 328;
-pub const LENGTH_CREDENTIAL_PRESENTATION: u64 = 448;
-pub const LENGTH_VERIFIED_CREDENTIAL: u64 = 448;
-
+pub const LENGTH_USER: u64 = 289;
+pub const LENGTH_CREDENTIAL_ISSUANCE: u64 = 304;
+pub const LENGTH_CREDENTIAL_PRESENTATION: u64 = 480;
+pub const LENGTH_VERIFIED_CREDENTIAL: u64 = 480;
+pub const LENGTH_ELGAMAL_KEYPAIR: u64 = 64;
+pub const LENGTH_ELGAMAL_PUBLIC_KEY: u64 = 32;
+pub const LENGTH_ROSTER_ENTRY_COMMITMENT: u64 = 64;
+pub const LENGTH_ROSTER_ENTRY_COMMITMENT_SANS_OPENING: u64 = 32;
+
+/// The numeric code of a `signal_credential::status::Status`.
+#[allow(non_camel_case_types)]
+pub type status_t = uint32_t;
+
+/// The numeric code of a `signal_credential::sizes::SerializedObject`.
+#[allow(non_camel_case_types)]
+pub type object_t = uint32_t;
+
+/// A pointer to some bytes and their length.
+///
+/// Every `buf_t` returned with a non-NULL `ptr` owns its allocation, which
+/// the caller must release by passing it to `buffer_free()` exactly once.
 #[repr(C)]
 pub struct buf_t {
     pub len: uint64_t,
     pub ptr: *const uint8_t,
 }
 
+/// Release a `buf_t` previously returned by any function in this API.
+///
+/// Passing a `buf_t` with a NULL `ptr` is a no-op.  Passing any other
+/// `buf_t`, or the same one twice, is undefined behaviour.
+#[no_mangle]
+pub extern "C" fn buffer_free(buffer: buf_t) {
+    if buffer.ptr.is_null() {
+        return;
+    }
+    unsafe {
+        let bytes: &mut [u8] = slice::from_raw_parts_mut(buffer.ptr as *mut uint8_t, buffer.len as size_t);
+
+        drop(Box::from_raw(bytes as *mut [u8]));
+    }
+}
+
+/// Write the length, in bytes, of the kind of serialised `object` to `out`.
+///
+/// The lengths are computed by the library itself, rather than being
+/// maintained by hand, and so are always correct for this version of it.
+/// Computing the length of a credential presentation or verified credential
+/// is slow, so callers should do so once and keep the result.
+#[no_mangle]
+pub extern "C" fn length_of(
+    object: object_t,
+    out: *mut uint64_t,
+) -> status_t
+{
+    if out.is_null() {
+        return Status::NullPointer.code();
+    }
+    unsafe { *out = 0; }
+
+    let length: usize = ok_or_return!(bindings::length_of(object));
+
+    unsafe { *out = length as uint64_t; }
+
+    Status::Ok.code()
+}
+
 #[no_mangle]
 pub extern "C" fn system_parameters_create(
     seed: *const uint8_t,  // should be 32 bytes exactly
-) -> buf_t
+    out: *mut buf_t,
+) -> status_t
 {
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
-    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut csprng);
-    let serialized: Vec<u8> = serialize_or_return!(&system_parameters);
+    out_or_return!(out);
 
-    slice_to_len_and_ptr!(&serialized[..])
+    let seed: &[u8] = seed_to_slice!(seed);
+
+    write_out!(out, ok_or_return!(bindings::system_parameters_create(seed)))
 }
 
-// returns a serialized Issuer keypair
+// writes a serialized Issuer keypair to `out`
 #[no_mangle]
 pub extern "C" fn issuer_create(
     system_parameters: *const uint8_t,
     system_parameters_length: uint64_t,
-    seed: *const uint8_t,
-) -> buf_t
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
 {
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
-    let system_params = deserialize_or_return!(SystemParameters, system_parameters_length, system_parameters);
-    let issuer: SignalIssuer = SignalIssuer::create(system_params, &mut csprng);
-    let serialized: Vec<u8> = serialize_or_return!(&issuer.issuer.keypair);
+    out_or_return!(out);
+
+    let seed: [u8; LENGTH_SEED] = seed_or_entropy!(seed);
+    let system_params: &[u8] = len_and_ptr_to_slice!(system_parameters_length, system_parameters);
 
-    slice_to_len_and_ptr!(&serialized[..])
+    write_out!(out, ok_or_return!(bindings::issuer_create(system_params, &seed)))
 }
 
 #[no_mangle]
@@ -102,27 +175,29 @@ pub extern "C" fn issuer_new(
     system_parameters_length: uint64_t,
     keypair: *const uint8_t,
     keypair_length: uint64_t,
-) -> buf_t
+    out: *mut buf_t,
+) -> status_t
 {
-    let system_params = deserialize_or_return!(SystemParameters, system_parameters_length, system_parameters);
-    let keys = deserialize_or_return!(AmacsKeypair, keypair_length, keypair);
-    let issuer: SignalIssuer = SignalIssuer::new(system_params, keys);
-    let serialized: Vec<u8> = serialize_or_return!(&issuer);
+    out_or_return!(out);
+
+    let system_params: &[u8] = len_and_ptr_to_slice!(system_parameters_length, system_parameters);
+    let keys: &[u8] = len_and_ptr_to_slice!(keypair_length, keypair);
 
-    slice_to_len_and_ptr!(&serialized[..])
+    write_out!(out, ok_or_return!(bindings::issuer_new(system_params, keys)))
 }
 
 #[no_mangle]
 pub extern "C" fn issuer_get_issuer_parameters(
     issuer: *const uint8_t,
     issuer_length: uint64_t,
-) -> buf_t
+    out: *mut buf_t,
+) -> status_t
 {
-    let deserialized = deserialize_or_return!(SignalIssuer, issuer_length, issuer);
-    let issuer_parameters: IssuerParameters = deserialized.get_issuer_parameters();
-    let serialized: Vec<u8> = serialize_or_return!(&issuer_parameters);
+    out_or_return!(out);
 
-    slice_to_len_and_ptr!(&serialized[..])
+    let issuer: &[u8] = len_and_ptr_to_slice!(issuer_length, issuer);
+
+    write_out!(out, ok_or_return!(bindings::issuer_get_issuer_parameters(issuer)))
 }
 
 #[no_mangle]
@@ -131,16 +206,17 @@ pub extern "C" fn issuer_issue(
     issuer_length: uint64_t,
     phone_number: *const uint8_t,
     phone_number_length: uint64_t,
-    seed: *const uint8_t, // must be 32 bytes exactly
-) -> buf_t
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
 {
-    let issuer = deserialize_or_return!(SignalIssuer, issuer_length, issuer);
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
+    out_or_return!(out);
+
+    let issuer: &[u8] = len_and_ptr_to_slice!(issuer_length, issuer);
+    let seed: [u8; LENGTH_SEED] = seed_or_entropy!(seed);
     let user_number: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
-    let issuance: SignalCredentialIssuance = ok_or_return!(issuer.issue(&user_number, &mut csprng));
-    let serialized: Vec<u8> = serialize_or_return!(&issuance);
 
-    slice_to_len_and_ptr!(&serialized[..])
+    write_out!(out, ok_or_return!(bindings::issuer_issue(issuer, user_number, &seed)))
 }
 
 #[no_mangle]
@@ -149,16 +225,15 @@ pub extern "C" fn issuer_verify(
     issuer_length: uint64_t,
     presentation: *const uint8_t,
     presentation_length: uint64_t,
-) -> buf_t
+    out: *mut buf_t,
+) -> status_t
 {
-    let issuer_deserialized = deserialize_or_return!(SignalIssuer, issuer_length, issuer);
-    let presentation_deserialized = deserialize_or_return!(SignalCredentialPresentation,
-                                                           presentation_length,
-                                                           presentation);
-    let verified = ok_or_return!(issuer_deserialized.verify(presentation_deserialized));
-    let serialized = serialize_or_return!(&verified);
-
-    slice_to_len_and_ptr!(&serialized[..])
+    out_or_return!(out);
+
+    let issuer: &[u8] = len_and_ptr_to_slice!(issuer_length, issuer);
+    let presentation: &[u8] = len_and_ptr_to_slice!(presentation_length, presentation);
+
+    write_out!(out, ok_or_return!(bindings::issuer_verify(issuer, presentation)))
 }
 
 #[no_mangle]
@@ -167,16 +242,15 @@ pub extern "C" fn issuer_verify_roster_membership(
     issuer_length: uint64_t,
     verified_credential: *const uint8_t,
     verified_credential_length: uint64_t,
-) -> buf_t
+    out: *mut buf_t,
+) -> status_t
 {
-    let issuer_deserialized = deserialize_or_return!(SignalIssuer, issuer_length, issuer);
-    let verified = deserialize_or_return!(VerifiedSignalCredential,
-                                          verified_credential_length,
-                                          verified_credential);
-    let roster_entry_commitment = ok_or_return!(issuer_deserialized.verify_roster_membership(&verified));
-    let serialized = serialize_or_return!(&roster_entry_commitment);
-
-    slice_to_len_and_ptr!(&serialized[..])
+    out_or_return!(out);
+
+    let issuer: &[u8] = len_and_ptr_to_slice!(issuer_length, issuer);
+    let verified: &[u8] = len_and_ptr_to_slice!(verified_credential_length, verified_credential);
+
+    write_out!(out, ok_or_return!(bindings::issuer_verify_roster_membership(issuer, verified)))
 }
 
 #[no_mangle]
@@ -189,22 +263,20 @@ pub extern "C" fn user_obtain_finish(
     issuer_parameters_length: uint64_t,
     issuance: *const uint8_t,
     issuance_length: uint64_t,
-) -> buf_t
+    out: *mut buf_t,
+) -> status_t
 {
-    let system_params = deserialize_or_return!(SystemParameters, system_parameters_length, system_parameters);
-    let issuer_params = deserialize_or_return!(IssuerParameters, issuer_parameters_length, issuer_parameters);
-    let issuance_deserialized = deserialize_or_return!(SignalCredentialIssuance, issuance_length, issuance);
-    let phone_number_bytes: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
-    let mut user: SignalUser = ok_or_return!(SignalUser::new(system_params,
-                                                             issuer_params,
-                                                             None,
-                                                             phone_number_bytes));
-
-    ok_or_return!(user.obtain_finish(Some(&issuance_deserialized)));
+    out_or_return!(out);
 
-    let serialized: Vec<u8> = serialize_or_return!(&user);
+    let system_params: &[u8] = len_and_ptr_to_slice!(system_parameters_length, system_parameters);
+    let issuer_params: &[u8] = len_and_ptr_to_slice!(issuer_parameters_length, issuer_parameters);
+    let issuance: &[u8] = len_and_ptr_to_slice!(issuance_length, issuance);
+    let phone_number_bytes: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
 
-    slice_to_len_and_ptr!(&serialized[..])
+    write_out!(out, ok_or_return!(bindings::user_obtain_finish(phone_number_bytes,
+                                                               system_params,
+                                                               issuer_params,
+                                                               issuance)))
 }
 
 #[no_mangle]
@@ -213,18 +285,131 @@ pub extern "C" fn user_show(
     user_length: uint64_t,
     roster_entry_commitment: *const uint8_t,
     roster_entry_commitment_length: uint64_t,
-    seed: *const uint8_t, // must be 32 bytes exactly
-) -> buf_t
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let seed: [u8; LENGTH_SEED] = seed_or_entropy!(seed);
+    let user: &[u8] = len_and_ptr_to_slice!(user_length, user);
+    let entry: &[u8] = len_and_ptr_to_slice!(roster_entry_commitment_length, roster_entry_commitment);
+
+    write_out!(out, ok_or_return!(bindings::user_show(user, entry, &seed)))
+}
+
+// writes a serialized elGamal keypair to `out`
+#[no_mangle]
+pub extern "C" fn elgamal_keypair_create(
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
 {
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
-    let user_deserialized = deserialize_or_return!(SignalUser, user_length, user);
-    let entry = deserialize_or_return!(RosterEntryCommitment,
-                                       roster_entry_commitment_length,
-                                       roster_entry_commitment);
-    let presentation: SignalCredentialPresentation = ok_or_return!(user_deserialized.show(&mut csprng, &entry));
-    let serialized: Vec<u8> = serialize_or_return!(&presentation);
-
-    slice_to_len_and_ptr!(&serialized[..])
+    out_or_return!(out);
+
+    let seed: [u8; LENGTH_SEED] = seed_or_entropy!(seed);
+
+    write_out!(out, ok_or_return!(bindings::elgamal_keypair_create(&seed)))
+}
+
+#[no_mangle]
+pub extern "C" fn elgamal_keypair_get_public_key(
+    keypair: *const uint8_t,
+    keypair_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let keypair: &[u8] = len_and_ptr_to_slice!(keypair_length, keypair);
+
+    write_out!(out, ok_or_return!(bindings::elgamal_keypair_get_public_key(keypair)))
+}
+
+// writes a serialized SignalUser, which has no credential yet, to `out`
+#[no_mangle]
+pub extern "C" fn user_create(
+    phone_number: *const uint8_t,
+    phone_number_length: uint64_t,
+    system_parameters: *const uint8_t,
+    system_parameters_length: uint64_t,
+    issuer_parameters: *const uint8_t,
+    issuer_parameters_length: uint64_t,
+    keypair: *const uint8_t, // may be NULL, for a user without an elGamal key
+    keypair_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let system_params: &[u8] = len_and_ptr_to_slice!(system_parameters_length, system_parameters);
+    let issuer_params: &[u8] = len_and_ptr_to_slice!(issuer_parameters_length, issuer_parameters);
+    let phone_number_bytes: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
+    let key: Option<&[u8]> = if keypair.is_null() {
+        None
+    } else {
+        Some(len_and_ptr_to_slice!(keypair_length, keypair))
+    };
+
+    write_out!(out, ok_or_return!(bindings::user_create(phone_number_bytes,
+                                                        system_params,
+                                                        issuer_params,
+                                                        key)))
+}
+
+// writes the serialized `user`, now holding the issued credential, to `out`
+#[no_mangle]
+pub extern "C" fn user_obtain(
+    user: *const uint8_t,
+    user_length: uint64_t,
+    issuance: *const uint8_t,
+    issuance_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let user: &[u8] = len_and_ptr_to_slice!(user_length, user);
+    let issuance: &[u8] = len_and_ptr_to_slice!(issuance_length, issuance);
+
+    write_out!(out, ok_or_return!(bindings::user_obtain(user, issuance)))
+}
+
+/// Write `1` to `out` if the `user` holds a credential, and `0` otherwise.
+#[no_mangle]
+pub extern "C" fn user_has_credential(
+    user: *const uint8_t,
+    user_length: uint64_t,
+    out: *mut uint8_t,
+) -> status_t
+{
+    if out.is_null() {
+        return Status::NullPointer.code();
+    }
+    unsafe { *out = 0; }
+
+    let user: &[u8] = len_and_ptr_to_slice!(user_length, user);
+    let has_credential: bool = ok_or_return!(bindings::user_has_credential(user));
+
+    unsafe { *out = has_credential as uint8_t; }
+
+    Status::Ok.code()
+}
+
+// writes the user's serialized elGamal public key to `out`, or returns
+// STATUS_MISSING_DATA if they have none
+#[no_mangle]
+pub extern "C" fn user_get_public_key(
+    user: *const uint8_t,
+    user_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let user: &[u8] = len_and_ptr_to_slice!(user_length, user);
+
+    write_out!(out, ok_or_return!(bindings::user_get_public_key(user)))
 }
 
 #[no_mangle]
@@ -233,32 +418,35 @@ pub extern "C" fn roster_entry_commitment_create(
     phone_number_length: uint64_t,
     system_parameters: *const uint8_t,
     system_parameters_length: uint64_t,
-    seed: *const uint8_t
-) -> buf_t
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
 {
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
+    out_or_return!(out);
+
+    let seed: [u8; LENGTH_SEED] = seed_or_entropy!(seed);
     let number: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
-    let system_params = deserialize_or_return!(SystemParameters, system_parameters_length, system_parameters);
-    let roster_entry = ok_or_return!(RosterEntryCommitment::create(&number, &system_params, &mut csprng));
-    let serialized: Vec<u8> = serialize_or_return!(&roster_entry);
+    let system_params: &[u8] = len_and_ptr_to_slice!(system_parameters_length, system_parameters);
 
-    slice_to_len_and_ptr!(&serialized[..])
+    write_out!(out, ok_or_return!(bindings::roster_entry_commitment_create(number, system_params, &seed)))
 }
 
 #[no_mangle]
 pub extern "C" fn roster_entry_commitment_remove_opening(
     roster_entry_commitment: *const uint8_t,
     roster_entry_commitment_length: uint64_t,
-) -> buf_t
+    out: *mut buf_t,
+) -> status_t
 {
-    let deserialized = deserialize_or_return!(RosterEntryCommitment,
-                                              roster_entry_commitment_length,
-                                              roster_entry_commitment);
-    let serialized = serialize_or_return!(deserialized.commitment);
+    out_or_return!(out);
+
+    let entry: &[u8] = len_and_ptr_to_slice!(roster_entry_commitment_length, roster_entry_commitment);
 
-    slice_to_len_and_ptr!(&serialized[..])
+    write_out!(out, ok_or_return!(bindings::roster_entry_commitment_remove_opening(entry)))
 }
 
+/// Returns `STATUS_OK` if the `roster_entry_commitment` opens to the
+/// `phone_number`, and otherwise the reason it does not.
 #[no_mangle]
 pub extern "C" fn roster_entry_commitment_open(
     roster_entry_commitment: *const uint8_t, // also contains the opening
@@ -267,26 +455,36 @@ pub extern "C" fn roster_entry_commitment_open(
     phone_number_length: uint64_t,
     system_parameters: *const uint8_t,
     system_parameters_length: uint64_t
-) -> buf_t  // XXX just return uint8_t
+) -> status_t
 {
     let number: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
-    let system_params = deserialize_or_return!(SystemParameters,
-                                               system_parameters_length,
-                                               system_parameters);
-    let roster_entry = deserialize_or_return!(RosterEntryCommitment,
-                                              roster_entry_commitment_length,
-                                              roster_entry_commitment);
-    
-    match roster_entry.open(&number, &system_params) {
-        Ok(_)  => buf_t{ ptr: ptr::null(), len: 1 },
-        Err(_) => buf_t{ ptr: ptr::null(), len: 0 },
-    }
+    let system_params: &[u8] = len_and_ptr_to_slice!(system_parameters_length, system_parameters);
+    let entry: &[u8] = len_and_ptr_to_slice!(roster_entry_commitment_length, roster_entry_commitment);
+
+    ok_or_return!(bindings::roster_entry_commitment_open(entry, number, system_params));
+
+    Status::Ok.code()
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use signal_credential::amacs::Keypair as AmacsKeypair; // The $t:tt in the macro can't munch ::
+    use signal_credential::credential::SignalCredentialPresentation;
+    use signal_credential::credential::VerifiedSignalCredential;
+    use signal_credential::elgamal::Keypair as ElgamalKeypair;
+    use signal_credential::issuer::IssuerParameters;
+    use signal_credential::issuer::SignalIssuer;
+    use signal_credential::parameters::SystemParameters;
+    use signal_credential::phone_number::RosterEntryCommitment;
+    use signal_credential::sizes::SerializedObject;
+    use signal_credential::user::SignalUser;
+
+    // The ownership contract for `buf_t`s may be checked with Miri, by running
+    // `cargo +nightly miri test` in this directory.  Tests which create or
+    // verify proofs are far too slow under Miri, and are skipped there.
+
     const RISTRETTO_BASEPOINT_COMPRESSED: [uint8_t; 32] =
         [0xe2, 0xf2, 0xae, 0x0a, 0x6a, 0xbc, 0x4e, 0x71,
          0xa8, 0x84, 0xa9, 0x61, 0xc5, 0x00, 0x51, 0x5f,
@@ -321,60 +519,63 @@ mod test {
         181, 163, 240, 18, 135, 11, 97, 2, 57, 142, 254, 227, 59, 136, 111, 75, 183, 4, 43, 137,
         125, 131, 219, 89, 183, 26, 5, 175, 247, 110, 155, 99, 59, 135, 202, 222, 125, 0];
 
-    const USER_WITH_CREDENTIAL: [uint8_t; 288] = [
-        15, 15, 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4, 15, 15, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        0, 0, 0, 0, 226, 242, 174, 10, 106, 188, 78, 113, 168, 132, 169, 97, 197, 0, 81, 95, 88,
-        227, 11, 106, 165, 130, 221, 141, 182, 166, 89, 69, 224, 141, 45, 118, 106, 145, 146, 226,
-        33, 103, 177, 113, 120, 62, 220, 68, 45, 29, 235, 234, 53, 200, 198, 44, 210, 123, 226,
-        92, 205, 208, 203, 136, 127, 196, 27, 83, 46, 15, 59, 90, 231, 240, 71, 218, 133, 156,
-        242, 69, 166, 177, 88, 83, 243, 194, 130, 14, 169, 80, 206, 142, 125, 241, 187, 64, 51,
-        107, 169, 47, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    const USER_WITH_CREDENTIAL: [uint8_t; 289] = [
+        32, 15, 15, 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4, 15, 15, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 226, 242, 174, 10, 106, 188, 78, 113, 168, 132, 169, 97, 197, 0, 81, 95,
+        88, 227, 11, 106, 165, 130, 221, 141, 182, 166, 89, 69, 224, 141, 45, 118, 106, 145, 146,
+        226, 33, 103, 177, 113, 120, 62, 220, 68, 45, 29, 235, 234, 53, 200, 198, 44, 210, 123,
+        226, 92, 205, 208, 203, 136, 127, 196, 27, 83, 46, 15, 59, 90, 231, 240, 71, 218, 133,
+        156, 242, 69, 166, 177, 88, 83, 243, 194, 130, 14, 169, 80, 206, 142, 125, 241, 187, 64,
+        51, 107, 169, 47, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
         0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 122, 76, 107, 20, 1, 73, 138, 242, 56, 18, 121, 38, 199, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 122, 76, 107, 20, 1, 73, 138, 242, 56, 18, 121, 38, 199, 0,
         206, 64, 243, 30, 78, 222, 189, 130, 97, 186, 212, 114, 87, 90, 66, 210, 80, 63, 116, 170,
         255, 227, 219, 207, 210, 31, 147, 76, 130, 38, 159, 252, 57, 29, 98, 221, 229, 146, 76,
         226, 65, 134, 228, 8, 25, 134, 11, 151, 89, 60, 15, 15, 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4,
         15, 15, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
 
-    const ISSUANCE: [uint8_t; 328] = [
-        244, 21, 196, 237, 191, 59, 210, 143, 229, 70, 189, 0, 157, 125, 163, 254, 183, 147, 38, 37,
-        34, 117, 65, 172, 102, 134, 221, 10, 12, 146, 206, 72, 122, 76, 107, 20, 1, 73, 138, 242, 56,
-        18, 121, 38, 199, 0, 206, 64, 243, 30, 78, 222, 189, 130, 97, 186, 212, 114, 87, 90, 66, 210,
-        80, 63, 116, 170, 255, 227, 219, 207, 210, 31, 147, 76, 130, 38, 159, 252, 57, 29, 98, 221,
-        229, 146, 76, 226, 65, 134, 228, 8, 25, 134, 11, 151, 89, 60, 15, 15, 1, 4, 1, 5, 5, 5, 5, 1,
-        2, 3, 4, 15, 15, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0,
-        49, 60, 77, 112, 78, 178, 70, 94, 208, 141, 192, 221, 54, 143, 148, 248, 125, 149, 135, 97,
-        163, 47, 126, 63, 133, 73, 107, 70, 210, 157, 147, 0, 32, 0, 0, 0, 0, 0, 0, 0, 175, 231, 41,
-        99, 155, 44, 234, 165, 190, 113, 68, 166, 29, 229, 114, 89, 30, 244, 86, 146, 57, 160, 55,
-        60, 246, 67, 19, 131, 109, 235, 31, 14, 32, 0, 0, 0, 0, 0, 0, 0, 92, 205, 217, 18, 252, 133,
-        24, 164, 240, 216, 36, 26, 203, 198, 13, 61, 102, 226, 144, 117, 172, 219, 69, 101, 101, 250,
-        45, 85, 83, 74, 85, 2, 32, 0, 0, 0, 0, 0, 0, 0, 237, 173, 233, 127, 230, 246, 150, 185, 10,
-        112, 234, 213, 120, 72, 210, 70, 172, 194, 30, 94, 21, 54, 221, 238, 55, 98, 33, 92, 111,
-        205, 12, 1, 32, 0, 0, 0, 0, 0, 0, 0, 141, 162, 73, 78, 206, 121, 204, 87, 103, 81, 19, 230,
-        107, 24, 14, 85, 111, 11, 16, 146, 127, 238, 28, 47, 42, 16, 213, 67, 85, 27, 117, 3];
-
-    const PRESENTATION: [uint8_t; 448] = [
-        248, 163, 151, 99, 15, 211, 184, 146, 98, 185, 185, 234, 81, 11, 77, 29, 213, 18, 4, 44, 126,
-        117, 2, 56, 30, 52, 155, 212, 181, 85, 242, 38, 146, 66, 44, 178, 12, 119, 96, 57, 105, 194,
-        58, 97, 59, 14, 109, 79, 103, 202, 97, 128, 134, 167, 190, 207, 115, 50, 142, 173, 50, 115,
-        247, 34, 112, 66, 255, 34, 60, 11, 103, 241, 75, 146, 145, 252, 141, 140, 82, 77, 206, 158,
-        248, 3, 171, 214, 36, 163, 123, 131, 100, 206, 191, 139, 201, 31, 142, 174, 47, 99, 85, 191,
-        79, 157, 165, 214, 12, 190, 34, 126, 148, 132, 249, 199, 75, 153, 9, 28, 231, 135, 51, 234,
-        170, 230, 186, 150, 209, 14, 32, 0, 0, 0, 0, 0, 0, 0, 120, 163, 143, 235, 243, 2, 155, 102,
-        176, 140, 150, 9, 153, 105, 159, 250, 250, 94, 98, 243, 67, 121, 36, 137, 203, 181, 181, 219,
-        79, 50, 185, 5, 32, 0, 0, 0, 0, 0, 0, 0, 238, 78, 222, 45, 89, 12, 191, 163, 207, 28, 202,
-        80, 156, 68, 200, 50, 216, 123, 52, 56, 60, 21, 233, 87, 26, 189, 195, 45, 68, 212, 211, 0,
-        32, 0, 0, 0, 0, 0, 0, 0, 22, 146, 55, 27, 75, 245, 255, 137, 5, 176, 171, 130, 19, 123, 165,
-        21, 155, 165, 71, 208, 110, 205, 10, 212, 123, 96, 164, 253, 24, 22, 189, 3, 32, 0, 0, 0, 0,
-        0, 0, 0, 87, 20, 92, 136, 108, 106, 141, 15, 237, 51, 82, 220, 125, 61, 252, 67, 133, 1, 26,
-        82, 201, 73, 111, 33, 224, 19, 84, 18, 229, 155, 240, 9, 32, 0, 0, 0, 0, 0, 0, 0, 246, 227,
-        44, 155, 172, 20, 30, 125, 6, 203, 95, 202, 118, 205, 113, 106, 246, 92, 227, 123, 180, 154,
-        248, 191, 236, 114, 170, 164, 232, 49, 133, 2, 32, 0, 0, 0, 0, 0, 0, 0, 30, 34, 247, 213, 5,
-        244, 20, 7, 13, 165, 187, 252, 119, 205, 2, 187, 16, 171, 230, 150, 136, 2, 10, 29, 137, 166,
-        211, 167, 22, 175, 194, 4, 32, 0, 0, 0, 0, 0, 0, 0, 184, 204, 213, 5, 200, 136, 130, 26, 21,
-        94, 3, 236, 24, 242, 209, 196, 244, 148, 250, 147, 10, 155, 25, 111, 4, 220, 1, 67, 2, 160,
-        61, 15, 32, 0, 0, 0, 0, 0, 0, 0, 135, 114, 45, 215, 51, 118, 253, 18, 35, 42, 124, 109, 164,
-        181, 48, 152, 22, 58, 145, 48, 181, 75, 151, 186, 49, 133, 213, 98, 138, 99, 83, 11];
+    const ISSUANCE: [uint8_t; 304] = [
+        244, 21, 196, 237, 191, 59, 210, 143, 229, 70, 189, 0, 157, 125, 163, 254, 183, 147, 38,
+        37, 34, 117, 65, 172, 102, 134, 221, 10, 12, 146, 206, 72, 96, 0, 0, 0, 0, 0, 0, 0, 122,
+        76, 107, 20, 1, 73, 138, 242, 56, 18, 121, 38, 199, 0, 206, 64, 243, 30, 78, 222, 189,
+        130, 97, 186, 212, 114, 87, 90, 66, 210, 80, 63, 116, 170, 255, 227, 219, 207, 210, 31,
+        147, 76, 130, 38, 159, 252, 57, 29, 98, 221, 229, 146, 76, 226, 65, 134, 228, 8, 25, 134,
+        11, 151, 89, 60, 15, 15, 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4, 15, 15, 11, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0, 13, 134, 10, 30, 202, 92, 167, 169,
+        137, 90, 165, 78, 245, 69, 189, 114, 242, 65, 240, 50, 217, 187, 63, 14, 23, 44, 149, 90,
+        99, 151, 173, 15, 3, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0, 174, 248, 178, 235,
+        154, 48, 76, 202, 181, 85, 111, 62, 51, 70, 152, 227, 210, 154, 121, 18, 160, 197, 67, 97,
+        196, 34, 134, 247, 3, 248, 14, 14, 32, 0, 0, 0, 0, 0, 0, 0, 19, 149, 47, 170, 151, 134,
+        255, 146, 6, 52, 7, 115, 29, 219, 11, 24, 114, 93, 66, 171, 222, 130, 141, 105, 97, 31,
+        104, 53, 219, 179, 191, 8, 32, 0, 0, 0, 0, 0, 0, 0, 37, 124, 155, 137, 135, 196, 106, 31,
+        227, 148, 254, 242, 235, 110, 146, 182, 152, 43, 181, 49, 141, 79, 79, 55, 62, 53, 156,
+        201, 172, 177, 241, 8];
+
+    const PRESENTATION: [uint8_t; 480] = [
+        146, 129, 202, 107, 220, 222, 106, 220, 203, 229, 114, 104, 102, 220, 92, 164, 155, 210,
+        40, 207, 115, 3, 226, 235, 75, 14, 155, 118, 197, 185, 234, 70, 238, 108, 200, 159, 203,
+        101, 141, 140, 208, 101, 31, 178, 148, 8, 174, 179, 68, 91, 103, 148, 10, 167, 85, 32, 63,
+        3, 250, 166, 65, 103, 85, 54, 64, 103, 178, 207, 196, 109, 144, 69, 171, 116, 41, 99, 72,
+        180, 190, 234, 26, 248, 25, 131, 159, 85, 210, 2, 173, 196, 69, 204, 229, 164, 234, 102,
+        0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0, 136, 133, 210,
+        71, 231, 171, 181, 228, 56, 4, 116, 97, 181, 235, 42, 142, 118, 42, 101, 103, 23, 114,
+        203, 187, 54, 151, 130, 125, 87, 234, 91, 7, 32, 0, 0, 0, 0, 0, 0, 0, 189, 163, 73, 108,
+        62, 229, 80, 191, 160, 78, 239, 231, 98, 78, 240, 114, 6, 55, 79, 22, 145, 184, 212, 10,
+        232, 18, 59, 244, 198, 92, 32, 5, 3, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0, 225,
+        168, 135, 76, 88, 7, 131, 239, 80, 183, 228, 223, 65, 55, 51, 128, 8, 249, 200, 27, 122,
+        133, 79, 101, 145, 125, 93, 169, 8, 191, 163, 12, 32, 0, 0, 0, 0, 0, 0, 0, 187, 109, 133,
+        75, 73, 42, 236, 41, 227, 24, 176, 221, 236, 210, 32, 96, 19, 5, 59, 50, 139, 232, 245,
+        28, 254, 237, 165, 178, 193, 185, 129, 1, 32, 0, 0, 0, 0, 0, 0, 0, 172, 54, 137, 190, 2,
+        31, 96, 237, 140, 21, 169, 155, 213, 98, 144, 161, 114, 135, 204, 178, 47, 241, 193, 244,
+        172, 206, 87, 21, 155, 123, 189, 14, 32, 0, 0, 0, 0, 0, 0, 0, 195, 250, 102, 248, 197, 43,
+        120, 242, 221, 40, 235, 115, 154, 59, 168, 178, 95, 111, 119, 113, 41, 207, 135, 156, 72,
+        7, 10, 236, 169, 100, 255, 13, 32, 0, 0, 0, 0, 0, 0, 0, 44, 113, 188, 222, 161, 231, 114,
+        228, 41, 148, 143, 239, 161, 27, 96, 87, 121, 41, 164, 6, 149, 225, 118, 61, 236, 106,
+        180, 92, 108, 120, 206, 2, 32, 0, 0, 0, 0, 0, 0, 0, 180, 231, 197, 85, 180, 65, 78, 253,
+        134, 107, 56, 110, 99, 171, 49, 127, 123, 204, 110, 240, 178, 85, 45, 126, 230, 178, 50,
+        84, 169, 216, 74, 11, 32, 0, 0, 0, 0, 0, 0, 0, 25, 166, 101, 5, 49, 97, 166, 196, 199,
+        162, 132, 21, 114, 115, 133, 250, 64, 184, 137, 38, 83, 229, 229, 67, 230, 0, 185, 127,
+        165, 78, 187, 15];
 
     macro_rules! assert_deserialized {
         ($t:tt, $len:expr, $ptr:expr) => {{
@@ -391,10 +592,37 @@ mod test {
         }}
     }
 
+    /// Call an FFI function with a fresh out-parameter appended to its
+    /// arguments, check that it succeeded, and return the `buf_t`.
+    macro_rules! assert_ok {
+        ($f:ident($($arg:expr),*)) => {{
+            let mut out: buf_t = zero_len_and_ptr!();
+            let status: status_t = $f($($arg,)* &mut out);
+
+            assert!(status == Status::Ok.code(), "{} returned status {}", stringify!($f), status);
+
+            out
+        }}
+    }
+
+    /// Call an FFI function with a fresh out-parameter appended to its
+    /// arguments, check that it failed with `$status`, and that the
+    /// out-parameter was left empty.
+    macro_rules! assert_status {
+        ($status:expr, $f:ident($($arg:expr),*)) => {{
+            let mut out: buf_t = zero_len_and_ptr!();
+            let status: status_t = $f($($arg,)* &mut out);
+
+            assert!(status == $status.code(), "{} returned status {}", stringify!($f), status);
+            assert!(out.len == 0);
+            assert!(out.ptr.is_null());
+        }}
+    }
+
     #[allow(unused_variables)]
     #[test]
     fn test_system_parameters_create () {
-        let system_parameters = system_parameters_create(SEED.as_ptr());
+        let system_parameters = assert_ok!(system_parameters_create(SEED.as_ptr()));
 
         assert!(system_parameters.len != 0);
         assert!(system_parameters.len == LENGTH_SYSTEM_PARAMETERS);
@@ -403,26 +631,30 @@ mod test {
                                                                   system_parameters.len,
                                                                   system_parameters.ptr);
         assert!(deserialized.g.compress().to_bytes() == RISTRETTO_BASEPOINT_COMPRESSED);
+
+        buffer_free(system_parameters);
     }
 
     #[test]
     fn test_issuer_create() {
-        let keypair = issuer_create(SYSTEM_PARAMETERS.as_ptr(),
-                                    SYSTEM_PARAMETERS.len() as uint64_t,
-                                    SEED.as_ptr());
+        let keypair = assert_ok!(issuer_create(SYSTEM_PARAMETERS.as_ptr(),
+                                               SYSTEM_PARAMETERS.len() as uint64_t,
+                                               ::core::ptr::null()));
 
         assert!(keypair.len != 0);
         assert!(keypair.len == LENGTH_ISSUER_KEYPAIR, "issuer keypair length was {}", keypair.len);
 
         assert_deserialized!(AmacsKeypair, keypair.len, keypair.ptr);
+
+        buffer_free(keypair);
     }
 
     #[test]
     fn test_issuer_new() {
-        let issuer = issuer_new(SYSTEM_PARAMETERS.as_ptr(),
-                                SYSTEM_PARAMETERS.len() as uint64_t,
-                                ISSUER_KEYPAIR.as_ptr(),
-                                ISSUER_KEYPAIR.len() as uint64_t);
+        let issuer = assert_ok!(issuer_new(SYSTEM_PARAMETERS.as_ptr(),
+                                           SYSTEM_PARAMETERS.len() as uint64_t,
+                                           ISSUER_KEYPAIR.as_ptr(),
+                                           ISSUER_KEYPAIR.len() as uint64_t));
 
         assert!(issuer.len != 0);
         assert!(issuer.len == LENGTH_ISSUER, "issuer length was {}", issuer.len);
@@ -432,16 +664,18 @@ mod test {
         assert!(deserialized.issuer.system_parameters.g.compress().to_bytes() == RISTRETTO_BASEPOINT_COMPRESSED,
                 "deserialized was {:?}, original was {:?}",
                 deserialized.issuer.system_parameters.h.compress().to_bytes(), RISTRETTO_BASEPOINT_COMPRESSED);
+
+        buffer_free(issuer);
     }
 
     #[allow(unused_variables)]
     #[test]
     fn test_issuer_get_issuer_parameters() {
-        let issuer = issuer_new(SYSTEM_PARAMETERS.as_ptr(),
-                                SYSTEM_PARAMETERS.len() as uint64_t,
-                                ISSUER_KEYPAIR.as_ptr(),
-                                ISSUER_KEYPAIR.len() as uint64_t);
-        let issuer_parameters = issuer_get_issuer_parameters(issuer.ptr, issuer.len);
+        let issuer = assert_ok!(issuer_new(SYSTEM_PARAMETERS.as_ptr(),
+                                           SYSTEM_PARAMETERS.len() as uint64_t,
+                                           ISSUER_KEYPAIR.as_ptr(),
+                                           ISSUER_KEYPAIR.len() as uint64_t));
+        let issuer_parameters = assert_ok!(issuer_get_issuer_parameters(issuer.ptr, issuer.len));
 
         assert!(issuer_parameters.len != 0);
         assert!(issuer_parameters.len == LENGTH_ISSUER_PARAMETERS,
@@ -452,77 +686,420 @@ mod test {
                                                                   issuer_parameters.ptr);
 
         assert!(deserialized.Xn.get(0).is_some());
+
+        buffer_free(issuer_parameters);
+        buffer_free(issuer);
     }
 
     #[allow(unused_variables)]
     #[test]
+    #[cfg_attr(miri, ignore)]
     fn test_issuer_issue() {
-        let issuer = issuer_new(SYSTEM_PARAMETERS.as_ptr(),
-                                SYSTEM_PARAMETERS.len() as uint64_t,
-                                ISSUER_KEYPAIR.as_ptr(),
-                                ISSUER_KEYPAIR.len() as uint64_t);
-        let issuance = issuer_issue(issuer.ptr,
-                                    issuer.len,
-                                    PHONE_NUMBER.as_ptr(),
-                                    PHONE_NUMBER.len() as uint64_t,
-                                    SEED.as_ptr());
+        let issuer = assert_ok!(issuer_new(SYSTEM_PARAMETERS.as_ptr(),
+                                           SYSTEM_PARAMETERS.len() as uint64_t,
+                                           ISSUER_KEYPAIR.as_ptr(),
+                                           ISSUER_KEYPAIR.len() as uint64_t));
+        let issuance = assert_ok!(issuer_issue(issuer.ptr,
+                                               issuer.len,
+                                               PHONE_NUMBER.as_ptr(),
+                                               PHONE_NUMBER.len() as uint64_t,
+                                               ::core::ptr::null()));
 
         assert!(issuance.len != 0);
         assert!(issuance.len == LENGTH_CREDENTIAL_ISSUANCE,
                 "issuance length was {}", issuance.len);
+
+        buffer_free(issuance);
+        buffer_free(issuer);
     }
 
 
     #[test]
+    #[cfg_attr(miri, ignore)]
     fn test_issuer_verify() {
-        let issuer = issuer_new(SYSTEM_PARAMETERS.as_ptr(),
-                                SYSTEM_PARAMETERS.len() as uint64_t,
-                                ISSUER_KEYPAIR.as_ptr(),
-                                ISSUER_KEYPAIR.len() as uint64_t);
-        let verified = issuer_verify(issuer.ptr, issuer.len,
-                                     PRESENTATION.as_ptr(), PRESENTATION.len() as uint64_t);
+        let issuer = assert_ok!(issuer_new(SYSTEM_PARAMETERS.as_ptr(),
+                                           SYSTEM_PARAMETERS.len() as uint64_t,
+                                           ISSUER_KEYPAIR.as_ptr(),
+                                           ISSUER_KEYPAIR.len() as uint64_t));
+        let verified = assert_ok!(issuer_verify(issuer.ptr, issuer.len,
+                                                PRESENTATION.as_ptr(), PRESENTATION.len() as uint64_t));
 
         assert!(verified.len != 0);
         assert!(verified.len == LENGTH_VERIFIED_CREDENTIAL,
                 "verified length was {}", verified.len);
 
         assert_deserialized!(VerifiedSignalCredential, verified.len, verified.ptr);
+
+        buffer_free(verified);
+        buffer_free(issuer);
     }
 
     #[allow(unused_variables)]
     #[test]
+    #[cfg_attr(miri, ignore)]
     fn test_user_obtain_finish() {
-        let user = user_obtain_finish(PHONE_NUMBER.as_ptr(),
-                                      PHONE_NUMBER.len() as uint64_t,
-                                      SYSTEM_PARAMETERS.as_ptr(),
-                                      SYSTEM_PARAMETERS.len() as uint64_t,
-                                      ISSUER_PARAMETERS.as_ptr(),
-                                      ISSUER_PARAMETERS.len() as uint64_t,
-                                      ISSUANCE.as_ptr(),
-                                      ISSUANCE.len() as uint64_t);
+        let user = assert_ok!(user_obtain_finish(PHONE_NUMBER.as_ptr(),
+                                                 PHONE_NUMBER.len() as uint64_t,
+                                                 SYSTEM_PARAMETERS.as_ptr(),
+                                                 SYSTEM_PARAMETERS.len() as uint64_t,
+                                                 ISSUER_PARAMETERS.as_ptr(),
+                                                 ISSUER_PARAMETERS.len() as uint64_t,
+                                                 ISSUANCE.as_ptr(),
+                                                 ISSUANCE.len() as uint64_t));
 
         assert!(user.len != 0);
         assert!(user.len == LENGTH_USER,
                 "user length was {}", user.len);
+
+        buffer_free(user);
     }
 
     #[test]
+    #[cfg_attr(miri, ignore)]
     fn test_user_show() {
-        let entry = roster_entry_commitment_create(PHONE_NUMBER.as_ptr(),
-                                                   PHONE_NUMBER.len() as uint64_t,
-                                                   SYSTEM_PARAMETERS.as_ptr(),
-                                                   SYSTEM_PARAMETERS.len() as uint64_t,
-                                                   SEED.as_ptr());
-        let presentation = user_show(USER_WITH_CREDENTIAL.as_ptr(),
-                                     USER_WITH_CREDENTIAL.len() as uint64_t,
-                                     entry.ptr,
-                                     entry.len,
-                                     SEED.as_ptr());
+        let entry = assert_ok!(roster_entry_commitment_create(PHONE_NUMBER.as_ptr(),
+                                                              PHONE_NUMBER.len() as uint64_t,
+                                                              SYSTEM_PARAMETERS.as_ptr(),
+                                                              SYSTEM_PARAMETERS.len() as uint64_t,
+                                                              ::core::ptr::null()));
+        let presentation = assert_ok!(user_show(USER_WITH_CREDENTIAL.as_ptr(),
+                                                USER_WITH_CREDENTIAL.len() as uint64_t,
+                                                entry.ptr,
+                                                entry.len,
+                                                ::core::ptr::null()));
 
         assert!(presentation.len != 0);
         assert!(presentation.len == LENGTH_CREDENTIAL_PRESENTATION,
                 "presentation length was {}", presentation.len);
 
         assert_deserialized!(SignalCredentialPresentation, presentation.len, presentation.ptr);
+
+        buffer_free(presentation);
+        buffer_free(entry);
+    }
+
+    #[test]
+    fn test_roster_entry_commitment_open() {
+        let other_number: &[uint8_t] = &[1, 4, 1, 5, 5, 5, 5, 4, 3, 2, 1];
+        let entry = assert_ok!(roster_entry_commitment_create(PHONE_NUMBER.as_ptr(),
+                                                              PHONE_NUMBER.len() as uint64_t,
+                                                              SYSTEM_PARAMETERS.as_ptr(),
+                                                              SYSTEM_PARAMETERS.len() as uint64_t,
+                                                              ::core::ptr::null()));
+        let opened = roster_entry_commitment_open(entry.ptr, entry.len,
+                                                  PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as uint64_t,
+                                                  SYSTEM_PARAMETERS.as_ptr(),
+                                                  SYSTEM_PARAMETERS.len() as uint64_t);
+        let not_opened = roster_entry_commitment_open(entry.ptr, entry.len,
+                                                      other_number.as_ptr(), other_number.len() as uint64_t,
+                                                      SYSTEM_PARAMETERS.as_ptr(),
+                                                      SYSTEM_PARAMETERS.len() as uint64_t);
+
+        assert!(opened == Status::Ok.code());
+        assert!(not_opened == Status::InvalidPhoneNumber.code(), "status was {}", not_opened);
+
+        buffer_free(entry);
+    }
+
+    #[test]
+    fn test_buffer_free_null() {
+        buffer_free(zero_len_and_ptr!());
+    }
+
+    #[test]
+    fn test_failure_returns_null_buffer() {
+        assert_status!(Status::NoSystemParameters,
+                       issuer_new(SYSTEM_PARAMETERS.as_ptr(),
+                                  (SYSTEM_PARAMETERS.len() - 1) as uint64_t,
+                                  ISSUER_KEYPAIR.as_ptr(),
+                                  ISSUER_KEYPAIR.len() as uint64_t));
+    }
+
+    #[test]
+    fn test_null_out_parameter() {
+        let status = system_parameters_create(SEED.as_ptr(), ::core::ptr::null_mut());
+
+        assert!(status == Status::NullPointer.code());
+    }
+
+    #[test]
+    fn test_null_seed() {
+        assert_status!(Status::InvalidSeed,
+                       system_parameters_create(::core::ptr::null()));
+    }
+
+    #[test]
+    fn test_null_seed_uses_os_entropy() {
+        let first = assert_ok!(elgamal_keypair_create(::core::ptr::null()));
+        let second = assert_ok!(elgamal_keypair_create(::core::ptr::null()));
+        let first_bytes: &[u8] = unsafe { slice::from_raw_parts(first.ptr, first.len as size_t) };
+        let second_bytes: &[u8] = unsafe { slice::from_raw_parts(second.ptr, second.len as size_t) };
+
+        assert!(first_bytes != second_bytes);
+
+        buffer_free(second);
+        buffer_free(first);
+    }
+
+    #[test]
+    fn test_reused_seed() {
+        // No other test may use this seed, since the guard is process-wide.
+        let seed: [uint8_t; 32] = [0xc5; 32];
+        let keypair = assert_ok!(elgamal_keypair_create(seed.as_ptr()));
+
+        // The seed is refused by every randomised function, not only the
+        // one it was first given to.
+        assert_status!(Status::SeedReused, elgamal_keypair_create(seed.as_ptr()));
+        assert_status!(Status::SeedReused,
+                       roster_entry_commitment_create(PHONE_NUMBER.as_ptr(),
+                                                      PHONE_NUMBER.len() as uint64_t,
+                                                      SYSTEM_PARAMETERS.as_ptr(),
+                                                      SYSTEM_PARAMETERS.len() as uint64_t,
+                                                      seed.as_ptr()));
+
+        buffer_free(keypair);
+    }
+
+    #[test]
+    fn test_empty_phone_number() {
+        assert_status!(Status::NullPointer,
+                       roster_entry_commitment_create(PHONE_NUMBER.as_ptr(),
+                                                      0,
+                                                      SYSTEM_PARAMETERS.as_ptr(),
+                                                      SYSTEM_PARAMETERS.len() as uint64_t,
+                                                      ::core::ptr::null()));
+    }
+
+    #[test]
+    fn test_malformed_issuer() {
+        assert_status!(Status::WrongNumberOfBytes,
+                       issuer_get_issuer_parameters(ISSUER_KEYPAIR.as_ptr(),
+                                                    ISSUER_KEYPAIR.len() as uint64_t));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_tampered_presentation() {
+        let issuer = assert_ok!(issuer_new(SYSTEM_PARAMETERS.as_ptr(),
+                                           SYSTEM_PARAMETERS.len() as uint64_t,
+                                           ISSUER_KEYPAIR.as_ptr(),
+                                           ISSUER_KEYPAIR.len() as uint64_t));
+        let mut tampered: [uint8_t; 480] = PRESENTATION;
+
+        // Alter the final byte of the last response in the presentation's
+        // proof, which ends 160 bytes before the roster membership proof,
+        // keeping it canonical.
+        tampered[319] ^= 1;
+
+        assert_status!(Status::MacVerification,
+                       issuer_verify(issuer.ptr, issuer.len,
+                                     tampered.as_ptr(), tampered.len() as uint64_t));
+
+        buffer_free(issuer);
+    }
+
+    #[test]
+    fn test_returned_buffers_outlive_their_call() {
+        let entry = assert_ok!(roster_entry_commitment_create(PHONE_NUMBER.as_ptr(),
+                                                              PHONE_NUMBER.len() as uint64_t,
+                                                              SYSTEM_PARAMETERS.as_ptr(),
+                                                              SYSTEM_PARAMETERS.len() as uint64_t,
+                                                              ::core::ptr::null()));
+        let commitment = assert_ok!(roster_entry_commitment_remove_opening(entry.ptr, entry.len));
+
+        // Both buffers must still be readable after the calls which created
+        // them have returned, and after one another has been created.
+        let deserialized = assert_deserialized!(RosterEntryCommitment, entry.len, entry.ptr);
+        let bytes: &[u8] = unsafe { slice::from_raw_parts(commitment.ptr, commitment.len as size_t) };
+
+        assert!(deserialized.commitment.to_bytes()[..] == bytes[..]);
+
+        buffer_free(commitment);
+        buffer_free(entry);
+    }
+
+    #[test]
+    fn test_length_of_unknown_object() {
+        let mut length: uint64_t = 1;
+
+        assert!(length_of(0, &mut length) == Status::UnknownObject.code());
+        assert!(length == 0);
+        assert!(length_of(SerializedObject::User as object_t, ::core::ptr::null_mut()) == Status::NullPointer.code());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_lengths_match_library() {
+        let expected: [(SerializedObject, uint64_t); 12] = [
+            (SerializedObject::SystemParameters, LENGTH_SYSTEM_PARAMETERS),
+            (SerializedObject::IssuerKeypair, LENGTH_ISSUER_KEYPAIR),
+            (SerializedObject::Issuer, LENGTH_ISSUER),
+            (SerializedObject::IssuerParameters, LENGTH_ISSUER_PARAMETERS),
+            (SerializedObject::ElgamalKeypair, LENGTH_ELGAMAL_KEYPAIR),
+            (SerializedObject::ElgamalPublicKey, LENGTH_ELGAMAL_PUBLIC_KEY),
+            (SerializedObject::User, LENGTH_USER),
+            (SerializedObject::CredentialIssuance, LENGTH_CREDENTIAL_ISSUANCE),
+            (SerializedObject::CredentialPresentation, LENGTH_CREDENTIAL_PRESENTATION),
+            (SerializedObject::VerifiedCredential, LENGTH_VERIFIED_CREDENTIAL),
+            (SerializedObject::RosterEntryCommitment, LENGTH_ROSTER_ENTRY_COMMITMENT),
+            (SerializedObject::RosterEntryCommitmentSansOpening, LENGTH_ROSTER_ENTRY_COMMITMENT_SANS_OPENING),
+        ];
+
+        for &(object, constant) in expected.iter() {
+            let mut length: uint64_t = 0;
+
+            assert!(length_of(object as object_t, &mut length) == Status::Ok.code());
+            assert!(length == constant, "{:?} has length {}, but the constant is {}", object, length, constant);
+        }
+    }
+
+    #[test]
+    fn test_elgamal_keypair_get_public_key() {
+        let keypair = assert_ok!(elgamal_keypair_create(::core::ptr::null()));
+
+        assert!(keypair.len == LENGTH_ELGAMAL_KEYPAIR, "keypair length was {}", keypair.len);
+
+        let public = assert_ok!(elgamal_keypair_get_public_key(keypair.ptr, keypair.len));
+        let deserialized = assert_deserialized!(ElgamalKeypair, keypair.len, keypair.ptr);
+        let bytes: &[u8] = unsafe { slice::from_raw_parts(public.ptr, public.len as size_t) };
+
+        assert!(public.len == LENGTH_ELGAMAL_PUBLIC_KEY);
+        assert!(deserialized.public.to_bytes()[..] == bytes[..]);
+
+        assert_status!(Status::WrongNumberOfBytes,
+                       elgamal_keypair_get_public_key(keypair.ptr, keypair.len - 1));
+
+        buffer_free(public);
+        buffer_free(keypair);
+    }
+
+    #[test]
+    fn test_user_create() {
+        let user = assert_ok!(user_create(PHONE_NUMBER.as_ptr(),
+                                          PHONE_NUMBER.len() as uint64_t,
+                                          SYSTEM_PARAMETERS.as_ptr(),
+                                          SYSTEM_PARAMETERS.len() as uint64_t,
+                                          ISSUER_PARAMETERS.as_ptr(),
+                                          ISSUER_PARAMETERS.len() as uint64_t,
+                                          ::core::ptr::null(),
+                                          0));
+        let mut has_credential: uint8_t = 1;
+
+        assert!(user.len == LENGTH_USER, "user length was {}", user.len);
+        assert!(user_has_credential(user.ptr, user.len, &mut has_credential) == Status::Ok.code());
+        assert!(has_credential == 0);
+        assert_status!(Status::MissingData, user_get_public_key(user.ptr, user.len));
+
+        let deserialized = assert_deserialized!(SignalUser, user.len, user.ptr);
+
+        assert!(deserialized.user.key.is_none());
+
+        buffer_free(user);
+    }
+
+    #[test]
+    fn test_user_create_with_keypair() {
+        let keypair = assert_ok!(elgamal_keypair_create(::core::ptr::null()));
+        let user = assert_ok!(user_create(PHONE_NUMBER.as_ptr(),
+                                          PHONE_NUMBER.len() as uint64_t,
+                                          SYSTEM_PARAMETERS.as_ptr(),
+                                          SYSTEM_PARAMETERS.len() as uint64_t,
+                                          ISSUER_PARAMETERS.as_ptr(),
+                                          ISSUER_PARAMETERS.len() as uint64_t,
+                                          keypair.ptr,
+                                          keypair.len));
+        let from_keypair = assert_ok!(elgamal_keypair_get_public_key(keypair.ptr, keypair.len));
+        let from_user = assert_ok!(user_get_public_key(user.ptr, user.len));
+        let expected: &[u8] = unsafe { slice::from_raw_parts(from_keypair.ptr, from_keypair.len as size_t) };
+        let actual: &[u8] = unsafe { slice::from_raw_parts(from_user.ptr, from_user.len as size_t) };
+
+        assert!(expected == actual);
+
+        assert_status!(Status::WrongNumberOfBytes,
+                       user_create(PHONE_NUMBER.as_ptr(),
+                                   PHONE_NUMBER.len() as uint64_t,
+                                   SYSTEM_PARAMETERS.as_ptr(),
+                                   SYSTEM_PARAMETERS.len() as uint64_t,
+                                   ISSUER_PARAMETERS.as_ptr(),
+                                   ISSUER_PARAMETERS.len() as uint64_t,
+                                   keypair.ptr,
+                                   keypair.len - 1));
+
+        buffer_free(from_user);
+        buffer_free(from_keypair);
+        buffer_free(user);
+        buffer_free(keypair);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_user_obtain() {
+        let user = assert_ok!(user_create(PHONE_NUMBER.as_ptr(),
+                                          PHONE_NUMBER.len() as uint64_t,
+                                          SYSTEM_PARAMETERS.as_ptr(),
+                                          SYSTEM_PARAMETERS.len() as uint64_t,
+                                          ISSUER_PARAMETERS.as_ptr(),
+                                          ISSUER_PARAMETERS.len() as uint64_t,
+                                          ::core::ptr::null(),
+                                          0));
+        let obtained = assert_ok!(user_obtain(user.ptr, user.len, ISSUANCE.as_ptr(), ISSUANCE.len() as uint64_t));
+        let finished = assert_ok!(user_obtain_finish(PHONE_NUMBER.as_ptr(),
+                                                     PHONE_NUMBER.len() as uint64_t,
+                                                     SYSTEM_PARAMETERS.as_ptr(),
+                                                     SYSTEM_PARAMETERS.len() as uint64_t,
+                                                     ISSUER_PARAMETERS.as_ptr(),
+                                                     ISSUER_PARAMETERS.len() as uint64_t,
+                                                     ISSUANCE.as_ptr(),
+                                                     ISSUANCE.len() as uint64_t));
+        let mut has_credential: uint8_t = 0;
+
+        assert!(user_has_credential(obtained.ptr, obtained.len, &mut has_credential) == Status::Ok.code());
+        assert!(has_credential == 1);
+
+        // Creating a user and then obtaining a credential is the same as
+        // doing both at once.
+        let obtained_bytes: &[u8] = unsafe { slice::from_raw_parts(obtained.ptr, obtained.len as size_t) };
+        let finished_bytes: &[u8] = unsafe { slice::from_raw_parts(finished.ptr, finished.len as size_t) };
+
+        assert!(obtained_bytes == finished_bytes);
+
+        buffer_free(finished);
+        buffer_free(obtained);
+        buffer_free(user);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_issuer_verify_roster_membership() {
+        let issuer = assert_ok!(issuer_new(SYSTEM_PARAMETERS.as_ptr(),
+                                           SYSTEM_PARAMETERS.len() as uint64_t,
+                                           ISSUER_KEYPAIR.as_ptr(),
+                                           ISSUER_KEYPAIR.len() as uint64_t));
+        let entry = assert_ok!(roster_entry_commitment_create(PHONE_NUMBER.as_ptr(),
+                                                              PHONE_NUMBER.len() as uint64_t,
+                                                              SYSTEM_PARAMETERS.as_ptr(),
+                                                              SYSTEM_PARAMETERS.len() as uint64_t,
+                                                              ::core::ptr::null()));
+        let presentation = assert_ok!(user_show(USER_WITH_CREDENTIAL.as_ptr(),
+                                                USER_WITH_CREDENTIAL.len() as uint64_t,
+                                                entry.ptr,
+                                                entry.len,
+                                                ::core::ptr::null()));
+        let verified = assert_ok!(issuer_verify(issuer.ptr, issuer.len, presentation.ptr, presentation.len));
+        let member = assert_ok!(issuer_verify_roster_membership(issuer.ptr, issuer.len,
+                                                                verified.ptr, verified.len));
+        let commitment = assert_ok!(roster_entry_commitment_remove_opening(entry.ptr, entry.len));
+        let member_bytes: &[u8] = unsafe { slice::from_raw_parts(member.ptr, member.len as size_t) };
+        let commitment_bytes: &[u8] = unsafe { slice::from_raw_parts(commitment.ptr, commitment.len as size_t) };
+
+        assert!(member.len == LENGTH_ROSTER_ENTRY_COMMITMENT_SANS_OPENING);
+        assert!(member_bytes == commitment_bytes);
+
+        buffer_free(commitment);
+        buffer_free(member);
+        buffer_free(verified);
+        buffer_free(presentation);
+        buffer_free(entry);
+        buffer_free(issuer);
     }
 }
@@ -0,0 +1,272 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Deterministic variants of the randomised functions in `c`, for tests.
+//!
+//! Each function here behaves exactly as its namesake without the
+//! `_deterministic` suffix, except that its `seed` is required, and may be
+//! reused: with the same version of this crate, the same seed and inputs
+//! give the same output.  Outputs are not stable across versions, since
+//! they depend on the order in which each proof draws its randomness, so
+//! test vectors must be regenerated whenever a protocol changes.  This is
+//! what tests and test vectors need, and exactly what a real host must
+//! avoid, since presentations made with the same seed are linkable.  These
+//! functions are therefore only built for this crate's own tests, or with
+//! the `deterministic` feature, and must never be shipped to users.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::boxed::Box;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::boxed::Box;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::slice;
+    } else {
+        use core::slice;
+    }
+}
+
+use signal_credential::bindings;
+use signal_credential::status::Status;
+
+use c::LENGTH_SEED;
+use c::buf_t;
+use c::size_t;
+use c::status_t;
+use c::uint8_t;
+use c::uint64_t;
+
+#[no_mangle]
+pub extern "C" fn issuer_create_deterministic(
+    system_parameters: *const uint8_t,
+    system_parameters_length: uint64_t,
+    seed: *const uint8_t, // must be 32 bytes exactly
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let seed: &[u8] = seed_to_slice!(seed);
+    let system_params: &[u8] = len_and_ptr_to_slice!(system_parameters_length, system_parameters);
+
+    write_out!(out, ok_or_return!(bindings::issuer_create(system_params, seed)))
+}
+
+#[no_mangle]
+pub extern "C" fn issuer_issue_deterministic(
+    issuer: *const uint8_t,
+    issuer_length: uint64_t,
+    phone_number: *const uint8_t,
+    phone_number_length: uint64_t,
+    seed: *const uint8_t, // must be 32 bytes exactly
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let issuer: &[u8] = len_and_ptr_to_slice!(issuer_length, issuer);
+    let seed: &[u8] = seed_to_slice!(seed);
+    let user_number: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
+
+    write_out!(out, ok_or_return!(bindings::issuer_issue(issuer, user_number, seed)))
+}
+
+#[no_mangle]
+pub extern "C" fn user_show_deterministic(
+    user: *const uint8_t,
+    user_length: uint64_t,
+    roster_entry_commitment: *const uint8_t,
+    roster_entry_commitment_length: uint64_t,
+    seed: *const uint8_t, // must be 32 bytes exactly
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let seed: &[u8] = seed_to_slice!(seed);
+    let user: &[u8] = len_and_ptr_to_slice!(user_length, user);
+    let entry: &[u8] = len_and_ptr_to_slice!(roster_entry_commitment_length, roster_entry_commitment);
+
+    write_out!(out, ok_or_return!(bindings::user_show(user, entry, seed)))
+}
+
+#[no_mangle]
+pub extern "C" fn elgamal_keypair_create_deterministic(
+    seed: *const uint8_t, // must be 32 bytes exactly
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let seed: &[u8] = seed_to_slice!(seed);
+
+    write_out!(out, ok_or_return!(bindings::elgamal_keypair_create(seed)))
+}
+
+#[no_mangle]
+pub extern "C" fn roster_entry_commitment_create_deterministic(
+    phone_number: *const uint8_t,
+    phone_number_length: uint64_t,
+    system_parameters: *const uint8_t,
+    system_parameters_length: uint64_t,
+    seed: *const uint8_t, // must be 32 bytes exactly
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let seed: &[u8] = seed_to_slice!(seed);
+    let number: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
+    let system_params: &[u8] = len_and_ptr_to_slice!(system_parameters_length, system_parameters);
+
+    write_out!(out, ok_or_return!(bindings::roster_entry_commitment_create(number, system_params, seed)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use c::*;
+
+    const SEED: [uint8_t; 32] = [  0,  1,  2,  3,  4,  5,  6,  7,
+                                   8,  9, 10, 11, 12, 13, 14, 15,
+                                  16, 17, 18, 19, 20, 21, 22, 23,
+                                  24, 25, 26, 27, 28, 29, 30, 31, ];
+
+    fn keypair(seed: *const uint8_t) -> buf_t {
+        let mut out: buf_t = zero_len_and_ptr!();
+
+        assert!(elgamal_keypair_create_deterministic(seed, &mut out) == Status::Ok.code());
+
+        out
+    }
+
+    const OTHER_SEED: [uint8_t; 32] = [ 31, 30, 29, 28, 27, 26, 25, 24,
+                                        23, 22, 21, 20, 19, 18, 17, 16,
+                                        15, 14, 13, 12, 11, 10,  9,  8,
+                                         7,  6,  5,  4,  3,  2,  1,  0, ];
+
+    const PHONE_NUMBER: &'static [uint8_t] = &[ 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4 ];
+
+    /// Call a C function with a fresh out-parameter appended to its
+    /// arguments, check that it succeeded, and return the out-parameter.
+    macro_rules! ok {
+        ($f:ident($($arg:expr),*)) => {{
+            let mut out: buf_t = zero_len_and_ptr!();
+
+            assert!($f($($arg,)* &mut out) == Status::Ok.code());
+            out
+        }}
+    }
+
+    fn bytes(buffer: &buf_t) -> &[u8] {
+        unsafe { slice::from_raw_parts(buffer.ptr, buffer.len as size_t) }
+    }
+
+    /// Create an issuer from `SEED`, and a user with a credential from it.
+    fn issuer_and_user() -> (buf_t, buf_t, buf_t) {
+        let system_parameters = ok!(system_parameters_create(SEED.as_ptr()));
+        let issuer_keypair = ok!(issuer_create_deterministic(system_parameters.ptr, system_parameters.len,
+                                                             SEED.as_ptr()));
+        let issuer = ok!(issuer_new(system_parameters.ptr, system_parameters.len,
+                                    issuer_keypair.ptr, issuer_keypair.len));
+        let issuer_parameters = ok!(issuer_get_issuer_parameters(issuer.ptr, issuer.len));
+        let key = keypair(OTHER_SEED.as_ptr());
+        let user = ok!(user_create(PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as uint64_t,
+                                   system_parameters.ptr, system_parameters.len,
+                                   issuer_parameters.ptr, issuer_parameters.len,
+                                   key.ptr, key.len));
+        let issuance = ok!(issuer_issue_deterministic(issuer.ptr, issuer.len,
+                                                      PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as uint64_t,
+                                                      SEED.as_ptr()));
+        let credentialed = ok!(user_obtain(user.ptr, user.len, issuance.ptr, issuance.len));
+
+        buffer_free(issuance);
+        buffer_free(user);
+        buffer_free(key);
+        buffer_free(issuer_parameters);
+        buffer_free(issuer_keypair);
+
+        (system_parameters, issuer, credentialed)
+    }
+
+    #[test]
+    fn same_seed_same_output() {
+        let first = keypair(SEED.as_ptr());
+        let second = keypair(SEED.as_ptr());
+
+        assert!(bytes(&first) == bytes(&second));
+
+        buffer_free(second);
+        buffer_free(first);
+    }
+
+    #[test]
+    fn issue_same_seed_same_output() {
+        let (system_parameters, issuer, user) = issuer_and_user();
+        let issue = |seed: &[uint8_t; 32]| ok!(issuer_issue_deterministic(issuer.ptr, issuer.len,
+                                                                           PHONE_NUMBER.as_ptr(),
+                                                                           PHONE_NUMBER.len() as uint64_t,
+                                                                           seed.as_ptr()));
+        let first = issue(&SEED);
+        let second = issue(&SEED);
+        let other = issue(&OTHER_SEED);
+
+        assert!(bytes(&first) == bytes(&second));
+        assert!(bytes(&first) != bytes(&other));
+
+        buffer_free(other);
+        buffer_free(second);
+        buffer_free(first);
+        buffer_free(user);
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+
+    #[test]
+    fn show_same_seed_same_output() {
+        let (system_parameters, issuer, user) = issuer_and_user();
+        let entry = ok!(roster_entry_commitment_create_deterministic(PHONE_NUMBER.as_ptr(),
+                                                                     PHONE_NUMBER.len() as uint64_t,
+                                                                     system_parameters.ptr,
+                                                                     system_parameters.len,
+                                                                     SEED.as_ptr()));
+        let show = |seed: &[uint8_t; 32]| ok!(user_show_deterministic(user.ptr, user.len,
+                                                                       entry.ptr, entry.len,
+                                                                       seed.as_ptr()));
+        let first = show(&SEED);
+        let second = show(&SEED);
+        let other = show(&OTHER_SEED);
+
+        assert!(bytes(&first) == bytes(&second));
+        assert!(bytes(&first) != bytes(&other));
+
+        buffer_free(other);
+        buffer_free(second);
+        buffer_free(first);
+        buffer_free(entry);
+        buffer_free(user);
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+
+    #[test]
+    fn null_seed() {
+        let mut out: buf_t = zero_len_and_ptr!();
+
+        assert!(elgamal_keypair_create_deterministic(::core::ptr::null(), &mut out)
+                == Status::InvalidSeed.code());
+        assert!(out.ptr.is_null());
+    }
+}
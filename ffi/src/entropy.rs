@@ -0,0 +1,119 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Seeds for the randomised functions of the FFI APIs.
+//!
+//! A randomised function given a NULL `seed` draws a fresh one from the
+//! operating system.  A function given a host's own `seed` uses it, but only
+//! once: reusing a seed would reuse the randomness of a proof or commitment,
+//! which links the presentations made with it, and so the second use of a
+//! seed within a process is refused with `Status::SeedReused`.
+//!
+//! Functions which should repeat themselves when given the same seed, for
+//! tests and test vectors, are in the `deterministic` module instead.
+//!
+//! # Limits of the Guard
+//!
+//! The guard remembers a 64-bit fingerprint of each of the last
+//! `REMEMBERED_SEEDS` seeds it was given, and forgets older ones, so that its
+//! memory is bounded.  It will therefore catch a host which reuses a constant
+//! or recently used seed, but not one which cycles through a long list of
+//! them.  It also knows nothing of other processes.
+
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+use core::sync::atomic::spin_loop_hint;
+
+use getrandom::getrandom;
+
+use signal_credential::status::Status;
+
+use c::LENGTH_SEED;
+
+/// The number of seeds whose fingerprints the guard remembers.
+pub const REMEMBERED_SEEDS: usize = 4096;
+
+/// Held while the fingerprints are being read or written.
+static LOCK: AtomicBool = AtomicBool::new(false);
+
+/// A ring of the fingerprints of the most recent seeds, where zero is an
+/// empty slot.
+static mut FINGERPRINTS: [u64; REMEMBERED_SEEDS] = [0u64; REMEMBERED_SEEDS];
+
+/// The slot in `FINGERPRINTS` to be overwritten next.
+static mut NEXT: usize = 0;
+
+/// Get a fresh seed from the operating system.
+pub fn os_seed() -> Result<[u8; LENGTH_SEED], Status> {
+    let mut seed: [u8; LENGTH_SEED] = [0u8; LENGTH_SEED];
+
+    match getrandom(&mut seed) {
+        Ok(())  => Ok(seed),
+        Err(_x) => Err(Status::Entropy),
+    }
+}
+
+/// Compute the 64-bit FNV-1a hash of a `seed`, which is never zero.
+///
+/// The fingerprint need not resist collisions made on purpose, since a
+/// collision only causes a fresh seed to be refused.
+fn fingerprint(seed: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for byte in seed.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    if hash == 0 { 1 } else { hash }
+}
+
+/// Record that a host's `seed` is being used, returning
+/// `Status::SeedReused` if it was already used recently by this process.
+pub fn check_seed_unused(seed: &[u8]) -> Result<(), Status> {
+    let fingerprint: u64 = fingerprint(seed);
+
+    while LOCK.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+        spin_loop_hint();
+    }
+
+    // SAFETY: FINGERPRINTS and NEXT are only touched while LOCK is held.
+    let result = unsafe {
+        if FINGERPRINTS.iter().any(|x| *x == fingerprint) {
+            Err(Status::SeedReused)
+        } else {
+            FINGERPRINTS[NEXT] = fingerprint;
+            NEXT = (NEXT + 1) % REMEMBERED_SEEDS;
+            Ok(())
+        }
+    };
+
+    LOCK.store(false, Ordering::Release);
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn os_seeds_differ() {
+        assert!(os_seed().unwrap() != os_seed().unwrap());
+    }
+
+    #[test]
+    fn reused_seed_is_refused() {
+        let seed: [u8; LENGTH_SEED] = [0xe7; LENGTH_SEED];
+        let other: [u8; LENGTH_SEED] = [0xe8; LENGTH_SEED];
+
+        assert_eq!(check_seed_unused(&seed), Ok(()));
+        assert_eq!(check_seed_unused(&other), Ok(()));
+        assert_eq!(check_seed_unused(&seed), Err(Status::SeedReused));
+    }
+}
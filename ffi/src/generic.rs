@@ -0,0 +1,400 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! C-like language FFI API for plain aeonflux credentials.
+//!
+//! The functions in `c` only deal in Signal's credentials on phone numbers.
+//! The functions here issue, present, and verify `aeonflux` credentials on
+//! any attributes, which are passed as canonically-encoded 32-byte scalars
+//! laid end to end.  The issuer's key determines how many attributes its
+//! credentials have, which is chosen when it is created; a request with any
+//! other number is refused with `STATUS_MAC_CREATION`.
+//!
+//! The conventions are those of `c`: every function returns a `status_t` and
+//! writes its result to a `buf_t` which must be released with
+//! `buffer_free()`, and every `seed` should be NULL (see `entropy`).  System
+//! parameters are created with `system_parameters_create()`.
+//!
+//! This module is only built with the `aeonflux_api` feature, which also
+//! generates its header, `aeonflux.h`, with cbindgen (see `build.rs`).
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::boxed::Box;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::boxed::Box;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::slice;
+    } else {
+        use core::slice;
+    }
+}
+
+use aeonflux::credential::CredentialIssuance;
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::credential::CredentialRequest;
+use aeonflux::credential::VerifiedCredential;
+use aeonflux::elgamal;
+use aeonflux::issuer::Issuer;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::nonces::Nonces;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::user::User;
+
+use signal_credential::status::Status;
+
+use c::LENGTH_SEED;
+use c::SeedableRng;
+use c::SignalRng;
+use c::buf_t;
+use c::size_t;
+use c::status_t;
+use c::uint8_t;
+use c::uint64_t;
+
+/// The length of one attribute, a canonically-encoded scalar.
+pub const AEONFLUX_LENGTH_ATTRIBUTE: u64 = 32;
+
+/// Create a new issuer, with fresh keys, of credentials on
+/// `number_of_attributes` attributes, and write it to `out`.
+///
+/// The `number_of_attributes` must be non-zero, or else
+/// `STATUS_WRONG_NUMBER_OF_ATTRIBUTES` is returned.  The `seed` should be
+/// NULL, so that the keys are drawn from the OS.
+#[no_mangle]
+pub extern "C" fn aeonflux_issuer_create(
+    system_parameters: *const uint8_t,
+    system_parameters_length: uint64_t,
+    number_of_attributes: uint64_t,
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    if number_of_attributes == 0 || number_of_attributes > usize::max_value() as uint64_t {
+        return Status::WrongNumberOfAttributes.code();
+    }
+
+    let mut csprng: SignalRng = SignalRng::from_seed(seed_or_entropy!(seed));
+    let system_params = deserialize_or_return!(SystemParameters, system_parameters_length, system_parameters);
+    let issuer: Issuer = ok_or_return!(Issuer::create_with_attributes(system_params, number_of_attributes as usize,
+                                                                       0, &mut csprng));
+
+    write_out!(out, issuer.to_bytes())
+}
+
+/// Write the public parameters of an `issuer` to `out`, for users.
+#[no_mangle]
+pub extern "C" fn aeonflux_issuer_get_issuer_parameters(
+    issuer: *const uint8_t,
+    issuer_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let issuer = deserialize_or_return!(Issuer, issuer_length, issuer);
+
+    write_out!(out, issuer.get_issuer_parameters().to_bytes())
+}
+
+/// Issue a credential on some `attributes`, and write the issuance to `out`.
+///
+/// The `attributes` are as many canonical scalars of `AEONFLUX_LENGTH_ATTRIBUTE`
+/// bytes each as the `issuer` was created for.  The `seed` should be NULL.
+#[no_mangle]
+pub extern "C" fn aeonflux_issuer_issue(
+    issuer: *const uint8_t,
+    issuer_length: uint64_t,
+    attributes: *const uint8_t,
+    attributes_length: uint64_t, // a multiple of AEONFLUX_LENGTH_ATTRIBUTE
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let mut csprng: SignalRng = SignalRng::from_seed(seed_or_entropy!(seed));
+    let issuer = deserialize_or_return!(Issuer, issuer_length, issuer);
+    let request = deserialize_or_return!(CredentialRequest, attributes_length, attributes);
+    let issuance: CredentialIssuance = ok_or_return!(issuer.issue(&request, &mut csprng));
+
+    write_out!(out, issuance.to_bytes())
+}
+
+/// Verify a user's `presentation` of their credential, and write the
+/// verified credential to `out`.
+#[no_mangle]
+pub extern "C" fn aeonflux_issuer_verify(
+    issuer: *const uint8_t,
+    issuer_length: uint64_t,
+    presentation: *const uint8_t,
+    presentation_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let issuer = deserialize_or_return!(Issuer, issuer_length, issuer);
+    let presentation = deserialize_or_return!(CredentialPresentation, presentation_length, presentation);
+    let verified: VerifiedCredential = ok_or_return!(issuer.verify(&presentation));
+
+    write_out!(out, verified.to_bytes())
+}
+
+/// Create a user, without a credential, and write it to `out`.
+///
+/// The `keypair` is an elGamal keypair of `LENGTH_ELGAMAL_KEYPAIR` bytes, or
+/// NULL for a user without one.
+#[no_mangle]
+pub extern "C" fn aeonflux_user_create(
+    system_parameters: *const uint8_t,
+    system_parameters_length: uint64_t,
+    issuer_parameters: *const uint8_t,
+    issuer_parameters_length: uint64_t,
+    keypair: *const uint8_t, // may be NULL, for a user without an elGamal key
+    keypair_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let system_params = deserialize_or_return!(SystemParameters, system_parameters_length, system_parameters);
+    let issuer_params = deserialize_or_return!(IssuerParameters, issuer_parameters_length, issuer_parameters);
+    let key: Option<elgamal::Keypair> = if keypair.is_null() {
+        None
+    } else {
+        let keys: &[u8] = len_and_ptr_to_slice!(keypair_length, keypair);
+
+        Some(ok_or_return!(elgamal::Keypair::from_bytes(keys)))
+    };
+    let user: User = User::new(system_params, issuer_params, key);
+
+    write_out!(out, user.to_bytes())
+}
+
+/// Check an `issuance` from the issuer, and write the `user`, now holding
+/// the issued credential, to `out`.
+#[no_mangle]
+pub extern "C" fn aeonflux_user_obtain_finish(
+    user: *const uint8_t,
+    user_length: uint64_t,
+    issuance: *const uint8_t,
+    issuance_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let mut user = deserialize_or_return!(User, user_length, user);
+    let issuance = deserialize_or_return!(CredentialIssuance, issuance_length, issuance);
+
+    ok_or_return!(user.obtain_finish(Some(&issuance)));
+
+    write_out!(out, user.to_bytes())
+}
+
+/// Present the `user`'s credential, with all of its attributes hidden, and
+/// write the presentation to `out`.
+///
+/// The `seed` should be NULL, since presentations made with the same seed
+/// are linkable.
+#[no_mangle]
+pub extern "C" fn aeonflux_user_show(
+    user: *const uint8_t,
+    user_length: uint64_t,
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let mut csprng: SignalRng = SignalRng::from_seed(seed_or_entropy!(seed));
+    let user = deserialize_or_return!(User, user_length, user);
+    let number_of_attributes: usize = match user.credential {
+        Some(ref credential) => credential.attributes.len(),
+        None                 => return Status::MissingData.code(),
+    };
+    let nonces: Nonces = Nonces::new(&mut csprng, number_of_attributes);
+    let presentation: CredentialPresentation = ok_or_return!(user.show(&nonces, &mut csprng));
+
+    write_out!(out, presentation.to_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use c::buffer_free;
+    use c::system_parameters_create;
+
+    const SEED: [uint8_t; 32] = [  0,  1,  2,  3,  4,  5,  6,  7,
+                                   8,  9, 10, 11, 12, 13, 14, 15,
+                                  16, 17, 18, 19, 20, 21, 22, 23,
+                                  24, 25, 26, 27, 28, 29, 30, 31, ];
+
+    /// A canonically-encoded scalar, for use as an attribute.
+    const ATTRIBUTE: [uint8_t; 32] = [7; 32];
+
+    macro_rules! assert_ok {
+        ($f:ident($($arg:expr),*)) => {{
+            let mut out: buf_t = zero_len_and_ptr!();
+            let status: status_t = $f($($arg,)* &mut out);
+
+            assert!(status == Status::Ok.code(), "{} returned status {}", stringify!($f), status);
+
+            out
+        }}
+    }
+
+    macro_rules! assert_status {
+        ($status:expr, $f:ident($($arg:expr),*)) => {{
+            let mut out: buf_t = zero_len_and_ptr!();
+            let status: status_t = $f($($arg,)* &mut out);
+
+            assert!(status == $status.code(), "{} returned status {}", stringify!($f), status);
+            assert!(out.ptr.is_null());
+        }}
+    }
+
+    fn bytes(buffer: &buf_t) -> &[u8] {
+        unsafe { slice::from_raw_parts(buffer.ptr, buffer.len as size_t) }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn full_protocol() {
+        let system_parameters = assert_ok!(system_parameters_create(SEED.as_ptr()));
+        let issuer = assert_ok!(aeonflux_issuer_create(system_parameters.ptr, system_parameters.len, 1,
+                                                       ::core::ptr::null()));
+        let issuer_parameters = assert_ok!(aeonflux_issuer_get_issuer_parameters(issuer.ptr, issuer.len));
+        let issuance = assert_ok!(aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                                        ATTRIBUTE.as_ptr(), ATTRIBUTE.len() as uint64_t,
+                                                        ::core::ptr::null()));
+        let user = assert_ok!(aeonflux_user_create(system_parameters.ptr, system_parameters.len,
+                                                   issuer_parameters.ptr, issuer_parameters.len,
+                                                   ::core::ptr::null(), 0));
+        let user_with_credential = assert_ok!(aeonflux_user_obtain_finish(user.ptr, user.len,
+                                                                          issuance.ptr, issuance.len));
+        let presentation = assert_ok!(aeonflux_user_show(user_with_credential.ptr, user_with_credential.len,
+                                                         ::core::ptr::null()));
+        let verified = assert_ok!(aeonflux_issuer_verify(issuer.ptr, issuer.len,
+                                                         presentation.ptr, presentation.len));
+
+        // The credential is on the attributes requested.
+        let credential = User::from_bytes(bytes(&user_with_credential)).unwrap().credential.unwrap();
+
+        assert!(credential.attributes[0].to_bytes() == ATTRIBUTE);
+        assert!(VerifiedCredential::from_bytes(bytes(&verified)).is_ok());
+
+        // A user without a credential has nothing to show.
+        assert_status!(Status::MissingData, aeonflux_user_show(user.ptr, user.len, ::core::ptr::null()));
+
+        buffer_free(verified);
+        buffer_free(presentation);
+        buffer_free(user_with_credential);
+        buffer_free(user);
+        buffer_free(issuance);
+        buffer_free(issuer_parameters);
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn many_attributes() {
+        let system_parameters = assert_ok!(system_parameters_create(SEED.as_ptr()));
+        let issuer = assert_ok!(aeonflux_issuer_create(system_parameters.ptr, system_parameters.len, 3,
+                                                       ::core::ptr::null()));
+        let issuer_parameters = assert_ok!(aeonflux_issuer_get_issuer_parameters(issuer.ptr, issuer.len));
+        let mut attributes: [uint8_t; 96] = [7; 96];
+
+        attributes[32] = 7;
+        attributes[64] = 9;
+
+        let issuance = assert_ok!(aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                                        attributes.as_ptr(), attributes.len() as uint64_t,
+                                                        ::core::ptr::null()));
+        let user = assert_ok!(aeonflux_user_create(system_parameters.ptr, system_parameters.len,
+                                                   issuer_parameters.ptr, issuer_parameters.len,
+                                                   ::core::ptr::null(), 0));
+        let user_with_credential = assert_ok!(aeonflux_user_obtain_finish(user.ptr, user.len,
+                                                                          issuance.ptr, issuance.len));
+        let presentation = assert_ok!(aeonflux_user_show(user_with_credential.ptr, user_with_credential.len,
+                                                         ::core::ptr::null()));
+        let verified = assert_ok!(aeonflux_issuer_verify(issuer.ptr, issuer.len,
+                                                         presentation.ptr, presentation.len));
+
+        // The credential is on every one of the attributes requested.
+        let credential = User::from_bytes(bytes(&user_with_credential)).unwrap().credential.unwrap();
+
+        for (i, attribute) in credential.attributes.iter().enumerate() {
+            assert!(attribute.to_bytes()[..] == attributes[32 * i..32 * (i + 1)]);
+        }
+        assert!(credential.attributes.len() == 3);
+        assert!(VerifiedCredential::from_bytes(bytes(&verified)).is_ok());
+
+        // The issuer refuses requests on any other number of attributes.
+        assert_status!(Status::MacCreation,
+                       aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                             attributes.as_ptr(), 64,
+                                             ::core::ptr::null()));
+
+        // An issuer of credentials on no attributes cannot be created.
+        assert_status!(Status::WrongNumberOfAttributes,
+                       aeonflux_issuer_create(system_parameters.ptr, system_parameters.len, 0,
+                                              ::core::ptr::null()));
+
+        buffer_free(verified);
+        buffer_free(presentation);
+        buffer_free(user_with_credential);
+        buffer_free(user);
+        buffer_free(issuance);
+        buffer_free(issuer_parameters);
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+
+    #[test]
+    fn malformed_attributes() {
+        let system_parameters = assert_ok!(system_parameters_create(SEED.as_ptr()));
+        let issuer = assert_ok!(aeonflux_issuer_create(system_parameters.ptr, system_parameters.len, 1,
+                                                       ::core::ptr::null()));
+        let mut two: [uint8_t; 64] = [7; 64];
+
+        assert_status!(Status::MacCreation,
+                       aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                             two.as_ptr(), two.len() as uint64_t,
+                                             ::core::ptr::null()));
+        assert_status!(Status::WrongNumberOfBytes,
+                       aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                             two.as_ptr(), 33,
+                                             ::core::ptr::null()));
+        assert_status!(Status::NullPointer,
+                       aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                             two.as_ptr(), 0,
+                                             ::core::ptr::null()));
+
+        // Scalars must be reduced modulo the group order.
+        two[31] = 0xff;
+        assert_status!(Status::ScalarFormat,
+                       aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                             two.as_ptr(), 32,
+                                             ::core::ptr::null()));
+
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+}
@@ -0,0 +1,600 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! C-like language FFI API over opaque handles.
+//!
+//! Rather than deserialising a `SignalIssuer` or `SignalUser` from bytes on
+//! every call, as the functions in `c` do, a caller may deserialise it once
+//! into a handle, use the handle for as many operations as they like, and
+//! then free it.  This also keeps the issuer's secret key out of the caller's
+//! memory after the handle has been created.
+//!
+//! # Thread Safety
+//!
+//! No function other than `issuer_handle_free()` or `user_handle_free()`
+//! modifies the state behind a handle, so a handle may be used by any number
+//! of threads at once.  Freeing a handle while another thread is still using
+//! it, or using it after it has been freed, is undefined behaviour.
+//!
+//! # Zeroisation
+//!
+//! Freeing a handle overwrites all secret state behind it, including the
+//! issuer's keys and the user's credential and identifier, with zeroes.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::boxed::Box;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::boxed::Box;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::ptr;
+        use std::slice;
+    } else {
+        use core::ptr;
+        use core::slice;
+    }
+}
+
+use clear_on_drop::clear::Clear;
+
+use signal_credential::account_identifier::AccountIdentifier;
+use signal_credential::credential::SignalCredentialIssuance;
+use signal_credential::credential::SignalCredentialPresentation;
+use signal_credential::credential::VerifiedSignalCredential;
+use signal_credential::issuer::IssuerParameters;
+use signal_credential::issuer::SignalIssuer;
+use signal_credential::parameters::SystemParameters;
+use signal_credential::phone_number::RosterEntryCommitment;
+use signal_credential::status::Status;
+use signal_credential::user::SignalUser;
+
+use c::LENGTH_SEED;
+use c::SeedableRng;
+use c::SignalRng;
+use c::buf_t;
+use c::size_t;
+use c::status_t;
+use c::uint8_t;
+use c::uint64_t;
+
+/// An opaque handle to a `SignalIssuer`.
+#[allow(non_camel_case_types)]
+pub struct issuer_t {
+    // The issuer's secret keys overwrite themselves when dropped.
+    issuer: SignalIssuer,
+}
+
+/// An opaque handle to a `SignalUser`.
+#[allow(non_camel_case_types)]
+pub struct user_t {
+    user: SignalUser,
+}
+
+impl user_t {
+    /// Overwrite the user's credential and identifier with null bytes.  Their
+    /// elGamal secret key, if any, overwrites itself when dropped.
+    fn clear(&mut self) {
+        if let Some(ref mut credential) = self.user.user.credential {
+            credential.mac.nonce.clear();
+            credential.mac.mac.clear();
+
+            for attribute in credential.attributes.iter_mut() {
+                attribute.clear();
+            }
+        }
+        match self.user.identifier {
+            AccountIdentifier::PhoneNumber(ref mut x) => x.0.clear(),
+            AccountIdentifier::Uuid(ref mut x)        => x.clear(),
+            AccountIdentifier::Username(ref mut x)    => unsafe { x.as_bytes_mut() }.clear(),
+        }
+    }
+}
+
+impl Drop for user_t {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Borrow the object behind a handle, or return `Status::NullPointer`.
+macro_rules! handle_or_return {
+    ($handle:ident) => {{
+        if $handle.is_null() {
+            return Status::NullPointer.code();
+        }
+        unsafe { &*$handle }
+    }}
+}
+
+/// Check that an out-parameter for a handle is non-NULL, and clear it.
+macro_rules! handle_out_or_return {
+    ($out:ident) => {{
+        if $out.is_null() {
+            return Status::NullPointer.code();
+        }
+        unsafe { *$out = ptr::null_mut(); }
+    }}
+}
+
+/// Create an `issuer_t` handle from a serialised `SignalIssuer`, which must
+/// later be released with `issuer_handle_free()`.
+#[no_mangle]
+pub extern "C" fn issuer_handle_from_bytes(
+    issuer: *const uint8_t,
+    issuer_length: uint64_t,
+    out: *mut *mut issuer_t,
+) -> status_t
+{
+    handle_out_or_return!(out);
+
+    let issuer = deserialize_or_return!(SignalIssuer, issuer_length, issuer);
+
+    unsafe { *out = Box::into_raw(Box::new(issuer_t { issuer })); }
+
+    Status::Ok.code()
+}
+
+/// Release an `issuer_t` handle, overwriting the issuer's secret keys.
+///
+/// Passing a NULL `handle` is a no-op.
+#[no_mangle]
+pub extern "C" fn issuer_handle_free(handle: *mut issuer_t) {
+    if handle.is_null() {
+        return;
+    }
+    unsafe { drop(Box::from_raw(handle)); }
+}
+
+#[no_mangle]
+pub extern "C" fn issuer_handle_get_issuer_parameters(
+    handle: *const issuer_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let handle: &issuer_t = handle_or_return!(handle);
+    let issuer_parameters: IssuerParameters = handle.issuer.get_issuer_parameters();
+    let serialized: Vec<u8> = serialize_or_return!(&issuer_parameters);
+
+    write_out!(out, serialized)
+}
+
+/// Issue a credential for a `phone_number`, without verifying that the user
+/// owns it or limiting how many credentials it may obtain.  As for
+/// `SignalIssuer::issue()`, this is unsafe to expose in a deployment.
+#[no_mangle]
+pub extern "C" fn issuer_handle_issue(
+    handle: *const issuer_t,
+    phone_number: *const uint8_t,
+    phone_number_length: uint64_t,
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let handle: &issuer_t = handle_or_return!(handle);
+    let mut csprng: SignalRng = SignalRng::from_seed(seed_or_entropy!(seed));
+    let user_number: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
+    let issuance: SignalCredentialIssuance = ok_or_return!(handle.issuer.issue(&user_number, &mut csprng));
+    let serialized: Vec<u8> = serialize_or_return!(&issuance);
+
+    write_out!(out, serialized)
+}
+
+/// As `issuer_handle_issue()`, but with a required `seed` which may be
+/// reused, for tests only.  See the `deterministic` module.
+#[cfg(any(test, feature = "deterministic"))]
+#[no_mangle]
+pub extern "C" fn issuer_handle_issue_deterministic(
+    handle: *const issuer_t,
+    phone_number: *const uint8_t,
+    phone_number_length: uint64_t,
+    seed: *const uint8_t, // must be 32 bytes exactly
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let handle: &issuer_t = handle_or_return!(handle);
+    let mut csprng: SignalRng = csprng_from_seed!(seed);
+    let user_number: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
+    let issuance: SignalCredentialIssuance = ok_or_return!(handle.issuer.issue(&user_number, &mut csprng));
+    let serialized: Vec<u8> = serialize_or_return!(&issuance);
+
+    write_out!(out, serialized)
+}
+
+#[no_mangle]
+pub extern "C" fn issuer_handle_verify(
+    handle: *const issuer_t,
+    presentation: *const uint8_t,
+    presentation_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let handle: &issuer_t = handle_or_return!(handle);
+    let presentation_deserialized = deserialize_or_return!(SignalCredentialPresentation,
+                                                           presentation_length,
+                                                           presentation);
+    let verified = ok_or_return!(handle.issuer.verify(presentation_deserialized));
+    let serialized = serialize_or_return!(&verified);
+
+    write_out!(out, serialized)
+}
+
+#[no_mangle]
+pub extern "C" fn issuer_handle_verify_roster_membership(
+    handle: *const issuer_t,
+    verified_credential: *const uint8_t,
+    verified_credential_length: uint64_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let handle: &issuer_t = handle_or_return!(handle);
+    let verified = deserialize_or_return!(VerifiedSignalCredential,
+                                          verified_credential_length,
+                                          verified_credential);
+    let roster_entry_commitment = ok_or_return!(handle.issuer.verify_roster_membership(&verified));
+    let serialized = serialize_or_return!(&roster_entry_commitment);
+
+    write_out!(out, serialized)
+}
+
+/// Create a `user_t` handle from a serialised `SignalUser`, which must later
+/// be released with `user_handle_free()`.
+#[no_mangle]
+pub extern "C" fn user_handle_from_bytes(
+    user: *const uint8_t,
+    user_length: uint64_t,
+    out: *mut *mut user_t,
+) -> status_t
+{
+    handle_out_or_return!(out);
+
+    let user = deserialize_or_return!(SignalUser, user_length, user);
+
+    unsafe { *out = Box::into_raw(Box::new(user_t { user })); }
+
+    Status::Ok.code()
+}
+
+/// As `user_obtain_finish()`, but create a `user_t` handle for the user
+/// rather than serialising it.
+#[no_mangle]
+pub extern "C" fn user_handle_obtain_finish(
+    phone_number: *const uint8_t,
+    phone_number_length: uint64_t,
+    system_parameters: *const uint8_t,
+    system_parameters_length: uint64_t,
+    issuer_parameters: *const uint8_t,
+    issuer_parameters_length: uint64_t,
+    issuance: *const uint8_t,
+    issuance_length: uint64_t,
+    out: *mut *mut user_t,
+) -> status_t
+{
+    handle_out_or_return!(out);
+
+    let system_params = deserialize_or_return!(SystemParameters, system_parameters_length, system_parameters);
+    let issuer_params = deserialize_or_return!(IssuerParameters, issuer_parameters_length, issuer_parameters);
+    let issuance_deserialized = deserialize_or_return!(SignalCredentialIssuance, issuance_length, issuance);
+    let phone_number_bytes: &[u8] = len_and_ptr_to_slice!(phone_number_length, phone_number);
+    let mut user: SignalUser = ok_or_return!(SignalUser::new(system_params,
+                                                             issuer_params,
+                                                             None,
+                                                             phone_number_bytes));
+
+    // Box the user before it obtains its credential, so that the credential
+    // is never moved, and thus never copied, once it exists.
+    let mut handle: Box<user_t> = Box::new(user_t { user });
+
+    ok_or_return!(handle.user.obtain_finish(Some(&issuance_deserialized)));
+
+    unsafe { *out = Box::into_raw(handle); }
+
+    Status::Ok.code()
+}
+
+/// Serialise the user behind a `user_t` handle, for example to persist it.
+#[no_mangle]
+pub extern "C" fn user_handle_to_bytes(
+    handle: *const user_t,
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let handle: &user_t = handle_or_return!(handle);
+    let serialized: Vec<u8> = serialize_or_return!(&handle.user);
+
+    write_out!(out, serialized)
+}
+
+#[no_mangle]
+pub extern "C" fn user_handle_show(
+    handle: *const user_t,
+    roster_entry_commitment: *const uint8_t,
+    roster_entry_commitment_length: uint64_t,
+    seed: *const uint8_t, // 32 bytes, or NULL to use the OS's entropy
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let handle: &user_t = handle_or_return!(handle);
+    let mut csprng: SignalRng = SignalRng::from_seed(seed_or_entropy!(seed));
+    let entry = deserialize_or_return!(RosterEntryCommitment,
+                                       roster_entry_commitment_length,
+                                       roster_entry_commitment);
+    let presentation: SignalCredentialPresentation = ok_or_return!(handle.user.show(&mut csprng, &entry));
+    let serialized: Vec<u8> = serialize_or_return!(&presentation);
+
+    write_out!(out, serialized)
+}
+
+/// As `user_handle_show()`, but with a required `seed` which may be reused,
+/// for tests only.  See the `deterministic` module.
+#[cfg(any(test, feature = "deterministic"))]
+#[no_mangle]
+pub extern "C" fn user_handle_show_deterministic(
+    handle: *const user_t,
+    roster_entry_commitment: *const uint8_t,
+    roster_entry_commitment_length: uint64_t,
+    seed: *const uint8_t, // must be 32 bytes exactly
+    out: *mut buf_t,
+) -> status_t
+{
+    out_or_return!(out);
+
+    let handle: &user_t = handle_or_return!(handle);
+    let mut csprng: SignalRng = csprng_from_seed!(seed);
+    let entry = deserialize_or_return!(RosterEntryCommitment,
+                                       roster_entry_commitment_length,
+                                       roster_entry_commitment);
+    let presentation: SignalCredentialPresentation = ok_or_return!(handle.user.show(&mut csprng, &entry));
+    let serialized: Vec<u8> = serialize_or_return!(&presentation);
+
+    write_out!(out, serialized)
+}
+
+/// Release a `user_t` handle, overwriting the user's secret state.
+///
+/// Passing a NULL `handle` is a no-op.
+#[no_mangle]
+pub extern "C" fn user_handle_free(handle: *mut user_t) {
+    if handle.is_null() {
+        return;
+    }
+    unsafe { drop(Box::from_raw(handle)); }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use c::*;
+    use deterministic::issuer_issue_deterministic;
+
+    const SEED: [uint8_t; 32] = [  0,  1,  2,  3,  4,  5,  6,  7,
+                                   8,  9, 10, 11, 12, 13, 14, 15,
+                                  16, 17, 18, 19, 20, 21, 22, 23,
+                                  24, 25, 26, 27, 28, 29, 30, 31, ];
+    const PHONE_NUMBER: &'static [uint8_t] = &[ 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4 ];
+
+    /// Call an FFI function with a fresh out-parameter for a `buf_t`
+    /// appended to its arguments, check that it succeeded, and return the
+    /// `buf_t`.
+    macro_rules! assert_ok {
+        ($f:ident($($arg:expr),*)) => {{
+            let mut out: buf_t = zero_len_and_ptr!();
+            let status: status_t = $f($($arg,)* &mut out);
+
+            assert!(status == Status::Ok.code(), "{} returned status {}", stringify!($f), status);
+
+            out
+        }}
+    }
+
+    /// Call an FFI function with a fresh out-parameter for a handle appended
+    /// to its arguments, check that it succeeded, and return the handle.
+    macro_rules! assert_handle {
+        ($f:ident($($arg:expr),*)) => {{
+            let mut out = ptr::null_mut();
+            let status: status_t = $f($($arg,)* &mut out);
+
+            assert!(status == Status::Ok.code(), "{} returned status {}", stringify!($f), status);
+            assert!(!out.is_null());
+
+            out
+        }}
+    }
+
+    fn bytes(buffer: &buf_t) -> &[u8] {
+        unsafe { slice::from_raw_parts(buffer.ptr, buffer.len as size_t) }
+    }
+
+    fn create_issuer(system_parameters: &buf_t) -> buf_t {
+        let keypair = assert_ok!(issuer_create(system_parameters.ptr, system_parameters.len, ptr::null()));
+        let issuer = assert_ok!(issuer_new(system_parameters.ptr, system_parameters.len,
+                                           keypair.ptr, keypair.len));
+        buffer_free(keypair);
+
+        issuer
+    }
+
+    #[test]
+    fn handles_are_send_and_sync() {
+        fn assert_send_and_sync<T: Send + Sync>() {}
+
+        assert_send_and_sync::<issuer_t>();
+        assert_send_and_sync::<user_t>();
+    }
+
+    #[test]
+    fn issuer_handle_get_issuer_parameters_matches_bytes_api() {
+        let system_parameters = assert_ok!(system_parameters_create(SEED.as_ptr()));
+        let issuer = create_issuer(&system_parameters);
+        let handle = assert_handle!(issuer_handle_from_bytes(issuer.ptr, issuer.len));
+        let from_bytes = assert_ok!(issuer_get_issuer_parameters(issuer.ptr, issuer.len));
+        let from_handle = assert_ok!(issuer_handle_get_issuer_parameters(handle));
+
+        assert!(bytes(&from_bytes) == bytes(&from_handle));
+
+        buffer_free(from_handle);
+        buffer_free(from_bytes);
+        issuer_handle_free(handle);
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn issuer_handle_issue_deterministic_matches_bytes_api() {
+        let system_parameters = assert_ok!(system_parameters_create(SEED.as_ptr()));
+        let issuer = create_issuer(&system_parameters);
+        let handle = assert_handle!(issuer_handle_from_bytes(issuer.ptr, issuer.len));
+        let from_bytes = assert_ok!(issuer_issue_deterministic(issuer.ptr, issuer.len,
+                                                               PHONE_NUMBER.as_ptr(),
+                                                               PHONE_NUMBER.len() as uint64_t,
+                                                               SEED.as_ptr()));
+        let from_handle = assert_ok!(issuer_handle_issue_deterministic(handle,
+                                                                       PHONE_NUMBER.as_ptr(),
+                                                                       PHONE_NUMBER.len() as uint64_t,
+                                                                       SEED.as_ptr()));
+
+        assert!(bytes(&from_bytes) == bytes(&from_handle));
+
+        buffer_free(from_handle);
+        buffer_free(from_bytes);
+        issuer_handle_free(handle);
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn full_protocol_with_handles() {
+        let system_parameters = assert_ok!(system_parameters_create(SEED.as_ptr()));
+        let issuer = create_issuer(&system_parameters);
+        let issuer_handle = assert_handle!(issuer_handle_from_bytes(issuer.ptr, issuer.len));
+        let issuer_parameters = assert_ok!(issuer_handle_get_issuer_parameters(issuer_handle));
+        let issuance = assert_ok!(issuer_handle_issue(issuer_handle,
+                                                      PHONE_NUMBER.as_ptr(),
+                                                      PHONE_NUMBER.len() as uint64_t,
+                                                      ptr::null()));
+        let user_handle = assert_handle!(user_handle_obtain_finish(PHONE_NUMBER.as_ptr(),
+                                                                   PHONE_NUMBER.len() as uint64_t,
+                                                                   system_parameters.ptr,
+                                                                   system_parameters.len,
+                                                                   issuer_parameters.ptr,
+                                                                   issuer_parameters.len,
+                                                                   issuance.ptr,
+                                                                   issuance.len));
+        let entry = assert_ok!(roster_entry_commitment_create(PHONE_NUMBER.as_ptr(),
+                                                              PHONE_NUMBER.len() as uint64_t,
+                                                              system_parameters.ptr,
+                                                              system_parameters.len,
+                                                              ptr::null()));
+        let presentation = assert_ok!(user_handle_show(user_handle, entry.ptr, entry.len, ptr::null()));
+        let verified = assert_ok!(issuer_handle_verify(issuer_handle, presentation.ptr, presentation.len));
+        let roster_entry_commitment = assert_ok!(issuer_handle_verify_roster_membership(issuer_handle,
+                                                                                        verified.ptr,
+                                                                                        verified.len));
+        // A user serialised from a handle can be loaded into a new one.
+        let user = assert_ok!(user_handle_to_bytes(user_handle));
+
+        assert!(user.len == LENGTH_USER);
+
+        let reloaded = assert_handle!(user_handle_from_bytes(user.ptr, user.len));
+        let reserialized = assert_ok!(user_handle_to_bytes(reloaded));
+
+        assert!(bytes(&user) == bytes(&reserialized));
+
+        buffer_free(reserialized);
+        user_handle_free(reloaded);
+        buffer_free(user);
+        buffer_free(roster_entry_commitment);
+        buffer_free(verified);
+        buffer_free(presentation);
+        buffer_free(entry);
+        user_handle_free(user_handle);
+        buffer_free(issuance);
+        buffer_free(issuer_parameters);
+        issuer_handle_free(issuer_handle);
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn user_handle_clear_overwrites_secrets() {
+        let system_parameters = assert_ok!(system_parameters_create(SEED.as_ptr()));
+        let issuer = create_issuer(&system_parameters);
+        let issuer_parameters = assert_ok!(issuer_get_issuer_parameters(issuer.ptr, issuer.len));
+        let issuance = assert_ok!(issuer_issue(issuer.ptr, issuer.len,
+                                               PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as uint64_t,
+                                               ptr::null()));
+        let user = assert_ok!(user_obtain_finish(PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as uint64_t,
+                                                 system_parameters.ptr, system_parameters.len,
+                                                 issuer_parameters.ptr, issuer_parameters.len,
+                                                 issuance.ptr, issuance.len));
+        let mut handle = user_t { user: SignalUser::from_bytes(bytes(&user)).unwrap() };
+
+        handle.clear();
+
+        match handle.user.identifier {
+            AccountIdentifier::PhoneNumber(ref x) => assert!(x.0.to_bytes() == [0u8; 32]),
+            _ => panic!(),
+        }
+        let credential = handle.user.user.credential.as_ref().unwrap();
+
+        for attribute in credential.attributes.iter() {
+            assert!(attribute.to_bytes() == [0u8; 32]);
+        }
+
+        buffer_free(user);
+        buffer_free(issuance);
+        buffer_free(issuer_parameters);
+        buffer_free(issuer);
+        buffer_free(system_parameters);
+    }
+
+    #[test]
+    fn null_and_malformed_handles() {
+        let mut out: buf_t = zero_len_and_ptr!();
+        let mut issuer_out: *mut issuer_t = ptr::null_mut();
+        let mut user_out: *mut user_t = ptr::null_mut();
+
+        assert!(issuer_handle_get_issuer_parameters(ptr::null(), &mut out) == Status::NullPointer.code());
+        assert!(out.ptr.is_null());
+        assert!(user_handle_to_bytes(ptr::null(), &mut out) == Status::NullPointer.code());
+        assert!(out.ptr.is_null());
+
+        assert!(issuer_handle_from_bytes(SEED.as_ptr(), SEED.len() as uint64_t, &mut issuer_out)
+                == Status::WrongNumberOfBytes.code());
+        assert!(issuer_out.is_null());
+        assert!(user_handle_from_bytes(SEED.as_ptr(), SEED.len() as uint64_t, &mut user_out)
+                == Status::MissingData.code());
+        assert!(user_out.is_null());
+
+        issuer_handle_free(ptr::null_mut());
+        user_handle_free(ptr::null_mut());
+    }
+}
@@ -0,0 +1,97 @@
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+#ifndef AEONFLUX_H
+#define AEONFLUX_H
+
+/* This file is generated by cbindgen from ffi/src/generic.rs.  Do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include "credential.h"
+
+#define AEONFLUX_LENGTH_ATTRIBUTE 32
+
+/**
+ * Create a new issuer, with fresh keys, of credentials on
+ * `number_of_attributes` attributes, and write it to `out`.
+ * The `number_of_attributes` must be non-zero, or else
+ * `STATUS_WRONG_NUMBER_OF_ATTRIBUTES` is returned.  The `seed` should be
+ * NULL, so that the keys are drawn from the OS.
+ */
+status_t aeonflux_issuer_create(const uint8_t *system_parameters,
+                                uint64_t system_parameters_length,
+                                uint64_t number_of_attributes,
+                                const uint8_t *seed,
+                                buf_t *out);
+
+/**
+ * Write the public parameters of an `issuer` to `out`, for users.
+ */
+status_t aeonflux_issuer_get_issuer_parameters(const uint8_t *issuer,
+                                               uint64_t issuer_length,
+                                               buf_t *out);
+
+/**
+ * Issue a credential on some `attributes`, and write the issuance to `out`.
+ * The `attributes` are as many canonical scalars of `AEONFLUX_LENGTH_ATTRIBUTE`
+ * bytes each as the `issuer` was created for.  The `seed` should be NULL.
+ */
+status_t aeonflux_issuer_issue(const uint8_t *issuer,
+                               uint64_t issuer_length,
+                               const uint8_t *attributes,
+                               uint64_t attributes_length,
+                               const uint8_t *seed,
+                               buf_t *out);
+
+/**
+ * Verify a user's `presentation` of their credential, and write the
+ * verified credential to `out`.
+ */
+status_t aeonflux_issuer_verify(const uint8_t *issuer,
+                                uint64_t issuer_length,
+                                const uint8_t *presentation,
+                                uint64_t presentation_length,
+                                buf_t *out);
+
+/**
+ * Create a user, without a credential, and write it to `out`.
+ * The `keypair` is an elGamal keypair of `LENGTH_ELGAMAL_KEYPAIR` bytes, or
+ * NULL for a user without one.
+ */
+status_t aeonflux_user_create(const uint8_t *system_parameters,
+                              uint64_t system_parameters_length,
+                              const uint8_t *issuer_parameters,
+                              uint64_t issuer_parameters_length,
+                              const uint8_t *keypair,
+                              uint64_t keypair_length,
+                              buf_t *out);
+
+/**
+ * Check an `issuance` from the issuer, and write the `user`, now holding
+ * the issued credential, to `out`.
+ */
+status_t aeonflux_user_obtain_finish(const uint8_t *user,
+                                     uint64_t user_length,
+                                     const uint8_t *issuance,
+                                     uint64_t issuance_length,
+                                     buf_t *out);
+
+/**
+ * Present the `user`'s credential, with all of its attributes hidden, and
+ * write the presentation to `out`.
+ * The `seed` should be NULL, since presentations made with the same seed
+ * are linkable.
+ */
+status_t aeonflux_user_show(const uint8_t *user,
+                            uint64_t user_length,
+                            const uint8_t *seed,
+                            buf_t *out);
+
+#endif /* AEONFLUX_H */
@@ -5,22 +5,138 @@
 // Authors:
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 
+#ifndef CREDENTIAL_H
+#define CREDENTIAL_H
+
 #include <stdint.h>
+#include <sys/types.h>
 
 const uint LENGTH_SEED = 32;
 const uint LENGTH_SYSTEM_PARAMETERS = 64;
 const uint LENGTH_ISSUER = 160;
 const uint LENGTH_ISSUER_PARAMETERS = 32;
 const uint LENGTH_ISSUER_KEYPAIR = 96;
-const uint LENGTH_USER = 288;
-const uint LENGTH_CREDENTIAL_ISSUANCE = 328;
-const uint LENGTH_CREDENTIAL_PRESENTATION = 448;
-const uint LENGTH_VERIFIED_CREDENTIAL = 448;
+const uint LENGTH_USER = 289;
+const uint LENGTH_CREDENTIAL_ISSUANCE = 304;
+const uint LENGTH_CREDENTIAL_PRESENTATION = 480;
+const uint LENGTH_VERIFIED_CREDENTIAL = 480;
 const uint LENGTH_ROSTER_ENTRY_COMMITMENT = 64;
 const uint LENGTH_ROSTER_ENTRY_COMMITMENT_SANS_OPENING = 32;
+const uint LENGTH_ELGAMAL_KEYPAIR = 64;
+const uint LENGTH_ELGAMAL_PUBLIC_KEY = 32;
+
+/**
+ * A kind of serialised object, whose length may be found with `length_of()`.
+ */
+// RUST_C_COUPLED: signal-credential/src/sizes.rs SerializedObject
+typedef uint32_t object_t;
+
+enum {
+  OBJECT_SYSTEM_PARAMETERS = 1,
+  OBJECT_ISSUER_KEYPAIR = 2,
+  OBJECT_ISSUER = 3,
+  OBJECT_ISSUER_PARAMETERS = 4,
+  OBJECT_ELGAMAL_KEYPAIR = 5,
+  OBJECT_ELGAMAL_PUBLIC_KEY = 6,
+  OBJECT_USER = 7,
+  OBJECT_CREDENTIAL_ISSUANCE = 8,
+  OBJECT_CREDENTIAL_PRESENTATION = 9,
+  OBJECT_VERIFIED_CREDENTIAL = 10,
+  OBJECT_ROSTER_ENTRY_COMMITMENT = 11,
+  OBJECT_ROSTER_ENTRY_COMMITMENT_SANS_OPENING = 12,
+};
+
+/**
+ * The status returned by every function in this API other than
+ * `buffer_free()`.
+ *
+ * `STATUS_OK` is returned on success.  Any other value is one of the codes
+ * below, which are grouped by the Rust error type they mirror.  A code is
+ * never reused for a different error.
+ */
+// RUST_C_COUPLED: signal-credential/src/status.rs Status
+typedef uint32_t status_t;
+
+enum {
+  STATUS_OK = 0,
+
+  /* Errors in the arguments to a function. */
+  STATUS_NULL_POINTER = 1,  /**< A required pointer was NULL or a required buffer was empty. */
+  STATUS_INVALID_SEED = 2,  /**< The seed was not `LENGTH_SEED` bytes. */
+  STATUS_SERIALISATION = 3,
+  STATUS_UNKNOWN_OBJECT = 4,  /**< An `object_t` passed to `length_of()` was not one of the `OBJECT_*` codes. */
+  STATUS_SEED_REUSED = 5,  /**< The seed was already passed to a function in this process. */
+  STATUS_ENTROPY = 6,  /**< The operating system could not provide randomness. */
+
+  /* aeonflux::errors::CredentialError */
+  STATUS_BAD_ATTRIBUTE = 100,
+  STATUS_CREDENTIAL_ISSUANCE = 101,
+  STATUS_MAC_CREATION = 102,
+  STATUS_MAC_VERIFICATION = 103,  /**< A proof or MAC in a presentation did not verify. */
+  STATUS_MISSING_DATA = 104,
+  STATUS_NO_ISSUER_KEY = 105,
+  STATUS_NO_ISSUER_PARAMETERS = 106,
+  STATUS_NO_SYSTEM_PARAMETERS = 107,
+  STATUS_POINT_DECOMPRESSION = 108,
+  STATUS_SCALAR_FORMAT = 109,
+  STATUS_WRONG_NUMBER_OF_ATTRIBUTES = 110,
+  STATUS_WRONG_NUMBER_OF_BYTES = 111,  /**< A serialised input had the wrong length. */
+  STATUS_VERIFICATION_FAILURE = 112,
+
+  /* aeonflux::errors::MacError */
+  STATUS_KEYPAIR_DESERIALISATION = 200,
+  STATUS_MAC_POINT_DECOMPRESSION = 201,
+  STATUS_MAC_SCALAR_FORMAT = 202,
+  STATUS_MESSAGE_LENGTH = 203,
+  STATUS_MAC_AUTHENTICATION = 204,
+
+  /* signal_credential::errors::PhoneNumberError */
+  STATUS_PHONE_NUMBER_LENGTH_EXCEEDED = 300,
+  STATUS_INVALID_PHONE_NUMBER = 301,
+  STATUS_EMPTY_PHONE_NUMBER = 302,
+  STATUS_MISSING_PLUS_SIGN = 303,
+  STATUS_INVALID_CHARACTER = 304,
+  STATUS_INVALID_COUNTRY_CODE = 305,
+  STATUS_TOO_FEW_DIGITS = 306,
+  STATUS_TOO_MANY_DIGITS = 307,
+
+  /* signal_credential::errors::AccountIdentifierError */
+  STATUS_INVALID_USERNAME = 400,
+  STATUS_WRONG_IDENTIFIER_LENGTH = 401,
+
+  /* signal_credential::errors::{VerificationError,IssuanceError} */
+  STATUS_UNVERIFIED = 500,
+  STATUS_VERIFICATION_EXPIRED = 501,
+  STATUS_VERIFICATION_RATE_LIMITED = 502,
+  STATUS_ISSUANCE_LIMIT_EXCEEDED = 503,
+  STATUS_ISSUANCE_STORAGE = 504,
+};
+
+/**
+ * **Randomness**
+ *
+ * Every randomised function other than `system_parameters_create()` takes a
+ * `seed`, which should be NULL, in which case the randomness is drawn from the
+ * operating system.  A caller may instead pass their own `LENGTH_SEED` bytes,
+ * but must then never pass the same bytes again: a presentation made with a
+ * reused seed is linkable to the others made with it.  A seed which has
+ * already been passed to any function in this process is refused with
+ * `STATUS_SEED_REUSED`.  (Only the most recent few thousand seeds are
+ * remembered, so this is a safeguard against mistakes, not a guarantee.)
+ *
+ * Tests which need repeatable results may instead call the `*_deterministic`
+ * variants at the end of this file, which accept any seed any number of times.
+ */
 
 /**
  * Contains a pointer to some data and a length.
+ *
+ * **Ownership**
+ *
+ * Every `buf_t` returned by a function in this API with a non-NULL `ptr` owns
+ * the memory it points to.  The caller must copy out whatever data they need
+ * and then release the memory by passing the `buf_t` to `buffer_free()`
+ * exactly once.  The memory must not be freed with `free()`.
  */
 // RUST_C_COUPLED: ffi/src/c.rs buf_t
 typedef struct buf_s {
@@ -28,21 +144,61 @@ typedef struct buf_s {
   const uint8_t* ptr; /**< The `ptr` to some `uint8_t`s. */
 } buf_t;
 
+/**
+ * Release the memory owned by a `buf_t` returned from any function in this API.
+ *
+ * **Inputs**
+ *
+ * - `buffer` is a `buf_t` previously returned by this API.  If its `ptr` is
+ *   NULL, this function does nothing.  Passing the same `buffer` twice, or a
+ *   `buf_t` which was not returned by this API, is undefined behaviour.
+ */
+void buffer_free(buf_t buffer);
+
+/**
+ * Find the length of a kind of serialised object.
+ *
+ * The `LENGTH_*` constants above are kept in agreement with these lengths by
+ * the tests, but this function is computed by the library itself, and so is
+ * always correct for the version of it which is linked.
+ *
+ * **Inputs**
+ *
+ * - `object` is one of the `OBJECT_*` codes.
+ * - `out` is a pointer to a `uint64_t`, which is overwritten with the result.
+ *
+ * **Returns**
+ *
+ * If successful, returns `STATUS_OK` and writes to `out` the length, in bytes,
+ * of the serialised `object`.  Otherwise, returns the code of the error, such
+ * as `STATUS_UNKNOWN_OBJECT`, and `out` will be `0`.
+ *
+ * **Note**
+ *
+ * Finding the length of an `OBJECT_CREDENTIAL_PRESENTATION` or
+ * `OBJECT_VERIFIED_CREDENTIAL` requires creating zero-knowledge proofs, so
+ * callers should find these lengths once and keep them.
+ */
+status_t length_of(const object_t object,
+                   uint64_t* out);
+
 /**
  * Create some globally-agreed upon `SystemParameters` by seeding a CSPRNG.
  *
  * **Inputs**
  *
  * - `seed` an array of `LENGTH_SEED` bytes, which will be used to seed an CSPRNG.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * **Returns**
  *
- * If successful, returns a `buf_t` containing the
- * `aeonflux::parameters::SystemParameters` as a pointer to
- * `LENGTH_SYSTEM_PARAMETERS` bytes.  Otherwise, the `buf_t` will have a length
- * of `0` and a NULL pointer.
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing
+ * the `aeonflux::parameters::SystemParameters` as a pointer to
+ * `LENGTH_SYSTEM_PARAMETERS` bytes.  Otherwise, returns the code of the error,
+ * and `out` will have a length of `0` and a NULL pointer.
  */
-buf_t system_parameters_create(const uint8_t* seed);
+status_t system_parameters_create(const uint8_t* seed,
+                                  buf_t* out);
 
 /**
  * Create a new credential issuer.
@@ -56,17 +212,22 @@ buf_t system_parameters_create(const uint8_t* seed);
  *   that this *should* be `LENGTH_SYSTEM_PARAMETERS` however the `buf_t.len`
  *   value returned from system_parameters_create()` is what must be used in
  *   order to avoid buffer overflow.
- * - `seed` an array of `LENGTH_SEED` bytes, which will be used to seed an RNG.
+ * - `seed` is NULL, to draw randomness from the operating system, or else an
+ *   array of `LENGTH_SEED` bytes which has never been passed to this API
+ *   before.  See **Randomness** above.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * **Returns**
  *
- * If successful, returns a `buf_t` containing an `aeonflux::amacs::Keypair` as
- * a pointer to `LENGTH_ISSUER_KEYPAIR` bytes.  Otherwise, the `buf_t` will have
- * a length of `0` and a NULL pointer.
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing
+ * an `aeonflux::amacs::Keypair` as a pointer to `LENGTH_ISSUER_KEYPAIR` bytes.
+ * Otherwise, returns the code of the error, and `out` will have a length of `0`
+ * and a NULL pointer.
  */
-buf_t issuer_create(const uint8_t* system_parameters,
-                    const uint64_t system_parameters_length,
-                    const uint8_t* seed);
+status_t issuer_create(const uint8_t* system_parameters,
+                       const uint64_t system_parameters_length,
+                       const uint8_t* seed,
+                       buf_t* out);
 
 /**
  * Initialise a credential issuer.
@@ -89,17 +250,20 @@ buf_t issuer_create(const uint8_t* system_parameters,
  * - `keypair_length` is the length of the `keypair`.  (Note that this *should*
  *   be `LENGTH_ISSUER_KEYPAIR` however the `buf_t.len` value returned from
  *   `issuer_create()` is what must be used in order to avoid buffer overflow.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * **Returns**
  *
- * If successful, returns a `buf_t` containing a
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing a
  * `signal_credential::issuer::SignalIssuer` as a pointer to `LENGTH_ISSUER`
- * bytes.  Otherwise, the `buf_t` will have a length of `0` and a NULL pointer.
+ * bytes.  Otherwise, returns the code of the error, and `out` will have a
+ * length of `0` and a NULL pointer.
  */
-buf_t issuer_new(const uint8_t* system_parameters,
-                 const uint64_t system_parameters_length,
-                 const uint8_t* keypair,
-                 const uint64_t keypair_length);
+status_t issuer_new(const uint8_t* system_parameters,
+                    const uint64_t system_parameters_length,
+                    const uint8_t* keypair,
+                    const uint64_t keypair_length,
+                    buf_t* out);
 
 /**
  * Get this credential issuer's parameters (a.k.a their public key material).
@@ -111,15 +275,18 @@ buf_t issuer_new(const uint8_t* system_parameters,
  * - `issuer_length` is the length of the `issuer`.  (Note that this *should* be
  *   `LENGTH_ISSUER` however the `buf_t.len` value returned from
  *   `issuer_create()` is what must be used in order to avoid buffer overflow.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * **Returns**
  *
- * If successful, returns a `buf_t` containing an `aeonflux::amacs::PublicKey`
- * as a pointer to `LENGTH_ISSUER_PARAMETERS` bytes.  Otherwise, the `buf_t`
- * will have a length of `0` and a NULL pointer.
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing
+ * an `aeonflux::amacs::PublicKey` as a pointer to `LENGTH_ISSUER_PARAMETERS`
+ * bytes.  Otherwise, returns the code of the error, and `out` will have a
+ * length of `0` and a NULL pointer.
  */
-buf_t issuer_get_issuer_parameters(const uint8_t* issuer,
-                                   const uint64_t issuer_length);
+status_t issuer_get_issuer_parameters(const uint8_t* issuer,
+                                      const uint64_t issuer_length,
+                                      buf_t* out);
 
 /**
  * Issue a new credential to a user.
@@ -137,22 +304,26 @@ buf_t issuer_get_issuer_parameters(const uint8_t* issuer,
  *   canonically encoded, such as with libphonenumber¹, **as if it were being
  *   dialed internationally from San Francisco.
  * - `phone_number_length` is the length of the `phone_number`.
- * - `seed` an array of `LENGTH_SEED` bytes, which will be used to seed an CSPRNG.
+ * - `seed` is NULL, to draw randomness from the operating system, or else an
+ *   array of `LENGTH_SEED` bytes which has never been passed to this API
+ *   before.  See **Randomness** above.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * # Returns
  *
- * If successful, returns a `buf_t` containing a
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing a
  * `signal_credential::credential::SignalCredentialIssuance` as a pointer to
- * `LENGTH_CREDENTIAL_ISSUANCE` bytes.  Otherwise, the `buf_t` will have a
- * length of `0` and a NULL pointer.
+ * `LENGTH_CREDENTIAL_ISSUANCE` bytes.  Otherwise, returns the code of the
+ * error, and `out` will have a length of `0` and a NULL pointer.
  *
  * ¹ https://github.com/googlei18n/libphonenumber
  */
-buf_t issuer_issue(const uint8_t* issuer,
-                   const uint64_t issuer_length,
-                   const uint8_t* phone_number,
-                   const uint64_t phone_number_length,
-                   const uint8_t* seed);
+status_t issuer_issue(const uint8_t* issuer,
+                      const uint64_t issuer_length,
+                      const uint8_t* phone_number,
+                      const uint64_t phone_number_length,
+                      const uint8_t* seed,
+                      buf_t* out);
 
 /**
  * Have the `issuer` check a `presentation` of a Signal user's credential.
@@ -171,18 +342,21 @@ buf_t issuer_issue(const uint8_t* issuer,
  *   *should* be `LENGTH_CREDENTIAL_PRESENTATION`, however the `buf_t.len` value
  *   returned from `user_show()` is what must be used in order to avoid
  *   buffer overflow.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * **Returns**
  *
- * If successful and the presentation was verifiable, returns a `buf_t`
- * containing a `signal_credential::credential::VerifiedSignalCredential` as a
- * pointer to `LENGTH_VERIFIED_CREDENTIAL` bytes.  Otherwise, the `buf_t` will
- * have a length of `0` and a NULL pointer.
+ * If successful and the presentation was verifiable, returns `STATUS_OK` and
+ * writes to `out` a `buf_t` containing a
+ * `signal_credential::credential::VerifiedSignalCredential` as a pointer to
+ * `LENGTH_VERIFIED_CREDENTIAL` bytes.  Otherwise, returns the code of the
+ * error, and `out` will have a length of `0` and a NULL pointer.
  */
-buf_t issuer_verify(const uint8_t* issuer,
-                    const uint64_t issuer_length,
-                    const uint8_t* presentation,
-                    const uint64_t presentation_length);
+status_t issuer_verify(const uint8_t* issuer,
+                       const uint64_t issuer_length,
+                       const uint8_t* presentation,
+                       const uint64_t presentation_length,
+                       buf_t* out);
 
 /**
  * Check if a user in a Signal group roster for some group and permissions level.
@@ -201,23 +375,25 @@ buf_t issuer_verify(const uint8_t* issuer,
  *   (Note that this *should* be `LENGTH_VERIFIED_CREDENTIAL` however the
  *   `buf_t.len` value returned from `issuer_verify()` is what must be used in
  *   order to avoid buffer overflow.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * # Returns
  *
  * If the verified credential has a committed phone number which matches the
- * phone number in the credential, then the committed phone number will be
- * returned inside a `buf_t` with its length (which should be
- * `LENGTH_ROSTER_ENTRY_COMMITMENT`).  This returned commitment can be used to
+ * phone number in the credential, then `STATUS_OK` is returned and the
+ * committed phone number will be written to `out` inside a `buf_t` with its
+ * length (which should be `LENGTH_ROSTER_ENTRY_COMMITMENT`).  This returned commitment can be used to
  * look up whether the user is in the specified group with the permissions they
  * are claiming to possess.
  *
- * Otherwise, the returned `buf_t` will have its length set to `0` and contain a
- * NULL pointer.
+ * Otherwise, returns the code of the error, and `out` will have a length of `0`
+ * and a NULL pointer.
  */
-buf_t issuer_verify_roster_membership(const uint8_t* issuer,
-                                      const uint64_t issuer_length,
-                                      const uint8_t* verified_credential,
-                                      const uint64_t verified_credential_length);
+status_t issuer_verify_roster_membership(const uint8_t* issuer,
+                                         const uint64_t issuer_length,
+                                         const uint8_t* verified_credential,
+                                         const uint64_t verified_credential_length,
+                                         buf_t* out);
 
 /**
  * Check the proof of correct issuance on a credential issuance and potentially
@@ -253,22 +429,25 @@ buf_t issuer_verify_roster_membership(const uint8_t* issuer,
  *   that this *should* be `LENGTH_CREDENTIAL_ISSUANCE` however the `buf_t.len`
  *   value returned from `issuer_issue()` is what must be used in
  *   order to avoid buffer overflow.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * # Returns
  *
- * If successful and the issuance zero-knowledge proof was verifiable, returns a
- * `buf_t` containing a `signal_credential::user::SignalUser` as a pointer to
- * `LENGTH_USER` bytes.  Otherwise, the returned `buf_t` will have its length
- * set to `0` and contain a NULL pointer.
+ * If successful and the issuance zero-knowledge proof was verifiable, returns
+ * `STATUS_OK` and writes to `out` a `buf_t` containing a
+ * `signal_credential::user::SignalUser` as a pointer to `LENGTH_USER` bytes.
+ * Otherwise, returns the code of the error, and `out` will have a length of `0`
+ * and a NULL pointer.
  */
-buf_t user_obtain_finish(const uint8_t* phone_number,
-                         const uint64_t phone_number_length,
-                         const uint8_t* system_parameters,
-                         const uint64_t system_parameters_length,
-                         const uint8_t* issuer_parameters,
-                         const uint64_t issuer_parameters_length,
-                         const uint8_t* issuance,
-                         const uint64_t issuance_length);
+status_t user_obtain_finish(const uint8_t* phone_number,
+                            const uint64_t phone_number_length,
+                            const uint8_t* system_parameters,
+                            const uint64_t system_parameters_length,
+                            const uint8_t* issuer_parameters,
+                            const uint64_t issuer_parameters_length,
+                            const uint8_t* issuance,
+                            const uint64_t issuance_length,
+                            buf_t* out);
 
 /**
  * Present a user's credential to the issuer for verification, along with a
@@ -292,20 +471,171 @@ buf_t user_obtain_finish(const uint8_t* phone_number,
  * - `roster_entry_commitment_length` is the length of the
  *   `roster_entry_commitment`, as can be obtained from
  *   `roster_entry_commitment_create()`.
- * - `seed` an array of `LENGTH_SEED` bytes, which will be used to seed an CSPRNG.
+ * - `seed` is NULL, to draw randomness from the operating system, or else an
+ *   array of `LENGTH_SEED` bytes which has never been passed to this API
+ *   before.  See **Randomness** above.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * **Returns**
  *
- * If successful, returns a `buf_t` containing a
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing a
  * `signal_credential::credential::SignalCredentialPresentation` as a pointer to
- * `LENGTH_CREDENTIAL_PRESENTATION` bytes.  Otherwise, the `buf_t` will have a
+ * `LENGTH_CREDENTIAL_PRESENTATION` bytes.  Otherwise, returns the code of the
+ * error, and `out` will have a length of `0` and a NULL pointer.
+ */
+status_t user_show(const uint8_t* user,
+                   const uint64_t user_length,
+                   const uint8_t* roster_entry_commitment,
+                   const uint64_t roster_entry_commitment_length,
+                   const uint8_t* seed,
+                   buf_t* out);
+
+/**
+ * Create a new elGamal keypair, with which a user may later have credentials
+ * issued on encrypted attributes.
+ *
+ * **Inputs**
+ *
+ * - `seed` is NULL, to draw randomness from the operating system, or else an
+ *   array of `LENGTH_SEED` bytes which has never been passed to this API
+ *   before.  See **Randomness** above.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
+ *
+ * **Returns**
+ *
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing
+ * an `aeonflux::elgamal::Keypair` as a pointer to `LENGTH_ELGAMAL_KEYPAIR`
+ * bytes.  Otherwise, returns the code of the error, and `out` will have a
+ * length of `0` and a NULL pointer.
+ */
+status_t elgamal_keypair_create(const uint8_t* seed,
+                                buf_t* out);
+
+/**
+ * Get the public half of an elGamal keypair.
+ *
+ * **Inputs**
+ *
+ * - `keypair` is an `aeonflux::elgamal::Keypair` as a pointer to some bytes,
+ *   as obtained from `elgamal_keypair_create()`.
+ * - `keypair_length` is the length of the `keypair`, which must be
+ *   `LENGTH_ELGAMAL_KEYPAIR`.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
+ *
+ * **Returns**
+ *
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing
+ * an `aeonflux::elgamal::PublicKey` as a pointer to `LENGTH_ELGAMAL_PUBLIC_KEY`
+ * bytes.  Otherwise, returns the code of the error, and `out` will have a
  * length of `0` and a NULL pointer.
  */
-buf_t user_show(const uint8_t* user,
-                const uint64_t user_length,
-                const uint8_t* roster_entry_commitment,
-                const uint64_t roster_entry_commitment_length,
-                const uint8_t* seed);
+status_t elgamal_keypair_get_public_key(const uint8_t* keypair,
+                                        const uint64_t keypair_length,
+                                        buf_t* out);
+
+/**
+ * Create a user who does not yet have a credential.
+ *
+ * **Inputs**
+ *
+ * - `phone_number`, `system_parameters`, and `issuer_parameters`, and their
+ *   lengths, are as for `user_obtain_finish()`.
+ * - `keypair` is an `aeonflux::elgamal::Keypair` as a pointer to some bytes,
+ *   as obtained from `elgamal_keypair_create()`, or NULL for a user without
+ *   one.
+ * - `keypair_length` is the length of the `keypair`, which must be
+ *   `LENGTH_ELGAMAL_KEYPAIR` unless `keypair` is NULL.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
+ *
+ * **Returns**
+ *
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing
+ * a `signal_credential::user::SignalUser` as a pointer to `LENGTH_USER` bytes.
+ * Otherwise, returns the code of the error, and `out` will have a length of `0`
+ * and a NULL pointer.
+ *
+ * The serialised user may be stored, and passed to any function taking a
+ * `user`, or loaded into a handle with `user_handle_from_bytes()`.
+ */
+status_t user_create(const uint8_t* phone_number,
+                     const uint64_t phone_number_length,
+                     const uint8_t* system_parameters,
+                     const uint64_t system_parameters_length,
+                     const uint8_t* issuer_parameters,
+                     const uint64_t issuer_parameters_length,
+                     const uint8_t* keypair,
+                     const uint64_t keypair_length,
+                     buf_t* out);
+
+/**
+ * Check the proof of correct issuance on a credential issuance, and save the
+ * credential to a user created with `user_create()`.
+ *
+ * **Inputs**
+ *
+ * - `user` is a `signal_credential::user::SignalUser` as a pointer to
+ *   some bytes.
+ * - `user_length` is the length of the `user`.
+ * - `issuance` is a `signal_credential::credential::SignalCredentialIssuance`
+ *   as a pointer to some bytes, which is obtainable via `issuer_issue()`.
+ * - `issuance_length` is the length of the `issuance`.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
+ *
+ * **Returns**
+ *
+ * If successful and the issuance zero-knowledge proof was verifiable, returns
+ * `STATUS_OK` and writes to `out` a `buf_t` containing the
+ * `signal_credential::user::SignalUser`, now holding the credential, as a
+ * pointer to `LENGTH_USER` bytes.  Otherwise, returns the code of the error,
+ * and `out` will have a length of `0` and a NULL pointer.
+ */
+status_t user_obtain(const uint8_t* user,
+                     const uint64_t user_length,
+                     const uint8_t* issuance,
+                     const uint64_t issuance_length,
+                     buf_t* out);
+
+/**
+ * Determine whether a user holds a credential.
+ *
+ * **Inputs**
+ *
+ * - `user` is a `signal_credential::user::SignalUser` as a pointer to
+ *   some bytes.
+ * - `user_length` is the length of the `user`.
+ * - `out` is a pointer to a `uint8_t`, which is overwritten with the result.
+ *
+ * **Returns**
+ *
+ * If successful, returns `STATUS_OK` and writes `1` to `out` if the user has
+ * obtained a credential, and `0` if they have not.  Otherwise, returns the
+ * code of the error, and `out` will be `0`.
+ */
+status_t user_has_credential(const uint8_t* user,
+                             const uint64_t user_length,
+                             uint8_t* out);
+
+/**
+ * Get the public half of a user's elGamal keypair.
+ *
+ * **Inputs**
+ *
+ * - `user` is a `signal_credential::user::SignalUser` as a pointer to
+ *   some bytes.
+ * - `user_length` is the length of the `user`.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
+ *
+ * **Returns**
+ *
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing
+ * an `aeonflux::elgamal::PublicKey` as a pointer to `LENGTH_ELGAMAL_PUBLIC_KEY`
+ * bytes.  If the user was created without a keypair, returns
+ * `STATUS_MISSING_DATA`.  Otherwise, returns the code of the error, and `out`
+ * will have a length of `0` and a NULL pointer.
+ */
+status_t user_get_public_key(const uint8_t* user,
+                             const uint64_t user_length,
+                             buf_t* out);
 
 /**
  * Create a commitment to a phone number.
@@ -331,19 +661,24 @@ buf_t user_show(const uint8_t* user,
  *   that this *should* be `LENGTH_SYSTEM_PARAMETERS` however the `buf_t.len`
  *   value returned from `system_parameters_create()` is what must be used in
  *   order to avoid buffer overflow.
+ * - `seed` is NULL, to draw randomness from the operating system, or else an
+ *   array of `LENGTH_SEED` bytes which has never been passed to this API
+ *   before.  See **Randomness** above.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * **Returns**
  *
- * If successful, a `buf_t` containing a
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing a
  * `signal_credential::phone_number::RosterEntryCommitment` as a pointer to
- * `LENGTH_ROSTER_ENTRY_COMMITMENT` bytes.  Otherwise, the `buf_t` will have a
- * length of `0` and a NULL pointer.
+ * `LENGTH_ROSTER_ENTRY_COMMITMENT` bytes.  Otherwise, returns the code of the
+ * error, and `out` will have a length of `0` and a NULL pointer.
  */
-buf_t roster_entry_commitment_create(const uint8_t* phone_number,
-                                     const uint64_t phone_number_length,
-                                     const uint8_t* system_parameters,
-                                     const uint64_t system_parameters_length,
-                                     const uint8_t* seed);
+status_t roster_entry_commitment_create(const uint8_t* phone_number,
+                                        const uint64_t phone_number_length,
+                                        const uint8_t* system_parameters,
+                                        const uint64_t system_parameters_length,
+                                        const uint8_t* seed,
+                                        buf_t* out);
 
 /**
  * Remove the opening from a roster entry, leaving just the commitment to the
@@ -359,16 +694,18 @@ buf_t roster_entry_commitment_create(const uint8_t* phone_number,
  * - `roster_entry_commitment_length` is the length of the
  *   `roster_entry_commitment`, as can be obtained from
  *   `roster_entry_commitment_create()`.
+ * - `out` is a pointer to a `buf_t`, which is overwritten with the result.
  *
  * **Returns**
  *
- * If successful, returns a `buf_t` containing the committed phone number
- * (without the commitment's opening) as a pointer to
- * `LENGTH_ROSTER_ENTRY_COMMITMENT_SANS_OPENING` bytes.  Otherwise, the `buf_t`
- * will have a length of `0` and a NULL pointer.
+ * If successful, returns `STATUS_OK` and writes to `out` a `buf_t` containing
+ * the committed phone number (without the commitment's opening) as a pointer to
+ * `LENGTH_ROSTER_ENTRY_COMMITMENT_SANS_OPENING` bytes.  Otherwise, returns the
+ * code of the error, and `out` will have a length of `0` and a NULL pointer.
  */
-buf_t roster_entry_commitment_remove_opening(const uint8_t* roster_entry_commitment,
-                                             const uint64_t roster_entry_commitment_length);
+status_t roster_entry_commitment_remove_opening(const uint8_t* roster_entry_commitment,
+                                                const uint64_t roster_entry_commitment_length,
+                                                buf_t* out);
 
 /**
  * Open a commitment, `roster_entry_commitment`, to a `phone_number`.
@@ -400,12 +737,197 @@ buf_t roster_entry_commitment_remove_opening(const uint8_t* roster_entry_commitm
  *
  * **Returns**
  *
- * A `buf_t` containing a NULL pointer and, if successful, its length set to
- * `1`.  Otherwise, the length is set to `0`.
+ * `STATUS_OK` if the `roster_entry_commitment` opens to the `phone_number`,
+ * `STATUS_INVALID_PHONE_NUMBER` if it does not, and otherwise the code of the
+ * error which prevented it from being opened.
+ */
+status_t roster_entry_commitment_open(const uint8_t* roster_entry_commitment, // also contains the opening
+                                      const uint64_t roster_entry_commitment_length,
+                                      const uint8_t* phone_number,
+                                      const uint64_t phone_number_length,
+                                      const uint8_t* system_parameters,
+                                      const uint64_t system_parameters_length);
+
+/**
+ * Opaque handles.
+ *
+ * Rather than passing a serialised `SignalIssuer` or `SignalUser` to every
+ * call, a caller may load it once into a handle, use the handle for as many
+ * operations as they like, and then free it.  This saves deserialising on
+ * every call, and keeps the issuer's secret key out of the caller's memory
+ * once the handle has been created.
+ *
+ * **Thread Safety**
+ *
+ * No function other than `issuer_handle_free()` or `user_handle_free()`
+ * modifies the state behind a handle, so a handle may be used by any number of
+ * threads at once.  Freeing a handle while another thread is still using it,
+ * or using it after it has been freed, is undefined behaviour.
+ *
+ * **Zeroisation**
+ *
+ * Freeing a handle overwrites all secret state behind it, including the
+ * issuer's keys and the user's credential and identifier, with zeroes.
+ */
+// RUST_C_COUPLED: ffi/src/handle.rs issuer_t
+typedef struct issuer_s issuer_t;
+// RUST_C_COUPLED: ffi/src/handle.rs user_t
+typedef struct user_s user_t;
+
+/**
+ * Load a serialised `signal_credential::issuer::SignalIssuer`, as returned by
+ * `issuer_new()`, into a handle.
+ *
+ * **Returns**
+ *
+ * If successful, returns `STATUS_OK` and writes to `out` a handle, which must
+ * later be released with `issuer_handle_free()`.  Otherwise, returns the code
+ * of the error, and `out` will be NULL.
+ */
+status_t issuer_handle_from_bytes(const uint8_t* issuer,
+                                  const uint64_t issuer_length,
+                                  issuer_t** out);
+
+/**
+ * Release an `issuer_t` handle, overwriting the issuer's secret keys.  Passing
+ * NULL does nothing.
+ */
+void issuer_handle_free(issuer_t* handle);
+
+/**
+ * As `issuer_get_issuer_parameters()`, but for the issuer behind a `handle`.
+ */
+status_t issuer_handle_get_issuer_parameters(const issuer_t* handle,
+                                             buf_t* out);
+
+/**
+ * As `issuer_issue()`, but for the issuer behind a `handle`.
+ */
+status_t issuer_handle_issue(const issuer_t* handle,
+                             const uint8_t* phone_number,
+                             const uint64_t phone_number_length,
+                             const uint8_t* seed,
+                             buf_t* out);
+
+/**
+ * As `issuer_verify()`, but for the issuer behind a `handle`.
+ */
+status_t issuer_handle_verify(const issuer_t* handle,
+                              const uint8_t* presentation,
+                              const uint64_t presentation_length,
+                              buf_t* out);
+
+/**
+ * As `issuer_verify_roster_membership()`, but for the issuer behind a `handle`.
+ */
+status_t issuer_handle_verify_roster_membership(const issuer_t* handle,
+                                                const uint8_t* verified_credential,
+                                                const uint64_t verified_credential_length,
+                                                buf_t* out);
+
+/**
+ * Load a serialised `signal_credential::user::SignalUser`, as returned by
+ * `user_obtain_finish()` or `user_handle_to_bytes()`, into a handle.
+ *
+ * **Returns**
+ *
+ * If successful, returns `STATUS_OK` and writes to `out` a handle, which must
+ * later be released with `user_handle_free()`.  Otherwise, returns the code of
+ * the error, and `out` will be NULL.
+ */
+status_t user_handle_from_bytes(const uint8_t* user,
+                                const uint64_t user_length,
+                                user_t** out);
+
+/**
+ * As `user_obtain_finish()`, but writes a handle to the user to `out` rather
+ * than a serialised user.  The handle must later be released with
+ * `user_handle_free()`.
+ */
+status_t user_handle_obtain_finish(const uint8_t* phone_number,
+                                   const uint64_t phone_number_length,
+                                   const uint8_t* system_parameters,
+                                   const uint64_t system_parameters_length,
+                                   const uint8_t* issuer_parameters,
+                                   const uint64_t issuer_parameters_length,
+                                   const uint8_t* issuance,
+                                   const uint64_t issuance_length,
+                                   user_t** out);
+
+/**
+ * Serialise the user behind a `handle`, for example to persist it, into a
+ * `buf_t` of `LENGTH_USER` bytes written to `out`.
+ */
+status_t user_handle_to_bytes(const user_t* handle,
+                              buf_t* out);
+
+/**
+ * As `user_show()`, but for the user behind a `handle`.
+ */
+status_t user_handle_show(const user_t* handle,
+                          const uint8_t* roster_entry_commitment,
+                          const uint64_t roster_entry_commitment_length,
+                          const uint8_t* seed,
+                          buf_t* out);
+
+/**
+ * Release a `user_t` handle, overwriting the user's credential and identifier.
+ * Passing NULL does nothing.
+ */
+void user_handle_free(user_t* handle);
+
+/*
+ * Deterministic variants, for tests only.
+ *
+ * Each of the following behaves exactly as the function it is named after,
+ * except that its `seed` must be an array of `LENGTH_SEED` bytes, which may be
+ * reused, so that the same inputs and seed always give the same output.  They
+ * are only built when the library is compiled with its `deterministic`
+ * feature, which must never be done for a library given to users.
  */
-buf_t roster_entry_commitment_open(const uint8_t* roster_entry_commitment, // also contains the opening
-                                     const uint64_t roster_entry_commitment_length,
-                                     const uint8_t* phone_number,
-                                     const uint64_t phone_number_length,
-                                     const uint8_t* system_parameters,
-                                     const uint64_t system_parameters_length);
+#ifdef CREDENTIAL_DETERMINISTIC
+
+status_t issuer_create_deterministic(const uint8_t* system_parameters,
+                                     const uint64_t system_parameters_length,
+                                     const uint8_t* seed,
+                                     buf_t* out);
+
+status_t issuer_issue_deterministic(const uint8_t* issuer,
+                                    const uint64_t issuer_length,
+                                    const uint8_t* phone_number,
+                                    const uint64_t phone_number_length,
+                                    const uint8_t* seed,
+                                    buf_t* out);
+
+status_t user_show_deterministic(const uint8_t* user,
+                                 const uint64_t user_length,
+                                 const uint8_t* roster_entry_commitment,
+                                 const uint64_t roster_entry_commitment_length,
+                                 const uint8_t* seed,
+                                 buf_t* out);
+
+status_t elgamal_keypair_create_deterministic(const uint8_t* seed,
+                                              buf_t* out);
+
+status_t roster_entry_commitment_create_deterministic(const uint8_t* phone_number,
+                                                      const uint64_t phone_number_length,
+                                                      const uint8_t* system_parameters,
+                                                      const uint64_t system_parameters_length,
+                                                      const uint8_t* seed,
+                                                      buf_t* out);
+
+status_t issuer_handle_issue_deterministic(const issuer_t* handle,
+                                           const uint8_t* phone_number,
+                                           const uint64_t phone_number_length,
+                                           const uint8_t* seed,
+                                           buf_t* out);
+
+status_t user_handle_show_deterministic(const user_t* handle,
+                                        const uint8_t* roster_entry_commitment,
+                                        const uint64_t roster_entry_commitment_length,
+                                        const uint8_t* seed,
+                                        buf_t* out);
+
+#endif /* CREDENTIAL_DETERMINISTIC */
+
+#endif /* CREDENTIAL_H */
@@ -17,6 +17,10 @@ extern crate std;
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 extern crate alloc;
 
+#[cfg(feature = "aeonflux_api")]
+extern crate aeonflux;
+extern crate clear_on_drop;
+extern crate getrandom;
 extern crate libc;
 extern crate rand;
 extern crate signal_credential;
@@ -26,3 +30,9 @@ extern crate signal_credential;
 pub mod macros;
 
 pub mod c;
+#[cfg(any(test, feature = "deterministic"))]
+pub mod deterministic;
+pub mod entropy;
+#[cfg(feature = "aeonflux_api")]
+pub mod generic;
+pub mod handle;
@@ -7,14 +7,25 @@
 // Authors:
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 
+/// Hand ownership of a `Vec<u8>` over to the caller as a `buf_t`.
+///
+/// The allocation is leaked as a `Box<[u8]>`, and the caller must release it
+/// by passing the `buf_t` to `buffer_free()` exactly once.
 #[macro_export]
-macro_rules! slice_to_len_and_ptr {
+macro_rules! vec_to_len_and_ptr {
     ($x:expr) => {{
-        let x: &[u8] = $x;
+        let x: Vec<u8> = $x;
 
-        buf_t {
-            len: x.len() as uint64_t,
-            ptr: x.as_ptr() as *const uint8_t,
+        if x.len() == 0 {
+            zero_len_and_ptr!()
+        } else {
+            let boxed: Box<[u8]> = x.into_boxed_slice();
+            let len: uint64_t = boxed.len() as uint64_t;
+
+            buf_t {
+                len: len,
+                ptr: Box::into_raw(boxed) as *const uint8_t,
+            }
         }
     }}
 }
@@ -22,7 +33,10 @@ macro_rules! slice_to_len_and_ptr {
 #[macro_export]
 macro_rules! zero_len_and_ptr {
     () => {
-        slice_to_len_and_ptr!(&[])
+        buf_t {
+            len: 0,
+            ptr: ::core::ptr::null(),
+        }
     }
 }
 
@@ -30,7 +44,7 @@ macro_rules! zero_len_and_ptr {
 macro_rules! len_and_ptr_to_slice {
     ($len:expr, $ptr:ident) => {{
         if $ptr.is_null() || $len == 0 {
-            return zero_len_and_ptr!();
+            return Status::NullPointer.code();
         } else {
             unsafe { slice::from_raw_parts($ptr, $len as size_t) } // XXX dangerous downcast
         }
@@ -41,20 +55,80 @@ macro_rules! len_and_ptr_to_slice {
 macro_rules! ok_or_return {
     ($expr:expr) => {
         match $expr {
-            Ok(x)   => x,
-            Err(_x) => {
+            Ok(x)  => x,
+            Err(x) => {
                 #[cfg(feature = "std")]
-                println!("{:?}", _x);
-                return zero_len_and_ptr!();
+                println!("{:?}", x);
+                return Status::from(x).code();
             },
         };
-    }
+    };
+    ($expr:expr, $status:expr) => {
+        match $expr {
+            Ok(x)   => x,
+            Err(_x) => return $status.code(),
+        };
+    };
+}
+
+/// Check that an out-parameter is non-NULL, and clear it so that the caller
+/// is never left holding a stale `buf_t` when a call fails.
+#[macro_export]
+macro_rules! out_or_return {
+    ($out:ident) => {{
+        if $out.is_null() {
+            return Status::NullPointer.code();
+        }
+        unsafe { *$out = zero_len_and_ptr!(); }
+    }}
+}
+
+/// Hand ownership of a `Vec<u8>` over to the caller through an
+/// out-parameter, and report success.
+#[macro_export]
+macro_rules! write_out {
+    ($out:ident, $x:expr) => {{
+        unsafe { *$out = vec_to_len_and_ptr!($x); }
+
+        Status::Ok.code()
+    }}
+}
+
+/// Borrow the `LENGTH_SEED` bytes of a seed, which has no separate length.
+#[macro_export]
+macro_rules! seed_to_slice {
+    ($seed:ident) => {{
+        if $seed.is_null() {
+            return Status::InvalidSeed.code();
+        }
+        unsafe { slice::from_raw_parts($seed, LENGTH_SEED) }
+    }}
+}
+
+/// Get the seed for a randomised function: a fresh one from the operating
+/// system if `$seed` is NULL, and otherwise the `LENGTH_SEED` bytes at
+/// `$seed`, so long as this process has not used them before.
+#[macro_export]
+macro_rules! seed_or_entropy {
+    ($seed:ident) => {{
+        if $seed.is_null() {
+            ok_or_return!($crate::entropy::os_seed())
+        } else {
+            let seed: &[u8] = unsafe { slice::from_raw_parts($seed, LENGTH_SEED) };
+            let mut seed_array: [u8; LENGTH_SEED] = [0u8; LENGTH_SEED];
+
+            ok_or_return!($crate::entropy::check_seed_unused(seed));
+            seed_array.copy_from_slice(seed);
+            seed_array
+        }
+    }}
 }
 
 #[macro_export]
 macro_rules! csprng_from_seed {
     ($seed:ident) => {{
-        let seed_array: [u8; LENGTH_SEED] = ok_or_return!(uint8_to_array!($seed, LENGTH_SEED));
+        let seed_array: [u8; LENGTH_SEED] = ok_or_return!(uint8_to_array!($seed, LENGTH_SEED),
+                                                          Status::InvalidSeed);
 
         SignalRng::from_seed(seed_array)
    }}
@@ -0,0 +1,86 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Build and run the C program in `tests/c/aeonflux.c` against the static
+//! library, to check that `aeonflux.h` matches the exported functions, and
+//! check that the copy of `aeonflux.h` in `src/include` is up to date.
+//!
+//! The C compiler is `$CC`, or else `cc`.
+
+#![cfg(all(feature = "aeonflux_api", unix))]
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Output;
+
+/// Find `libcredential.a`, which cargo puts in the directory above the one
+/// holding this test's executable.
+fn static_library() -> PathBuf {
+    let exe: PathBuf = env::current_exe().unwrap();
+    let deps: &Path = exe.parent().unwrap();
+
+    for dir in [deps, deps.parent().unwrap()].iter() {
+        let library: PathBuf = dir.join("libcredential.a");
+
+        if library.exists() {
+            return library;
+        }
+    }
+    panic!("could not find libcredential.a near {}", exe.display());
+}
+
+/// The header generated by `build.rs`.
+fn generated_header() -> PathBuf {
+    PathBuf::from(env!("AEONFLUX_HEADER"))
+}
+
+#[test]
+fn checked_in_header_is_up_to_date() {
+    let crate_dir: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let checked_in: String = fs::read_to_string(crate_dir.join("src").join("include").join("aeonflux.h")).unwrap();
+    let generated: String = fs::read_to_string(generated_header()).unwrap();
+
+    assert!(checked_in == generated, "src/include/aeonflux.h is out of date; run `make aeonflux.h`");
+}
+
+#[test]
+fn aeonflux_c_api() {
+    let crate_dir: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let out_dir: PathBuf = env::temp_dir();
+    let program: PathBuf = out_dir.join(format!("aeonflux-c-{}", std::process::id()));
+    let cc: String = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    let mut compile = Command::new(&cc);
+
+    // The generated aeonflux.h comes first, and includes credential.h.
+    compile.arg("-Wall")
+           .arg("-I").arg(generated_header().parent().unwrap())
+           .arg("-I").arg(crate_dir.join("src").join("include"))
+           .arg(crate_dir.join("tests").join("c").join("aeonflux.c"))
+           .arg(static_library())
+           .arg("-o").arg(&program)
+           .args(&["-lpthread", "-ldl", "-lm"]);
+
+    if cfg!(target_os = "macos") {
+        compile.args(&["-framework", "Security"]);
+    }
+
+    let compiled: Output = compile.output().expect("could not run the C compiler");
+
+    assert!(compiled.status.success(), "{} failed:\n{}", cc, String::from_utf8_lossy(&compiled.stderr));
+
+    let ran: Output = Command::new(&program).output().unwrap();
+    let _ = std::fs::remove_file(&program);
+
+    assert!(ran.status.success(), "{}", String::from_utf8_lossy(&ran.stderr));
+    assert_eq!(String::from_utf8_lossy(&ran.stdout), "ok\n");
+}
@@ -0,0 +1,103 @@
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+// Run the whole aeonflux protocol through the C API in aeonflux.h, and check
+// that malformed attributes are refused.  This is compiled, linked against
+// libcredential.a, and run by tests/aeonflux-c.rs.
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "aeonflux.h"
+
+#define CHECK(expected, call)                                           \
+  do {                                                                  \
+    status_t status = (call);                                           \
+    if (status != (expected)) {                                         \
+      fprintf(stderr, "%s:%d: %s returned %u, expected %u\n",           \
+              __FILE__, __LINE__, #call, status, (status_t)(expected)); \
+      exit(1);                                                          \
+    }                                                                   \
+  } while (0)
+
+// The number of attributes on our issuer's credentials.
+#define NUMBER_OF_ATTRIBUTES 2
+
+static const uint8_t SEED[32] = {
+   0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15,
+  16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+};
+
+int main(void) {
+  uint8_t attributes[NUMBER_OF_ATTRIBUTES * AEONFLUX_LENGTH_ATTRIBUTE];
+  buf_t system_parameters;
+  buf_t issuer;
+  buf_t issuer_parameters;
+  buf_t issuance;
+  buf_t user;
+  buf_t user_with_credential;
+  buf_t presentation;
+  buf_t verified;
+  buf_t refused;
+
+  // Canonical scalars, since their high bytes are below 0x10.
+  memset(attributes, 7, sizeof(attributes));
+
+  CHECK(STATUS_OK, system_parameters_create(SEED, &system_parameters));
+  CHECK(STATUS_OK, aeonflux_issuer_create(system_parameters.ptr, system_parameters.len,
+                                          NUMBER_OF_ATTRIBUTES, NULL, &issuer));
+  CHECK(STATUS_OK, aeonflux_issuer_get_issuer_parameters(issuer.ptr, issuer.len,
+                                                         &issuer_parameters));
+  CHECK(STATUS_OK, aeonflux_user_create(system_parameters.ptr, system_parameters.len,
+                                        issuer_parameters.ptr, issuer_parameters.len,
+                                        NULL, 0, &user));
+  CHECK(STATUS_OK, aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                         attributes, sizeof(attributes),
+                                         NULL, &issuance));
+  CHECK(STATUS_OK, aeonflux_user_obtain_finish(user.ptr, user.len,
+                                               issuance.ptr, issuance.len,
+                                               &user_with_credential));
+  CHECK(STATUS_OK, aeonflux_user_show(user_with_credential.ptr, user_with_credential.len,
+                                      NULL, &presentation));
+  CHECK(STATUS_OK, aeonflux_issuer_verify(issuer.ptr, issuer.len,
+                                          presentation.ptr, presentation.len,
+                                          &verified));
+
+  // The issuer's key only covers NUMBER_OF_ATTRIBUTES attributes.
+  CHECK(STATUS_MAC_CREATION, aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                                   attributes, AEONFLUX_LENGTH_ATTRIBUTE,
+                                                   NULL, &refused));
+  if (refused.ptr != NULL) {
+    fprintf(stderr, "a refused issuance left data in its out-parameter\n");
+    return 1;
+  }
+
+  // Credentials must have at least one attribute.
+  CHECK(STATUS_WRONG_NUMBER_OF_ATTRIBUTES, aeonflux_issuer_create(system_parameters.ptr,
+                                                                  system_parameters.len,
+                                                                  0, NULL, &refused));
+
+  // Attributes must be reduced modulo the group order.
+  memset(attributes, 0xff, sizeof(attributes));
+  CHECK(STATUS_SCALAR_FORMAT, aeonflux_issuer_issue(issuer.ptr, issuer.len,
+                                                    attributes, AEONFLUX_LENGTH_ATTRIBUTE,
+                                                    NULL, &refused));
+
+  buffer_free(verified);
+  buffer_free(presentation);
+  buffer_free(user_with_credential);
+  buffer_free(issuance);
+  buffer_free(user);
+  buffer_free(issuer_parameters);
+  buffer_free(issuer);
+  buffer_free(system_parameters);
+
+  printf("ok\n");
+
+  return 0;
+}
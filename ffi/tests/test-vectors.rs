@@ -0,0 +1,246 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Replay the known-answer test vectors in `test-vectors/credential.json`
+//! against aeonflux, signal-credential, and the C API.
+//!
+//! Each step is replayed from the recorded inputs, rather than from the
+//! outputs of the previous step, so that a failure points at the step which
+//! changed.  Every seed is used once by the C API, so that the seed reuse
+//! guard is never tripped; this file must not gain other tests which call it
+//! with the recorded seeds.
+
+extern crate aeonflux;
+extern crate credential;
+extern crate hex;
+extern crate rand;
+extern crate serde_json;
+extern crate signal_credential;
+
+use std::fs;
+use std::path::PathBuf;
+use std::slice;
+
+use aeonflux::amacs;
+use aeonflux::credential::CredentialIssuance;
+use aeonflux::credential::CredentialRequest;
+use aeonflux::elgamal;
+use aeonflux::issuer::Issuer;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::user::User;
+
+use credential::c::*;
+
+use rand::ChaChaRng;
+use rand::SeedableRng;
+
+use serde_json::Value;
+
+use signal_credential::credential::SignalCredentialIssuance;
+use signal_credential::credential::SignalCredentialPresentation;
+use signal_credential::credential::VerifiedSignalCredential;
+use signal_credential::issuer::SignalIssuer;
+use signal_credential::phone_number::RosterEntryCommitment;
+use signal_credential::status::Status;
+use signal_credential::user::SignalUser;
+
+fn vectors() -> Value {
+    let path: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("test-vectors").join("credential.json");
+    let json: String = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("{} is missing; run `cargo run --example generate-test-vectors`", path.display()));
+
+    serde_json::from_str(&json).unwrap()
+}
+
+/// Decode the hex string at `vectors[step][name]`, or `vectors[step]` if
+/// `name` is empty.
+fn get(vectors: &Value, step: &str, name: &str) -> Vec<u8> {
+    let value: &Value = if name.is_empty() { &vectors[step] } else { &vectors[step][name] };
+
+    hex::decode(value.as_str().unwrap_or_else(|| panic!("no test vector for {} {}", step, name))).unwrap()
+}
+
+fn csprng(vectors: &Value, step: &str) -> ChaChaRng {
+    let mut seed: [u8; 32] = [0u8; 32];
+
+    seed.copy_from_slice(&get(vectors, step, "seed"));
+
+    ChaChaRng::from_seed(seed)
+}
+
+#[test]
+fn replay_aeonflux() {
+    let v: Value = vectors();
+
+    let system_parameters = SystemParameters::hunt_and_peck(&mut csprng(&v, "system_parameters"));
+
+    assert_eq!(system_parameters.to_bytes(), get(&v, "system_parameters", "system_parameters"));
+
+    let issuer: Issuer = Issuer::create(system_parameters, &mut csprng(&v, "issuer"));
+
+    assert_eq!(issuer.keypair.to_bytes(), get(&v, "issuer", "keypair"));
+    assert_eq!(issuer.get_issuer_parameters().to_bytes(), get(&v, "issuer", "issuer_parameters"));
+
+    let keypair = elgamal::Keypair::generate(&mut csprng(&v, "elgamal_keypair"));
+
+    assert_eq!(keypair.to_bytes(), get(&v, "elgamal_keypair", "keypair"));
+    assert_eq!(keypair.public.to_bytes().to_vec(), get(&v, "elgamal_keypair", "public_key"));
+
+    // The phone number is the credential's only attribute.
+    let issuer = Issuer::new(SystemParameters::from_bytes(&get(&v, "system_parameters", "system_parameters")).unwrap(),
+                             amacs::Keypair::from_bytes(&get(&v, "issuer", "keypair")).unwrap());
+    let request = CredentialRequest::from_bytes(&get(&v, "attribute", "")).unwrap();
+    let issuance = issuer.issue(&request, &mut csprng(&v, "issuance")).unwrap();
+
+    assert_eq!(issuance.to_bytes(), get(&v, "issuance", "credential_issuance"));
+
+    let mut user: User = User::new(system_parameters,
+                                   IssuerParameters::from_bytes(&get(&v, "issuer", "issuer_parameters")).unwrap(),
+                                   None);
+    let issuance = CredentialIssuance::from_bytes(&get(&v, "issuance", "credential_issuance")).unwrap();
+
+    user.obtain_finish(Some(&issuance)).unwrap();
+
+    assert_eq!(user.credential.unwrap().attributes[0].to_bytes().to_vec(), get(&v, "attribute", ""));
+
+    // The credential inside the Signal presentation is a plain aeonflux one.
+    let presentation = SignalCredentialPresentation::from_bytes(&get(&v, "presentation", "credential_presentation")).unwrap();
+
+    assert!(issuer.verify(&presentation.presentation).is_ok());
+}
+
+#[test]
+fn replay_signal_credential() {
+    let v: Value = vectors();
+    let phone_number: Vec<u8> = get(&v, "phone_number", "");
+    let system_parameters = SystemParameters::from_bytes(&get(&v, "system_parameters", "system_parameters")).unwrap();
+
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut csprng(&v, "issuer"));
+
+    assert_eq!(issuer.issuer.keypair.to_bytes(), get(&v, "issuer", "keypair"));
+
+    let issuer: SignalIssuer = SignalIssuer::from_bytes(&get(&v, "issuer", "issuer")).unwrap();
+
+    assert_eq!(issuer.to_bytes(), get(&v, "issuer", "issuer"));
+
+    let issuance: SignalCredentialIssuance = issuer.issue(&phone_number[..], &mut csprng(&v, "issuance")).unwrap();
+
+    assert_eq!(issuance.to_bytes(), get(&v, "issuance", "credential_issuance"));
+
+    let user: SignalUser = SignalUser::new(system_parameters,
+                                           issuer.get_issuer_parameters(),
+                                           Some(elgamal::Keypair::from_bytes(&get(&v, "elgamal_keypair", "keypair")).unwrap()),
+                                           &phone_number[..]).unwrap();
+
+    assert_eq!(user.to_bytes(), get(&v, "user", "user"));
+
+    let mut user: SignalUser = SignalUser::from_bytes(&get(&v, "user", "user")).unwrap();
+
+    user.obtain_finish(Some(&issuance)).unwrap();
+
+    assert_eq!(user.to_bytes(), get(&v, "user", "user_with_credential"));
+
+    let entry = RosterEntryCommitment::create(&phone_number[..], &system_parameters,
+                                              &mut csprng(&v, "roster_entry_commitment")).unwrap();
+
+    assert_eq!(entry.to_bytes(), get(&v, "roster_entry_commitment", "roster_entry_commitment"));
+    assert_eq!(entry.commitment.to_bytes(), get(&v, "roster_entry_commitment", "roster_entry_commitment_sans_opening"));
+    assert!(entry.open(&phone_number[..], &system_parameters).is_ok());
+
+    let user: SignalUser = SignalUser::from_bytes(&get(&v, "user", "user_with_credential")).unwrap();
+    let entry = RosterEntryCommitment::from_bytes(&get(&v, "roster_entry_commitment", "roster_entry_commitment")).unwrap();
+    let presentation: SignalCredentialPresentation = user.show(&mut csprng(&v, "presentation"), &entry).unwrap();
+
+    assert_eq!(presentation.to_bytes(), get(&v, "presentation", "credential_presentation"));
+
+    let verified: VerifiedSignalCredential = issuer.verify(presentation).unwrap();
+
+    assert_eq!(verified.to_bytes(), get(&v, "verification", "verified_credential"));
+
+    let verified = VerifiedSignalCredential::from_bytes(&get(&v, "verification", "verified_credential")).unwrap();
+    let roster_membership = issuer.verify_roster_membership(&verified).unwrap();
+
+    assert_eq!(roster_membership.to_bytes(), get(&v, "verification", "roster_membership"));
+}
+
+/// Call a C API function which writes to a `buf_t`, and take a copy of it.
+macro_rules! call {
+    ($f:ident($($arg:expr),*)) => {{
+        let mut out: buf_t = buf_t { len: 0, ptr: ::std::ptr::null() };
+        let status: status_t = $f($($arg,)* &mut out);
+
+        assert!(status == Status::Ok.code(), "{} returned status {}", stringify!($f), status);
+
+        let bytes: Vec<u8> = unsafe { slice::from_raw_parts(out.ptr, out.len as usize) }.to_vec();
+
+        buffer_free(out);
+        bytes
+    }}
+}
+
+#[test]
+fn replay_c_api() {
+    let v: Value = vectors();
+    let phone_number: Vec<u8> = get(&v, "phone_number", "");
+    let system_parameters: Vec<u8> = get(&v, "system_parameters", "system_parameters");
+    let keypair: Vec<u8> = get(&v, "issuer", "keypair");
+    let issuer: Vec<u8> = get(&v, "issuer", "issuer");
+    let issuer_parameters: Vec<u8> = get(&v, "issuer", "issuer_parameters");
+    let elgamal_keypair: Vec<u8> = get(&v, "elgamal_keypair", "keypair");
+    let user: Vec<u8> = get(&v, "user", "user");
+    let user_with_credential: Vec<u8> = get(&v, "user", "user_with_credential");
+    let issuance: Vec<u8> = get(&v, "issuance", "credential_issuance");
+    let entry: Vec<u8> = get(&v, "roster_entry_commitment", "roster_entry_commitment");
+    let presentation: Vec<u8> = get(&v, "presentation", "credential_presentation");
+    let verified: Vec<u8> = get(&v, "verification", "verified_credential");
+
+    assert_eq!(call!(system_parameters_create(get(&v, "system_parameters", "seed").as_ptr())),
+               system_parameters);
+    assert_eq!(call!(issuer_create(system_parameters.as_ptr(), system_parameters.len() as u64,
+                                   get(&v, "issuer", "seed").as_ptr())),
+               keypair);
+    assert_eq!(call!(issuer_new(system_parameters.as_ptr(), system_parameters.len() as u64,
+                                keypair.as_ptr(), keypair.len() as u64)),
+               issuer);
+    assert_eq!(call!(issuer_get_issuer_parameters(issuer.as_ptr(), issuer.len() as u64)),
+               issuer_parameters);
+    assert_eq!(call!(elgamal_keypair_create(get(&v, "elgamal_keypair", "seed").as_ptr())),
+               elgamal_keypair);
+    assert_eq!(call!(elgamal_keypair_get_public_key(elgamal_keypair.as_ptr(), elgamal_keypair.len() as u64)),
+               get(&v, "elgamal_keypair", "public_key"));
+    assert_eq!(call!(user_create(phone_number.as_ptr(), phone_number.len() as u64,
+                                 system_parameters.as_ptr(), system_parameters.len() as u64,
+                                 issuer_parameters.as_ptr(), issuer_parameters.len() as u64,
+                                 elgamal_keypair.as_ptr(), elgamal_keypair.len() as u64)),
+               user);
+    assert_eq!(call!(issuer_issue(issuer.as_ptr(), issuer.len() as u64,
+                                  phone_number.as_ptr(), phone_number.len() as u64,
+                                  get(&v, "issuance", "seed").as_ptr())),
+               issuance);
+    assert_eq!(call!(user_obtain(user.as_ptr(), user.len() as u64,
+                                 issuance.as_ptr(), issuance.len() as u64)),
+               user_with_credential);
+    assert_eq!(call!(roster_entry_commitment_create(phone_number.as_ptr(), phone_number.len() as u64,
+                                                    system_parameters.as_ptr(), system_parameters.len() as u64,
+                                                    get(&v, "roster_entry_commitment", "seed").as_ptr())),
+               entry);
+    assert_eq!(call!(roster_entry_commitment_remove_opening(entry.as_ptr(), entry.len() as u64)),
+               get(&v, "roster_entry_commitment", "roster_entry_commitment_sans_opening"));
+    assert_eq!(call!(user_show(user_with_credential.as_ptr(), user_with_credential.len() as u64,
+                               entry.as_ptr(), entry.len() as u64,
+                               get(&v, "presentation", "seed").as_ptr())),
+               presentation);
+    assert_eq!(call!(issuer_verify(issuer.as_ptr(), issuer.len() as u64,
+                                   presentation.as_ptr(), presentation.len() as u64)),
+               verified);
+    assert_eq!(call!(issuer_verify_roster_membership(issuer.as_ptr(), issuer.len() as u64,
+                                                     verified.as_ptr(), verified.len() as u64)),
+               get(&v, "verification", "roster_membership"));
+}
@@ -0,0 +1,20 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate aeonflux;
+
+use aeonflux::amacs_ddh::PublicKey;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = PublicKey::from_bytes(data);
+});
@@ -0,0 +1,34 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate aeonflux;
+extern crate rand;
+
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::issuer::Issuer;
+use aeonflux::parameters::SystemParameters;
+
+use rand::ChaChaRng;
+use rand::SeedableRng;
+
+thread_local! {
+    // Creating an issuer is slow, so each fuzzing thread only does it once.
+    static ISSUER: Issuer = Issuer::create(SystemParameters::hunt_and_peck(&mut ChaChaRng::from_seed([0u8; 32])),
+                                           &mut ChaChaRng::from_seed([1u8; 32]));
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(presentation) = CredentialPresentation::from_bytes(data) {
+        ISSUER.with(|issuer| { let _ = issuer.verify(&presentation); });
+    }
+});
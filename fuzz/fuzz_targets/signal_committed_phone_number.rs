@@ -0,0 +1,20 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate signal_credential;
+
+use signal_credential::phone_number::CommittedPhoneNumber;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = CommittedPhoneNumber::from_bytes(data);
+});
@@ -0,0 +1,253 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Identifiers for Signal accounts, which may be used as credential
+//! attributes.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::string::String;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::string::String;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use aeonflux::credential::RevealedAttribute;
+
+use curve25519_dalek::scalar::Scalar;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use sha2::Sha512;
+
+use errors::AccountIdentifierError;
+use phone_number::PhoneNumber;
+use phone_number::SIZEOF_PHONE_NUMBER;
+
+pub const SIZEOF_UUID: usize = 16;
+
+/// The maximum length, in bytes, of a username.
+pub const MAXIMUM_USERNAME_LENGTH: usize = 32;
+
+const KIND_UUID: u8 = 1;
+const KIND_USERNAME: u8 = 2;
+
+const SIZEOF_SERIALIZED_UUID: usize = 1 + SIZEOF_UUID;
+const SIZEOF_SERIALIZED_USERNAME: usize = 2 + MAXIMUM_USERNAME_LENGTH;
+
+/// An identifier for a Signal account.
+///
+/// Each kind of identifier is mapped to a `Scalar` attribute in its own way:
+///
+/// * phone numbers are packed digit-by-digit as described for `PhoneNumber`,
+///   which keeps them compatible with previously issued credentials, and
+///   with the commitments and encryptions made directly from a
+///   `PhoneNumber`, while
+/// * UUIDs and usernames are hashed to a `Scalar` with a domain separator
+///   unique to their kind, and so never collide with each other.
+///
+/// Nor can a hashed identifier collide with a packed phone number.  Every
+/// byte of a packed phone number is a digit, the encoding of a plus sign, a
+/// `0x0f` marker, its length, or zero, so there are fewer than
+/// \\( 2^{140} \\) of them, whereas a hashed identifier is a uniformly random
+/// scalar modulo \\( \ell \approx 2^{252} \\).  Finding a UUID or username
+/// which hashes to *any* packed phone number would therefore take around
+/// \\( 2^{112} \\) hash evaluations, and to a *particular* one, a preimage
+/// attack on SHA-512.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AccountIdentifier {
+    PhoneNumber(PhoneNumber),
+    Uuid([u8; SIZEOF_UUID]),
+    Username(String),
+}
+
+impl AccountIdentifier {
+    /// Create an `AccountIdentifier` from a `username`.
+    ///
+    /// Usernames are case-insensitive, and are canonicalised to lowercase.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AccountIdentifierError::InvalidUsername` if the `username` is
+    /// empty, longer than `MAXIMUM_USERNAME_LENGTH`, or contains anything
+    /// other than ASCII letters, digits, underscores, and dots.
+    pub fn username(username: &str) -> Result<AccountIdentifier, AccountIdentifierError> {
+        if username.len() == 0 || username.len() > MAXIMUM_USERNAME_LENGTH {
+            return Err(AccountIdentifierError::InvalidUsername);
+        }
+        if !username.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'.') {
+            return Err(AccountIdentifierError::InvalidUsername);
+        }
+
+        Ok(AccountIdentifier::Username(username.to_ascii_lowercase()))
+    }
+
+    /// Encode this identifier as a credential attribute.
+    ///
+    /// Phone numbers are not hashed, and do not need their own domain
+    /// separator, for the reasons given for `AccountIdentifier`.
+    pub fn to_scalar(&self) -> RevealedAttribute {
+        match self {
+            AccountIdentifier::PhoneNumber(x) => x.0,
+            AccountIdentifier::Uuid(x) => {
+                let mut v: Vec<u8> = Vec::with_capacity(64);
+
+                v.extend_from_slice(b"signal-credential account identifier uuid");
+                v.extend_from_slice(&x[..]);
+
+                Scalar::hash_from_bytes::<Sha512>(&v)
+            },
+            AccountIdentifier::Username(x) => {
+                let mut v: Vec<u8> = Vec::with_capacity(96);
+
+                v.extend_from_slice(b"signal-credential account identifier username");
+                v.extend_from_slice(x.as_bytes());
+
+                Scalar::hash_from_bytes::<Sha512>(&v)
+            },
+        }
+    }
+
+    /// Deserialise an `AccountIdentifier`.
+    ///
+    /// The kind of identifier is determined by the length of the `bytes`,
+    /// such that a serialised `PhoneNumber` is also a valid serialised
+    /// `AccountIdentifier`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<AccountIdentifier, AccountIdentifierError> {
+        match bytes.len() {
+            SIZEOF_PHONE_NUMBER => Ok(AccountIdentifier::PhoneNumber(PhoneNumber::from_bytes(bytes)?)),
+            SIZEOF_SERIALIZED_UUID if bytes[0] == KIND_UUID => {
+                let mut uuid: [u8; SIZEOF_UUID] = [0u8; SIZEOF_UUID];
+
+                uuid.copy_from_slice(&bytes[1..]);
+
+                Ok(AccountIdentifier::Uuid(uuid))
+            },
+            SIZEOF_SERIALIZED_USERNAME if bytes[0] == KIND_USERNAME => {
+                let length: usize = bytes[1] as usize;
+
+                if length > MAXIMUM_USERNAME_LENGTH || bytes[2+length..].iter().any(|x| *x != 0) {
+                    return Err(AccountIdentifierError::InvalidUsername);
+                }
+                match ::core::str::from_utf8(&bytes[2..2+length]) {
+                    Ok(x)  => AccountIdentifier::username(x),
+                    Err(_) => Err(AccountIdentifierError::InvalidUsername),
+                }
+            },
+            _ => Err(AccountIdentifierError::WrongNumberOfBytes),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            AccountIdentifier::PhoneNumber(x) => x.to_bytes(),
+            AccountIdentifier::Uuid(x) => {
+                let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_SERIALIZED_UUID);
+
+                v.push(KIND_UUID);
+                v.extend_from_slice(&x[..]);
+                v
+            },
+            AccountIdentifier::Username(x) => {
+                let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_SERIALIZED_USERNAME);
+
+                v.push(KIND_USERNAME);
+                v.push(x.len() as u8);
+                v.extend_from_slice(x.as_bytes());
+                v.resize(SIZEOF_SERIALIZED_USERNAME, 0u8);
+                v
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(AccountIdentifier,
+                                         "A valid byte sequence representing an AccountIdentifier");
+
+impl From<PhoneNumber> for AccountIdentifier {
+    fn from(source: PhoneNumber) -> AccountIdentifier {
+        AccountIdentifier::PhoneNumber(source)
+    }
+}
+
+/// Types which may be interpreted as an `AccountIdentifier`.
+///
+/// This allows methods which previously took a phone number as bytes to
+/// continue to do so, while also accepting any other kind of identifier.
+pub trait ToAccountIdentifier {
+    fn to_account_identifier(&self) -> Result<AccountIdentifier, AccountIdentifierError>;
+}
+
+impl ToAccountIdentifier for AccountIdentifier {
+    fn to_account_identifier(&self) -> Result<AccountIdentifier, AccountIdentifierError> {
+        Ok(self.clone())
+    }
+}
+
+impl ToAccountIdentifier for PhoneNumber {
+    fn to_account_identifier(&self) -> Result<AccountIdentifier, AccountIdentifierError> {
+        Ok(AccountIdentifier::PhoneNumber(self.clone()))
+    }
+}
+
+/// A phone number given as bytes, as for `PhoneNumber::try_from_bytes()`.
+impl ToAccountIdentifier for [u8] {
+    fn to_account_identifier(&self) -> Result<AccountIdentifier, AccountIdentifierError> {
+        Ok(AccountIdentifier::PhoneNumber(PhoneNumber::try_from_bytes(self)?))
+    }
+}
+
+impl<'a, T: ToAccountIdentifier + ?Sized> ToAccountIdentifier for &'a T {
+    fn to_account_identifier(&self) -> Result<AccountIdentifier, AccountIdentifierError> {
+        (**self).to_account_identifier()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn identifier_kinds_are_domain_separated() {
+        let uuid = AccountIdentifier::Uuid([7u8; SIZEOF_UUID]);
+        let username = AccountIdentifier::username("alice").unwrap();
+        let phone_number = [1u8, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4].to_account_identifier().unwrap();
+
+        assert!(uuid.to_scalar() != username.to_scalar());
+        assert!(uuid.to_scalar() != phone_number.to_scalar());
+        assert!(username.to_scalar() != phone_number.to_scalar());
+    }
+
+    #[test]
+    fn usernames_are_case_insensitive() {
+        let a = AccountIdentifier::username("Alice").unwrap();
+        let b = AccountIdentifier::username("alice").unwrap();
+
+        assert!(a == b);
+        assert!(AccountIdentifier::username("").is_err());
+        assert!(AccountIdentifier::username("alice smith").is_err());
+    }
+
+    #[test]
+    fn identifier_serialize_deserialize() {
+        let identifiers = [
+            AccountIdentifier::Uuid([7u8; SIZEOF_UUID]),
+            AccountIdentifier::username("alice_1.2").unwrap(),
+            [1u8, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4].to_account_identifier().unwrap(),
+        ];
+
+        for identifier in identifiers.iter() {
+            let deserialized = AccountIdentifier::from_bytes(&identifier.to_bytes()).unwrap();
+
+            assert!(deserialized == *identifier);
+        }
+    }
+}
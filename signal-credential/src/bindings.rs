@@ -0,0 +1,326 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! The core of every language binding.
+//!
+//! Each function here takes its inputs as serialised bytes and returns either
+//! serialised bytes or the `Status` describing why it failed.  The C and
+//! Javascript APIs are thin wrappers which only convert their arguments to
+//! and from byte slices, so that both give identical results for identical
+//! inputs and seeds, and so that a function added here is available to both.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use aeonflux::amacs;
+use aeonflux::elgamal;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::parameters::SystemParameters;
+
+use rand::ChaChaRng;
+use rand::SeedableRng;
+
+use credential::SignalCredentialIssuance;
+use credential::SignalCredentialPresentation;
+use credential::VerifiedSignalCredential;
+use issuer::SignalIssuer;
+use phone_number::RosterEntryCommitment;
+use sizes::SerializedObject;
+use status::Status;
+use user::SignalUser;
+
+/// The length of a seed for the CSPRNG used by the bindings.
+pub const SIZEOF_SEED: usize = 32;
+
+/// The CSPRNG used by the bindings.
+pub type SignalRng = ChaChaRng;
+
+/// Create a `SignalRng` seeded with exactly `SIZEOF_SEED` bytes.
+///
+/// # Errors
+///
+/// `Status::InvalidSeed` if the `seed` has the wrong length.
+pub fn csprng_from_seed(seed: &[u8]) -> Result<SignalRng, Status> {
+    if seed.len() != SIZEOF_SEED {
+        return Err(Status::InvalidSeed);
+    }
+
+    let mut array: [u8; SIZEOF_SEED] = [0u8; SIZEOF_SEED];
+
+    array.copy_from_slice(seed);
+
+    Ok(SignalRng::from_seed(array))
+}
+
+/// Find the length of the kind of serialised object with the code `object`.
+pub fn length_of(object: u32) -> Result<usize, Status> {
+    match SerializedObject::from_code(object) {
+        Some(kind) => kind.length(),
+        None       => Err(Status::UnknownObject),
+    }
+}
+
+/// Create some globally-agreed upon `SystemParameters` by seeding a CSPRNG.
+pub fn system_parameters_create(seed: &[u8]) -> Result<Vec<u8>, Status> {
+    let mut csprng: SignalRng = csprng_from_seed(seed)?;
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut csprng);
+
+    Ok(system_parameters.to_bytes())
+}
+
+/// Create a new issuer, returning its `amacs::Keypair`.
+pub fn issuer_create(system_parameters: &[u8], seed: &[u8]) -> Result<Vec<u8>, Status> {
+    let mut csprng: SignalRng = csprng_from_seed(seed)?;
+    let system_params: SystemParameters = SystemParameters::from_bytes(system_parameters)?;
+    let issuer: SignalIssuer = SignalIssuer::create(system_params, &mut csprng);
+
+    Ok(issuer.issuer.keypair.to_bytes())
+}
+
+/// Instantiate a `SignalIssuer` from an `amacs::Keypair` previously created
+/// with `issuer_create()`.
+pub fn issuer_new(system_parameters: &[u8], keypair: &[u8]) -> Result<Vec<u8>, Status> {
+    let system_params: SystemParameters = SystemParameters::from_bytes(system_parameters)?;
+    let keys: amacs::Keypair = amacs::Keypair::from_bytes(keypair)?;
+    let issuer: SignalIssuer = SignalIssuer::new(system_params, keys);
+
+    Ok(issuer.to_bytes())
+}
+
+/// Get the public `IssuerParameters` of a `SignalIssuer`.
+pub fn issuer_get_issuer_parameters(issuer: &[u8]) -> Result<Vec<u8>, Status> {
+    let issuer: SignalIssuer = SignalIssuer::from_bytes(issuer)?;
+
+    Ok(issuer.get_issuer_parameters().to_bytes())
+}
+
+/// Issue a credential for a `phone_number`.
+pub fn issuer_issue(issuer: &[u8], phone_number: &[u8], seed: &[u8]) -> Result<Vec<u8>, Status> {
+    let mut csprng: SignalRng = csprng_from_seed(seed)?;
+    let issuer: SignalIssuer = SignalIssuer::from_bytes(issuer)?;
+    let issuance: SignalCredentialIssuance = issuer.issue(phone_number, &mut csprng)?;
+
+    Ok(issuance.to_bytes())
+}
+
+/// Verify a `SignalCredentialPresentation`.
+pub fn issuer_verify(issuer: &[u8], presentation: &[u8]) -> Result<Vec<u8>, Status> {
+    let issuer: SignalIssuer = SignalIssuer::from_bytes(issuer)?;
+    let presentation: SignalCredentialPresentation = SignalCredentialPresentation::from_bytes(presentation)?;
+    let verified: VerifiedSignalCredential = issuer.verify(presentation)?;
+
+    Ok(verified.to_bytes())
+}
+
+/// Verify that a `VerifiedSignalCredential` is for the roster entry it was
+/// presented with, returning the roster entry's commitment.
+pub fn issuer_verify_roster_membership(issuer: &[u8], verified_credential: &[u8]) -> Result<Vec<u8>, Status> {
+    let issuer: SignalIssuer = SignalIssuer::from_bytes(issuer)?;
+    let verified: VerifiedSignalCredential = VerifiedSignalCredential::from_bytes(verified_credential)?;
+    let roster_entry_commitment = issuer.verify_roster_membership(&verified)?;
+
+    Ok(roster_entry_commitment.to_bytes())
+}
+
+/// Create a new `elgamal::Keypair` for a user.
+pub fn elgamal_keypair_create(seed: &[u8]) -> Result<Vec<u8>, Status> {
+    let mut csprng: SignalRng = csprng_from_seed(seed)?;
+    let keypair: elgamal::Keypair = elgamal::Keypair::generate(&mut csprng);
+
+    Ok(keypair.to_bytes())
+}
+
+/// Get the public half of an `elgamal::Keypair`.
+pub fn elgamal_keypair_get_public_key(keypair: &[u8]) -> Result<Vec<u8>, Status> {
+    let keypair: elgamal::Keypair = elgamal::Keypair::from_bytes(keypair)?;
+
+    Ok(keypair.public.to_bytes())
+}
+
+/// Create a `SignalUser` without a credential, and with an optional
+/// `elgamal::Keypair`.
+pub fn user_create(
+    phone_number: &[u8],
+    system_parameters: &[u8],
+    issuer_parameters: &[u8],
+    keypair: Option<&[u8]>,
+) -> Result<Vec<u8>, Status>
+{
+    let system_params: SystemParameters = SystemParameters::from_bytes(system_parameters)?;
+    let issuer_params: IssuerParameters = IssuerParameters::from_bytes(issuer_parameters)?;
+    let key: Option<elgamal::Keypair> = match keypair {
+        Some(x) => Some(elgamal::Keypair::from_bytes(x)?),
+        None    => None,
+    };
+    let user: SignalUser = SignalUser::new(system_params, issuer_params, key, phone_number)?;
+
+    Ok(user.to_bytes())
+}
+
+/// Check a `SignalCredentialIssuance` and save its credential to a
+/// `SignalUser`, returning the updated user.
+pub fn user_obtain(user: &[u8], issuance: &[u8]) -> Result<Vec<u8>, Status> {
+    let mut user: SignalUser = SignalUser::from_bytes(user)?;
+    let issuance: SignalCredentialIssuance = SignalCredentialIssuance::from_bytes(issuance)?;
+
+    user.obtain_finish(Some(&issuance))?;
+
+    Ok(user.to_bytes())
+}
+
+/// Create a `SignalUser` and have them obtain a credential, all at once.
+pub fn user_obtain_finish(
+    phone_number: &[u8],
+    system_parameters: &[u8],
+    issuer_parameters: &[u8],
+    issuance: &[u8],
+) -> Result<Vec<u8>, Status>
+{
+    let user: Vec<u8> = user_create(phone_number, system_parameters, issuer_parameters, None)?;
+
+    user_obtain(&user, issuance)
+}
+
+/// Determine whether a `SignalUser` holds a credential.
+pub fn user_has_credential(user: &[u8]) -> Result<bool, Status> {
+    let user: SignalUser = SignalUser::from_bytes(user)?;
+
+    Ok(user.user.credential.is_some())
+}
+
+/// Get the public half of a `SignalUser`'s `elgamal::Keypair`.
+///
+/// # Errors
+///
+/// `Status::MissingData` if the user has no keypair.
+pub fn user_get_public_key(user: &[u8]) -> Result<Vec<u8>, Status> {
+    let user: SignalUser = SignalUser::from_bytes(user)?;
+
+    match user.user.key {
+        Some(ref key) => Ok(key.public.to_bytes()),
+        None          => Err(Status::MissingData),
+    }
+}
+
+/// Present a `SignalUser`'s credential, along with a proof that it is for
+/// the same identifier as the `roster_entry_commitment`.
+pub fn user_show(user: &[u8], roster_entry_commitment: &[u8], seed: &[u8]) -> Result<Vec<u8>, Status> {
+    let mut csprng: SignalRng = csprng_from_seed(seed)?;
+    let user: SignalUser = SignalUser::from_bytes(user)?;
+    let entry: RosterEntryCommitment = RosterEntryCommitment::from_bytes(roster_entry_commitment)?;
+    let presentation: SignalCredentialPresentation = user.show(&mut csprng, &entry)?;
+
+    Ok(presentation.to_bytes())
+}
+
+/// Commit to a `phone_number`, returning the commitment and its opening.
+pub fn roster_entry_commitment_create(
+    phone_number: &[u8],
+    system_parameters: &[u8],
+    seed: &[u8],
+) -> Result<Vec<u8>, Status>
+{
+    let mut csprng: SignalRng = csprng_from_seed(seed)?;
+    let system_params: SystemParameters = SystemParameters::from_bytes(system_parameters)?;
+    let entry: RosterEntryCommitment = RosterEntryCommitment::create(phone_number, &system_params, &mut csprng)?;
+
+    Ok(entry.to_bytes())
+}
+
+/// Strip the opening from a `RosterEntryCommitment`.
+pub fn roster_entry_commitment_remove_opening(roster_entry_commitment: &[u8]) -> Result<Vec<u8>, Status> {
+    let entry: RosterEntryCommitment = RosterEntryCommitment::from_bytes(roster_entry_commitment)?;
+
+    Ok(entry.commitment.to_bytes())
+}
+
+/// Check that a `RosterEntryCommitment` opens to a `phone_number`.
+///
+/// # Errors
+///
+/// `Status::InvalidPhoneNumber` if it is a commitment to some other number.
+pub fn roster_entry_commitment_open(
+    roster_entry_commitment: &[u8],
+    phone_number: &[u8],
+    system_parameters: &[u8],
+) -> Result<(), Status>
+{
+    let system_params: SystemParameters = SystemParameters::from_bytes(system_parameters)?;
+    let entry: RosterEntryCommitment = RosterEntryCommitment::from_bytes(roster_entry_commitment)?;
+
+    Ok(entry.open(phone_number, &system_params)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEED: [u8; 32] = [0u8; 32];
+    const PHONE_NUMBER: &'static [u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+
+    #[test]
+    fn bad_seeds() {
+        assert!(system_parameters_create(&[]) == Err(Status::InvalidSeed));
+        assert!(system_parameters_create(&[0u8; 33]) == Err(Status::InvalidSeed));
+    }
+
+    #[test]
+    fn malformed_elgamal_keypair() {
+        assert!(elgamal_keypair_get_public_key(&[0u8; 63]) == Err(Status::WrongNumberOfBytes));
+    }
+
+    #[test]
+    fn unknown_object() {
+        assert!(length_of(0) == Err(Status::UnknownObject));
+    }
+
+    #[test]
+    fn full_protocol() {
+        let system_parameters = system_parameters_create(&SEED).unwrap();
+        let keypair = issuer_create(&system_parameters, &SEED).unwrap();
+        let issuer = issuer_new(&system_parameters, &keypair).unwrap();
+        let issuer_parameters = issuer_get_issuer_parameters(&issuer).unwrap();
+        let issuance = issuer_issue(&issuer, PHONE_NUMBER, &SEED).unwrap();
+        let user = user_create(PHONE_NUMBER, &system_parameters, &issuer_parameters, None).unwrap();
+
+        assert!(!user_has_credential(&user).unwrap());
+        assert!(user_get_public_key(&user) == Err(Status::MissingData));
+
+        let user = user_obtain(&user, &issuance).unwrap();
+
+        assert!(user_has_credential(&user).unwrap());
+        assert!(user == user_obtain_finish(PHONE_NUMBER, &system_parameters,
+                                           &issuer_parameters, &issuance).unwrap());
+
+        let entry = roster_entry_commitment_create(PHONE_NUMBER, &system_parameters, &SEED).unwrap();
+
+        assert!(roster_entry_commitment_open(&entry, PHONE_NUMBER, &system_parameters).is_ok());
+
+        let presentation = user_show(&user, &entry, &SEED).unwrap();
+        let verified = issuer_verify(&issuer, &presentation).unwrap();
+        let commitment = issuer_verify_roster_membership(&issuer, &verified).unwrap();
+
+        assert!(commitment == roster_entry_commitment_remove_opening(&entry).unwrap());
+    }
+
+    #[test]
+    fn user_with_keypair() {
+        let system_parameters = system_parameters_create(&SEED).unwrap();
+        let keypair = issuer_create(&system_parameters, &SEED).unwrap();
+        let issuer = issuer_new(&system_parameters, &keypair).unwrap();
+        let issuer_parameters = issuer_get_issuer_parameters(&issuer).unwrap();
+        let elgamal_keypair = elgamal_keypair_create(&SEED).unwrap();
+        let user = user_create(PHONE_NUMBER, &system_parameters, &issuer_parameters,
+                               Some(&elgamal_keypair)).unwrap();
+
+        assert!(user_get_public_key(&user).unwrap() == elgamal_keypair_get_public_key(&elgamal_keypair).unwrap());
+    }
+}
@@ -19,6 +19,8 @@ use aeonflux::credential::Credential;
 use aeonflux::credential::CredentialIssuance;
 use aeonflux::credential::CredentialPresentation;
 use aeonflux::errors::CredentialError;
+use aeonflux::one_out_of_many;
+use aeonflux::proofs::committed_value_encrypted;
 use aeonflux::proofs::committed_values_equal;
 
 use bincode::{deserialize, serialize};
@@ -28,6 +30,7 @@ use serde::de::Visitor;
 
 use phone_number::SIZEOF_COMMITTED_PHONE_NUMBER;
 use phone_number::CommittedPhoneNumber;
+use phone_number::EncryptedPhoneNumber;
 
 /// The number of revealed attributes on a `SignalCredential` during issuance.
 pub const ISSUANCE_NUMBER_OF_REVEALED_ATTRIBUTES: usize = 1;
@@ -111,6 +114,107 @@ impl SignalCredentialPresentation {
 impl_serde_with_to_bytes_and_from_bytes!(SignalCredentialPresentation,
                                          "A valid byte sequence representing a SignalCredentialPresentation");
 
+/// A presentation of a `SignalCredential` along with a proof that the
+/// credential's committed phone number is in some entry of a `GroupRoster`,
+/// without revealing which.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignalCredentialAnonymousPresentation {
+    /// A rerandomisation of the user's roster entry, which is unlinkable to
+    /// the entry itself.
+    pub rerandomized_roster_entry: CommittedPhoneNumber,
+    /// A `CredentialPresentation` showing that this credential is valid.
+    pub presentation: CredentialPresentation,
+    /// A proof that the committed attribute in the `presentation` is the same
+    /// as the value in the `rerandomized_roster_entry`.
+    pub roster_membership_proof: committed_values_equal::Proof,
+    /// A proof that the `rerandomized_roster_entry` is a rerandomisation of
+    /// one of the entries in the roster.
+    pub roster_entry_proof: one_out_of_many::Proof,
+}
+
+impl SignalCredentialAnonymousPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalCredentialAnonymousPresentation, CredentialError> {
+        let (rerandomized_roster_entry, presentation, roster_membership_proof, roster_entry_proof) =
+            match deserialize(bytes) {
+                Ok(x)   => x,
+                Err(_x) => {
+                    #[cfg(feature = "std")]
+                    println!("Error while deserializing SignalCredentialAnonymousPresentation: {}", _x);
+                    return Err(CredentialError::MissingData);
+                },
+            };
+
+        Ok(SignalCredentialAnonymousPresentation {
+            rerandomized_roster_entry,
+            presentation,
+            roster_membership_proof,
+            roster_entry_proof,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.rerandomized_roster_entry, &self.presentation,
+                      &self.roster_membership_proof, &self.roster_entry_proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing SignalCredentialAnonymousPresentation: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignalCredentialAnonymousPresentation,
+                                         "A valid byte sequence representing a SignalCredentialAnonymousPresentation");
+
+/// A presentation of a `SignalCredential` along with proof that the user's
+/// phone number is the one encrypted in their entry in an
+/// `EncryptedGroupRoster`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignalCredentialEncryptedPresentation {
+    /// The user's roster entry, encrypted to the group's public key.
+    pub roster_entry: EncryptedPhoneNumber,
+    /// A `CredentialPresentation` showing that this credential is valid.
+    pub presentation: CredentialPresentation,
+    /// A proof that the committed attribute in the `presentation` is the same
+    /// as the value encrypted in the `roster_entry`.
+    pub roster_membership_proof: committed_value_encrypted::Proof,
+}
+
+impl SignalCredentialEncryptedPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalCredentialEncryptedPresentation, CredentialError> {
+        let (roster_entry, presentation, roster_membership_proof) = match deserialize(bytes) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while deserializing SignalCredentialEncryptedPresentation: {}", _x);
+                return Err(CredentialError::MissingData);
+            },
+        };
+
+        Ok(SignalCredentialEncryptedPresentation { roster_entry, presentation, roster_membership_proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.roster_entry, &self.presentation, &self.roster_membership_proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing SignalCredentialEncryptedPresentation: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignalCredentialEncryptedPresentation,
+                                         "A valid byte sequence representing a SignalCredentialEncryptedPresentation");
+
 /// An anonymous credential belonging to a `SignalUser` and issued and verified
 /// by a `SignalIssuer`.
 pub type SignalCredential = Credential;
@@ -25,6 +25,12 @@ use aeonflux::errors::CredentialError;
 pub enum PhoneNumberError {
     LengthExceeded,
     InvalidPhoneNumber,
+    EmptyPhoneNumber,
+    MissingPlusSign,
+    InvalidCharacter,
+    InvalidCountryCode,
+    TooFewDigits,
+    TooManyDigits,
 }
 
 impl fmt::Display for PhoneNumberError {
@@ -34,6 +40,18 @@ impl fmt::Display for PhoneNumberError {
                 => write!(f, "A canonicalised phone number cannot be more than 32 bytes"),
             PhoneNumberError::InvalidPhoneNumber
                 => write!(f, "The user's proof of roster membership could not be verified"),
+            PhoneNumberError::EmptyPhoneNumber
+                => write!(f, "The phone number was empty"),
+            PhoneNumberError::MissingPlusSign
+                => write!(f, "An E.164 phone number must begin with a '+'"),
+            PhoneNumberError::InvalidCharacter
+                => write!(f, "The phone number contained a character which is neither a digit nor a separator"),
+            PhoneNumberError::InvalidCountryCode
+                => write!(f, "The phone number did not begin with an assigned E.164 country code"),
+            PhoneNumberError::TooFewDigits
+                => write!(f, "The phone number had too few digits after its country code"),
+            PhoneNumberError::TooManyDigits
+                => write!(f, "An E.164 phone number cannot be more than 15 digits"),
         }
     }
 }
@@ -46,6 +64,49 @@ impl From<NoneError> for PhoneNumberError {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum AccountIdentifierError {
+    InvalidPhoneNumber(PhoneNumberError),
+    InvalidUsername,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for AccountIdentifierError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccountIdentifierError::InvalidPhoneNumber(ref x)
+                => write!(f, "Invalid phone number: {}", x),
+            AccountIdentifierError::InvalidUsername
+                => write!(f, "A username must be 1 to 32 ASCII letters, digits, underscores, or dots"),
+            AccountIdentifierError::WrongNumberOfBytes
+                => write!(f, "The account identifier could not be deserialised because it had the wrong length"),
+        }
+    }
+}
+
+impl ::failure::Fail for AccountIdentifierError { }
+
+impl From<PhoneNumberError> for AccountIdentifierError {
+    fn from(source: PhoneNumberError) -> AccountIdentifierError {
+        AccountIdentifierError::InvalidPhoneNumber(source)
+    }
+}
+
+impl From<AccountIdentifierError> for PhoneNumberError {
+    fn from(source: AccountIdentifierError) -> PhoneNumberError {
+        match source {
+            AccountIdentifierError::InvalidPhoneNumber(x) => x,
+            _ => PhoneNumberError::InvalidPhoneNumber,
+        }
+    }
+}
+
+impl From<AccountIdentifierError> for CredentialError {
+    fn from(_source: AccountIdentifierError) -> CredentialError {
+        NoneError.into()
+    }
+}
+
 impl From<PhoneNumberError> for CredentialError {
     fn from(_source: PhoneNumberError) -> CredentialError {
         NoneError.into()
@@ -57,3 +118,82 @@ impl From<CredentialError> for PhoneNumberError {
         NoneError.into()
     }
 }
+
+/// Errors returned by a `PhoneNumberVerifier` when a user could not prove
+/// that they own the phone number they are requesting a credential for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum VerificationError {
+    /// No verification code was pending for the number, or the code given
+    /// did not match it.
+    Unverified,
+    /// A verification code was pending for the number, but it has expired.
+    Expired,
+    /// Too many verification attempts have been made for the number.
+    RateLimited,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerificationError::Unverified
+                => write!(f, "Ownership of the phone number could not be verified"),
+            VerificationError::Expired
+                => write!(f, "The verification code for the phone number has expired"),
+            VerificationError::RateLimited
+                => write!(f, "Too many verification attempts were made for the phone number"),
+        }
+    }
+}
+
+impl ::failure::Fail for VerificationError { }
+
+/// Errors which may occur when a `SignalIssuer` issues a credential only
+/// after checking the user's ownership of their identifier, or how many
+/// credentials their identifier has already been issued.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IssuanceError {
+    Verification(VerificationError),
+    Credential(CredentialError),
+    /// The identifier has already been issued as many credentials as the
+    /// `IssuancePolicy` permits for now.
+    ///
+    /// The identifier may try again after `retry_after` seconds.
+    LimitExceeded{ retry_after: u64 },
+    /// The record of prior issuances could not be read or written.
+    Storage,
+}
+
+impl fmt::Display for IssuanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IssuanceError::Verification(ref x)
+                => write!(f, "Verification failed: {}", x),
+            IssuanceError::Credential(ref x)
+                => write!(f, "Issuance failed: {}", x),
+            IssuanceError::LimitExceeded{ retry_after }
+                => write!(f, "Too many credentials were issued for this identifier, retry after {} seconds", retry_after),
+            IssuanceError::Storage
+                => write!(f, "The record of prior issuances could not be read or written"),
+        }
+    }
+}
+
+impl ::failure::Fail for IssuanceError { }
+
+impl From<VerificationError> for IssuanceError {
+    fn from(source: VerificationError) -> IssuanceError {
+        IssuanceError::Verification(source)
+    }
+}
+
+impl From<CredentialError> for IssuanceError {
+    fn from(source: CredentialError) -> IssuanceError {
+        IssuanceError::Credential(source)
+    }
+}
+
+impl From<AccountIdentifierError> for IssuanceError {
+    fn from(source: AccountIdentifierError) -> IssuanceError {
+        IssuanceError::Credential(source.into())
+    }
+}
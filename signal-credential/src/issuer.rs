@@ -13,14 +13,31 @@ use alloc::vec::Vec;
 use std::vec::Vec;
 
 use aeonflux::amacs::{self};
+use aeonflux::credential::CredentialPresentation;
 use aeonflux::credential::CredentialRequest;
 use aeonflux::credential::RevealedAttribute;
+use aeonflux::elgamal::{self};
 use aeonflux::errors::CredentialError;
 use aeonflux::issuer::Issuer;
+use aeonflux::linear_relations::Proof;
 pub use aeonflux::issuer::IssuerParameters;
 pub use aeonflux::issuer::IssuerSecretKey;
 use aeonflux::parameters::SystemParameters;
+use aeonflux::nonces::Ephemeral;
+use aeonflux::pedersen;
+use aeonflux::proofs::committed_value_encrypted;
 use aeonflux::proofs::committed_values_equal;
+use aeonflux::proofs::encrypted_attribute;
+use aeonflux::proofs::issuance_blinded_renewal;
+use aeonflux::proofs::issuance_partially_blinded;
+use aeonflux::proofs::issuance_revealed_role;
+use aeonflux::proofs::valid_credential_renewal;
+use aeonflux::proofs::valid_credential_with_encryption;
+use aeonflux::proofs::valid_role_credential;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
 
 use merlin::Transcript;
 
@@ -30,28 +47,135 @@ use rand_core::CryptoRng;
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
+use account_identifier::AccountIdentifier;
+use account_identifier::ToAccountIdentifier;
 use credential::NUMBER_OF_ATTRIBUTES;
+use credential::SignalCredentialAnonymousPresentation;
+use credential::SignalCredentialEncryptedPresentation;
 use credential::SignalCredentialIssuance;
 use credential::SignalCredentialPresentation;
 use credential::VerifiedSignalCredential;
+use errors::IssuanceError;
 use phone_number::CommittedPhoneNumber;
-use phone_number::PhoneNumber;
+use policy::IssuanceLimiter;
+use policy::IssuanceStore;
+use policy::RenewalLimiter;
+use profile_key::EncryptedProfileKey;
+use profile_key::NUMBER_OF_PROFILE_KEY_ATTRIBUTES;
+use profile_key::ProfileKeyCredentialIssuance;
+use profile_key::ProfileKeyCredentialPresentation;
+use profile_key::ProfileKeyCredentialRequest;
+use renewal::NUMBER_OF_RENEWAL_KEY_ATTRIBUTES;
+use renewal::RenewalKeyCredentialIssuance;
+use renewal::RenewalKeyCredentialRequest;
+use renewal::RenewalKeyIssuancePublics;
+use renewal::SignalCredentialRenewal;
+use renewal::SignalCredentialRenewalRequest;
+use renewal::renewal_key_issuance_statement;
+use renewal::renewal_tag_statement;
+use renewal::revealed_identifier;
+use role::NUMBER_OF_ROLE_ATTRIBUTES;
+use role::Role;
+use role::SignalRoleCredential;
+use role::SignalRoleCredentialIssuance;
+use role::SignalRoleCredentialPresentation;
+use roster::EncryptedGroupRoster;
+use roster::GroupRoster;
+use verification::PhoneNumberVerifier;
+
+/// The size of a serialised `Issuer` for single-attribute `SignalCredential`s.
+const SIZEOF_ISSUER: usize = 160;
+
+/// The size of a serialised `amacs::Keypair` for two-attribute credentials.
+const SIZEOF_TWO_ATTRIBUTE_KEYPAIR: usize = 160;
+
+/// The tags identifying each optional keypair in a serialised `SignalIssuer`.
+const KEYPAIR_ROLES: u8 = 1;
+const KEYPAIR_PROFILE_KEYS: u8 = 2;
+const KEYPAIR_RENEWAL_KEYS: u8 = 3;
 
 /// An issuer and honest verifier of `SignalCredential`s.
 #[repr(C)]
 pub struct SignalIssuer {
     pub issuer: Issuer,
+    /// An optional keypair for issuing and verifying `SignalRoleCredential`s.
+    pub role_keypair: Option<amacs::Keypair>,
+    /// An optional keypair for issuing and verifying `ProfileKeyCredential`s.
+    pub profile_key_keypair: Option<amacs::Keypair>,
+    /// An optional keypair for issuing and verifying `RenewalKeyCredential`s.
+    pub renewal_key_keypair: Option<amacs::Keypair>,
+}
+
+/// Get the commitment to the first, and only, blinded attribute of a
+/// `presentation`, which is the user's phone number.
+fn first_blinded_attribute(presentation: &CredentialPresentation) -> Result<RistrettoPoint, CredentialError> {
+    match presentation.attributes_blinded.get(0) {
+        Some(x) => Ok(x.clone().into()),
+        None    => Err(CredentialError::WrongNumberOfAttributes),
+    }
+}
+
+/// Get an optional two-attribute `keypair`, checking that it really is one,
+/// since the keys are indexed by attribute.
+fn two_attribute_keypair(keypair: &Option<amacs::Keypair>) -> Result<&amacs::Keypair, CredentialError> {
+    match *keypair {
+        Some(ref x) if x.secret.xn.len() == 2 && x.public.Xn.len() == 2 => Ok(x),
+        Some(_) => Err(CredentialError::WrongNumberOfAttributes),
+        None    => Err(CredentialError::NoIssuerKey),
+    }
 }
 
 impl SignalIssuer {
+    /// Deserialise a `SignalIssuer`.
+    ///
+    /// A serialised `Issuer` is a valid serialised `SignalIssuer` without any
+    /// optional keypairs.  Each optional keypair follows the `Issuer`,
+    /// prefixed with a byte identifying which keypair it is.
     pub fn from_bytes(bytes: &[u8]) -> Result<SignalIssuer, CredentialError> {
-        Ok(SignalIssuer {
-            issuer: Issuer::from_bytes(bytes)?,
-        })
+        if bytes.len() < SIZEOF_ISSUER ||
+            (bytes.len() - SIZEOF_ISSUER) % (1 + SIZEOF_TWO_ATTRIBUTE_KEYPAIR) != 0
+        {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut issuer = SignalIssuer {
+            issuer: Issuer::from_bytes(&bytes[..SIZEOF_ISSUER])?,
+            role_keypair: None,
+            profile_key_keypair: None,
+            renewal_key_keypair: None,
+        };
+
+        for chunk in bytes[SIZEOF_ISSUER..].chunks(1 + SIZEOF_TWO_ATTRIBUTE_KEYPAIR) {
+            let keypair = Some(amacs::Keypair::from_bytes(&chunk[1..])?);
+
+            match chunk[0] {
+                KEYPAIR_ROLES if issuer.role_keypair.is_none() => issuer.role_keypair = keypair,
+                KEYPAIR_PROFILE_KEYS if issuer.profile_key_keypair.is_none() => issuer.profile_key_keypair = keypair,
+                KEYPAIR_RENEWAL_KEYS if issuer.renewal_key_keypair.is_none() => issuer.renewal_key_keypair = keypair,
+                _ => return Err(CredentialError::NoIssuerKey),
+            }
+        }
+
+        Ok(issuer)
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        self.issuer.to_bytes()
+        let mut v: Vec<u8> = self.issuer.to_bytes();
+
+        if let Some(ref keypair) = self.role_keypair {
+            v.push(KEYPAIR_ROLES);
+            v.extend(keypair.to_bytes());
+        }
+        if let Some(ref keypair) = self.profile_key_keypair {
+            v.push(KEYPAIR_PROFILE_KEYS);
+            v.extend(keypair.to_bytes());
+        }
+        if let Some(ref keypair) = self.renewal_key_keypair {
+            v.push(KEYPAIR_RENEWAL_KEYS);
+            v.extend(keypair.to_bytes());
+        }
+
+        v
     }
 }
 
@@ -69,6 +193,9 @@ impl SignalIssuer {
     {
         SignalIssuer {
             issuer: Issuer::create(system_parameters, csprng),
+            role_keypair: None,
+            profile_key_keypair: None,
+            renewal_key_keypair: None,
         }
     }
 
@@ -87,14 +214,71 @@ impl SignalIssuer {
     {
         SignalIssuer {
             issuer: Issuer::new(system_parameters, keypair),
+            role_keypair: None,
+            profile_key_keypair: None,
+            renewal_key_keypair: None,
         }
     }
 
+    /// Generate a new keypair for issuing and verifying `SignalRoleCredential`s.
+    pub fn with_roles<R>(mut self, csprng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = amacs::SecretKey::new(NUMBER_OF_ROLE_ATTRIBUTES, csprng);
+        let public = secret.get_public_key(&self.issuer.system_parameters.h);
+
+        self.role_keypair = Some(amacs::Keypair { public, secret });
+        self
+    }
+
+    /// Generate a new keypair for issuing and verifying `ProfileKeyCredential`s.
+    pub fn with_profile_keys<R>(mut self, csprng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = amacs::SecretKey::new(NUMBER_OF_PROFILE_KEY_ATTRIBUTES, csprng);
+        let public = secret.get_public_key(&self.issuer.system_parameters.h);
+
+        self.profile_key_keypair = Some(amacs::Keypair { public, secret });
+        self
+    }
+
+    /// Generate a new keypair for issuing and verifying `RenewalKeyCredential`s.
+    pub fn with_renewal_keys<R>(mut self, csprng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = amacs::SecretKey::new(NUMBER_OF_RENEWAL_KEY_ATTRIBUTES, csprng);
+        let public = secret.get_public_key(&self.issuer.system_parameters.h);
+
+        self.renewal_key_keypair = Some(amacs::Keypair { public, secret });
+        self
+    }
+
     /// Get an owned copy of this `SignalIssuer`'s public aMAC key material.
     pub fn get_issuer_parameters(&self) -> IssuerParameters {
         self.issuer.get_issuer_parameters()
     }
 
+    /// Get an owned copy of this `SignalIssuer`'s public aMAC key material for
+    /// `SignalRoleCredential`s, if it has any.
+    pub fn get_role_issuer_parameters(&self) -> Option<IssuerParameters> {
+        self.role_keypair.as_ref().map(|x| x.public.clone())
+    }
+
+    /// Get an owned copy of this `SignalIssuer`'s public aMAC key material for
+    /// `ProfileKeyCredential`s, if it has any.
+    pub fn get_profile_key_issuer_parameters(&self) -> Option<IssuerParameters> {
+        self.profile_key_keypair.as_ref().map(|x| x.public.clone())
+    }
+
+    /// Get an owned copy of this `SignalIssuer`'s public aMAC key material for
+    /// `RenewalKeyCredential`s, if it has any.
+    pub fn get_renewal_key_issuer_parameters(&self) -> Option<IssuerParameters> {
+        self.renewal_key_keypair.as_ref().map(|x| x.public.clone())
+    }
+
     /// Unblinded credential issuance.
     ///
     /// # Note
@@ -103,11 +287,20 @@ impl SignalIssuer {
     /// using this method, it does not necessarily see all attributes upon
     /// presentation.
     ///
+    /// # Warning
+    ///
+    /// This method neither checks that the user owns their `identifier` nor
+    /// limits how many credentials are issued for it, and so is unsafe to
+    /// expose in a deployment: anyone could obtain unboundedly many
+    /// credentials for any phone number.  Deployments should instead use
+    /// `SignalIssuer::issue_verified_and_rate_limited()`.
+    ///
     /// # Inputs
     ///
-    /// * `phone_number` is the the user's phone number as bytes, e.g. the phone
-    ///   number `"+14155551234"` should be given as
-    ///   `[0, 0, 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4]`.
+    /// * `identifier` is the user's `AccountIdentifier`, or anything which may
+    ///   be converted into one.  For example, the user's phone number may be
+    ///   given as bytes, so that the phone number `"+14155551234"` should be
+    ///   given as `[0, 0, 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4]`.
     /// * `rng` is an implementation of `rand::RngCore + rand::CryptoRng`.
     ///
     /// # Errors
@@ -118,34 +311,715 @@ impl SignalIssuer {
     ///   `issuer_parameters` didn't contain the correct length of public key.
     /// * `CredentialError::NoIssuerKey` if this `SignalIssuer`'s secret `key`
     ///   was not the correct length.
-    /// * `CredentialError::MissingData` if the user's supplied phone number (as
-    ///   a `String`) could not be successfully converted into a `PhoneNumber`
-    ///   as a `Scalar`.
+    /// * `CredentialError::MissingData` if the user's supplied `identifier`
+    ///   could not be successfully converted into an `AccountIdentifier`.
     /// * `CredentialError::VerificationFailure` if the `request`'s `proof`
     ///   couldn't be verified.
     ///
     /// # Returns
     ///
     /// A `SignalCredentialRequest` upon successful issuance.
-    pub fn issue<R>(
+    pub fn issue<I, R>(
         &self,
-        phone_number: &[u8],
+        identifier: &I,
         rng: &mut R,
     ) -> Result<SignalCredentialIssuance, CredentialError>
     where
+        I: ToAccountIdentifier + ?Sized,
         R: RngCore + CryptoRng,
     {
-        // Construct the phone number and form a credential out of it.
-        let number: PhoneNumber = PhoneNumber::try_from_bytes(&phone_number)?;
+        // Construct the identifier and form a credential out of it.
+        let identifier: AccountIdentifier = identifier.to_account_identifier()?;
         let mut attributes_revealed: Vec<RevealedAttribute> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
 
-        attributes_revealed.push(number.0);
+        attributes_revealed.push(identifier.to_scalar());
 
-        let request: CredentialRequest = CredentialRequest { attributes_revealed };
+        let request: CredentialRequest = CredentialRequest {
+            attributes_revealed,
+            point_attributes_revealed: Vec::new(),
+        };
 
         self.issuer.issue(&request, rng)
     }
 
+    /// Issue a new `SignalCredential` to a user, only after checking with a
+    /// `PhoneNumberVerifier` that they own their `identifier`.
+    ///
+    /// # Inputs
+    ///
+    /// * `verifier` is an implementation of `PhoneNumberVerifier`, for example
+    ///   one which checks a code previously sent to the user via SMS.
+    /// * `identifier` is the user's `AccountIdentifier`, or anything which may
+    ///   be converted into one, as for `SignalIssuer::issue()`.
+    /// * `code` is the verification code supplied by the user.
+    /// * `rng` is an implementation of `rand::RngCore + rand::CryptoRng`.
+    ///
+    /// # Errors
+    ///
+    /// * `IssuanceError::Verification` if the `verifier` rejected the `code`,
+    ///   in which case no credential is issued.
+    /// * `IssuanceError::Credential` for any of the errors which
+    ///   `SignalIssuer::issue()` may return.
+    pub fn issue_verified<V, I, R>(
+        &self,
+        verifier: &mut V,
+        identifier: &I,
+        code: &[u8],
+        rng: &mut R,
+    ) -> Result<SignalCredentialIssuance, IssuanceError>
+    where
+        V: PhoneNumberVerifier + ?Sized,
+        I: ToAccountIdentifier + ?Sized,
+        R: RngCore + CryptoRng,
+    {
+        let identifier: AccountIdentifier = identifier.to_account_identifier()?;
+
+        verifier.verify(&identifier, code)?;
+
+        Ok(self.issue(&identifier, rng)?)
+    }
+
+    /// Issue a new `SignalCredential` to a user, only if their `identifier`
+    /// has not exceeded the `limiter`'s `IssuancePolicy`.
+    ///
+    /// # Inputs
+    ///
+    /// * `limiter` is an `IssuanceLimiter`, holding the `IssuancePolicy` and
+    ///   the `IssuanceStore` recording prior issuances.
+    /// * `identifier` is the user's `AccountIdentifier`, or anything which may
+    ///   be converted into one, as for `SignalIssuer::issue()`.
+    /// * `now` is the current time, in seconds since the UNIX epoch.
+    /// * `rng` is an implementation of `rand::RngCore + rand::CryptoRng`.
+    ///
+    /// # Errors
+    ///
+    /// * `IssuanceError::LimitExceeded` if the `identifier` may not be issued
+    ///   another credential until `retry_after` seconds have passed.
+    /// * `IssuanceError::Storage` if the `limiter`'s store failed.
+    /// * `IssuanceError::Credential` for any of the errors which
+    ///   `SignalIssuer::issue()` may return.
+    pub fn issue_rate_limited<S, I, R>(
+        &self,
+        limiter: &mut IssuanceLimiter<S>,
+        identifier: &I,
+        now: u64,
+        rng: &mut R,
+    ) -> Result<SignalCredentialIssuance, IssuanceError>
+    where
+        S: IssuanceStore,
+        I: ToAccountIdentifier + ?Sized,
+        R: RngCore + CryptoRng,
+    {
+        let identifier: AccountIdentifier = identifier.to_account_identifier()?;
+
+        limiter.record(&identifier, now)?;
+
+        Ok(self.issue(&identifier, rng)?)
+    }
+
+    /// Issue a new `SignalCredential` to a user, only after checking with a
+    /// `PhoneNumberVerifier` that they own their `identifier`, and only if
+    /// their `identifier` has not exceeded the `limiter`'s `IssuancePolicy`.
+    ///
+    /// This is the issuance path which deployments should expose.  The code
+    /// is verified before the issuance is recorded, so that nobody but the
+    /// owner of an `identifier` can use up its allowance of credentials.
+    ///
+    /// # Inputs
+    ///
+    /// * `verifier` is an implementation of `PhoneNumberVerifier`, as for
+    ///   `SignalIssuer::issue_verified()`.
+    /// * `limiter` is an `IssuanceLimiter`, as for
+    ///   `SignalIssuer::issue_rate_limited()`.
+    /// * `identifier` is the user's `AccountIdentifier`, or anything which may
+    ///   be converted into one, as for `SignalIssuer::issue()`.
+    /// * `code` is the verification code supplied by the user.
+    /// * `now` is the current time, in seconds since the UNIX epoch.
+    /// * `rng` is an implementation of `rand::RngCore + rand::CryptoRng`.
+    ///
+    /// # Errors
+    ///
+    /// * `IssuanceError::Verification` if the `verifier` rejected the `code`,
+    ///   in which case no issuance is recorded.
+    /// * `IssuanceError::LimitExceeded` if the `identifier` may not be issued
+    ///   another credential until `retry_after` seconds have passed.
+    /// * `IssuanceError::Storage` if the `limiter`'s store failed.
+    /// * `IssuanceError::Credential` for any of the errors which
+    ///   `SignalIssuer::issue()` may return.
+    pub fn issue_verified_and_rate_limited<V, S, I, R>(
+        &self,
+        verifier: &mut V,
+        limiter: &mut IssuanceLimiter<S>,
+        identifier: &I,
+        code: &[u8],
+        now: u64,
+        rng: &mut R,
+    ) -> Result<SignalCredentialIssuance, IssuanceError>
+    where
+        V: PhoneNumberVerifier + ?Sized,
+        S: IssuanceStore,
+        I: ToAccountIdentifier + ?Sized,
+        R: RngCore + CryptoRng,
+    {
+        let identifier: AccountIdentifier = identifier.to_account_identifier()?;
+
+        verifier.verify(&identifier, code)?;
+        limiter.record(&identifier, now)?;
+
+        Ok(self.issue(&identifier, rng)?)
+    }
+
+    /// Issue a `SignalRoleCredential` on a user's `identifier` and their
+    /// `role` in a group.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerKey` if this `SignalIssuer` has no
+    ///   `role_keypair`.
+    /// * `CredentialError::WrongNumberOfAttributes` if the `role_keypair`
+    ///   isn't for two attributes.
+    /// * `CredentialError::MissingData` if the `identifier` could not be
+    ///   converted into an `AccountIdentifier`.
+    /// * `CredentialError::MacCreation` if the aMAC could not be computed.
+    pub fn issue_with_role<I, R>(
+        &self,
+        identifier: &I,
+        role: Role,
+        rng: &mut R,
+    ) -> Result<SignalRoleCredentialIssuance, CredentialError>
+    where
+        I: ToAccountIdentifier + ?Sized,
+        R: RngCore + CryptoRng,
+    {
+        let keypair: &amacs::Keypair = two_attribute_keypair(&self.role_keypair)?;
+        let identifier: AccountIdentifier = identifier.to_account_identifier()?;
+
+        let mut transcript = Transcript::new(b"SIGNAL ROLE ISSUANCE");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(NUMBER_OF_ROLE_ATTRIBUTES);
+
+        attributes.push(identifier.to_scalar());
+        attributes.push(role.to_scalar());
+
+        let tag: amacs::Tag = keypair.secret.mac(&attributes.into(), &mut csprng)
+            .or(Err(CredentialError::MacCreation))?;
+        let credential = SignalRoleCredential {
+            mac: tag,
+            identifier: identifier.to_scalar(),
+            role: role,
+        };
+
+        let A: &RistrettoPoint = &self.issuer.system_parameters.h;
+        let B: &RistrettoPoint = &self.issuer.system_parameters.g;
+        let P: &RistrettoPoint = &credential.mac.nonce;
+
+        // Commit to our secret key.
+        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let secret_key_commitment = pedersen::Commitment::to(&(B * keypair.secret.x0), &x0_tilde, A);
+
+        let secrets = issuance_revealed_role::Secrets {
+            x0: &keypair.secret.x0,
+            x1: &keypair.secret.xn[0],
+            x2: &keypair.secret.xn[1],
+            x0_tilde: (&x0_tilde).into(),
+        };
+        let publics = issuance_revealed_role::Publics {
+            P: P,
+            Q: &credential.mac.mac,
+            Cx0: &secret_key_commitment.into(),
+            B: B,
+            A: A,
+            X1: &keypair.public.Xn[0],
+            X2: &keypair.public.Xn[1],
+            M1: &(P * credential.identifier),
+            M2: &(P * credential.role.to_scalar()),
+        };
+        let proof = issuance_revealed_role::Proof::create(&mut transcript, publics, secrets, &mut csprng);
+
+        Ok(SignalRoleCredentialIssuance { credential, secret_key_commitment, proof })
+    }
+
+    /// Blindly issue a `ProfileKeyCredential` on a user's identifier and
+    /// their encrypted profile key.
+    ///
+    /// # Note
+    ///
+    /// The issuer MUST check that the `request`'s `identifier` belongs to the
+    /// user making the request.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerKey` if this `SignalIssuer` has no
+    ///   `profile_key_keypair`.
+    /// * `CredentialError::WrongNumberOfAttributes` if the `profile_key_keypair`
+    ///   isn't for two attributes.
+    /// * `CredentialError::VerificationFailure` if the proof that the user's
+    ///   encrypted profile key is well-formed couldn't be verified.
+    pub fn issue_profile_key<R>(
+        &self,
+        request: &ProfileKeyCredentialRequest,
+        rng: &mut R,
+    ) -> Result<ProfileKeyCredentialIssuance, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let keypair: &amacs::Keypair = two_attribute_keypair(&self.profile_key_keypair)?;
+        let A: &RistrettoPoint = &self.issuer.system_parameters.h;
+        let B: &RistrettoPoint = &self.issuer.system_parameters.g;
+        let D: RistrettoPoint = request.public_key.into();
+        let E: &elgamal::Encryption = &request.encrypted_profile_key.0;
+
+        // Check that the encrypted profile key is well-formed.
+        let mut transcript = Transcript::new(b"SIGNAL PROFILE KEY REQUEST");
+        let publics = encrypted_attribute::Publics {
+            B: B,
+            D: &D,
+            E1: &E.commitment,
+            E2: &E.encryption,
+        };
+
+        if request.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let mut transcript = Transcript::new(b"SIGNAL PROFILE KEY ISSUANCE");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let x0: &Scalar = &keypair.secret.x0;
+        let x1: &Scalar = &keypair.secret.xn[0];
+        let x2: &Scalar = &keypair.secret.xn[1];
+        let m1: Scalar = request.identifier.to_scalar();
+
+        // Choose the nonce, P = b B, and compute the encryption of the aMAC,
+        //
+        //     Q = (x0 + x1 m1) P + x2 b Enc(m2 B),
+        //
+        // using the additive homomorphism of elGamal encryption.
+        let b: Scalar = Scalar::random(&mut csprng);
+        let s: Scalar = Scalar::random(&mut csprng);
+        let t: Scalar = x2 * b;
+        let P: RistrettoPoint = B * b;
+        let T: RistrettoPoint = A * t;
+        let M1: RistrettoPoint = P * m1;
+        let encrypted_mac = elgamal::Encryption {
+            commitment: B * s + E.commitment * t,
+            encryption: D * s + E.encryption * t + P * x0 + M1 * x1,
+        };
+
+        // Commit to our secret key.
+        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let secret_key_commitment = pedersen::Commitment::to(&(B * x0), &x0_tilde, A);
+
+        let secrets = issuance_partially_blinded::Secrets {
+            x0: x0,
+            x1: x1,
+            x2: x2,
+            x0_tilde: (&x0_tilde).into(),
+            b: &b,
+            s: &s,
+            t: &t,
+        };
+        let publics = issuance_partially_blinded::Publics {
+            B: B,
+            A: A,
+            Cx0: &secret_key_commitment.into(),
+            X1: &keypair.public.Xn[0],
+            X2: &keypair.public.Xn[1],
+            P: &P,
+            T0: &T,
+            T1: &T,
+            D: &D,
+            E1: &E.commitment,
+            E2: &E.encryption,
+            M1: &M1,
+            EQ1: &encrypted_mac.commitment,
+            EQ2: &encrypted_mac.encryption,
+        };
+        let proof = issuance_partially_blinded::Proof::create(&mut transcript, publics, secrets, &mut csprng);
+
+        Ok(ProfileKeyCredentialIssuance {
+            nonce: P,
+            encrypted_mac,
+            auxiliary_commitment: T,
+            secret_key_commitment,
+            proof,
+        })
+    }
+
+    /// Blindly issue a `RenewalKeyCredential` on a user's identifier and
+    /// their encrypted renewal key.
+    ///
+    /// # Note
+    ///
+    /// The issuer MUST check that the `request`'s `identifier` belongs to the
+    /// user making the request, and MUST NOT issue renewal key credentials
+    /// more often than `SignalCredential`s, since each permits another
+    /// renewal per epoch.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerKey` if this `SignalIssuer` has no
+    ///   `renewal_key_keypair`.
+    /// * `CredentialError::WrongNumberOfAttributes` if the
+    ///   `renewal_key_keypair` isn't for two attributes.
+    /// * `CredentialError::VerificationFailure` if the proof that the user's
+    ///   encrypted renewal key is well-formed couldn't be verified.
+    pub fn issue_renewal_key<R>(
+        &self,
+        request: &RenewalKeyCredentialRequest,
+        rng: &mut R,
+    ) -> Result<RenewalKeyCredentialIssuance, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let keypair: &amacs::Keypair = two_attribute_keypair(&self.renewal_key_keypair)?;
+        let D: RistrettoPoint = request.public_key.into();
+        let F: &elgamal::Encryption = &request.encrypted_renewal_key;
+
+        // Check that the encrypted renewal key is well-formed.
+        let mut transcript = Transcript::new(b"SIGNAL RENEWAL KEY REQUEST");
+        let publics = encrypted_attribute::Publics {
+            B: &self.issuer.system_parameters.g,
+            D: &D,
+            E1: &F.commitment,
+            E2: &F.encryption,
+        };
+
+        if request.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let E: elgamal::Encryption = revealed_identifier(&self.issuer.system_parameters,
+                                                         &request.identifier.to_scalar());
+
+        self.issue_blinded_renewal_key(keypair, &request.public_key, &E, F, rng)
+    }
+
+    /// Issue a `RenewalKeyCredential` with the `keypair`, over an identifier
+    /// and a renewal key encrypted to a user's `public_key`.
+    fn issue_blinded_renewal_key<R>(
+        &self,
+        keypair: &amacs::Keypair,
+        public_key: &elgamal::PublicKey,
+        encrypted_identifier: &elgamal::Encryption,
+        encrypted_renewal_key: &elgamal::Encryption,
+        rng: &mut R,
+    ) -> Result<RenewalKeyCredentialIssuance, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let A: &RistrettoPoint = &self.issuer.system_parameters.h;
+        let B: &RistrettoPoint = &self.issuer.system_parameters.g;
+        let D: RistrettoPoint = (*public_key).into();
+        let E: &elgamal::Encryption = encrypted_identifier;
+        let F: &elgamal::Encryption = encrypted_renewal_key;
+
+        let mut transcript = Transcript::new(b"SIGNAL RENEWAL KEY ISSUANCE");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let x0: Scalar = keypair.secret.x0;
+        let x1: Scalar = keypair.secret.xn[0];
+        let x2: Scalar = keypair.secret.xn[1];
+
+        // Choose the nonce, P = b B, and compute the encryption of the aMAC,
+        //
+        //     Q = x0 P + x1 b Enc(m1 B) + x2 b Enc(m2 B),
+        //
+        // using the additive homomorphism of elGamal encryption.
+        let b: Scalar = Scalar::random(&mut csprng);
+        let s: Scalar = Scalar::random(&mut csprng);
+        let t1: Scalar = x1 * b;
+        let t2: Scalar = x2 * b;
+        let P: RistrettoPoint = B * b;
+        let T1: RistrettoPoint = A * t1;
+        let T2: RistrettoPoint = A * t2;
+        let encrypted_mac = elgamal::Encryption {
+            commitment: B * s + E.commitment * t1 + F.commitment * t2,
+            encryption: D * s + E.encryption * t1 + F.encryption * t2 + P * x0,
+        };
+
+        // Commit to our secret key.
+        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let secret_key_commitment = pedersen::Commitment::to(&(B * x0), &x0_tilde, A);
+
+        let statement = renewal_key_issuance_statement(&self.issuer.system_parameters, &keypair.public,
+                                                       &RenewalKeyIssuancePublics {
+            public_key,
+            encrypted_identifier: E,
+            encrypted_renewal_key: F,
+            nonce: &P,
+            encrypted_mac: &encrypted_mac,
+            identifier_auxiliary_commitment: &T1,
+            renewal_key_auxiliary_commitment: &T2,
+            secret_key_commitment: &secret_key_commitment,
+        });
+        let secrets = [x0, *<&Scalar>::from(&x0_tilde), x1, x2, b, s, t1, t2];
+        let proof = match Proof::create(&mut transcript, &statement, &secrets, &mut csprng) {
+            Ok(x)   => x,
+            Err(_)  => return Err(CredentialError::MacCreation),
+        };
+
+        Ok(RenewalKeyCredentialIssuance {
+            nonce: P,
+            encrypted_mac,
+            identifier_auxiliary_commitment: T1,
+            renewal_key_auxiliary_commitment: T2,
+            secret_key_commitment,
+            proof,
+        })
+    }
+
+    /// Renew a user's `SignalCredential` without learning their identifier.
+    ///
+    /// The `request` presents the user's current credential and their
+    /// `RenewalKeyCredential`, which were issued by the `previous` issuer.  If
+    /// the keys have not been rotated since, `previous` is this
+    /// `SignalIssuer`.  Both credentials are renewed under this
+    /// `SignalIssuer`'s keys, over the identifier and renewal key encrypted in
+    /// the `request`.
+    ///
+    /// # Note
+    ///
+    /// This method does not limit how many times a credential is renewed, so
+    /// a renewal request may be replayed to obtain any number of credentials.
+    /// Deployments should instead use `SignalIssuer::renew_rate_limited()`,
+    /// which refuses a second renewal with the same tag in an epoch.
+    ///
+    /// # Inputs
+    ///
+    /// * `request` is the user's `SignalCredentialRenewalRequest`.
+    /// * `previous` is the `SignalIssuer` which issued the current credential.
+    /// * `epoch` is the current epoch, for which the `request`'s renewal tag
+    ///   must have been computed.
+    /// * `rng` is an implementation of `rand::RngCore + rand::CryptoRng`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerParameters` if either issuer's key is not
+    ///   for a single attribute.
+    /// * `CredentialError::NoIssuerKey` if either issuer has no
+    ///   `renewal_key_keypair`.
+    /// * `CredentialError::WrongNumberOfAttributes` if either issuer's
+    ///   `renewal_key_keypair` isn't for two attributes.
+    /// * `CredentialError::MacVerification` if the current credential, the
+    ///   proof that the encrypted identifier is the one in it, or the proof
+    ///   that the renewal key credential is valid, on that identifier, and of
+    ///   the renewal tag for the `epoch` couldn't be verified.
+    pub fn renew<R>(
+        &self,
+        request: &SignalCredentialRenewalRequest,
+        previous: &SignalIssuer,
+        epoch: u64,
+        rng: &mut R,
+    ) -> Result<SignalCredentialRenewal, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.verify_renewal_request(request, previous, epoch)?;
+        self.issue_renewal(request, rng)
+    }
+
+    /// Renew a user's `SignalCredential`, as for `SignalIssuer::renew()`, only
+    /// if the `limiter` has not already seen the `request`'s renewal tag in
+    /// the epoch of time `now`.
+    ///
+    /// # Inputs
+    ///
+    /// * `request` is the user's `SignalCredentialRenewalRequest`, whose tag
+    ///   must be for the epoch given by `limiter.epoch(now)`.
+    /// * `previous` is the `SignalIssuer` which issued the current credential.
+    /// * `limiter` is a `RenewalLimiter`, recording the renewal tags seen so
+    ///   far in the current epoch.
+    /// * `now` is the current time, in seconds since the UNIX epoch.
+    /// * `rng` is an implementation of `rand::RngCore + rand::CryptoRng`.
+    ///
+    /// # Errors
+    ///
+    /// * `IssuanceError::LimitExceeded` if the credential was already renewed
+    ///   in this epoch.
+    /// * `IssuanceError::Credential` for any of the errors which
+    ///   `SignalIssuer::renew()` may return.
+    pub fn renew_rate_limited<R>(
+        &self,
+        request: &SignalCredentialRenewalRequest,
+        previous: &SignalIssuer,
+        limiter: &mut RenewalLimiter,
+        now: u64,
+        rng: &mut R,
+    ) -> Result<SignalCredentialRenewal, IssuanceError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.verify_renewal_request(request, previous, limiter.epoch(now))?;
+
+        limiter.record(&request.renewal_key.tag, now)?;
+
+        Ok(self.issue_renewal(request, rng)?)
+    }
+
+    /// Check that a renewal `request` presents a valid credential and renewal
+    /// key credential from the `previous` issuer, on the same identifier, and
+    /// a renewal tag for the `epoch` of the renewal key.
+    fn verify_renewal_request(
+        &self,
+        request: &SignalCredentialRenewalRequest,
+        previous: &SignalIssuer,
+        epoch: u64,
+    ) -> Result<(), CredentialError>
+    {
+        let old: &amacs::Keypair = &previous.issuer.keypair;
+        let new: &amacs::Keypair = &self.issuer.keypair;
+
+        if old.public.Xn.len() != NUMBER_OF_ATTRIBUTES || new.public.Xn.len() != NUMBER_OF_ATTRIBUTES ||
+            old.secret.xn.len() != NUMBER_OF_ATTRIBUTES || new.secret.xn.len() != NUMBER_OF_ATTRIBUTES
+        {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+
+        let old_renewal_key: &amacs::Keypair = two_attribute_keypair(&previous.renewal_key_keypair)?;
+
+        two_attribute_keypair(&self.renewal_key_keypair)?;
+
+        let A: &RistrettoPoint = &self.issuer.system_parameters.h;
+        let B: &RistrettoPoint = &self.issuer.system_parameters.g;
+        let D: RistrettoPoint = request.public_key.into();
+        let E: &elgamal::Encryption = &request.encrypted_identifier;
+        let P: &RistrettoPoint = &request.rerandomized_nonce;
+        let Cm0: RistrettoPoint = request.identifier_commitment.into();
+
+        if *P == RistrettoPoint::identity() {
+            return Err(CredentialError::MacVerification);
+        }
+
+        // Recompute the MAC over the committed identifier with the key which
+        // issued the current credential.
+        let mut V_prime: RistrettoPoint = P * old.secret.x0 + Cm0 * old.secret.xn[0];
+
+        V_prime -= request.rerandomized_mac_commitment;
+
+        let mut transcript = Transcript::new(b"SIGNAL CREDENTIAL RENEWAL REQUEST");
+        let publics = valid_credential_renewal::Publics {
+            B: B,
+            A: A,
+            X0: &old.public.Xn[0],
+            P: P,
+            V: &V_prime,
+            Cm0: &Cm0,
+            D: &D,
+            E1: &E.commitment,
+            E2: &E.encryption,
+        };
+
+        if request.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::MacVerification);
+        }
+
+        // Likewise for the renewal key credential, continuing the same
+        // transcript.
+        let presentation = &request.renewal_key;
+        let P: &RistrettoPoint = &presentation.rerandomized_nonce;
+        let Cm1: RistrettoPoint = presentation.identifier_commitment.into();
+        let Ck: RistrettoPoint = presentation.renewal_key_commitment.into();
+
+        if *P == RistrettoPoint::identity() {
+            return Err(CredentialError::MacVerification);
+        }
+
+        let mut V_prime: RistrettoPoint = P * old_renewal_key.secret.x0 +
+            Cm1 * old_renewal_key.secret.xn[0] + Ck * old_renewal_key.secret.xn[1];
+
+        V_prime -= presentation.rerandomized_mac_commitment;
+
+        let statement = renewal_tag_statement(&self.issuer.system_parameters, &old_renewal_key.public,
+                                              &V_prime, &request.public_key, E, presentation, epoch);
+
+        if request.renewal_key_proof.verify(&mut transcript, &statement).is_err() {
+            return Err(CredentialError::MacVerification);
+        }
+
+        Ok(())
+    }
+
+    /// Issue a renewed credential and renewal key credential over the
+    /// identifier and renewal key encrypted in a `request` which has already
+    /// been verified.
+    fn issue_renewal<R>(
+        &self,
+        request: &SignalCredentialRenewalRequest,
+        rng: &mut R,
+    ) -> Result<SignalCredentialRenewal, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let new: &amacs::Keypair = &self.issuer.keypair;
+        let A: &RistrettoPoint = &self.issuer.system_parameters.h;
+        let B: &RistrettoPoint = &self.issuer.system_parameters.g;
+        let D: RistrettoPoint = request.public_key.into();
+        let E: &elgamal::Encryption = &request.encrypted_identifier;
+
+        let mut transcript = Transcript::new(b"SIGNAL CREDENTIAL RENEWAL");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let x0: &Scalar = &new.secret.x0;
+        let x1: &Scalar = &new.secret.xn[0];
+
+        // Choose the nonce, P = b B, and compute the encryption of the aMAC,
+        //
+        //     Q = x0 P + x1 b Enc(m B),
+        //
+        // using the additive homomorphism of elGamal encryption.
+        let b: Scalar = Scalar::random(&mut csprng);
+        let s: Scalar = Scalar::random(&mut csprng);
+        let t: Scalar = x1 * b;
+        let P: RistrettoPoint = B * b;
+        let T: RistrettoPoint = A * t;
+        let encrypted_mac = elgamal::Encryption {
+            commitment: B * s + E.commitment * t,
+            encryption: D * s + E.encryption * t + P * x0,
+        };
+
+        // Commit to our secret key.
+        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let secret_key_commitment = pedersen::Commitment::to(&(B * x0), &x0_tilde, A);
+
+        let secrets = issuance_blinded_renewal::Secrets {
+            x0: x0,
+            x1: x1,
+            x0_tilde: (&x0_tilde).into(),
+            b: &b,
+            s: &s,
+            t: &t,
+        };
+        let publics = issuance_blinded_renewal::Publics {
+            B: B,
+            A: A,
+            Cx0: &secret_key_commitment.into(),
+            X1: &new.public.Xn[0],
+            P: &P,
+            T0: &T,
+            T1: &T,
+            D: &D,
+            E1: &E.commitment,
+            E2: &E.encryption,
+            EQ1: &encrypted_mac.commitment,
+            EQ2: &encrypted_mac.encryption,
+        };
+        let proof = issuance_blinded_renewal::Proof::create(&mut transcript, publics, secrets, &mut csprng);
+        let renewal_key = self.issue_blinded_renewal_key(two_attribute_keypair(&self.renewal_key_keypair)?,
+                                                         &request.public_key, E,
+                                                         &request.renewal_key.encrypted_renewal_key, rng)?;
+
+        Ok(SignalCredentialRenewal {
+            nonce: P,
+            encrypted_mac,
+            auxiliary_commitment: T,
+            secret_key_commitment,
+            proof,
+            renewal_key,
+        })
+    }
+
     pub fn verify(&self, signal_presentation: SignalCredentialPresentation)
         -> Result<VerifiedSignalCredential, CredentialError>
     {
@@ -164,11 +1038,12 @@ impl SignalIssuer {
         credential: &VerifiedSignalCredential,
     ) -> Result<CommittedPhoneNumber, CredentialError>
     {
+        let Cm0: RistrettoPoint = first_blinded_attribute(&credential.0.presentation)?;
         let publics = committed_values_equal::Publics {
             B: &self.issuer.system_parameters.g,
             A: &self.issuer.system_parameters.h,
             P: &credential.0.presentation.rerandomized_nonce,
-            Cm0: &credential.0.presentation.attributes_blinded[0].clone().into(),
+            Cm0: &Cm0,
             Cm1: &credential.0.roster_entry_commitment.0.into(),
         };
         let mut transcript = Transcript::new(b"SIGNAL GROUP MEMBERSHIP");
@@ -179,6 +1054,252 @@ impl SignalIssuer {
             Err(CredentialError::VerificationFailure)
         }
     }
+
+    /// Verify a user's `signal_presentation`, their proof that the committed
+    /// identifier in their roster entry is the one in their credential, and
+    /// that their roster entry is in the group's `roster`.
+    ///
+    /// # Errors
+    ///
+    /// * Any error returned by `SignalIssuer::verify()` or
+    ///   `SignalIssuer::verify_roster_membership()`.
+    /// * `CredentialError::VerificationFailure` if the user's roster entry is
+    ///   not in the `roster`.
+    pub fn verify_membership(
+        &self,
+        signal_presentation: SignalCredentialPresentation,
+        roster: &GroupRoster,
+    ) -> Result<VerifiedSignalCredential, CredentialError>
+    {
+        let verified: VerifiedSignalCredential = self.verify(signal_presentation)?;
+        let roster_entry_commitment: CommittedPhoneNumber = self.verify_roster_membership(&verified)?;
+
+        if !roster.contains(&roster_entry_commitment) {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        Ok(verified)
+    }
+
+    /// Verify a user's anonymous `presentation` of their credential and of
+    /// their membership in the group's `roster`.
+    ///
+    /// # Errors
+    ///
+    /// * Any error returned by `SignalIssuer::verify()`.
+    /// * `CredentialError::VerificationFailure` if either the proof that the
+    ///   rerandomised roster entry is for the credential's phone number, or
+    ///   the proof that it is a rerandomisation of some entry in the `roster`,
+    ///   couldn't be verified.
+    pub fn verify_anonymous_membership(
+        &self,
+        presentation: &SignalCredentialAnonymousPresentation,
+        roster: &GroupRoster,
+    ) -> Result<(), CredentialError>
+    {
+        self.issuer.verify(&presentation.presentation)?;
+
+        let A: &RistrettoPoint = &self.issuer.system_parameters.h;
+        let B: &RistrettoPoint = &self.issuer.system_parameters.g;
+
+        let Cm0: RistrettoPoint = first_blinded_attribute(&presentation.presentation)?;
+        let publics = committed_values_equal::Publics {
+            B: B,
+            A: A,
+            P: &presentation.presentation.rerandomized_nonce,
+            Cm0: &Cm0,
+            Cm1: &presentation.rerandomized_roster_entry.0.into(),
+        };
+        let mut transcript = Transcript::new(b"SIGNAL ANONYMOUS GROUP MEMBERSHIP");
+
+        if presentation.roster_membership_proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let C_prime: RistrettoPoint = presentation.rerandomized_roster_entry.0.into();
+        let differences: Vec<RistrettoPoint> = roster.to_points().iter().map(|C| C - C_prime).collect();
+
+        match presentation.roster_entry_proof.verify(&mut transcript, A, B, &differences) {
+            Ok(_)  => Ok(()),
+            Err(_) => Err(CredentialError::VerificationFailure),
+        }
+    }
+
+    /// Verify a user's `presentation` of their credential, and their proof
+    /// that their encrypted roster entry is for the credential's phone number
+    /// and is in the group's `roster`.
+    ///
+    /// # Errors
+    ///
+    /// * Any error returned by `SignalIssuer::verify()`.
+    /// * `CredentialError::VerificationFailure` if the proof that the
+    ///   encrypted roster entry is for the credential's phone number couldn't
+    ///   be verified, or if the entry is not in the `roster`.
+    pub fn verify_encrypted_membership(
+        &self,
+        presentation: &SignalCredentialEncryptedPresentation,
+        roster: &EncryptedGroupRoster,
+    ) -> Result<(), CredentialError>
+    {
+        self.issuer.verify(&presentation.presentation)?;
+
+        let Cm0: RistrettoPoint = first_blinded_attribute(&presentation.presentation)?;
+        let publics = committed_value_encrypted::Publics {
+            B: &self.issuer.system_parameters.g,
+            A: &self.issuer.system_parameters.h,
+            D: &(*roster.public_key()).into(),
+            P: &presentation.presentation.rerandomized_nonce,
+            Cm0: &Cm0,
+            E1: &presentation.roster_entry.0.commitment,
+            E2: &presentation.roster_entry.0.encryption,
+        };
+        let mut transcript = Transcript::new(b"SIGNAL ENCRYPTED GROUP MEMBERSHIP");
+
+        if presentation.roster_membership_proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::VerificationFailure);
+        }
+        if !roster.contains(&presentation.roster_entry) {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        Ok(())
+    }
+
+    /// Verify a user's `presentation` of their `ProfileKeyCredential`, and that
+    /// its encrypted profile key is an encryption, to the group's
+    /// `public_key`, of the profile key in the credential.
+    ///
+    /// # Returns
+    ///
+    /// The `EncryptedProfileKey`, which may be stored by the group.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerKey` if this `SignalIssuer` has no
+    ///   `profile_key_keypair`.
+    /// * `CredentialError::WrongNumberOfAttributes` if the `profile_key_keypair`
+    ///   isn't for two attributes.
+    /// * `CredentialError::MacVerification` if the credential is invalid or
+    ///   the encrypted profile key is inconsistent with it.
+    pub fn verify_profile_key(
+        &self,
+        presentation: &ProfileKeyCredentialPresentation,
+        public_key: &elgamal::PublicKey,
+    ) -> Result<EncryptedProfileKey, CredentialError>
+    {
+        let keypair: &amacs::Keypair = two_attribute_keypair(&self.profile_key_keypair)?;
+        let P: &RistrettoPoint = &presentation.rerandomized_nonce;
+        let Cm1: RistrettoPoint = presentation.identifier_commitment.into();
+        let Cm2: RistrettoPoint = presentation.profile_key_commitment.into();
+
+        if *P == RistrettoPoint::identity() {
+            return Err(CredentialError::MacVerification);
+        }
+
+        // Recompute the MAC over the committed attributes.
+        let mut V_prime: RistrettoPoint = P * keypair.secret.x0 +
+            Cm1 * keypair.secret.xn[0] + Cm2 * keypair.secret.xn[1];
+
+        V_prime -= presentation.rerandomized_mac_commitment;
+
+        let mut transcript = Transcript::new(b"SIGNAL PROFILE KEY PRESENTATION");
+        let publics = valid_credential_with_encryption::Publics {
+            B: &self.issuer.system_parameters.g,
+            A: &self.issuer.system_parameters.h,
+            X1: &keypair.public.Xn[0],
+            X2: &keypair.public.Xn[1],
+            P: P,
+            V: &V_prime,
+            Cm1: &Cm1,
+            Cm2: &Cm2,
+            D: &(*public_key).into(),
+            K1: &presentation.encrypted_profile_key.0.commitment,
+            K2: &presentation.encrypted_profile_key.0.encryption,
+        };
+
+        match presentation.proof.verify(&mut transcript, publics) {
+            Ok(_)  => Ok(presentation.encrypted_profile_key),
+            Err(_) => Err(CredentialError::MacVerification),
+        }
+    }
+
+    /// Verify a user's `presentation` of their `SignalRoleCredential`, their
+    /// proof that their role is at least the `minimum`, and their proof that
+    /// the committed identifier in their roster entry is the one in their
+    /// credential.
+    ///
+    /// # Note
+    ///
+    /// As with `SignalIssuer::verify_roster_membership()`, if the proofs are
+    /// okay, the issuer MUST still check that the returned
+    /// `roster_entry_commitment` is actually in the desired roster.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerKey` if this `SignalIssuer` has no
+    ///   `role_keypair`.
+    /// * `CredentialError::WrongNumberOfAttributes` if the `role_keypair`
+    ///   isn't for two attributes.
+    /// * `CredentialError::MacVerification` if the credential is invalid.
+    /// * `CredentialError::VerificationFailure` if either the proof of the
+    ///   user's role or the proof of their roster membership couldn't be
+    ///   verified.
+    pub fn verify_roster_membership_with_role(
+        &self,
+        presentation: &SignalRoleCredentialPresentation,
+        minimum: Role,
+    ) -> Result<CommittedPhoneNumber, CredentialError>
+    {
+        let keypair: &amacs::Keypair = two_attribute_keypair(&self.role_keypair)?;
+        let A: &RistrettoPoint = &self.issuer.system_parameters.h;
+        let B: &RistrettoPoint = &self.issuer.system_parameters.g;
+        let P: &RistrettoPoint = &presentation.rerandomized_nonce;
+        let Cm1: RistrettoPoint = presentation.identifier_commitment.into();
+        let Cm2: RistrettoPoint = presentation.role_commitment.into();
+
+        if *P == RistrettoPoint::identity() {
+            return Err(CredentialError::MacVerification);
+        }
+
+        // Recompute the MAC over the committed attributes.
+        let mut V_prime: RistrettoPoint = P * keypair.secret.x0 +
+            Cm1 * keypair.secret.xn[0] + Cm2 * keypair.secret.xn[1];
+
+        V_prime -= presentation.rerandomized_mac_commitment;
+
+        let mut transcript = Transcript::new(b"SIGNAL ROLE PRESENTATION");
+        let publics = valid_role_credential::Publics {
+            B: B,
+            A: A,
+            X1: &keypair.public.Xn[0],
+            X2: &keypair.public.Xn[1],
+            P: P,
+            V: &V_prime,
+            Cm1: &Cm1,
+            Cm2: &Cm2,
+        };
+
+        if presentation.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::MacVerification);
+        }
+        if presentation.role_proof.verify(&mut transcript, A, P, &Cm2, minimum).is_err() {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let roster_membership_publics = committed_values_equal::Publics {
+            B: B,
+            A: A,
+            P: P,
+            Cm0: &Cm1,
+            Cm1: &presentation.roster_entry_commitment.0.into(),
+        };
+
+        if presentation.roster_membership_proof.verify(&mut transcript, roster_membership_publics).is_err() {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        Ok(presentation.roster_entry_commitment)
+    }
 }
 
 impl SignalIssuer {
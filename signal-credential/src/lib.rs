@@ -33,18 +33,41 @@ extern crate failure;
 extern crate rand;
 extern crate rand_core;
 extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate sha2;
+extern crate subtle;
 
+pub mod account_identifier;
+pub mod bindings;
 pub mod credential;
 pub mod errors;
 pub mod issuer;
 pub mod phone_number;
+pub mod policy;
+pub mod profile_key;
+pub mod renewal;
+pub mod role;
+pub mod roster;
+pub mod sizes;
+pub mod status;
 pub mod user;
+pub mod verification;
 
+pub use account_identifier::*;
 pub use credential::*;
 pub use errors::*;
 pub use issuer::*;
 pub use phone_number::*;
+pub use policy::*;
+pub use profile_key::*;
+pub use renewal::*;
+pub use role::*;
+pub use roster::*;
+pub use sizes::*;
+pub use status::*;
 pub use user::*;
+pub use verification::*;
 
 // Re-export common externally-used types from aeonflux.
 pub use aeonflux::prelude::*;
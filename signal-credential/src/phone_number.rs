@@ -28,6 +28,7 @@ use aeonflux::nonces::Ephemeral;
 use aeonflux::parameters::SystemParameters;
 use aeonflux::pedersen::{self};
 
+use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
 
@@ -37,12 +38,70 @@ use serde::de::Visitor;
 use rand_core::RngCore;
 use rand_core::CryptoRng;
 
+use account_identifier::AccountIdentifier;
+use account_identifier::ToAccountIdentifier;
 use errors::PhoneNumberError;
 
 pub const SIZEOF_PHONE_NUMBER: usize = 32;
 pub const SIZEOF_COMMITTED_PHONE_NUMBER: usize = pedersen::SIZEOF_COMMITMENT;
 pub const SIZEOF_ENCRYPTED_PHONE_NUMBER: usize = elgamal::SIZEOF_ENCRYPTION;
 
+/// The maximum number of digits, including the country code, in an E.164
+/// phone number.
+pub const E164_MAXIMUM_DIGITS: usize = 15;
+
+/// The minimum number of digits following the country code in an E.164 phone
+/// number which we accept.
+pub const E164_MINIMUM_SUBSCRIBER_DIGITS: usize = 4;
+
+/// The value with which the leading `+` of an E.164 phone number is encoded.
+const PLUS_SIGN: u8 = 11;
+
+/// The maximum number of digits which fit into a `PhoneNumber`, after the two
+/// prefixed and two suffixed bogus digits and the trailing length byte.
+pub const PHONE_NUMBER_MAXIMUM_DIGITS: usize = SIZEOF_PHONE_NUMBER - 5;
+
+/// Get the length of the assigned ITU-T E.164 country code with which some
+/// `digits` begin, if any.
+///
+/// Country codes are prefix-free, so at most one can match.
+fn country_code_length(digits: &[u8]) -> Option<usize> {
+    let d = |i: usize| -> u16 { digits.get(i).map(|x| *x as u16).unwrap_or(10) };
+
+    match d(0) {
+        1 | 7 => return Some(1),
+        10    => return None,
+        _     => (),
+    }
+
+    if d(1) > 9 {
+        return None;
+    }
+
+    let two: u16 = d(0) * 10 + d(1);
+
+    match two {
+        20 | 27 | 30 ... 34 | 36 | 39 | 40 | 41 | 43 ... 49 | 51 ... 58 |
+        60 ... 66 | 81 | 82 | 84 | 86 | 90 ... 95 | 98 => return Some(2),
+        _ => (),
+    }
+
+    if d(2) > 9 {
+        return None;
+    }
+
+    let three: u16 = two * 10 + d(2);
+
+    match three {
+        211 ... 213 | 216 | 218 | 220 ... 258 | 260 ... 269 | 290 | 291 | 297 ... 299 |
+        350 ... 359 | 370 ... 378 | 380 ... 383 | 385 ... 387 | 389 | 420 | 421 | 423 |
+        500 ... 509 | 590 ... 599 | 670 | 672 ... 683 | 685 ... 692 | 800 | 808 |
+        850 | 852 | 853 | 855 | 856 | 870 | 878 | 880 ... 883 | 886 | 888 |
+        960 ... 968 | 970 ... 977 | 979 | 992 ... 996 | 998 => Some(3),
+        _ => None,
+    }
+}
+
 /// A `Scalar` which represents a canonicalised phone number and may be used
 /// arithmetically.
 ///
@@ -89,6 +148,8 @@ impl PhoneNumber {
     /// Convert a `String` containing a canonicalised (as if it were to be
     /// dialed from San Francisco) phone number into a `PhoneNumber`.
     ///
+    /// For untrusted input, prefer the stricter `PhoneNumber::parse_e164()`.
+    ///
     /// # Warning
     ///
     /// If ever there are two phone numbers whose canonicalisations map to the
@@ -135,7 +196,7 @@ impl PhoneNumber {
         let bytes: &[u8] = source.as_bytes();
         let length: usize = bytes.len();
 
-        if length > 32 {
+        if length > PHONE_NUMBER_MAXIMUM_DIGITS {
             return Err(PhoneNumberError::LengthExceeded);
         }
         let mut bits: [u8; 32] = [0u8; 32];
@@ -172,10 +233,93 @@ impl PhoneNumber {
         Ok(PhoneNumber(number))
     }
 
+    /// Parse a phone number in E.164 format, e.g. `"+1 (415) 555-0101"`,
+    /// into a `PhoneNumber`.
+    ///
+    /// The number must begin with a `+` followed by an assigned country code.
+    /// Spaces, dashes, dots, and parentheses are permitted as separators and
+    /// are ignored, so that every spelling of the same number produces the
+    /// same `PhoneNumber`, which converts back into a `String` in its
+    /// canonical form, e.g. `"+14155550101"`.
+    ///
+    /// # Errors
+    ///
+    /// * `PhoneNumberError::EmptyPhoneNumber` if the `source` is empty.
+    /// * `PhoneNumberError::MissingPlusSign` if it doesn't begin with a `+`.
+    /// * `PhoneNumberError::InvalidCharacter` if it contains anything other
+    ///   than digits and separators after the `+`.
+    /// * `PhoneNumberError::TooManyDigits` if it has more than
+    ///   `E164_MAXIMUM_DIGITS` digits.
+    /// * `PhoneNumberError::InvalidCountryCode` if it doesn't begin with an
+    ///   assigned country code.
+    /// * `PhoneNumberError::TooFewDigits` if fewer than
+    ///   `E164_MINIMUM_SUBSCRIBER_DIGITS` digits follow the country code.
+    pub fn parse_e164(source: &str) -> Result<Self, PhoneNumberError> {
+        let mut chars = source.chars();
+
+        match chars.next() {
+            None      => return Err(PhoneNumberError::EmptyPhoneNumber),
+            Some('+') => (),
+            Some(_)   => return Err(PhoneNumberError::MissingPlusSign),
+        }
+
+        let mut digits: [u8; E164_MAXIMUM_DIGITS] = [0u8; E164_MAXIMUM_DIGITS];
+        let mut length: usize = 0;
+
+        for c in chars {
+            match c {
+                '0' ... '9' => {
+                    if length == E164_MAXIMUM_DIGITS {
+                        return Err(PhoneNumberError::TooManyDigits);
+                    }
+                    digits[length] = c as u8 - b'0';
+                    length += 1;
+                },
+                ' ' | '-' | '.' | '(' | ')' => continue,
+                _ => return Err(PhoneNumberError::InvalidCharacter),
+            }
+        }
+
+        let digits: &[u8] = &digits[..length];
+        let country_code_length: usize = match country_code_length(digits) {
+            Some(x) => x,
+            None    => return Err(PhoneNumberError::InvalidCountryCode),
+        };
+
+        if length < country_code_length + E164_MINIMUM_SUBSCRIBER_DIGITS {
+            return Err(PhoneNumberError::TooFewDigits);
+        }
+
+        let mut canonical: Vec<u8> = Vec::with_capacity(length + 1);
+
+        canonical.push(PLUS_SIGN);
+        canonical.extend_from_slice(digits);
+
+        PhoneNumber::from_digits(&canonical)
+    }
+
+    /// Convert some raw digit values, each from 0 to 9, into a `PhoneNumber`.
+    ///
+    /// # Errors
+    ///
+    /// * `PhoneNumberError::InvalidCharacter` if any byte is not a digit
+    ///   value.  In particular, the value with which `parse_e164()` encodes
+    ///   the leading `+` is rejected, so that raw bytes cannot produce the
+    ///   same `PhoneNumber` as a validated E.164 number.
+    /// * `PhoneNumberError::LengthExceeded` if there are more than
+    ///   `PHONE_NUMBER_MAXIMUM_DIGITS` digits.
     pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, PhoneNumberError> {
+        if bytes.iter().any(|x| *x > 9) {
+            return Err(PhoneNumberError::InvalidCharacter);
+        }
+        PhoneNumber::from_digits(bytes)
+    }
+
+    /// Pack some already validated digit values into a `PhoneNumber`.
+    fn from_digits(bytes: &[u8]) -> Result<Self, PhoneNumberError> {
         let length: usize = bytes.len();
 
-        if length > 32 {
+        if length > PHONE_NUMBER_MAXIMUM_DIGITS {
             return Err(PhoneNumberError::LengthExceeded);
         }
         let mut bits: [u8; 32] = [0u8; 32];
@@ -228,6 +372,7 @@ impl From<PhoneNumber> for String {
                 7 => s.push_str("7"),
                 8 => s.push_str("8"),
                 9 => s.push_str("9"),
+                PLUS_SIGN => s.push_str("+"),
                 _ => {
                     #[cfg(feature = "std")]
                     println!("Got weird digit in phone number {:?}", source[i]);
@@ -259,9 +404,77 @@ impl PhoneNumber {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct EncryptedPhoneNumber(pub elgamal::Encryption);
 
+impl EncryptedPhoneNumber {
+    pub fn from_bytes(bytes: &[u8]) -> Result<EncryptedPhoneNumber, PhoneNumberError> {
+        if bytes.len() != SIZEOF_ENCRYPTED_PHONE_NUMBER {
+            return Err(PhoneNumberError::InvalidPhoneNumber);
+        }
+
+        let mut commitment: [u8; 32] = [0u8; 32];
+        let mut encryption: [u8; 32] = [0u8; 32];
+
+        commitment.copy_from_slice(&bytes[00..32]);
+        encryption.copy_from_slice(&bytes[32..64]);
+
+        Ok(EncryptedPhoneNumber(elgamal::Encryption {
+            commitment: CompressedRistretto(commitment).decompress()?,
+            encryption: CompressedRistretto(encryption).decompress()?,
+        }))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ENCRYPTED_PHONE_NUMBER);
+
+        v.extend(self.0.commitment.compress().to_bytes().iter());
+        v.extend(self.0.encryption.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(EncryptedPhoneNumber,
+                                         "A valid byte sequence representing an EncryptedPhoneNumber");
+
+impl EncryptedPhoneNumber {
+    /// Encrypt any kind of `AccountIdentifier` to a group's `public_key`.
+    pub fn from_account_identifier(
+        identifier: &AccountIdentifier,
+        public_key: &elgamal::PublicKey,
+        nonce: &Ephemeral,
+    ) -> EncryptedPhoneNumber
+    {
+        let message: elgamal::Message = (&identifier.to_scalar()).into();
+
+        EncryptedPhoneNumber(public_key.encrypt(&message, nonce))
+    }
+
+    /// Decrypt this `EncryptedPhoneNumber` with a group's `secret_key`.
+    ///
+    /// # Returns
+    ///
+    /// The identifier's attribute multiplied by the basepoint, which is not
+    /// invertible, but which may be compared with the identifiers of known
+    /// accounts, as in `EncryptedPhoneNumber::is_encryption_of()`.
+    pub fn decrypt(&self, secret_key: &elgamal::SecretKey) -> RistrettoPoint {
+        secret_key.decrypt(&self.0)
+    }
+
+    /// Determine whether this is an encryption of some `identifier`.
+    pub fn is_encryption_of(
+        &self,
+        identifier: &AccountIdentifier,
+        secret_key: &elgamal::SecretKey,
+    ) -> bool
+    {
+        let message: elgamal::Message = (&identifier.to_scalar()).into();
+
+        self.decrypt(secret_key) == RistrettoPoint::from(message)
+    }
+}
+
 impl From<EncryptedPhoneNumber> for Vec<EncryptedAttribute> {
     fn from(source: EncryptedPhoneNumber) -> Vec<EncryptedAttribute> {
         let mut v = Vec::with_capacity(1);
@@ -301,6 +514,19 @@ impl CommittedPhoneNumber {
         CommittedPhoneNumber(number)
     }
 
+    /// Commit to any kind of `AccountIdentifier`.
+    pub fn from_account_identifier(
+        identifier: &AccountIdentifier,
+        nonce: &Ephemeral,
+        g: &RistrettoPoint,
+        h: &RistrettoPoint,
+    ) -> CommittedPhoneNumber
+    {
+        let number = pedersen::Commitment::to(&(identifier.to_scalar() * h), nonce, &g);
+
+        CommittedPhoneNumber(number)
+    }
+
     pub fn open(
         &self,
         phone_number: &PhoneNumber,
@@ -313,8 +539,26 @@ impl CommittedPhoneNumber {
 
         Ok(())
     }
+
+    /// Open a commitment to any kind of `AccountIdentifier`.
+    pub fn open_account_identifier(
+        &self,
+        identifier: &AccountIdentifier,
+        nonce: &Ephemeral,
+        g: &RistrettoPoint,
+        h: &RistrettoPoint,
+    ) -> Result<(), ()>
+    {
+        self.0.open(&(&identifier.to_scalar() * h), nonce, &g)?;
+
+        Ok(())
+    }
 }
 
+/// A commitment to an `AccountIdentifier`, which is not necessarily a phone
+/// number.
+pub type CommittedAccountIdentifier = CommittedPhoneNumber;
+
 /// DOCDOC
 pub struct RosterEntryCommitment {
     pub commitment: CommittedPhoneNumber,
@@ -323,6 +567,10 @@ pub struct RosterEntryCommitment {
 
 impl RosterEntryCommitment {
     pub fn from_bytes(bytes: &[u8]) -> Result<RosterEntryCommitment, PhoneNumberError> {
+        if bytes.len() != SIZEOF_COMMITTED_PHONE_NUMBER + 32 {
+            return Err(PhoneNumberError::InvalidPhoneNumber);
+        }
+
         let commitment = CommittedPhoneNumber::from_bytes(&bytes[..SIZEOF_COMMITTED_PHONE_NUMBER])?;
 
         let mut tmp = [0u8; 32];
@@ -348,38 +596,101 @@ impl_serde_with_to_bytes_and_from_bytes!(RosterEntryCommitment,
                                          "A valid byte sequence representing a RosterEntryCommitment");
 
 impl RosterEntryCommitment {
-    pub fn create<R>(
-        phone_number: &[u8],
+    /// Commit to a user's `identifier`, which may be an `AccountIdentifier`
+    /// or, e.g., a phone number given as bytes.
+    pub fn create<I, R>(
+        identifier: &I,
         system_parameters: &SystemParameters,
         csprng: &mut R,
     ) -> Result<RosterEntryCommitment, PhoneNumberError>
     where
+        I: ToAccountIdentifier + ?Sized,
         R: RngCore + CryptoRng,
     {
         let opening = Ephemeral::new(csprng);
-        let number = PhoneNumber::try_from_bytes(&phone_number)?;
-        let commitment = CommittedPhoneNumber::from_phone_number(&number,
-                                                                 &opening,
-                                                                 &system_parameters.g,
-                                                                 &system_parameters.h);
+        let identifier = identifier.to_account_identifier()?;
+        let commitment = CommittedPhoneNumber::from_account_identifier(&identifier,
+                                                                       &opening,
+                                                                       &system_parameters.g,
+                                                                       &system_parameters.h);
         Ok(RosterEntryCommitment{ commitment, opening })
     }
 
-    pub fn open(
+    pub fn open<I>(
         &self,
-        phone_number: &[u8],
+        identifier: &I,
         system_parameters: &SystemParameters,
     ) -> Result<(), PhoneNumberError>
+    where
+        I: ToAccountIdentifier + ?Sized,
     {
-        let number = PhoneNumber::try_from_bytes(&phone_number)?;
+        let identifier = identifier.to_account_identifier()?;
 
-        match self.commitment.open(&number, &self.opening, &system_parameters.g, &system_parameters.h) {
+        match self.commitment.open_account_identifier(&identifier, &self.opening,
+                                                      &system_parameters.g, &system_parameters.h) {
             Ok(x)  => Ok(x),
             Err(_) => Err(PhoneNumberError::InvalidPhoneNumber)
         }
     }
 }
 
+/// A user's roster entry, encrypted to the group's public key, along with the
+/// nonce used to encrypt it.
+pub struct RosterEntryEncryption {
+    pub encryption: EncryptedPhoneNumber,
+    pub nonce: Ephemeral,
+}
+
+impl RosterEntryEncryption {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RosterEntryEncryption, PhoneNumberError> {
+        if bytes.len() != SIZEOF_ENCRYPTED_PHONE_NUMBER + 32 {
+            return Err(PhoneNumberError::InvalidPhoneNumber);
+        }
+
+        let encryption = EncryptedPhoneNumber::from_bytes(&bytes[..SIZEOF_ENCRYPTED_PHONE_NUMBER])?;
+
+        let mut tmp = [0u8; 32];
+
+        tmp.copy_from_slice(&bytes[SIZEOF_ENCRYPTED_PHONE_NUMBER..]);
+
+        let nonce: Ephemeral = Scalar::from_bits(tmp).into();
+
+        Ok(RosterEntryEncryption { encryption, nonce })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ENCRYPTED_PHONE_NUMBER + 32);
+
+        v.extend(self.encryption.to_bytes().iter());
+        v.extend(self.nonce.to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RosterEntryEncryption,
+                                         "A valid byte sequence representing a RosterEntryEncryption");
+
+impl RosterEntryEncryption {
+    /// Encrypt a user's `identifier`, which may be an `AccountIdentifier` or,
+    /// e.g., a phone number given as bytes, to a group's `public_key`.
+    pub fn create<I, R>(
+        identifier: &I,
+        public_key: &elgamal::PublicKey,
+        csprng: &mut R,
+    ) -> Result<RosterEntryEncryption, PhoneNumberError>
+    where
+        I: ToAccountIdentifier + ?Sized,
+        R: RngCore + CryptoRng,
+    {
+        let nonce = Ephemeral::new(csprng);
+        let identifier = identifier.to_account_identifier()?;
+        let encryption = EncryptedPhoneNumber::from_account_identifier(&identifier, public_key, &nonce);
+
+        Ok(RosterEntryEncryption { encryption, nonce })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -402,4 +713,82 @@ mod test {
 
         assert!(a != b);
     }
+
+    #[test]
+    fn e164_roundtrip() {
+        let input: &str = "+14155550101";
+        let number: PhoneNumber = PhoneNumber::parse_e164(input).unwrap();
+        let check: String = String::from(number);
+
+        assert!(input == check, "input {:?} did not equal {:?} after roundtrip", input, check);
+    }
+
+    #[test]
+    fn e164_separators_are_canonicalised() {
+        let a: PhoneNumber = PhoneNumber::parse_e164("+14155550101").unwrap();
+        let b: PhoneNumber = PhoneNumber::parse_e164("+1 (415) 555-0101").unwrap();
+        let c: PhoneNumber = PhoneNumber::parse_e164("+1.415.555.0101").unwrap();
+
+        assert!(a == b);
+        assert!(a == c);
+
+        // E.164 numbers must not collide with the legacy encoding.
+        assert!(a != PhoneNumber::try_from_string(&String::from("14155550101")).unwrap());
+    }
+
+    #[test]
+    fn raw_bytes_cannot_forge_e164() {
+        let a: PhoneNumber = PhoneNumber::parse_e164("+14155550101").unwrap();
+
+        assert_eq!(PhoneNumber::try_from_bytes(&[PLUS_SIGN, 1, 4, 1, 5, 5, 5, 5, 0, 1, 0, 1]),
+                   Err(PhoneNumberError::InvalidCharacter));
+        assert_eq!(PhoneNumber::try_from_bytes(&[1, 4, 1, 5, 15, 5, 5, 5, 0, 1, 0, 1]),
+                   Err(PhoneNumberError::InvalidCharacter));
+        assert!(a != PhoneNumber::try_from_bytes(&[1, 4, 1, 5, 5, 5, 5, 0, 1, 0, 1]).unwrap());
+    }
+
+    #[test]
+    fn too_many_digits() {
+        let digits: [u8; SIZEOF_PHONE_NUMBER + 1] = [1u8; SIZEOF_PHONE_NUMBER + 1];
+        let string: String = String::from("1111111111111111111111111111111111");
+
+        for length in PHONE_NUMBER_MAXIMUM_DIGITS + 1..SIZEOF_PHONE_NUMBER + 2 {
+            assert_eq!(PhoneNumber::try_from_bytes(&digits[..length]),
+                       Err(PhoneNumberError::LengthExceeded));
+            assert_eq!(PhoneNumber::try_from_string(&String::from(&string[..length])),
+                       Err(PhoneNumberError::LengthExceeded));
+        }
+
+        assert!(PhoneNumber::try_from_bytes(&digits[..PHONE_NUMBER_MAXIMUM_DIGITS]).is_ok());
+        assert!(PhoneNumber::try_from_string(&String::from(&string[..PHONE_NUMBER_MAXIMUM_DIGITS])).is_ok());
+
+        // One digit fewer, the length byte isn't the scalar's top byte, so
+        // there's no modular reduction and the number survives a roundtrip.
+        let number: PhoneNumber = PhoneNumber::try_from_bytes(&digits[..PHONE_NUMBER_MAXIMUM_DIGITS - 1]).unwrap();
+        let check: String = String::from(number);
+
+        assert_eq!(check, string[..PHONE_NUMBER_MAXIMUM_DIGITS - 1]);
+    }
+
+    #[test]
+    fn e164_errors() {
+        assert_eq!(PhoneNumber::parse_e164(""), Err(PhoneNumberError::EmptyPhoneNumber));
+        assert_eq!(PhoneNumber::parse_e164("14155550101"), Err(PhoneNumberError::MissingPlusSign));
+        assert_eq!(PhoneNumber::parse_e164("+1415555O101"), Err(PhoneNumberError::InvalidCharacter));
+        assert_eq!(PhoneNumber::parse_e164("+1234567890123456"), Err(PhoneNumberError::TooManyDigits));
+        assert_eq!(PhoneNumber::parse_e164("+0123456789"), Err(PhoneNumberError::InvalidCountryCode));
+        assert_eq!(PhoneNumber::parse_e164("+2105550101"), Err(PhoneNumberError::InvalidCountryCode));
+        assert_eq!(PhoneNumber::parse_e164("+"), Err(PhoneNumberError::InvalidCountryCode));
+        assert_eq!(PhoneNumber::parse_e164("+44 12"), Err(PhoneNumberError::TooFewDigits));
+        assert!(PhoneNumber::parse_e164("+683 4002").is_ok());
+    }
+
+    #[test]
+    fn roster_entries_wrong_number_of_bytes() {
+        assert!(RosterEntryCommitment::from_bytes(&[]).is_err());
+        assert!(RosterEntryCommitment::from_bytes(&[0u8; SIZEOF_COMMITTED_PHONE_NUMBER]).is_err());
+        assert!(RosterEntryCommitment::from_bytes(&[0u8; SIZEOF_COMMITTED_PHONE_NUMBER + 33]).is_err());
+        assert!(RosterEntryEncryption::from_bytes(&[0u8; SIZEOF_ENCRYPTED_PHONE_NUMBER]).is_err());
+        assert!(CommittedPhoneNumber::from_bytes(&[0u8; 31]).is_err());
+    }
 }
@@ -0,0 +1,450 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Policies limiting how many credentials a `SignalIssuer` issues for each
+//! identifier.
+//!
+//! Every credential issued for an identifier gives its holder another set of
+//! unlinkable presentations for it, so an issuer should bound the number of
+//! credentials any single phone number may obtain.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io::ErrorKind;
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+#[cfg(feature = "std")]
+use sha2::Digest;
+#[cfg(feature = "std")]
+use sha2::Sha512;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use account_identifier::AccountIdentifier;
+use errors::IssuanceError;
+
+/// The size of a serialised `IssuanceRecord`.
+pub const SIZEOF_ISSUANCE_RECORD: usize = 20;
+
+/// The size of the digest of an identifier under which a `FileIssuanceStore`
+/// keeps its `IssuanceRecord`.
+#[cfg(feature = "std")]
+const SIZEOF_IDENTIFIER_DIGEST: usize = 32;
+
+/// How many credentials a `SignalIssuer` will issue for each identifier.
+///
+/// Time is divided into epochs of `epoch_length` seconds, and at most
+/// `max_per_epoch` credentials are issued for an identifier in each.
+/// Within an epoch, each further credential must additionally wait for a
+/// `backoff` which doubles with every credential already issued.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IssuancePolicy {
+    pub max_per_epoch: u32,
+    pub epoch_length: u64,
+    pub backoff: u64,
+}
+
+impl Default for IssuancePolicy {
+    /// At most five credentials a day, a minute apart at first.
+    fn default() -> IssuancePolicy {
+        IssuancePolicy {
+            max_per_epoch: 5,
+            epoch_length: 86400,
+            backoff: 60,
+        }
+    }
+}
+
+impl IssuancePolicy {
+    pub fn new(max_per_epoch: u32, epoch_length: u64, backoff: u64) -> IssuancePolicy {
+        IssuancePolicy { max_per_epoch, epoch_length, backoff }
+    }
+
+    /// Compute the epoch which the time `now` falls into.
+    pub fn epoch(&self, now: u64) -> u64 {
+        now / self.epoch_length.max(1)
+    }
+
+    /// Determine whether an identifier with the given `record` may be issued
+    /// another credential at time `now`.
+    ///
+    /// # Returns
+    ///
+    /// The updated `IssuanceRecord` to store if the credential is issued, or
+    /// `IssuanceError::LimitExceeded` with the number of seconds until
+    /// another credential may be issued.
+    pub fn check(
+        &self,
+        record: Option<&IssuanceRecord>,
+        now: u64,
+    ) -> Result<IssuanceRecord, IssuanceError>
+    {
+        let epoch: u64 = self.epoch(now);
+        let previous: IssuanceRecord = match record {
+            Some(x) if x.epoch == epoch => *x,
+            _ => IssuanceRecord { epoch, count: 0, last_issued: 0 },
+        };
+
+        if previous.count >= self.max_per_epoch {
+            let next_epoch: u64 = (epoch + 1).saturating_mul(self.epoch_length.max(1));
+
+            return Err(IssuanceError::LimitExceeded{ retry_after: next_epoch - now });
+        }
+        if previous.count > 0 {
+            let exponent: u32 = (previous.count - 1).min(63);
+            let wait: u64 = self.backoff.checked_shl(exponent)
+                .filter(|x| x >> exponent == self.backoff)
+                .unwrap_or(u64::max_value());
+            let next: u64 = previous.last_issued.saturating_add(wait);
+
+            if now < next {
+                return Err(IssuanceError::LimitExceeded{ retry_after: next - now });
+            }
+        }
+
+        Ok(IssuanceRecord { epoch, count: previous.count + 1, last_issued: now })
+    }
+}
+
+/// A record of the credentials issued for an identifier in its latest epoch.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IssuanceRecord {
+    pub epoch: u64,
+    pub count: u32,
+    pub last_issued: u64,
+}
+
+impl IssuanceRecord {
+    pub fn from_bytes(bytes: &[u8]) -> Result<IssuanceRecord, IssuanceError> {
+        if bytes.len() != SIZEOF_ISSUANCE_RECORD {
+            return Err(IssuanceError::Storage);
+        }
+        let mut epoch: [u8; 8] = [0u8; 8];
+        let mut count: [u8; 4] = [0u8; 4];
+        let mut last_issued: [u8; 8] = [0u8; 8];
+
+        epoch.copy_from_slice(&bytes[0..8]);
+        count.copy_from_slice(&bytes[8..12]);
+        last_issued.copy_from_slice(&bytes[12..20]);
+
+        Ok(IssuanceRecord {
+            epoch: u64::from_le_bytes(epoch),
+            count: u32::from_le_bytes(count),
+            last_issued: u64::from_le_bytes(last_issued),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ISSUANCE_RECORD);
+
+        v.extend_from_slice(&self.epoch.to_le_bytes());
+        v.extend_from_slice(&self.count.to_le_bytes());
+        v.extend_from_slice(&self.last_issued.to_le_bytes());
+        v
+    }
+}
+
+/// Storage for the `IssuanceRecord` of each identifier.
+pub trait IssuanceStore {
+    /// Get the latest `IssuanceRecord` for the `identifier`, if any.
+    fn get(&self, identifier: &AccountIdentifier) -> Result<Option<IssuanceRecord>, IssuanceError>;
+
+    /// Replace the `IssuanceRecord` for the `identifier`.
+    fn set(&mut self, identifier: &AccountIdentifier, record: IssuanceRecord) -> Result<(), IssuanceError>;
+}
+
+/// An `IssuanceStore` which only keeps its records in memory.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct InMemoryIssuanceStore {
+    records: Vec<(AccountIdentifier, IssuanceRecord)>,
+}
+
+impl InMemoryIssuanceStore {
+    pub fn new() -> InMemoryIssuanceStore {
+        InMemoryIssuanceStore { records: Vec::new() }
+    }
+}
+
+impl IssuanceStore for InMemoryIssuanceStore {
+    fn get(&self, identifier: &AccountIdentifier) -> Result<Option<IssuanceRecord>, IssuanceError> {
+        Ok(self.records.iter().find(|x| &x.0 == identifier).map(|x| x.1))
+    }
+
+    fn set(&mut self, identifier: &AccountIdentifier, record: IssuanceRecord) -> Result<(), IssuanceError> {
+        match self.records.iter_mut().find(|x| &x.0 == identifier) {
+            Some(entry) => entry.1 = record,
+            None => self.records.push((identifier.clone(), record)),
+        }
+        Ok(())
+    }
+}
+
+/// An `IssuanceStore` which persists its records to a file.
+///
+/// Identifiers are not written to the file directly, only a hash of each, so
+/// that the file is not a plaintext list of every phone number which has
+/// been issued a credential.  (Phone numbers are easily enumerated, so the
+/// file must still be protected.)  The whole file is rewritten, via a
+/// temporary file and a rename, on every update.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileIssuanceStore {
+    path: PathBuf,
+    records: Vec<([u8; SIZEOF_IDENTIFIER_DIGEST], IssuanceRecord)>,
+}
+
+#[cfg(feature = "std")]
+impl FileIssuanceStore {
+    /// Open the `FileIssuanceStore` at `path`, which is created upon the
+    /// first update if it does not yet exist.
+    ///
+    /// # Errors
+    ///
+    /// * `IssuanceError::Storage` if the file exists but could not be read or
+    ///   was malformed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<FileIssuanceStore, IssuanceError> {
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let bytes: Vec<u8> = match fs::read(&path) {
+            Ok(x) => x,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(_) => return Err(IssuanceError::Storage),
+        };
+        let chunk_length: usize = SIZEOF_IDENTIFIER_DIGEST + SIZEOF_ISSUANCE_RECORD;
+
+        if bytes.len() % chunk_length != 0 {
+            return Err(IssuanceError::Storage);
+        }
+        let mut records = Vec::with_capacity(bytes.len() / chunk_length);
+
+        for chunk in bytes.chunks(chunk_length) {
+            let mut digest: [u8; SIZEOF_IDENTIFIER_DIGEST] = [0u8; SIZEOF_IDENTIFIER_DIGEST];
+
+            digest.copy_from_slice(&chunk[..SIZEOF_IDENTIFIER_DIGEST]);
+            records.push((digest, IssuanceRecord::from_bytes(&chunk[SIZEOF_IDENTIFIER_DIGEST..])?));
+        }
+        Ok(FileIssuanceStore { path, records })
+    }
+
+    fn digest(identifier: &AccountIdentifier) -> [u8; SIZEOF_IDENTIFIER_DIGEST] {
+        let mut h = Sha512::default();
+        let mut digest: [u8; SIZEOF_IDENTIFIER_DIGEST] = [0u8; SIZEOF_IDENTIFIER_DIGEST];
+
+        h.input(b"SIGNAL ISSUANCE RECORD");
+        h.input(&identifier.to_bytes());
+        digest.copy_from_slice(&h.result()[..SIZEOF_IDENTIFIER_DIGEST]);
+        digest
+    }
+
+    fn flush(&self) -> Result<(), IssuanceError> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(self.records.len() *
+                                                    (SIZEOF_IDENTIFIER_DIGEST + SIZEOF_ISSUANCE_RECORD));
+
+        for (digest, record) in self.records.iter() {
+            bytes.extend_from_slice(&digest[..]);
+            bytes.extend(record.to_bytes());
+        }
+        let temporary: PathBuf = self.path.with_extension("tmp");
+
+        fs::write(&temporary, &bytes).or(Err(IssuanceError::Storage))?;
+        fs::rename(&temporary, &self.path).or(Err(IssuanceError::Storage))
+    }
+}
+
+#[cfg(feature = "std")]
+impl IssuanceStore for FileIssuanceStore {
+    fn get(&self, identifier: &AccountIdentifier) -> Result<Option<IssuanceRecord>, IssuanceError> {
+        let digest = FileIssuanceStore::digest(identifier);
+
+        Ok(self.records.iter().find(|x| x.0 == digest).map(|x| x.1))
+    }
+
+    fn set(&mut self, identifier: &AccountIdentifier, record: IssuanceRecord) -> Result<(), IssuanceError> {
+        let digest = FileIssuanceStore::digest(identifier);
+
+        match self.records.iter_mut().find(|x| x.0 == digest) {
+            Some(entry) => entry.1 = record,
+            None => self.records.push((digest, record)),
+        }
+        self.flush()
+    }
+}
+
+/// An `IssuancePolicy` together with the `IssuanceStore` it is enforced
+/// against, as consulted by `SignalIssuer::issue_rate_limited()` and
+/// `SignalIssuer::issue_verified_and_rate_limited()`.
+#[derive(Clone, Debug)]
+pub struct IssuanceLimiter<S: IssuanceStore> {
+    pub policy: IssuancePolicy,
+    pub store: S,
+}
+
+impl<S: IssuanceStore> IssuanceLimiter<S> {
+    pub fn new(policy: IssuancePolicy, store: S) -> IssuanceLimiter<S> {
+        IssuanceLimiter { policy, store }
+    }
+
+    /// Check that the `identifier` may be issued another credential at time
+    /// `now`, in seconds since the UNIX epoch, and if so record that it was.
+    ///
+    /// The issuance is recorded before the caller issues the credential, so
+    /// that a failure to write the record never results in an unrecorded
+    /// credential.
+    ///
+    /// # Errors
+    ///
+    /// * `IssuanceError::LimitExceeded` if the `identifier` may not be issued
+    ///   another credential yet,
+    /// * `IssuanceError::Storage` if the `store` could not be read or updated.
+    pub fn record(&mut self, identifier: &AccountIdentifier, now: u64) -> Result<(), IssuanceError> {
+        let previous: Option<IssuanceRecord> = self.store.get(identifier)?;
+        let record: IssuanceRecord = self.policy.check(previous.as_ref(), now)?;
+
+        self.store.set(identifier, record)
+    }
+}
+
+/// A record of the renewal tags seen in the current epoch, which permits
+/// each credential to be renewed at most once per epoch.
+///
+/// See the `renewal` module for how the tags are computed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RenewalLimiter {
+    pub epoch_length: u64,
+    epoch: u64,
+    tags: Vec<CompressedRistretto>,
+}
+
+impl RenewalLimiter {
+    pub fn new(epoch_length: u64) -> RenewalLimiter {
+        RenewalLimiter { epoch_length, epoch: 0, tags: Vec::new() }
+    }
+
+    /// Compute the epoch which the time `now` falls into, for which users
+    /// must compute their renewal tags.
+    pub fn epoch(&self, now: u64) -> u64 {
+        now / self.epoch_length.max(1)
+    }
+
+    /// Check that no renewal with this `tag` has been seen in the epoch of
+    /// time `now`, in seconds since the UNIX epoch, and if so record that one
+    /// has.  The tags of earlier epochs are forgotten.
+    ///
+    /// # Errors
+    ///
+    /// * `IssuanceError::LimitExceeded` if the `tag` was already seen in this
+    ///   epoch, with the number of seconds until the next.
+    pub fn record(&mut self, tag: &RistrettoPoint, now: u64) -> Result<(), IssuanceError> {
+        let epoch: u64 = self.epoch(now);
+        let tag: CompressedRistretto = tag.compress();
+
+        if epoch != self.epoch {
+            self.epoch = epoch;
+            self.tags.clear();
+        }
+        if self.tags.contains(&tag) {
+            let next_epoch: u64 = (epoch + 1).saturating_mul(self.epoch_length.max(1));
+
+            return Err(IssuanceError::LimitExceeded{ retry_after: next_epoch - now });
+        }
+        self.tags.push(tag);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::env;
+    use std::process;
+
+    use account_identifier::ToAccountIdentifier;
+    use renewal::renewal_tag_generator;
+
+    const PHONE_NUMBER: &'static [u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+
+    #[test]
+    fn policy_limits_per_epoch_with_backoff() {
+        let identifier: AccountIdentifier = PHONE_NUMBER.to_account_identifier().unwrap();
+        let policy = IssuancePolicy::new(3, 1000, 10);
+        let mut limiter = IssuanceLimiter::new(policy, InMemoryIssuanceStore::new());
+
+        assert!(limiter.record(&identifier, 5000).is_ok());
+        assert_eq!(limiter.record(&identifier, 5005), Err(IssuanceError::LimitExceeded{ retry_after: 5 }));
+        assert!(limiter.record(&identifier, 5010).is_ok());
+        // The backoff has doubled.
+        assert_eq!(limiter.record(&identifier, 5020), Err(IssuanceError::LimitExceeded{ retry_after: 10 }));
+        assert!(limiter.record(&identifier, 5030).is_ok());
+        // The epoch's limit has been reached.
+        assert_eq!(limiter.record(&identifier, 5500), Err(IssuanceError::LimitExceeded{ retry_after: 500 }));
+        // And resets in the next epoch.
+        assert!(limiter.record(&identifier, 6000).is_ok());
+    }
+
+    #[test]
+    fn policy_is_per_identifier() {
+        let alice: AccountIdentifier = PHONE_NUMBER.to_account_identifier().unwrap();
+        let bob: AccountIdentifier = AccountIdentifier::username("bob").unwrap();
+        let mut limiter = IssuanceLimiter::new(IssuancePolicy::new(1, 1000, 0), InMemoryIssuanceStore::new());
+
+        assert!(limiter.record(&alice, 0).is_ok());
+        assert!(limiter.record(&alice, 1).is_err());
+        assert!(limiter.record(&bob, 1).is_ok());
+    }
+
+    #[test]
+    fn renewal_tags_are_seen_once_per_epoch() {
+        let mut limiter = RenewalLimiter::new(1000);
+        let alice: RistrettoPoint = renewal_tag_generator(5);
+        let bob: RistrettoPoint = renewal_tag_generator(6);
+
+        assert!(limiter.record(&alice, 5000).is_ok());
+        assert_eq!(limiter.record(&alice, 5900), Err(IssuanceError::LimitExceeded{ retry_after: 100 }));
+        assert!(limiter.record(&bob, 5900).is_ok());
+        // The tags are forgotten in the next epoch.
+        assert!(limiter.record(&alice, 6000).is_ok());
+    }
+
+    #[test]
+    fn issuance_record_encode_decode() {
+        let record = IssuanceRecord { epoch: 17, count: 2, last_issued: 17_000_042 };
+
+        assert_eq!(IssuanceRecord::from_bytes(&record.to_bytes()), Ok(record));
+    }
+
+    #[test]
+    fn file_store_persists_records() {
+        let identifier: AccountIdentifier = PHONE_NUMBER.to_account_identifier().unwrap();
+        let path: PathBuf = env::temp_dir().join(format!("signal-issuance-{}.records", process::id()));
+        let policy = IssuancePolicy::new(1, 1000, 0);
+
+        let _ = fs::remove_file(&path);
+        {
+            let mut limiter = IssuanceLimiter::new(policy, FileIssuanceStore::open(&path).unwrap());
+
+            assert!(limiter.record(&identifier, 0).is_ok());
+        }
+        let mut limiter = IssuanceLimiter::new(policy, FileIssuanceStore::open(&path).unwrap());
+
+        assert_eq!(limiter.record(&identifier, 1), Err(IssuanceError::LimitExceeded{ retry_after: 999 }));
+
+        let _ = fs::remove_file(&path);
+    }
+}
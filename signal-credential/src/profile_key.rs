@@ -0,0 +1,381 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Profile key credentials.
+//!
+//! A profile key credential is a MAC_GGM credential on two attributes: the
+//! user's account identifier, which is revealed to the issuer, and their
+//! profile key, which is not.  The user blinds their profile key by
+//! encrypting it to an elGamal key of their own, and the issuer computes the
+//! aMAC homomorphically over the encryption.
+//!
+//! When presenting the credential to a group server, the user hides both
+//! attributes, and proves that an encryption of their profile key to the
+//! group's public key is of the profile key in their credential.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use aeonflux::amacs;
+use aeonflux::credential::RevealedAttribute;
+use aeonflux::elgamal::{self};
+use aeonflux::errors::CredentialError;
+use aeonflux::nonces::Ephemeral;
+use aeonflux::pedersen;
+use aeonflux::proofs::encrypted_attribute;
+use aeonflux::proofs::issuance_partially_blinded;
+use aeonflux::proofs::valid_credential_with_encryption;
+
+use bincode::{deserialize, serialize};
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use sha2::Sha512;
+
+use account_identifier::AccountIdentifier;
+
+pub const SIZEOF_PROFILE_KEY: usize = 32;
+pub const SIZEOF_ENCRYPTED_PROFILE_KEY: usize = elgamal::SIZEOF_ENCRYPTION;
+pub const SIZEOF_PROFILE_KEY_CREDENTIAL: usize = 64 + 32 + SIZEOF_PROFILE_KEY;
+
+/// The number of attributes on a profile key credential: the account
+/// identifier and the profile key.
+pub const NUMBER_OF_PROFILE_KEY_ATTRIBUTES: usize = 2;
+
+/// A key with which a user's Signal profile is encrypted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ProfileKey(pub [u8; SIZEOF_PROFILE_KEY]);
+
+impl ProfileKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProfileKey, CredentialError> {
+        if bytes.len() != SIZEOF_PROFILE_KEY {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut key: [u8; SIZEOF_PROFILE_KEY] = [0u8; SIZEOF_PROFILE_KEY];
+
+        key.copy_from_slice(bytes);
+
+        Ok(ProfileKey(key))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_PROFILE_KEY);
+
+        v.extend_from_slice(&self.0[..]);
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ProfileKey,
+                                         "A valid byte sequence representing a ProfileKey");
+
+impl ProfileKey {
+    /// Encode this profile key as a credential attribute.
+    pub fn to_scalar(&self) -> RevealedAttribute {
+        let mut v: Vec<u8> = Vec::with_capacity(64);
+
+        v.extend_from_slice(b"signal-credential profile key");
+        v.extend_from_slice(&self.0[..]);
+
+        Scalar::hash_from_bytes::<Sha512>(&v)
+    }
+
+    /// Encrypt this profile key to some elGamal `public_key`.
+    pub fn encrypt(&self, public_key: &elgamal::PublicKey, nonce: &Ephemeral) -> EncryptedProfileKey {
+        let message: elgamal::Message = (&self.to_scalar()).into();
+
+        EncryptedProfileKey(public_key.encrypt(&message, nonce))
+    }
+}
+
+/// An elGamal encryption of a `ProfileKey`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EncryptedProfileKey(pub elgamal::Encryption);
+
+impl EncryptedProfileKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<EncryptedProfileKey, CredentialError> {
+        if bytes.len() != SIZEOF_ENCRYPTED_PROFILE_KEY {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut commitment: [u8; 32] = [0u8; 32];
+        let mut encryption: [u8; 32] = [0u8; 32];
+
+        commitment.copy_from_slice(&bytes[00..32]);
+        encryption.copy_from_slice(&bytes[32..64]);
+
+        Ok(EncryptedProfileKey(elgamal::Encryption {
+            commitment: CompressedRistretto(commitment).decompress()?,
+            encryption: CompressedRistretto(encryption).decompress()?,
+        }))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ENCRYPTED_PROFILE_KEY);
+
+        v.extend(self.0.commitment.compress().to_bytes().iter());
+        v.extend(self.0.encryption.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(EncryptedProfileKey,
+                                         "A valid byte sequence representing an EncryptedProfileKey");
+
+impl EncryptedProfileKey {
+    /// Determine whether this is an encryption of some `profile_key`, by
+    /// decrypting it with the `secret_key`.
+    pub fn is_encryption_of(&self, profile_key: &ProfileKey, secret_key: &elgamal::SecretKey) -> bool {
+        let message: elgamal::Message = (&profile_key.to_scalar()).into();
+
+        secret_key.decrypt(&self.0) == RistrettoPoint::from(message)
+    }
+}
+
+/// A request from a user for a `ProfileKeyCredential`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProfileKeyCredentialRequest {
+    /// The user's account identifier, which is revealed to the issuer.
+    pub identifier: AccountIdentifier,
+    /// The user's elGamal public key, to which the `encrypted_profile_key`
+    /// and the resulting aMAC are encrypted.
+    pub public_key: elgamal::PublicKey,
+    /// The user's profile key, encrypted to their `public_key`.
+    pub encrypted_profile_key: EncryptedProfileKey,
+    /// A proof that the `encrypted_profile_key` is well-formed, and that the
+    /// user knows the secret key for their `public_key`.
+    pub proof: encrypted_attribute::Proof,
+}
+
+impl ProfileKeyCredentialRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProfileKeyCredentialRequest, CredentialError> {
+        let (identifier, public_key, encrypted_profile_key, proof) = match deserialize(bytes) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while deserializing ProfileKeyCredentialRequest: {}", _x);
+                return Err(CredentialError::MissingData);
+            },
+        };
+
+        Ok(ProfileKeyCredentialRequest { identifier, public_key, encrypted_profile_key, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.identifier, &self.public_key, &self.encrypted_profile_key, &self.proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing ProfileKeyCredentialRequest: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ProfileKeyCredentialRequest,
+                                         "A valid byte sequence representing a ProfileKeyCredentialRequest");
+
+/// The response from a `SignalIssuer` to a `ProfileKeyCredentialRequest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProfileKeyCredentialIssuance {
+    /// The nonce, \\( P \\), of the credential's tag.
+    pub nonce: RistrettoPoint,
+    /// The MAC, \\( Q \\), of the credential's tag, encrypted to the user's
+    /// public key.
+    pub encrypted_mac: elgamal::Encryption,
+    /// An auxiliary commitment to the product of the issuer's secret key for
+    /// the profile key attribute and the discrete log of the `nonce`.
+    pub auxiliary_commitment: RistrettoPoint,
+    /// A commitment to the issuer's secret key, \\( x\_0 \\).
+    pub secret_key_commitment: pedersen::Commitment,
+    /// A proof that the credential was issued correctly.
+    pub proof: issuance_partially_blinded::Proof,
+}
+
+impl ProfileKeyCredentialIssuance {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProfileKeyCredentialIssuance, CredentialError> {
+        let (nonce, encrypted_mac, auxiliary_commitment, secret_key_commitment, proof) =
+            match deserialize(bytes) {
+                Ok(x)   => x,
+                Err(_x) => {
+                    #[cfg(feature = "std")]
+                    println!("Error while deserializing ProfileKeyCredentialIssuance: {}", _x);
+                    return Err(CredentialError::MissingData);
+                },
+            };
+
+        Ok(ProfileKeyCredentialIssuance {
+            nonce,
+            encrypted_mac,
+            auxiliary_commitment,
+            secret_key_commitment,
+            proof,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.nonce, &self.encrypted_mac, &self.auxiliary_commitment,
+                      &self.secret_key_commitment, &self.proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing ProfileKeyCredentialIssuance: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ProfileKeyCredentialIssuance,
+                                         "A valid byte sequence representing a ProfileKeyCredentialIssuance");
+
+/// A credential on an account identifier and a `ProfileKey`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProfileKeyCredential {
+    pub mac: amacs::Tag,
+    pub identifier: RevealedAttribute,
+    pub profile_key: ProfileKey,
+}
+
+impl ProfileKeyCredential {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProfileKeyCredential, CredentialError> {
+        if bytes.len() != SIZEOF_PROFILE_KEY_CREDENTIAL {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mac: amacs::Tag = amacs::Tag::from_bytes(&bytes[00..64])?;
+        let mut tmp: [u8; 32] = [0u8; 32];
+
+        tmp.copy_from_slice(&bytes[64..96]);
+
+        let identifier: RevealedAttribute = match Scalar::from_canonical_bytes(tmp) {
+            Some(x) => x,
+            None    => return Err(CredentialError::ScalarFormatError),
+        };
+        let profile_key: ProfileKey = ProfileKey::from_bytes(&bytes[96..])?;
+
+        Ok(ProfileKeyCredential { mac, identifier, profile_key })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_PROFILE_KEY_CREDENTIAL);
+
+        v.extend(self.mac.to_bytes());
+        v.extend(self.identifier.to_bytes().iter());
+        v.extend(self.profile_key.to_bytes());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ProfileKeyCredential,
+                                         "A valid byte sequence representing a ProfileKeyCredential");
+
+/// A presentation of a `ProfileKeyCredential` to a group server, showing that
+/// the `encrypted_profile_key` is an encryption, to the group's public key, of
+/// the profile key in the credential.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProfileKeyCredentialPresentation {
+    /// The rerandomised nonce, \\( P \\), of the credential's tag.
+    pub rerandomized_nonce: RistrettoPoint,
+    /// A commitment to the rerandomised MAC, \\( Q \\), of the credential's tag.
+    pub rerandomized_mac_commitment: pedersen::Commitment,
+    /// A commitment to the account identifier attribute.
+    pub identifier_commitment: pedersen::Commitment,
+    /// A commitment to the profile key attribute.
+    pub profile_key_commitment: pedersen::Commitment,
+    /// The user's profile key, encrypted to the group's public key.
+    pub encrypted_profile_key: EncryptedProfileKey,
+    /// A proof that the credential is valid, and that the
+    /// `encrypted_profile_key` is of its profile key.
+    pub proof: valid_credential_with_encryption::Proof,
+}
+
+impl ProfileKeyCredentialPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProfileKeyCredentialPresentation, CredentialError> {
+        let (rerandomized_nonce,
+             rerandomized_mac_commitment,
+             identifier_commitment,
+             profile_key_commitment,
+             encrypted_profile_key,
+             proof) = match deserialize(bytes) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while deserializing ProfileKeyCredentialPresentation: {}", _x);
+                return Err(CredentialError::MissingData);
+            },
+        };
+
+        Ok(ProfileKeyCredentialPresentation {
+            rerandomized_nonce,
+            rerandomized_mac_commitment,
+            identifier_commitment,
+            profile_key_commitment,
+            encrypted_profile_key,
+            proof,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.rerandomized_nonce,
+                      &self.rerandomized_mac_commitment,
+                      &self.identifier_commitment,
+                      &self.profile_key_commitment,
+                      &self.encrypted_profile_key,
+                      &self.proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing ProfileKeyCredentialPresentation: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ProfileKeyCredentialPresentation,
+                                         "A valid byte sequence representing a ProfileKeyCredentialPresentation");
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn profile_key_encryption() {
+        let mut rng = thread_rng();
+        let key: elgamal::Keypair = elgamal::Keypair::generate(&mut rng);
+        let profile_key: ProfileKey = ProfileKey([42u8; SIZEOF_PROFILE_KEY]);
+        let other_profile_key: ProfileKey = ProfileKey([7u8; SIZEOF_PROFILE_KEY]);
+        let encrypted: EncryptedProfileKey = profile_key.encrypt(&key.public, &Ephemeral::new(&mut rng));
+        let deserialized = EncryptedProfileKey::from_bytes(&encrypted.to_bytes()).unwrap();
+
+        assert!(deserialized == encrypted);
+        assert!(encrypted.is_encryption_of(&profile_key, &key.secret));
+        assert!(!encrypted.is_encryption_of(&other_profile_key, &key.secret));
+    }
+}
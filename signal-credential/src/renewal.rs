@@ -0,0 +1,629 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Renewal of `SignalCredential`s without revealing the identifier again.
+//!
+//! To renew, the user presents their current credential with its identifier
+//! hidden, together with an encryption of the identifier to an elGamal key of
+//! their own, and proves that both are of the same attribute.  The issuer
+//! then computes a fresh aMAC under its current key homomorphically over the
+//! encryption, as for blinded issuance of a `ProfileKeyCredential`, and so
+//! never learns the identifier.
+//!
+//! So that renewals may be rate limited, each request also reveals a renewal
+//! tag, \\( T = k H\_e \\), for a generator \\( H\_e \\) derived from the current
+//! epoch \\( e \\) and a `RenewalKey`, \\( k \\), which the user chose at random.
+//! The issuer certifies the renewal key, without learning it, in a
+//! `RenewalKeyCredential` on the user's identifier.  The user presents this
+//! credential too, with both attributes hidden, and proves that it is on the
+//! identifier they encrypted and that the tag is of its renewal key.  Every
+//! request to renew the same credential within an epoch carries the same tag,
+//! which the issuer can refuse to see twice, while requests in different
+//! epochs are unlinkable.  Since the renewal key is never revealed, the issuer
+//! can't compute the tag for a candidate phone number, as it could were the
+//! tag derived from the identifier alone.
+//!
+//! The `RenewalKeyCredential` is renewed alongside the `SignalCredential`, over
+//! an encryption of the renewal key, under the issuer's current keys.
+//!
+//! # Note
+//!
+//! Each renewal key permits another renewal per epoch, so an issuer MUST issue
+//! `RenewalKeyCredential`s only to the owner of the identifier, and no more
+//! often than it issues `SignalCredential`s, e.g. alongside them.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use aeonflux::amacs;
+use aeonflux::credential::RevealedAttribute;
+use aeonflux::elgamal::{self};
+use aeonflux::errors::CredentialError;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::linear_relations::Proof;
+use aeonflux::linear_relations::Statement;
+use aeonflux::nonces::Ephemeral;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::pedersen;
+use aeonflux::proofs::encrypted_attribute;
+use aeonflux::proofs::issuance_blinded_renewal;
+use aeonflux::proofs::valid_credential_renewal;
+
+use bincode::{deserialize, serialize};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use sha2::Sha512;
+
+use account_identifier::AccountIdentifier;
+
+pub const SIZEOF_RENEWAL_KEY: usize = 32;
+pub const SIZEOF_RENEWAL_KEY_CREDENTIAL: usize = 64 + 32 + SIZEOF_RENEWAL_KEY;
+
+/// The number of attributes on a renewal key credential: the account
+/// identifier and the renewal key.
+pub const NUMBER_OF_RENEWAL_KEY_ATTRIBUTES: usize = 2;
+
+/// Derive the generator, \\( H\_e \\), of the renewal tags for an `epoch`.
+pub fn renewal_tag_generator(epoch: u64) -> RistrettoPoint {
+    let mut bytes: Vec<u8> = Vec::with_capacity(40);
+
+    bytes.extend_from_slice(b"signal credential renewal tag");
+    bytes.extend_from_slice(&epoch.to_le_bytes());
+
+    RistrettoPoint::hash_from_bytes::<Sha512>(&bytes)
+}
+
+/// A secret key, chosen at random by a user, from which their renewal tags
+/// are computed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RenewalKey(pub Scalar);
+
+impl RenewalKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RenewalKey, CredentialError> {
+        if bytes.len() != SIZEOF_RENEWAL_KEY {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp: [u8; 32] = [0u8; 32];
+
+        tmp.copy_from_slice(bytes);
+
+        match Scalar::from_canonical_bytes(tmp) {
+            Some(x) => Ok(RenewalKey(x)),
+            None    => Err(CredentialError::ScalarFormatError),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()[..].to_vec()
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RenewalKey,
+                                         "A valid byte sequence representing a RenewalKey");
+
+impl RenewalKey {
+    /// Generate a new, random renewal key.
+    pub fn generate<R>(csprng: &mut R) -> RenewalKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        RenewalKey(Scalar::random(csprng))
+    }
+
+    /// Encrypt this renewal key to some elGamal `public_key`.
+    pub fn encrypt(&self, public_key: &elgamal::PublicKey, nonce: &Ephemeral) -> elgamal::Encryption {
+        public_key.encrypt(&(&self.0).into(), nonce)
+    }
+
+    /// Determine whether this renewal key is the plaintext of some
+    /// `encryption`, by decrypting it with the `secret_key`.
+    pub fn is_plaintext_of(&self, encryption: &elgamal::Encryption, secret_key: &elgamal::SecretKey) -> bool {
+        let message: elgamal::Message = (&self.0).into();
+
+        secret_key.decrypt(encryption) == RistrettoPoint::from(message)
+    }
+
+    /// Compute the renewal tag, \\( T = k H\_e \\), for an `epoch`.
+    pub fn tag(&self, epoch: u64) -> RistrettoPoint {
+        renewal_tag_generator(epoch) * self.0
+    }
+}
+
+/// The encryption, \\( (0, m G) \\), of a revealed identifier, \\( m \\), under
+/// a nonce of zero, so that a `RenewalKeyCredential` is first issued as it is
+/// renewed, over encryptions of both its attributes.
+pub(crate) fn revealed_identifier(
+    system_parameters: &SystemParameters,
+    identifier: &RevealedAttribute,
+) -> elgamal::Encryption
+{
+    elgamal::Encryption {
+        commitment: RistrettoPoint::identity(),
+        encryption: system_parameters.g * identifier,
+    }
+}
+
+/// The public values of the statement that a `RenewalKeyCredential` was
+/// issued correctly, over the `encrypted_identifier` and the
+/// `encrypted_renewal_key`, encrypted to the user's `public_key`.
+pub(crate) struct RenewalKeyIssuancePublics<'a> {
+    pub public_key: &'a elgamal::PublicKey,
+    pub encrypted_identifier: &'a elgamal::Encryption,
+    pub encrypted_renewal_key: &'a elgamal::Encryption,
+    pub nonce: &'a RistrettoPoint,
+    pub encrypted_mac: &'a elgamal::Encryption,
+    pub identifier_auxiliary_commitment: &'a RistrettoPoint,
+    pub renewal_key_auxiliary_commitment: &'a RistrettoPoint,
+    pub secret_key_commitment: &'a pedersen::Commitment,
+}
+
+/// The statement that a `RenewalKeyCredential` was issued correctly under the
+/// `renewal_key_issuer_parameters`, which must be for two attributes.
+///
+/// The secrets are the issuer's secret key, \\( x\_0, x\_1, x\_2 \\), the
+/// opening, \\( \tilde{x\_0} \\), of its commitment to \\( x\_0 \\), the
+/// discrete log, \\( b \\), of the nonce, the nonce, \\( s \\), of the
+/// encryption of the aMAC, and the products \\( t\_i = x\_i b \\).
+pub(crate) fn renewal_key_issuance_statement(
+    system_parameters: &SystemParameters,
+    renewal_key_issuer_parameters: &IssuerParameters,
+    publics: &RenewalKeyIssuancePublics,
+) -> Statement
+{
+    let A: RistrettoPoint = system_parameters.h;
+    let B: RistrettoPoint = system_parameters.g;
+    let D: RistrettoPoint = (*publics.public_key).into();
+    let X1: RistrettoPoint = renewal_key_issuer_parameters.Xn[0];
+    let X2: RistrettoPoint = renewal_key_issuer_parameters.Xn[1];
+    let E: &elgamal::Encryption = publics.encrypted_identifier;
+    let F: &elgamal::Encryption = publics.encrypted_renewal_key;
+    let T1: RistrettoPoint = *publics.identifier_auxiliary_commitment;
+    let T2: RistrettoPoint = *publics.renewal_key_auxiliary_commitment;
+    let mut statement = Statement::new(b"signal credential renewal key issuance", 8);
+
+    // The secrets are, in order, x0, x0~, x1, x2, b, s, t1, and t2.
+    statement.equation((*publics.secret_key_commitment).into(), [(0, B), (1, A)].to_vec());
+    statement.equation(X1, [(2, A)].to_vec());
+    statement.equation(X2, [(3, A)].to_vec());
+    statement.equation(*publics.nonce, [(4, B)].to_vec());
+    statement.equation(T1, [(4, X1)].to_vec());
+    statement.equation(T1, [(6, A)].to_vec());
+    statement.equation(T2, [(4, X2)].to_vec());
+    statement.equation(T2, [(7, A)].to_vec());
+    statement.equation(publics.encrypted_mac.commitment,
+                       [(5, B), (6, E.commitment), (7, F.commitment)].to_vec());
+    statement.equation(publics.encrypted_mac.encryption,
+                       [(5, D), (6, E.encryption), (7, F.encryption), (0, *publics.nonce)].to_vec());
+    statement
+}
+
+/// The statement that a `presentation` is of a valid `RenewalKeyCredential`
+/// under the `renewal_key_issuer_parameters`, which must be for two
+/// attributes, that its identifier is the `encrypted_identifier` and its
+/// renewal key the presentation's encrypted renewal key, both encrypted to
+/// the `public_key`, and that its tag is of the renewal key for the `epoch`.
+///
+/// The error factor, \\( V \\), is computed by the user from the openings of
+/// their commitments, and by the issuer from its secret key.
+///
+/// The secrets are the identifier, \\( m\_0 \\), the renewal key, \\( k \\),
+/// the nonces of their encryptions, the openings of the commitments to them,
+/// and the negated opening of the commitment to the aMAC.
+pub(crate) fn renewal_tag_statement(
+    system_parameters: &SystemParameters,
+    renewal_key_issuer_parameters: &IssuerParameters,
+    V: &RistrettoPoint,
+    public_key: &elgamal::PublicKey,
+    encrypted_identifier: &elgamal::Encryption,
+    presentation: &RenewalKeyCredentialPresentation,
+    epoch: u64,
+) -> Statement
+{
+    let H: RistrettoPoint = renewal_tag_generator(epoch);
+    let A: RistrettoPoint = system_parameters.h;
+    let G: RistrettoPoint = system_parameters.g;
+    let D: RistrettoPoint = (*public_key).into();
+    let P: RistrettoPoint = presentation.rerandomized_nonce;
+    let X1: RistrettoPoint = renewal_key_issuer_parameters.Xn[0];
+    let X2: RistrettoPoint = renewal_key_issuer_parameters.Xn[1];
+    let E: &elgamal::Encryption = encrypted_identifier;
+    let F: &elgamal::Encryption = &presentation.encrypted_renewal_key;
+    let mut statement = Statement::new(b"signal credential renewal tag", 7);
+
+    // The secrets are, in order, m0, k, e, f, z1, z2, and -zQ.
+    statement.equation(presentation.tag, [(1, H)].to_vec());
+    statement.equation(presentation.identifier_commitment.into(), [(0, P), (4, A)].to_vec());
+    statement.equation(presentation.renewal_key_commitment.into(), [(1, P), (5, A)].to_vec());
+    statement.equation(*V, [(4, X1), (5, X2), (6, A)].to_vec());
+    statement.equation(E.commitment, [(2, G)].to_vec());
+    statement.equation(E.encryption, [(0, G), (2, D)].to_vec());
+    statement.equation(F.commitment, [(3, G)].to_vec());
+    statement.equation(F.encryption, [(1, G), (3, D)].to_vec());
+    statement
+}
+
+/// A request from a user for a `RenewalKeyCredential`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RenewalKeyCredentialRequest {
+    /// The user's account identifier, which is revealed to the issuer.
+    pub identifier: AccountIdentifier,
+    /// The user's elGamal public key, to which the `encrypted_renewal_key`
+    /// and the resulting aMAC are encrypted.
+    pub public_key: elgamal::PublicKey,
+    /// The user's renewal key, encrypted to their `public_key`.
+    pub encrypted_renewal_key: elgamal::Encryption,
+    /// A proof that the `encrypted_renewal_key` is well-formed, and that the
+    /// user knows the secret key for their `public_key`.
+    pub proof: encrypted_attribute::Proof,
+}
+
+impl RenewalKeyCredentialRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RenewalKeyCredentialRequest, CredentialError> {
+        let (identifier, public_key, encrypted_renewal_key, proof) = match deserialize(bytes) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while deserializing RenewalKeyCredentialRequest: {}", _x);
+                return Err(CredentialError::MissingData);
+            },
+        };
+
+        Ok(RenewalKeyCredentialRequest { identifier, public_key, encrypted_renewal_key, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.identifier, &self.public_key, &self.encrypted_renewal_key, &self.proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing RenewalKeyCredentialRequest: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RenewalKeyCredentialRequest,
+                                         "A valid byte sequence representing a RenewalKeyCredentialRequest");
+
+/// The response from a `SignalIssuer` to a `RenewalKeyCredentialRequest`, or
+/// the renewed `RenewalKeyCredential` in a `SignalCredentialRenewal`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RenewalKeyCredentialIssuance {
+    /// The nonce, \\( P \\), of the credential's tag.
+    pub nonce: RistrettoPoint,
+    /// The MAC, \\( Q \\), of the credential's tag, encrypted to the user's
+    /// public key.
+    pub encrypted_mac: elgamal::Encryption,
+    /// An auxiliary commitment to the product of the issuer's secret key for
+    /// the identifier attribute and the discrete log of the `nonce`.
+    pub identifier_auxiliary_commitment: RistrettoPoint,
+    /// An auxiliary commitment to the product of the issuer's secret key for
+    /// the renewal key attribute and the discrete log of the `nonce`.
+    pub renewal_key_auxiliary_commitment: RistrettoPoint,
+    /// A commitment to the issuer's secret key, \\( x\_0 \\).
+    pub secret_key_commitment: pedersen::Commitment,
+    /// A proof that the credential was issued correctly.
+    pub proof: Proof,
+}
+
+impl RenewalKeyCredentialIssuance {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RenewalKeyCredentialIssuance, CredentialError> {
+        let (nonce, encrypted_mac, identifier_auxiliary_commitment, renewal_key_auxiliary_commitment,
+             secret_key_commitment, proof) = match deserialize(bytes) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while deserializing RenewalKeyCredentialIssuance: {}", _x);
+                return Err(CredentialError::MissingData);
+            },
+        };
+
+        Ok(RenewalKeyCredentialIssuance {
+            nonce,
+            encrypted_mac,
+            identifier_auxiliary_commitment,
+            renewal_key_auxiliary_commitment,
+            secret_key_commitment,
+            proof,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.nonce, &self.encrypted_mac, &self.identifier_auxiliary_commitment,
+                      &self.renewal_key_auxiliary_commitment, &self.secret_key_commitment, &self.proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing RenewalKeyCredentialIssuance: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+
+    /// Borrow the public values of the statement that this credential was
+    /// issued correctly over the `encrypted_identifier` and the
+    /// `encrypted_renewal_key`, encrypted to the `public_key`.
+    pub(crate) fn publics<'a>(
+        &'a self,
+        public_key: &'a elgamal::PublicKey,
+        encrypted_identifier: &'a elgamal::Encryption,
+        encrypted_renewal_key: &'a elgamal::Encryption,
+    ) -> RenewalKeyIssuancePublics<'a>
+    {
+        RenewalKeyIssuancePublics {
+            public_key,
+            encrypted_identifier,
+            encrypted_renewal_key,
+            nonce: &self.nonce,
+            encrypted_mac: &self.encrypted_mac,
+            identifier_auxiliary_commitment: &self.identifier_auxiliary_commitment,
+            renewal_key_auxiliary_commitment: &self.renewal_key_auxiliary_commitment,
+            secret_key_commitment: &self.secret_key_commitment,
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RenewalKeyCredentialIssuance,
+                                         "A valid byte sequence representing a RenewalKeyCredentialIssuance");
+
+/// A credential on an account identifier and a `RenewalKey`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RenewalKeyCredential {
+    pub mac: amacs::Tag,
+    pub identifier: RevealedAttribute,
+    pub renewal_key: RenewalKey,
+}
+
+impl RenewalKeyCredential {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RenewalKeyCredential, CredentialError> {
+        if bytes.len() != SIZEOF_RENEWAL_KEY_CREDENTIAL {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mac: amacs::Tag = amacs::Tag::from_bytes(&bytes[00..64])?;
+        let mut tmp: [u8; 32] = [0u8; 32];
+
+        tmp.copy_from_slice(&bytes[64..96]);
+
+        let identifier: RevealedAttribute = match Scalar::from_canonical_bytes(tmp) {
+            Some(x) => x,
+            None    => return Err(CredentialError::ScalarFormatError),
+        };
+        let renewal_key: RenewalKey = RenewalKey::from_bytes(&bytes[96..])?;
+
+        Ok(RenewalKeyCredential { mac, identifier, renewal_key })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_RENEWAL_KEY_CREDENTIAL);
+
+        v.extend(self.mac.to_bytes());
+        v.extend(self.identifier.to_bytes().iter());
+        v.extend(self.renewal_key.to_bytes());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RenewalKeyCredential,
+                                         "A valid byte sequence representing a RenewalKeyCredential");
+
+/// A presentation of a `RenewalKeyCredential` with both attributes hidden,
+/// and the renewal tag computed from its renewal key, as part of a
+/// `SignalCredentialRenewalRequest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RenewalKeyCredentialPresentation {
+    /// The rerandomised nonce, \\( P \\), of the credential's tag.
+    pub rerandomized_nonce: RistrettoPoint,
+    /// A commitment to the rerandomised MAC, \\( Q \\), of the credential's tag.
+    pub rerandomized_mac_commitment: pedersen::Commitment,
+    /// A commitment to the account identifier attribute.
+    pub identifier_commitment: pedersen::Commitment,
+    /// A commitment to the renewal key attribute.
+    pub renewal_key_commitment: pedersen::Commitment,
+    /// The renewal key, encrypted to the user's public key.
+    pub encrypted_renewal_key: elgamal::Encryption,
+    /// The renewal tag, \\( T = k H\_e \\), for the epoch in which the
+    /// renewal was requested.
+    pub tag: RistrettoPoint,
+}
+
+impl RenewalKeyCredentialPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RenewalKeyCredentialPresentation, CredentialError> {
+        let (rerandomized_nonce,
+             rerandomized_mac_commitment,
+             identifier_commitment,
+             renewal_key_commitment,
+             encrypted_renewal_key,
+             tag) = match deserialize(bytes) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while deserializing RenewalKeyCredentialPresentation: {}", _x);
+                return Err(CredentialError::MissingData);
+            },
+        };
+
+        Ok(RenewalKeyCredentialPresentation {
+            rerandomized_nonce,
+            rerandomized_mac_commitment,
+            identifier_commitment,
+            renewal_key_commitment,
+            encrypted_renewal_key,
+            tag,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.rerandomized_nonce,
+                      &self.rerandomized_mac_commitment,
+                      &self.identifier_commitment,
+                      &self.renewal_key_commitment,
+                      &self.encrypted_renewal_key,
+                      &self.tag);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing RenewalKeyCredentialPresentation: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RenewalKeyCredentialPresentation,
+                                         "A valid byte sequence representing a RenewalKeyCredentialPresentation");
+
+/// A request from a user to renew their `SignalCredential`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignalCredentialRenewalRequest {
+    /// The user's elGamal public key, to which the `encrypted_identifier` and
+    /// the renewed aMAC are encrypted.
+    pub public_key: elgamal::PublicKey,
+    /// The rerandomised nonce, \\( P \\), of the current credential's tag.
+    pub rerandomized_nonce: RistrettoPoint,
+    /// A commitment to the rerandomised MAC of the current credential's tag.
+    pub rerandomized_mac_commitment: pedersen::Commitment,
+    /// A commitment to the identifier in the current credential.
+    pub identifier_commitment: pedersen::Commitment,
+    /// The identifier, encrypted to the user's `public_key`.
+    pub encrypted_identifier: elgamal::Encryption,
+    /// A proof that the current credential is valid, that the
+    /// `encrypted_identifier` is of the identifier in it, and that the user
+    /// knows the secret key for their `public_key`.
+    pub proof: valid_credential_renewal::Proof,
+    /// A presentation of the user's `RenewalKeyCredential`, carrying their
+    /// renewal tag.
+    pub renewal_key: RenewalKeyCredentialPresentation,
+    /// A proof that the `renewal_key` presentation is of a valid credential
+    /// on the identifier which is the `encrypted_identifier`, and that its
+    /// tag is of the renewal key in it.
+    pub renewal_key_proof: Proof,
+}
+
+impl SignalCredentialRenewalRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalCredentialRenewalRequest, CredentialError> {
+        let (public_key, rerandomized_nonce, rerandomized_mac_commitment,
+             identifier_commitment, encrypted_identifier, proof, renewal_key, renewal_key_proof) = match deserialize(bytes) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while deserializing SignalCredentialRenewalRequest: {}", _x);
+                return Err(CredentialError::MissingData);
+            },
+        };
+
+        Ok(SignalCredentialRenewalRequest {
+            public_key,
+            rerandomized_nonce,
+            rerandomized_mac_commitment,
+            identifier_commitment,
+            encrypted_identifier,
+            proof,
+            renewal_key,
+            renewal_key_proof,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.public_key, &self.rerandomized_nonce, &self.rerandomized_mac_commitment,
+                      &self.identifier_commitment, &self.encrypted_identifier, &self.proof,
+                      &self.renewal_key, &self.renewal_key_proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing SignalCredentialRenewalRequest: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignalCredentialRenewalRequest,
+                                         "A valid byte sequence representing a SignalCredentialRenewalRequest");
+
+/// The response from a `SignalIssuer` to a `SignalCredentialRenewalRequest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignalCredentialRenewal {
+    /// The nonce, \\( P \\), of the renewed credential's tag.
+    pub nonce: RistrettoPoint,
+    /// The MAC, \\( Q \\), of the renewed credential's tag, encrypted to the
+    /// user's public key.
+    pub encrypted_mac: elgamal::Encryption,
+    /// An auxiliary commitment to the product of the issuer's secret key for
+    /// the identifier and the discrete log of the `nonce`.
+    pub auxiliary_commitment: RistrettoPoint,
+    /// A commitment to the issuer's secret key, \\( x\_0 \\).
+    pub secret_key_commitment: pedersen::Commitment,
+    /// A proof that the credential was issued correctly.
+    pub proof: issuance_blinded_renewal::Proof,
+    /// The renewed `RenewalKeyCredential`.
+    pub renewal_key: RenewalKeyCredentialIssuance,
+}
+
+impl SignalCredentialRenewal {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalCredentialRenewal, CredentialError> {
+        let (nonce, encrypted_mac, auxiliary_commitment, secret_key_commitment, proof, renewal_key) =
+            match deserialize(bytes) {
+                Ok(x)   => x,
+                Err(_x) => {
+                    #[cfg(feature = "std")]
+                    println!("Error while deserializing SignalCredentialRenewal: {}", _x);
+                    return Err(CredentialError::MissingData);
+                },
+            };
+
+        Ok(SignalCredentialRenewal {
+            nonce,
+            encrypted_mac,
+            auxiliary_commitment,
+            secret_key_commitment,
+            proof,
+            renewal_key,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.nonce, &self.encrypted_mac, &self.auxiliary_commitment,
+                      &self.secret_key_commitment, &self.proof, &self.renewal_key);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => {
+                #[cfg(feature = "std")]
+                println!("Error while serializing SignalCredentialRenewal: {}", _x);
+                panic!();  // XXX clean this up
+            },
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignalCredentialRenewal,
+                                         "A valid byte sequence representing a SignalCredentialRenewal");
@@ -0,0 +1,421 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Role credentials, certifying both an account identifier and the account's
+//! role in a group.
+//!
+//! A role credential is a MAC_GGM credential on two attributes, the account
+//! identifier and the `Role`, issued with a separate keypair from ordinary
+//! `SignalCredential`s.  When presenting it, both attributes are hidden, and
+//! the user proves that their role is at least some minimum role, without
+//! revealing which role it is.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use aeonflux::amacs;
+use aeonflux::credential::RevealedAttribute;
+use aeonflux::errors::CredentialError;
+use aeonflux::pedersen;
+use aeonflux::proofs::committed_values_equal;
+use aeonflux::proofs::issuance_revealed_role;
+use aeonflux::proofs::valid_role_credential;
+
+use bincode::{deserialize, serialize};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use phone_number::CommittedPhoneNumber;
+
+/// The number of attributes on a role credential: the account identifier and
+/// the `Role`.
+pub const NUMBER_OF_ROLE_ATTRIBUTES: usize = 2;
+
+/// A member's role in a group.  Roles are ordered, such that an `Owner` has
+/// every permission an `Admin` does, and so on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Deserialize, Serialize)]
+#[repr(u8)]
+pub enum Role {
+    Member = 0,
+    Admin = 1,
+    Owner = 2,
+}
+
+/// Every `Role`, in increasing order.
+pub static ROLES: [Role; 3] = [Role::Member, Role::Admin, Role::Owner];
+
+impl Role {
+    /// Encode this `Role` as a credential attribute.
+    pub fn to_scalar(&self) -> Scalar {
+        Scalar::from(*self as u64)
+    }
+
+    /// Decode a `Role` from a credential attribute.
+    pub fn from_scalar(scalar: &Scalar) -> Option<Role> {
+        ROLES.iter().find(|x| x.to_scalar() == *scalar).map(|x| *x)
+    }
+}
+
+pub const SIZEOF_SIGNAL_ROLE_CREDENTIAL: usize = 97;
+
+/// A role credential on an account identifier and a `Role`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignalRoleCredential {
+    pub mac: amacs::Tag,
+    pub identifier: RevealedAttribute,
+    pub role: Role,
+}
+
+impl SignalRoleCredential {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalRoleCredential, CredentialError> {
+        if bytes.len() != SIZEOF_SIGNAL_ROLE_CREDENTIAL {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mac: amacs::Tag = amacs::Tag::from_bytes(&bytes[00..64])?;
+        let mut tmp: [u8; 32] = [0u8; 32];
+
+        tmp.copy_from_slice(&bytes[64..96]);
+
+        let identifier: RevealedAttribute = match Scalar::from_canonical_bytes(tmp) {
+            Some(x) => x,
+            None    => return Err(CredentialError::ScalarFormatError),
+        };
+        let role: Role = match ROLES.get(bytes[96] as usize) {
+            Some(x) => *x,
+            None    => return Err(CredentialError::BadAttribute),
+        };
+
+        Ok(SignalRoleCredential { mac, identifier, role })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_SIGNAL_ROLE_CREDENTIAL);
+
+        v.extend(self.mac.to_bytes());
+        v.extend(self.identifier.to_bytes().iter());
+        v.push(self.role as u8);
+
+        v
+    }
+
+    /// Get the attributes of this credential, in the order they are
+    /// authenticated by its `mac`.
+    pub fn attributes(&self) -> Vec<RevealedAttribute> {
+        let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(NUMBER_OF_ROLE_ATTRIBUTES);
+
+        attributes.push(self.identifier);
+        attributes.push(self.role.to_scalar());
+        attributes
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignalRoleCredential,
+                                         "A valid byte sequence representing a SignalRoleCredential");
+
+/// The response from a `SignalIssuer` to a request for a role credential.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignalRoleCredentialIssuance {
+    pub credential: SignalRoleCredential,
+    pub secret_key_commitment: pedersen::Commitment,
+    pub proof: issuance_revealed_role::Proof,
+}
+
+impl SignalRoleCredentialIssuance {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalRoleCredentialIssuance, CredentialError> {
+        let (credential, secret_key_commitment, proof) = match deserialize(bytes) {
+            Ok(x)  => x,
+            Err(_) => return Err(CredentialError::MissingData),
+        };
+
+        Ok(SignalRoleCredentialIssuance { credential, secret_key_commitment, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.credential, &self.secret_key_commitment, &self.proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => panic!(),  // XXX clean this up
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignalRoleCredentialIssuance,
+                                         "A valid byte sequence representing a SignalRoleCredentialIssuance");
+
+/// The responses for one branch of a `RoleProof`.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+struct BranchResponses {
+    challenge: Scalar,
+    z: Scalar,
+}
+
+/// A zero-knowledge proof that the role committed to in \\( C\_{m\_2} = m\_2 P + z A \\)
+/// is one of the roles greater than or equal to some minimum role, i.e. that
+/// for some such role \\( r \\), the user knows \\( z \\) such that
+/// \\( C\_{m\_2} - r P = z A \\).
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct RoleProof {
+    branches: Vec<BranchResponses>,
+}
+
+impl RoleProof {
+    fn commit_publics(
+        transcript: &mut Transcript,
+        A: &RistrettoPoint,
+        P: &RistrettoPoint,
+        Cm2: &RistrettoPoint,
+        minimum: Role,
+    ) {
+        transcript.commit_bytes(b"domain-sep", b"signal_role_at_least");
+        transcript.commit_bytes(b"A", A.compress().as_bytes());
+        transcript.commit_bytes(b"P", P.compress().as_bytes());
+        transcript.commit_bytes(b"Cm2", Cm2.compress().as_bytes());
+        transcript.commit_bytes(b"minimum", &[minimum as u8]);
+    }
+
+    fn challenge(transcript: &mut Transcript) -> Scalar {
+        let mut bytes = [0u8; 64];
+
+        transcript.challenge_bytes(b"chal", &mut bytes);
+
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+
+    /// Get the roles which are at least the `minimum`.
+    fn branches(minimum: Role) -> &'static [Role] {
+        &ROLES[minimum as usize..]
+    }
+
+    /// Prove that the `role` committed to in `Cm2`, with blinding factor `z`,
+    /// is at least the `minimum`.
+    pub(crate) fn create<R>(
+        transcript: &mut Transcript,
+        A: &RistrettoPoint,
+        P: &RistrettoPoint,
+        Cm2: &RistrettoPoint,
+        minimum: Role,
+        role: Role,
+        z: &Scalar,
+        csprng: &mut R,
+    ) -> Result<RoleProof, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if role < minimum {
+            return Err(CredentialError::BadAttribute);
+        }
+        let index: usize = role as usize - minimum as usize;
+
+        RoleProof::commit_publics(transcript, A, P, Cm2, minimum);
+
+        let r_z: Scalar = Scalar::random(csprng);
+        let mut branches: Vec<BranchResponses> = Vec::with_capacity(ROLES.len());
+
+        for (k, r) in RoleProof::branches(minimum).iter().enumerate() {
+            let Y: RistrettoPoint = Cm2 - P * r.to_scalar();
+
+            // For the real branch, commit to our randomness, and otherwise
+            // simulate the branch with a random challenge and response.
+            let branch = if k == index {
+                BranchResponses { challenge: Scalar::zero(), z: r_z }
+            } else {
+                BranchResponses { challenge: Scalar::random(csprng), z: Scalar::random(csprng) }
+            };
+            let T: RistrettoPoint = A * branch.z - Y * branch.challenge;
+
+            transcript.commit_bytes(b"com Y", T.compress().as_bytes());
+
+            branches.push(branch);
+        }
+
+        let mut challenge: Scalar = RoleProof::challenge(transcript);
+
+        for (k, branch) in branches.iter().enumerate() {
+            if k != index {
+                challenge -= branch.challenge;
+            }
+        }
+        branches[index] = BranchResponses {
+            challenge: challenge,
+            z: &r_z + &(&challenge * z),
+        };
+
+        Ok(RoleProof { branches })
+    }
+
+    /// Verify a proof that the role committed to in `Cm2` is at least the
+    /// `minimum`.
+    pub(crate) fn verify(
+        &self,
+        transcript: &mut Transcript,
+        A: &RistrettoPoint,
+        P: &RistrettoPoint,
+        Cm2: &RistrettoPoint,
+        minimum: Role,
+    ) -> Result<(), ()>
+    {
+        let roles: &[Role] = RoleProof::branches(minimum);
+
+        if self.branches.len() != roles.len() {
+            return Err(());
+        }
+
+        RoleProof::commit_publics(transcript, A, P, Cm2, minimum);
+
+        let mut challenge_sum: Scalar = Scalar::zero();
+
+        for (branch, r) in self.branches.iter().zip(roles.iter()) {
+            let Y: RistrettoPoint = Cm2 - P * r.to_scalar();
+            let T: RistrettoPoint = A * branch.z - Y * branch.challenge;
+
+            transcript.commit_bytes(b"com Y", T.compress().as_bytes());
+
+            challenge_sum += branch.challenge;
+        }
+
+        if RoleProof::challenge(transcript) == challenge_sum {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// A presentation of a role credential, showing that the user is in a group
+/// roster with a role of at least some minimum, without revealing their
+/// account identifier nor their exact role.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignalRoleCredentialPresentation {
+    /// The user's corresponding committed identifier in a roster entry.
+    pub roster_entry_commitment: CommittedPhoneNumber,
+    /// The rerandomised nonce, \\( P \\), of the credential's tag.
+    pub rerandomized_nonce: RistrettoPoint,
+    /// A commitment to the rerandomised MAC, \\( Q \\), of the credential's tag.
+    pub rerandomized_mac_commitment: pedersen::Commitment,
+    /// A commitment to the account identifier attribute.
+    pub identifier_commitment: pedersen::Commitment,
+    /// A commitment to the role attribute.
+    pub role_commitment: pedersen::Commitment,
+    /// A proof that the credential is valid.
+    pub proof: valid_role_credential::Proof,
+    /// A proof that the committed role is at least the minimum role.
+    pub role_proof: RoleProof,
+    /// A proof that the committed identifier is the same as the one in the
+    /// `roster_entry_commitment`.
+    pub roster_membership_proof: committed_values_equal::Proof,
+}
+
+impl SignalRoleCredentialPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalRoleCredentialPresentation, CredentialError> {
+        let (roster_entry_commitment,
+             rerandomized_nonce,
+             rerandomized_mac_commitment,
+             identifier_commitment,
+             role_commitment,
+             proof,
+             role_proof,
+             roster_membership_proof) = match deserialize(bytes) {
+            Ok(x)  => x,
+            Err(_) => return Err(CredentialError::MissingData),
+        };
+
+        Ok(SignalRoleCredentialPresentation {
+            roster_entry_commitment,
+            rerandomized_nonce,
+            rerandomized_mac_commitment,
+            identifier_commitment,
+            role_commitment,
+            proof,
+            role_proof,
+            roster_membership_proof,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = (&self.roster_entry_commitment,
+                      &self.rerandomized_nonce,
+                      &self.rerandomized_mac_commitment,
+                      &self.identifier_commitment,
+                      &self.role_commitment,
+                      &self.proof,
+                      &self.role_proof,
+                      &self.roster_membership_proof);
+
+        match serialize(&fields) {
+            Ok(x)   => x,
+            Err(_x) => panic!(),  // XXX clean this up
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignalRoleCredentialPresentation,
+                                         "A valid byte sequence representing a SignalRoleCredentialPresentation");
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn role_proof() {
+        let mut rng = thread_rng();
+        let A: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let P: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let z: Scalar = Scalar::random(&mut rng);
+
+        for role in ROLES.iter() {
+            let Cm2: RistrettoPoint = P * role.to_scalar() + A * z;
+
+            for minimum in ROLES.iter() {
+                let mut prover_transcript = Transcript::new(b"ROLE TEST");
+                let mut verifier_transcript = Transcript::new(b"ROLE TEST");
+                let proof = RoleProof::create(&mut prover_transcript, &A, &P, &Cm2, *minimum,
+                                              *role, &z, &mut rng);
+
+                if role < minimum {
+                    assert!(proof.is_err());
+                } else {
+                    assert!(proof.unwrap().verify(&mut verifier_transcript, &A, &P, &Cm2, *minimum).is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn role_proof_lying_about_role() {
+        let mut rng = thread_rng();
+        let A: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let P: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let z: Scalar = Scalar::random(&mut rng);
+        let Cm2: RistrettoPoint = P * Role::Member.to_scalar() + A * z;
+
+        let mut prover_transcript = Transcript::new(b"ROLE TEST");
+        let mut verifier_transcript = Transcript::new(b"ROLE TEST");
+
+        // Claim to be an admin when we're merely a member.
+        let proof = RoleProof::create(&mut prover_transcript, &A, &P, &Cm2, Role::Admin,
+                                      Role::Admin, &z, &mut rng).unwrap();
+
+        assert!(proof.verify(&mut verifier_transcript, &A, &P, &Cm2, Role::Admin).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Rosters of the members of a Signal group.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::slice::Iter;
+#[cfg(not(feature = "std"))]
+use core::slice::Iter;
+
+use aeonflux::elgamal::{self};
+use aeonflux::errors::CredentialError;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use subtle::Choice;
+use subtle::ConstantTimeEq;
+
+use account_identifier::AccountIdentifier;
+use phone_number::CommittedPhoneNumber;
+use phone_number::EncryptedPhoneNumber;
+use phone_number::SIZEOF_COMMITTED_PHONE_NUMBER;
+use phone_number::SIZEOF_ENCRYPTED_PHONE_NUMBER;
+
+/// The roster of a Signal group, containing a commitment to each member's
+/// account identifier.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GroupRoster {
+    members: Vec<CommittedPhoneNumber>,
+}
+
+impl GroupRoster {
+    pub fn from_bytes(bytes: &[u8]) -> Result<GroupRoster, CredentialError> {
+        if bytes.len() % SIZEOF_COMMITTED_PHONE_NUMBER != 0 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut roster = GroupRoster::new();
+
+        for chunk in bytes.chunks(SIZEOF_COMMITTED_PHONE_NUMBER) {
+            if !roster.add(CommittedPhoneNumber::from_bytes(chunk)?) {
+                return Err(CredentialError::BadAttribute);
+            }
+        }
+
+        Ok(roster)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(self.members.len() * SIZEOF_COMMITTED_PHONE_NUMBER);
+
+        for member in self.members.iter() {
+            v.extend(member.to_bytes());
+        }
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(GroupRoster,
+                                         "A valid byte sequence representing a GroupRoster");
+
+impl GroupRoster {
+    /// Create an empty `GroupRoster`.
+    pub fn new() -> GroupRoster {
+        GroupRoster { members: Vec::new() }
+    }
+
+    /// The number of members in this roster.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Iterate over the commitments to the members of this roster.
+    pub fn iter(&self) -> Iter<CommittedPhoneNumber> {
+        self.members.iter()
+    }
+
+    /// Find the index of a `member`, in time which depends only upon the
+    /// number of members in the roster.
+    pub fn position(&self, member: &CommittedPhoneNumber) -> Option<usize> {
+        let needle: Vec<u8> = member.to_bytes();
+        let mut found: Choice = Choice::from(0u8);
+        let mut index: usize = 0;
+
+        for (i, entry) in self.members.iter().enumerate() {
+            let equal: Choice = entry.to_bytes()[..].ct_eq(&needle[..]);
+
+            index |= i & (0usize.wrapping_sub(equal.unwrap_u8() as usize));
+            found |= equal;
+        }
+
+        if found.unwrap_u8() == 1 {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Get the commitments to the members of this roster as points.
+    pub fn to_points(&self) -> Vec<RistrettoPoint> {
+        self.members.iter().map(|x| x.0.into()).collect()
+    }
+
+    /// Determine whether a `member` is in this roster.
+    ///
+    /// # Note
+    ///
+    /// This runs in time which depends only upon the number of members in the
+    /// roster, and not upon which member (if any) matched.
+    pub fn contains(&self, member: &CommittedPhoneNumber) -> bool {
+        self.position(member).is_some()
+    }
+
+    /// Add a `member` to this roster.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the `member` was added, or `false` if they were already
+    /// present.
+    pub fn add(&mut self, member: CommittedPhoneNumber) -> bool {
+        if self.contains(&member) {
+            return false;
+        }
+        self.members.push(member);
+
+        true
+    }
+
+    /// Remove a `member` from this roster.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the `member` was removed, or `false` if they were not present.
+    pub fn remove(&mut self, member: &CommittedPhoneNumber) -> bool {
+        match self.position(member) {
+            Some(i) => { self.members.remove(i); true },
+            None    => false,
+        }
+    }
+}
+
+/// The roster of a Signal group, containing an encryption of each member's
+/// account identifier to a key shared by the members of the group.
+///
+/// Unlike a `GroupRoster`, any member holding the group's
+/// `elgamal::SecretKey` may decrypt the roster locally to learn who else is
+/// in the group, while the server learns nothing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EncryptedGroupRoster {
+    public_key: elgamal::PublicKey,
+    members: Vec<EncryptedPhoneNumber>,
+}
+
+impl EncryptedGroupRoster {
+    pub fn from_bytes(bytes: &[u8]) -> Result<EncryptedGroupRoster, CredentialError> {
+        if bytes.len() < elgamal::SIZEOF_PUBLIC_KEY ||
+            (bytes.len() - elgamal::SIZEOF_PUBLIC_KEY) % SIZEOF_ENCRYPTED_PHONE_NUMBER != 0
+        {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let public_key = elgamal::PublicKey::from_bytes(&bytes[..elgamal::SIZEOF_PUBLIC_KEY])?;
+        let mut roster = EncryptedGroupRoster::new(public_key);
+
+        for chunk in bytes[elgamal::SIZEOF_PUBLIC_KEY..].chunks(SIZEOF_ENCRYPTED_PHONE_NUMBER) {
+            if !roster.add(EncryptedPhoneNumber::from_bytes(chunk)?) {
+                return Err(CredentialError::BadAttribute);
+            }
+        }
+
+        Ok(roster)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(elgamal::SIZEOF_PUBLIC_KEY +
+                                                self.members.len() * SIZEOF_ENCRYPTED_PHONE_NUMBER);
+
+        v.extend(self.public_key.to_bytes());
+
+        for member in self.members.iter() {
+            v.extend(member.to_bytes());
+        }
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(EncryptedGroupRoster,
+                                         "A valid byte sequence representing an EncryptedGroupRoster");
+
+impl EncryptedGroupRoster {
+    /// Create an empty `EncryptedGroupRoster` whose entries are encrypted to
+    /// the group's `public_key`.
+    pub fn new(public_key: elgamal::PublicKey) -> EncryptedGroupRoster {
+        EncryptedGroupRoster { public_key, members: Vec::new() }
+    }
+
+    /// The group's public key, to which every entry is encrypted.
+    pub fn public_key(&self) -> &elgamal::PublicKey {
+        &self.public_key
+    }
+
+    /// The number of members in this roster.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Iterate over the encryptions of the members of this roster.
+    pub fn iter(&self) -> Iter<EncryptedPhoneNumber> {
+        self.members.iter()
+    }
+
+    /// Find the index of a `member`, in time which depends only upon the
+    /// number of members in the roster.
+    pub fn position(&self, member: &EncryptedPhoneNumber) -> Option<usize> {
+        let needle: Vec<u8> = member.to_bytes();
+        let mut found: Choice = Choice::from(0u8);
+        let mut index: usize = 0;
+
+        for (i, entry) in self.members.iter().enumerate() {
+            let equal: Choice = entry.to_bytes()[..].ct_eq(&needle[..]);
+
+            index |= i & (0usize.wrapping_sub(equal.unwrap_u8() as usize));
+            found |= equal;
+        }
+
+        if found.unwrap_u8() == 1 {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Determine whether a `member` is in this roster.
+    pub fn contains(&self, member: &EncryptedPhoneNumber) -> bool {
+        self.position(member).is_some()
+    }
+
+    /// Add a `member` to this roster.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the `member` was added, or `false` if they were already
+    /// present.
+    pub fn add(&mut self, member: EncryptedPhoneNumber) -> bool {
+        if self.contains(&member) {
+            return false;
+        }
+        self.members.push(member);
+
+        true
+    }
+
+    /// Remove a `member` from this roster.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the `member` was removed, or `false` if they were not present.
+    pub fn remove(&mut self, member: &EncryptedPhoneNumber) -> bool {
+        match self.position(member) {
+            Some(i) => { self.members.remove(i); true },
+            None    => false,
+        }
+    }
+
+    /// Decrypt every entry in this roster with the group's `secret_key`.
+    ///
+    /// See `EncryptedPhoneNumber::decrypt()` for the form of the results.
+    pub fn decrypt(&self, secret_key: &elgamal::SecretKey) -> Vec<RistrettoPoint> {
+        self.members.iter().map(|x| x.decrypt(secret_key)).collect()
+    }
+
+    /// Find the index of the entry for some account `identifier`, by
+    /// decrypting the roster with the group's `secret_key`.
+    pub fn find(&self, identifier: &AccountIdentifier, secret_key: &elgamal::SecretKey) -> Option<usize> {
+        self.members.iter().position(|x| x.is_encryption_of(identifier, secret_key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use aeonflux::parameters::SystemParameters;
+
+    use rand::thread_rng;
+
+    use account_identifier::ToAccountIdentifier;
+    use phone_number::RosterEntryCommitment;
+    use phone_number::RosterEntryEncryption;
+
+    #[test]
+    fn roster_add_remove_lookup() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut rng);
+        let alice = RosterEntryCommitment::create(&[1u8, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4][..],
+                                                  &system_parameters, &mut rng).unwrap();
+        let bob = RosterEntryCommitment::create(&[1u8, 4, 1, 5, 5, 5, 5, 6, 6, 6, 6][..],
+                                                &system_parameters, &mut rng).unwrap();
+        let mut roster = GroupRoster::new();
+
+        assert!(roster.add(alice.commitment));
+        assert!(!roster.add(alice.commitment));
+        assert!(roster.add(bob.commitment));
+        assert!(roster.contains(&alice.commitment));
+        assert!(roster.len() == 2);
+
+        let deserialized = GroupRoster::from_bytes(&roster.to_bytes()).unwrap();
+
+        assert!(deserialized == roster);
+
+        assert!(roster.remove(&alice.commitment));
+        assert!(!roster.remove(&alice.commitment));
+        assert!(!roster.contains(&alice.commitment));
+        assert!(roster.contains(&bob.commitment));
+    }
+
+    #[test]
+    fn encrypted_roster_decryption() {
+        let mut rng = thread_rng();
+        let group_key: elgamal::Keypair = elgamal::Keypair::generate(&mut rng);
+        let alice_phone_number: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+        let bob_phone_number: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 6, 6, 6, 6];
+        let alice = RosterEntryEncryption::create(alice_phone_number, &group_key.public, &mut rng).unwrap();
+        let bob = RosterEntryEncryption::create(bob_phone_number, &group_key.public, &mut rng).unwrap();
+        let mut roster = EncryptedGroupRoster::new(group_key.public);
+
+        assert!(roster.add(alice.encryption));
+        assert!(!roster.add(alice.encryption));
+        assert!(roster.add(bob.encryption));
+
+        let deserialized = EncryptedGroupRoster::from_bytes(&roster.to_bytes()).unwrap();
+
+        assert!(deserialized == roster);
+
+        // A member holding the group key can tell who is in the group.
+        let alice_identifier = alice_phone_number.to_account_identifier().unwrap();
+        let bob_identifier = bob_phone_number.to_account_identifier().unwrap();
+
+        assert!(roster.find(&alice_identifier, &group_key.secret) == Some(0));
+        assert!(roster.find(&bob_identifier, &group_key.secret) == Some(1));
+
+        // Someone with another key can't.
+        let other_key: elgamal::Keypair = elgamal::Keypair::generate(&mut rng);
+
+        assert!(roster.find(&alice_identifier, &other_key.secret).is_none());
+    }
+}
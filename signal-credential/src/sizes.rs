@@ -0,0 +1,161 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! The lengths of serialised objects, for language bindings which must
+//! allocate buffers for them.
+//!
+//! Rather than being maintained by hand, each length is computed by creating
+//! an example of the object and serialising it, so that it always agrees with
+//! the code which serialises the object.
+
+use aeonflux::elgamal;
+use aeonflux::parameters::SystemParameters;
+
+use rand::ChaChaRng;
+use rand::SeedableRng;
+
+use credential::SignalCredentialIssuance;
+use credential::SignalCredentialPresentation;
+use credential::VerifiedSignalCredential;
+use issuer::SignalIssuer;
+use phone_number::RosterEntryCommitment;
+use status::Status;
+use user::SignalUser;
+
+/// The phone number for which example objects are created.  Lengths of
+/// objects containing an identifier are thus those for phone numbers.
+const EXAMPLE_PHONE_NUMBER: &'static [u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+
+/// A kind of serialised object which may be passed across a language binding.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum SerializedObject {
+    SystemParameters = 1,
+    IssuerKeypair = 2,
+    Issuer = 3,
+    IssuerParameters = 4,
+    ElgamalKeypair = 5,
+    ElgamalPublicKey = 6,
+    User = 7,
+    CredentialIssuance = 8,
+    CredentialPresentation = 9,
+    VerifiedCredential = 10,
+    RosterEntryCommitment = 11,
+    RosterEntryCommitmentSansOpening = 12,
+}
+
+impl SerializedObject {
+    /// Get the `SerializedObject` with the numeric `code`, if there is one.
+    pub fn from_code(code: u32) -> Option<SerializedObject> {
+        match code {
+            1  => Some(SerializedObject::SystemParameters),
+            2  => Some(SerializedObject::IssuerKeypair),
+            3  => Some(SerializedObject::Issuer),
+            4  => Some(SerializedObject::IssuerParameters),
+            5  => Some(SerializedObject::ElgamalKeypair),
+            6  => Some(SerializedObject::ElgamalPublicKey),
+            7  => Some(SerializedObject::User),
+            8  => Some(SerializedObject::CredentialIssuance),
+            9  => Some(SerializedObject::CredentialPresentation),
+            10 => Some(SerializedObject::VerifiedCredential),
+            11 => Some(SerializedObject::RosterEntryCommitment),
+            12 => Some(SerializedObject::RosterEntryCommitmentSansOpening),
+            _  => None,
+        }
+    }
+
+    /// Compute the length of this kind of object, in bytes, once serialised.
+    ///
+    /// # Note
+    ///
+    /// Finding the length of a `CredentialPresentation` or
+    /// `VerifiedCredential` requires creating zero-knowledge proofs, so
+    /// callers should compute these lengths once and keep them.
+    pub fn length(&self) -> Result<usize, Status> {
+        // The examples need not be secret, so a fixed seed is fine.
+        let mut csprng: ChaChaRng = ChaChaRng::from_seed([0u8; 32]);
+        let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut csprng);
+
+        let issuer: SignalIssuer = match *self {
+            SerializedObject::SystemParameters =>
+                return Ok(system_parameters.to_bytes().len()),
+            SerializedObject::ElgamalKeypair =>
+                return Ok(elgamal::Keypair::generate(&mut csprng).to_bytes().len()),
+            SerializedObject::ElgamalPublicKey =>
+                return Ok(elgamal::Keypair::generate(&mut csprng).public.to_bytes().len()),
+            SerializedObject::RosterEntryCommitment =>
+                return Ok(RosterEntryCommitment::create(EXAMPLE_PHONE_NUMBER, &system_parameters,
+                                                        &mut csprng)?.to_bytes().len()),
+            SerializedObject::RosterEntryCommitmentSansOpening =>
+                return Ok(RosterEntryCommitment::create(EXAMPLE_PHONE_NUMBER, &system_parameters,
+                                                        &mut csprng)?.commitment.to_bytes().len()),
+            _ => SignalIssuer::create(system_parameters, &mut csprng),
+        };
+
+        match *self {
+            SerializedObject::IssuerKeypair    => return Ok(issuer.issuer.keypair.to_bytes().len()),
+            SerializedObject::Issuer           => return Ok(issuer.to_bytes().len()),
+            SerializedObject::IssuerParameters => return Ok(issuer.get_issuer_parameters().to_bytes().len()),
+            _ => (),
+        }
+
+        let issuance: SignalCredentialIssuance = issuer.issue(EXAMPLE_PHONE_NUMBER, &mut csprng)?;
+
+        if *self == SerializedObject::CredentialIssuance {
+            return Ok(issuance.to_bytes().len());
+        }
+
+        let mut user: SignalUser = SignalUser::new(system_parameters,
+                                                   issuer.get_issuer_parameters(),
+                                                   Some(elgamal::Keypair::generate(&mut csprng)),
+                                                   EXAMPLE_PHONE_NUMBER)?;
+        user.obtain_finish(Some(&issuance))?;
+
+        if *self == SerializedObject::User {
+            return Ok(user.to_bytes().len());
+        }
+
+        let entry = RosterEntryCommitment::create(EXAMPLE_PHONE_NUMBER, &system_parameters, &mut csprng)?;
+        let presentation: SignalCredentialPresentation = user.show(&mut csprng, &entry)?;
+
+        if *self == SerializedObject::CredentialPresentation {
+            return Ok(presentation.to_bytes().len());
+        }
+
+        let verified: VerifiedSignalCredential = issuer.verify(presentation)?;
+
+        Ok(verified.to_bytes().len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        for code in 1..13 {
+            assert_eq!(SerializedObject::from_code(code).unwrap() as u32, code);
+        }
+        assert!(SerializedObject::from_code(0).is_none());
+        assert!(SerializedObject::from_code(13).is_none());
+    }
+
+    #[test]
+    fn lengths() {
+        assert_eq!(SerializedObject::SystemParameters.length().unwrap(), 64);
+        assert_eq!(SerializedObject::IssuerKeypair.length().unwrap(), 96);
+        assert_eq!(SerializedObject::Issuer.length().unwrap(), 160);
+        assert_eq!(SerializedObject::IssuerParameters.length().unwrap(), 32);
+        assert_eq!(SerializedObject::ElgamalKeypair.length().unwrap(), elgamal::SIZEOF_KEYPAIR);
+        assert_eq!(SerializedObject::ElgamalPublicKey.length().unwrap(), elgamal::SIZEOF_PUBLIC_KEY);
+        assert_eq!(SerializedObject::User.length().unwrap(), 289);
+        assert_eq!(SerializedObject::RosterEntryCommitmentSansOpening.length().unwrap(), 32);
+    }
+}
@@ -0,0 +1,275 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Numeric status codes for the errors of this crate and of aeonflux.
+//!
+//! The codes are shared by every language binding, so that a C, Swift, or
+//! Javascript caller can tell, for example, a malformed seed from a proof
+//! which failed to verify.  Codes are grouped by the error type they mirror,
+//! and once assigned, a code must never be reused for a different error.
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+use aeonflux::errors::CredentialError;
+use aeonflux::errors::MacError;
+
+use errors::AccountIdentifierError;
+use errors::IssuanceError;
+use errors::PhoneNumberError;
+use errors::VerificationError;
+
+/// A status code returned across a language binding.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Status {
+    Ok = 0,
+
+    // Errors in the arguments handed to a binding.
+    NullPointer = 1,
+    InvalidSeed = 2,
+    Serialisation = 3,
+    UnknownObject = 4,
+    SeedReused = 5,
+    Entropy = 6,
+
+    // Mirrors of `aeonflux::errors::CredentialError`.
+    BadAttribute = 100,
+    CredentialIssuance = 101,
+    MacCreation = 102,
+    MacVerification = 103,
+    MissingData = 104,
+    NoIssuerKey = 105,
+    NoIssuerParameters = 106,
+    NoSystemParameters = 107,
+    PointDecompression = 108,
+    ScalarFormat = 109,
+    WrongNumberOfAttributes = 110,
+    WrongNumberOfBytes = 111,
+    VerificationFailure = 112,
+
+    // Mirrors of `aeonflux::errors::MacError`.
+    KeypairDeserialisation = 200,
+    MacPointDecompression = 201,
+    MacScalarFormat = 202,
+    MessageLength = 203,
+    MacAuthentication = 204,
+
+    // Mirrors of `PhoneNumberError`.
+    PhoneNumberLengthExceeded = 300,
+    InvalidPhoneNumber = 301,
+    EmptyPhoneNumber = 302,
+    MissingPlusSign = 303,
+    InvalidCharacter = 304,
+    InvalidCountryCode = 305,
+    TooFewDigits = 306,
+    TooManyDigits = 307,
+
+    // Mirrors of the remaining `AccountIdentifierError`s.
+    InvalidUsername = 400,
+    WrongIdentifierLength = 401,
+
+    // Mirrors of `VerificationError` and `IssuanceError`.
+    Unverified = 500,
+    VerificationExpired = 501,
+    VerificationRateLimited = 502,
+    IssuanceLimitExceeded = 503,
+    IssuanceStorage = 504,
+}
+
+impl Status {
+    /// Get the numeric code for this `Status`.
+    pub fn code(&self) -> u32 {
+        *self as u32
+    }
+
+    /// Determine whether this `Status` is `Status::Ok`.
+    pub fn is_ok(&self) -> bool {
+        *self == Status::Ok
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Status::Ok
+                => write!(f, "Success"),
+            Status::NullPointer
+                => write!(f, "A required pointer was NULL or a required buffer was empty"),
+            Status::InvalidSeed
+                => write!(f, "The seed must be exactly 32 bytes"),
+            Status::Serialisation
+                => write!(f, "A value could not be serialised or deserialised"),
+            Status::UnknownObject
+                => write!(f, "There is no kind of serialised object with that code"),
+            Status::SeedReused
+                => write!(f, "The seed has already been used by this process"),
+            Status::Entropy
+                => write!(f, "Could not get randomness from the operating system"),
+            Status::BadAttribute
+                => write!(f, "An attribute was unacceptable"),
+            Status::CredentialIssuance
+                => write!(f, "Failed to get a credential issued"),
+            Status::MacCreation
+                => write!(f, "Could not create a MAC"),
+            Status::MacVerification
+                => write!(f, "Could not verify a MAC"),
+            Status::MissingData
+                => write!(f, "Some data, such as a key or zkproof, was missing"),
+            Status::NoIssuerKey
+                => write!(f, "The issuer was not initialised properly and has no secret key"),
+            Status::NoIssuerParameters
+                => write!(f, "The issuer was not initialised properly and has no parameters"),
+            Status::NoSystemParameters
+                => write!(f, "The system parameters were not initialised"),
+            Status::PointDecompression | Status::MacPointDecompression
+                => write!(f, "Cannot decompress Ristretto point"),
+            Status::ScalarFormat | Status::MacScalarFormat
+                => write!(f, "Cannot use scalar with high-bit set"),
+            Status::WrongNumberOfAttributes
+                => write!(f, "The credential did not have the correct number of attributes"),
+            Status::WrongNumberOfBytes
+                => write!(f, "The value could not be deserialised because it had the wrong length"),
+            Status::VerificationFailure
+                => write!(f, "The proof could not be verified"),
+            Status::KeypairDeserialisation
+                => write!(f, "Cannot deserialise keypair"),
+            Status::MessageLength
+                => write!(f, "The message had the wrong length"),
+            Status::MacAuthentication
+                => write!(f, "MAC could not be authenticated"),
+            Status::PhoneNumberLengthExceeded
+                => write!(f, "A canonicalised phone number cannot be more than 32 bytes"),
+            Status::InvalidPhoneNumber
+                => write!(f, "The phone number was invalid"),
+            Status::EmptyPhoneNumber
+                => write!(f, "The phone number was empty"),
+            Status::MissingPlusSign
+                => write!(f, "An E.164 phone number must begin with a '+'"),
+            Status::InvalidCharacter
+                => write!(f, "The phone number contained a character which is neither a digit nor a separator"),
+            Status::InvalidCountryCode
+                => write!(f, "The phone number did not begin with an assigned E.164 country code"),
+            Status::TooFewDigits
+                => write!(f, "The phone number had too few digits after its country code"),
+            Status::TooManyDigits
+                => write!(f, "An E.164 phone number cannot be more than 15 digits"),
+            Status::InvalidUsername
+                => write!(f, "A username must be 1 to 32 ASCII letters, digits, underscores, or dots"),
+            Status::WrongIdentifierLength
+                => write!(f, "The account identifier could not be deserialised because it had the wrong length"),
+            Status::Unverified
+                => write!(f, "Ownership of the phone number could not be verified"),
+            Status::VerificationExpired
+                => write!(f, "The verification code for the phone number has expired"),
+            Status::VerificationRateLimited
+                => write!(f, "Too many verification attempts were made for the phone number"),
+            Status::IssuanceLimitExceeded
+                => write!(f, "Too many credentials were issued for this identifier"),
+            Status::IssuanceStorage
+                => write!(f, "The record of prior issuances could not be read or written"),
+        }
+    }
+}
+
+impl From<CredentialError> for Status {
+    fn from(source: CredentialError) -> Status {
+        match source {
+            CredentialError::BadAttribute            => Status::BadAttribute,
+            CredentialError::CredentialIssuance      => Status::CredentialIssuance,
+            CredentialError::MacCreation             => Status::MacCreation,
+            CredentialError::MacVerification         => Status::MacVerification,
+            CredentialError::MissingData             => Status::MissingData,
+            CredentialError::NoIssuerKey             => Status::NoIssuerKey,
+            CredentialError::NoIssuerParameters      => Status::NoIssuerParameters,
+            CredentialError::NoSystemParameters      => Status::NoSystemParameters,
+            CredentialError::PointDecompressionError => Status::PointDecompression,
+            CredentialError::ScalarFormatError       => Status::ScalarFormat,
+            CredentialError::WrongNumberOfAttributes => Status::WrongNumberOfAttributes,
+            CredentialError::WrongNumberOfBytes      => Status::WrongNumberOfBytes,
+            CredentialError::VerificationFailure     => Status::VerificationFailure,
+        }
+    }
+}
+
+impl From<MacError> for Status {
+    fn from(source: MacError) -> Status {
+        match source {
+            MacError::KeypairDeserialisation    => Status::KeypairDeserialisation,
+            MacError::PointDecompressionError   => Status::MacPointDecompression,
+            MacError::ScalarFormatError         => Status::MacScalarFormat,
+            MacError::MessageLengthError{ .. }  => Status::MessageLength,
+            MacError::AuthenticationError       => Status::MacAuthentication,
+        }
+    }
+}
+
+impl From<PhoneNumberError> for Status {
+    fn from(source: PhoneNumberError) -> Status {
+        match source {
+            PhoneNumberError::LengthExceeded     => Status::PhoneNumberLengthExceeded,
+            PhoneNumberError::InvalidPhoneNumber => Status::InvalidPhoneNumber,
+            PhoneNumberError::EmptyPhoneNumber   => Status::EmptyPhoneNumber,
+            PhoneNumberError::MissingPlusSign    => Status::MissingPlusSign,
+            PhoneNumberError::InvalidCharacter   => Status::InvalidCharacter,
+            PhoneNumberError::InvalidCountryCode => Status::InvalidCountryCode,
+            PhoneNumberError::TooFewDigits       => Status::TooFewDigits,
+            PhoneNumberError::TooManyDigits      => Status::TooManyDigits,
+        }
+    }
+}
+
+impl From<AccountIdentifierError> for Status {
+    fn from(source: AccountIdentifierError) -> Status {
+        match source {
+            AccountIdentifierError::InvalidPhoneNumber(x) => x.into(),
+            AccountIdentifierError::InvalidUsername       => Status::InvalidUsername,
+            AccountIdentifierError::WrongNumberOfBytes    => Status::WrongIdentifierLength,
+        }
+    }
+}
+
+impl From<VerificationError> for Status {
+    fn from(source: VerificationError) -> Status {
+        match source {
+            VerificationError::Unverified  => Status::Unverified,
+            VerificationError::Expired     => Status::VerificationExpired,
+            VerificationError::RateLimited => Status::VerificationRateLimited,
+        }
+    }
+}
+
+impl From<IssuanceError> for Status {
+    fn from(source: IssuanceError) -> Status {
+        match source {
+            IssuanceError::Verification(x)       => x.into(),
+            IssuanceError::Credential(x)         => x.into(),
+            IssuanceError::LimitExceeded{ .. }   => Status::IssuanceLimitExceeded,
+            IssuanceError::Storage               => Status::IssuanceStorage,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn status_codes_are_stable() {
+        assert_eq!(Status::Ok.code(), 0);
+        assert_eq!(Status::from(CredentialError::VerificationFailure).code(), 112);
+        assert_eq!(Status::from(MacError::MessageLengthError{ length: 32 }).code(), 203);
+        assert_eq!(Status::from(PhoneNumberError::TooManyDigits).code(), 307);
+        assert_eq!(Status::from(AccountIdentifierError::InvalidPhoneNumber(PhoneNumberError::EmptyPhoneNumber)),
+                   Status::EmptyPhoneNumber);
+    }
+}
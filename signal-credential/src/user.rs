@@ -12,14 +12,31 @@ use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
+use aeonflux::amacs;
 use aeonflux::elgamal::{self};
 use aeonflux::errors::CredentialError;
 use aeonflux::issuer::IssuerParameters;
+use aeonflux::linear_relations::Proof;
+use aeonflux::nonces::Ephemeral;
 use aeonflux::nonces::Nonces;
+use aeonflux::one_out_of_many;
 use aeonflux::parameters::NUMBER_OF_ATTRIBUTES;
 use aeonflux::parameters::SystemParameters;
 use aeonflux::user::User;
+use aeonflux::user::SIZEOF_USER;
+use aeonflux::pedersen;
+use aeonflux::proofs::committed_value_encrypted;
 use aeonflux::proofs::committed_values_equal;
+use aeonflux::proofs::encrypted_attribute;
+use aeonflux::proofs::issuance_blinded_renewal;
+use aeonflux::proofs::issuance_partially_blinded;
+use aeonflux::proofs::issuance_revealed_role;
+use aeonflux::proofs::valid_credential_renewal;
+use aeonflux::proofs::valid_credential_with_encryption;
+use aeonflux::proofs::valid_role_credential;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
 
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
@@ -29,31 +46,73 @@ use merlin::Transcript;
 use rand_core::RngCore;
 use rand_core::CryptoRng;
 
+use account_identifier::AccountIdentifier;
+use account_identifier::ToAccountIdentifier;
+use credential::SignalCredentialAnonymousPresentation;
+use credential::SignalCredentialEncryptedPresentation;
 use credential::SignalCredentialIssuance;
 use credential::SignalCredentialPresentation;
 use credential::SignalCredential;
-use phone_number::PhoneNumber;
+use phone_number::CommittedPhoneNumber;
 use phone_number::RosterEntryCommitment;
+use phone_number::RosterEntryEncryption;
+use profile_key::ProfileKey;
+use profile_key::ProfileKeyCredential;
+use profile_key::ProfileKeyCredentialIssuance;
+use profile_key::ProfileKeyCredentialPresentation;
+use profile_key::ProfileKeyCredentialRequest;
+use renewal::RenewalKey;
+use renewal::RenewalKeyCredential;
+use renewal::RenewalKeyCredentialIssuance;
+use renewal::RenewalKeyCredentialPresentation;
+use renewal::RenewalKeyCredentialRequest;
+use renewal::SignalCredentialRenewal;
+use renewal::SignalCredentialRenewalRequest;
+use renewal::renewal_key_issuance_statement;
+use renewal::renewal_tag_statement;
+use renewal::revealed_identifier;
+use role::Role;
+use role::RoleProof;
+use role::SignalRoleCredential;
+use role::SignalRoleCredentialIssuance;
+use role::SignalRoleCredentialPresentation;
+use roster::GroupRoster;
 
 /// DOCDOC
 #[derive(Debug, Eq, PartialEq)]
 pub struct SignalUser {
-    pub phone_number: PhoneNumber,
+    pub identifier: AccountIdentifier,
     pub user: User,
 }
 
 impl SignalUser {
+    /// Deserialise a `SignalUser` from the length of its serialised
+    /// identifier as a single byte, followed by the identifier and then the
+    /// `User`, which may be of an issuer on any number of attributes.
     pub fn from_bytes(bytes: &[u8]) -> Result<SignalUser, CredentialError> {
-        let phone_number = PhoneNumber::from_bytes(&bytes[00..32])?;
-        let user = User::from_bytes(&bytes[32..])?;
+        if bytes.len() < 1 + SIZEOF_USER {
+            return Err(CredentialError::MissingData);
+        }
+
+        let split: usize = 1 + bytes[0] as usize;
+
+        if bytes.len() < split + SIZEOF_USER {
+            return Err(CredentialError::MissingData);
+        }
 
-        Ok(SignalUser { phone_number, user })
+        let identifier = AccountIdentifier::from_bytes(&bytes[1..split])?;
+        let user = User::from_bytes(&bytes[split..])?;
+
+        Ok(SignalUser { identifier, user })
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
+        let identifier: Vec<u8> = self.identifier.to_bytes();
         let mut v: Vec<u8> = Vec::new();
 
-        v.extend(self.phone_number.to_bytes());
+        // Every serialised identifier is shorter than 256 bytes.
+        v.push(identifier.len() as u8);
+        v.extend(identifier);
         v.extend(self.user.to_bytes());
 
         v
@@ -65,21 +124,21 @@ impl_serde_with_to_bytes_and_from_bytes!(SignalUser,
 
 impl SignalUser {
     /// DOCDOC
-    pub fn new(
+    pub fn new<I>(
         system_parameters: SystemParameters,
         issuer_parameters: IssuerParameters,
         key: Option<elgamal::Keypair>,
-        phone_number: &[u8],
+        identifier: &I,
     ) -> Result<SignalUser, CredentialError>
+    where
+        I: ToAccountIdentifier + ?Sized,
     {
         let user = User::new(system_parameters, issuer_parameters, key);
-
-        // Map our phone number to a scalar mod ell.
-        let number: PhoneNumber = PhoneNumber::try_from_bytes(&phone_number)?;
+        let identifier: AccountIdentifier = identifier.to_account_identifier()?;
 
         Ok(SignalUser {
             user: user,
-            phone_number: number,
+            identifier: identifier,
         })
     }
 
@@ -92,6 +151,365 @@ impl SignalUser {
         self.user.obtain_finish(issuance)
     }
 
+    /// Request the renewal of our `SignalCredential`, without revealing our
+    /// identifier to the issuer, by presenting it together with our
+    /// `renewal_key_credential`, issued by the same issuer, whose public key
+    /// material for renewal key credentials is
+    /// `renewal_key_issuer_parameters`.
+    ///
+    /// The request carries our renewal tag for the `epoch`, which must be the
+    /// issuer's current epoch, e.g. as given by `RenewalLimiter::epoch()`.
+    /// See the `renewal` module for what the tag reveals.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no credential or no
+    ///   elGamal `key`.
+    /// * `CredentialError::NoIssuerParameters` if our issuer parameters are
+    ///   not for a single attribute, or the `renewal_key_issuer_parameters`
+    ///   are not for two attributes.
+    /// * `CredentialError::BadAttribute` if the `renewal_key_credential` is
+    ///   not on the identifier in our credential.
+    pub fn request_renewal<R>(
+        &self,
+        renewal_key_credential: &RenewalKeyCredential,
+        renewal_key_issuer_parameters: &IssuerParameters,
+        epoch: u64,
+        rng: &mut R,
+    ) -> Result<SignalCredentialRenewalRequest, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &SignalCredential = match self.user.credential {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        let key: &elgamal::Keypair = match self.user.key {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        if self.user.issuer_parameters.Xn.len() != NUMBER_OF_ATTRIBUTES ||
+            credential.attributes.len() != NUMBER_OF_ATTRIBUTES ||
+            renewal_key_issuer_parameters.Xn.len() != 2
+        {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+        if renewal_key_credential.identifier != credential.attributes[0] {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let mut transcript = Transcript::new(b"SIGNAL CREDENTIAL RENEWAL REQUEST");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let A: &RistrettoPoint = &self.user.system_parameters.h;
+        let B: &RistrettoPoint = &self.user.system_parameters.g;
+        let X0: &RistrettoPoint = &self.user.issuer_parameters.Xn[0];
+
+        // Rerandomise the aMAC to prevent trivial linkages.
+        let r: Scalar = Scalar::random(&mut csprng);
+        let P: RistrettoPoint = credential.mac.nonce * r;
+        let Q: RistrettoPoint = credential.mac.mac * r;
+
+        let m0: Scalar = credential.attributes[0];
+        let z0: Ephemeral = Ephemeral::new(&mut csprng);
+        let zQ: Ephemeral = Ephemeral::new(&mut csprng);
+        let e: Ephemeral = Ephemeral::new(&mut csprng);
+
+        // Commit to the identifier and the rerandomised aMAC, and encrypt the
+        // identifier to ourselves.
+        let identifier_commitment = pedersen::Commitment::to(&(P * m0), &z0, A);
+        let rerandomized_mac_commitment = pedersen::Commitment::to(&Q, &zQ, A);
+        let encrypted_identifier = key.public.encrypt(&(&m0).into(), &e);
+
+        // Calculate the error factor.
+        let V: RistrettoPoint = X0 * &z0 - A * &zQ;
+        let minus_zQ = -zQ;
+
+        let secrets = valid_credential_renewal::Secrets {
+            m0: &m0,
+            z0: (&z0).into(),
+            minus_zQ: (&minus_zQ).into(),
+            d: &key.secret.clone().into(),
+            e: (&e).into(),
+        };
+        let publics = valid_credential_renewal::Publics {
+            B: B,
+            A: A,
+            X0: X0,
+            P: &P,
+            V: &V,
+            Cm0: &identifier_commitment.into(),
+            D: &key.public.into(),
+            E1: &encrypted_identifier.commitment,
+            E2: &encrypted_identifier.encryption,
+        };
+        let proof = valid_credential_renewal::Proof::create(&mut transcript, publics, secrets, &mut csprng);
+
+        // Likewise present our renewal key credential, with both attributes
+        // hidden, and encrypt our renewal key to ourselves.
+        let X1: &RistrettoPoint = &renewal_key_issuer_parameters.Xn[0];
+        let X2: &RistrettoPoint = &renewal_key_issuer_parameters.Xn[1];
+        let r_k: Scalar = Scalar::random(&mut csprng);
+        let P_k: RistrettoPoint = renewal_key_credential.mac.nonce * r_k;
+        let Q_k: RistrettoPoint = renewal_key_credential.mac.mac * r_k;
+
+        let k: Scalar = renewal_key_credential.renewal_key.0;
+        let z1: Ephemeral = Ephemeral::new(&mut csprng);
+        let z2: Ephemeral = Ephemeral::new(&mut csprng);
+        let zQ_k: Ephemeral = Ephemeral::new(&mut csprng);
+        let f: Ephemeral = Ephemeral::new(&mut csprng);
+
+        // Compute our renewal tag for this epoch, and prove that it is of the
+        // renewal key in a credential on the identifier we encrypted,
+        // continuing the same transcript.
+        let renewal_key = RenewalKeyCredentialPresentation {
+            rerandomized_nonce: P_k,
+            rerandomized_mac_commitment: pedersen::Commitment::to(&Q_k, &zQ_k, A),
+            identifier_commitment: pedersen::Commitment::to(&(P_k * m0), &z1, A),
+            renewal_key_commitment: pedersen::Commitment::to(&(P_k * k), &z2, A),
+            encrypted_renewal_key: renewal_key_credential.renewal_key.encrypt(&key.public, &f),
+            tag: renewal_key_credential.renewal_key.tag(epoch),
+        };
+        let V_k: RistrettoPoint = X1 * &z1 + X2 * &z2 - A * &zQ_k;
+        let minus_zQ_k = -zQ_k;
+        let statement = renewal_tag_statement(&self.user.system_parameters, renewal_key_issuer_parameters,
+                                              &V_k, &key.public, &encrypted_identifier, &renewal_key, epoch);
+        let secrets = [m0, k, *<&Scalar>::from(&e), *<&Scalar>::from(&f),
+                       *<&Scalar>::from(&z1), *<&Scalar>::from(&z2), *<&Scalar>::from(&minus_zQ_k)];
+        let renewal_key_proof = match Proof::create(&mut transcript, &statement, &secrets, &mut csprng) {
+            Ok(x)   => x,
+            Err(_)  => return Err(CredentialError::MacCreation),
+        };
+
+        Ok(SignalCredentialRenewalRequest {
+            public_key: key.public,
+            rerandomized_nonce: P,
+            rerandomized_mac_commitment,
+            identifier_commitment,
+            encrypted_identifier,
+            proof,
+            renewal_key,
+            renewal_key_proof,
+        })
+    }
+
+    /// Check a `SignalCredentialRenewal` in response to our `request`, made
+    /// with our `renewal_key_credential`, from an issuer whose current public
+    /// key material is `issuer_parameters`, and for renewal key credentials
+    /// `renewal_key_issuer_parameters`.  Replace our credential with the
+    /// renewed one, and decrypt our renewed renewal key credential.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no credential or no
+    ///   elGamal `key`.
+    /// * `CredentialError::NoIssuerParameters` if the `issuer_parameters` are
+    ///   not for a single attribute, or the `renewal_key_issuer_parameters`
+    ///   are not for two attributes.
+    /// * `CredentialError::BadAttribute` if the `request` was not for the
+    ///   identifier in our credential and the renewal key in our
+    ///   `renewal_key_credential`.
+    /// * `CredentialError::CredentialIssuance` if the proof of correct
+    ///   issuance of either credential couldn't be verified.
+    pub fn obtain_renewal(
+        &mut self,
+        renewal_key_credential: &RenewalKeyCredential,
+        request: &SignalCredentialRenewalRequest,
+        renewal: &SignalCredentialRenewal,
+        issuer_parameters: IssuerParameters,
+        renewal_key_issuer_parameters: &IssuerParameters,
+    ) -> Result<RenewalKeyCredential, CredentialError>
+    {
+        let identifier: Scalar = match self.user.credential {
+            Some(ref x) if x.attributes.len() == NUMBER_OF_ATTRIBUTES => x.attributes[0],
+            _ => return Err(CredentialError::MissingData),
+        };
+        let key: &elgamal::Keypair = match self.user.key {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        if issuer_parameters.Xn.len() != NUMBER_OF_ATTRIBUTES || renewal_key_issuer_parameters.Xn.len() != 2 {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+        let message: elgamal::Message = (&identifier).into();
+        let renewal_key: &RenewalKey = &renewal_key_credential.renewal_key;
+
+        if request.public_key != key.public ||
+            key.secret.decrypt(&request.encrypted_identifier) != RistrettoPoint::from(message) ||
+            !renewal_key.is_plaintext_of(&request.renewal_key.encrypted_renewal_key, &key.secret)
+        {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let P: &RistrettoPoint = &renewal.nonce;
+
+        let mut transcript = Transcript::new(b"SIGNAL CREDENTIAL RENEWAL");
+        let publics = issuance_blinded_renewal::Publics {
+            B: &self.user.system_parameters.g,
+            A: &self.user.system_parameters.h,
+            Cx0: &renewal.secret_key_commitment.into(),
+            X1: &issuer_parameters.Xn[0],
+            P: P,
+            T0: &renewal.auxiliary_commitment,
+            T1: &renewal.auxiliary_commitment,
+            D: &key.public.into(),
+            E1: &request.encrypted_identifier.commitment,
+            E2: &request.encrypted_identifier.encryption,
+            EQ1: &renewal.encrypted_mac.commitment,
+            EQ2: &renewal.encrypted_mac.encryption,
+        };
+
+        if renewal.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::CredentialIssuance);
+        }
+
+        let renewed_renewal_key_credential = self.obtain_blinded_renewal_key_credential(
+            identifier, renewal_key, &request.encrypted_identifier,
+            &request.renewal_key.encrypted_renewal_key, &renewal.renewal_key, renewal_key_issuer_parameters)?;
+
+        // Decrypt the MAC of the tag.
+        let tag = amacs::Tag {
+            nonce: *P,
+            mac: key.secret.decrypt(&renewal.encrypted_mac),
+        };
+        let mut attributes: Vec<Scalar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        attributes.push(identifier);
+
+        self.user.credential = Some(SignalCredential { mac: tag, attributes, point_attributes: Vec::new() });
+        self.user.issuer_parameters = issuer_parameters;
+
+        Ok(renewed_renewal_key_credential)
+    }
+
+    /// Request a `RenewalKeyCredential` on our identifier and our
+    /// `renewal_key`, which is blinded by encrypting it to our elGamal `key`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no elGamal `key`.
+    pub fn request_renewal_key_credential<R>(
+        &self,
+        renewal_key: &RenewalKey,
+        rng: &mut R,
+    ) -> Result<RenewalKeyCredentialRequest, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let key: &elgamal::Keypair = match self.user.key {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        let mut transcript = Transcript::new(b"SIGNAL RENEWAL KEY REQUEST");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let f: Ephemeral = Ephemeral::new(&mut csprng);
+        let encrypted_renewal_key = renewal_key.encrypt(&key.public, &f);
+
+        let secrets = encrypted_attribute::Secrets {
+            d: &key.secret.clone().into(),
+            e: (&f).into(),
+            m: &renewal_key.0,
+        };
+        let publics = encrypted_attribute::Publics {
+            B: &self.user.system_parameters.g,
+            D: &key.public.into(),
+            E1: &encrypted_renewal_key.commitment,
+            E2: &encrypted_renewal_key.encryption,
+        };
+        let proof = encrypted_attribute::Proof::create(&mut transcript, publics, secrets, &mut csprng);
+
+        Ok(RenewalKeyCredentialRequest {
+            identifier: self.identifier.clone(),
+            public_key: key.public,
+            encrypted_renewal_key,
+            proof,
+        })
+    }
+
+    /// Check a `RenewalKeyCredentialIssuance` in response to our `request`,
+    /// from an issuer whose public key material for renewal key credentials
+    /// is `renewal_key_issuer_parameters`, and decrypt our credential.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no elGamal `key`.
+    /// * `CredentialError::NoIssuerParameters` if the
+    ///   `renewal_key_issuer_parameters` are not for two attributes.
+    /// * `CredentialError::BadAttribute` if the `request` was not for our
+    ///   identifier and `renewal_key`.
+    /// * `CredentialError::CredentialIssuance` if the proof of correct
+    ///   issuance couldn't be verified.
+    pub fn obtain_renewal_key_credential(
+        &self,
+        renewal_key: &RenewalKey,
+        request: &RenewalKeyCredentialRequest,
+        issuance: &RenewalKeyCredentialIssuance,
+        renewal_key_issuer_parameters: &IssuerParameters,
+    ) -> Result<RenewalKeyCredential, CredentialError>
+    {
+        let key: &elgamal::Keypair = match self.user.key {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        if renewal_key_issuer_parameters.Xn.len() != 2 {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+        if request.identifier != self.identifier || request.public_key != key.public ||
+            !renewal_key.is_plaintext_of(&request.encrypted_renewal_key, &key.secret)
+        {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let identifier: Scalar = self.identifier.to_scalar();
+        let encrypted_identifier = revealed_identifier(&self.user.system_parameters, &identifier);
+
+        self.obtain_blinded_renewal_key_credential(identifier, renewal_key, &encrypted_identifier,
+                                                   &request.encrypted_renewal_key, issuance,
+                                                   renewal_key_issuer_parameters)
+    }
+
+    /// Check a `RenewalKeyCredentialIssuance` over the `encrypted_identifier`
+    /// and `encrypted_renewal_key`, which are of our `identifier` and
+    /// `renewal_key` encrypted to our elGamal `key`, and decrypt the
+    /// credential.
+    fn obtain_blinded_renewal_key_credential(
+        &self,
+        identifier: Scalar,
+        renewal_key: &RenewalKey,
+        encrypted_identifier: &elgamal::Encryption,
+        encrypted_renewal_key: &elgamal::Encryption,
+        issuance: &RenewalKeyCredentialIssuance,
+        renewal_key_issuer_parameters: &IssuerParameters,
+    ) -> Result<RenewalKeyCredential, CredentialError>
+    {
+        let key: &elgamal::Keypair = match self.user.key {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        let statement = renewal_key_issuance_statement(
+            &self.user.system_parameters, renewal_key_issuer_parameters,
+            &issuance.publics(&key.public, encrypted_identifier, encrypted_renewal_key));
+
+        let mut transcript = Transcript::new(b"SIGNAL RENEWAL KEY ISSUANCE");
+
+        if issuance.proof.verify(&mut transcript, &statement).is_err() {
+            return Err(CredentialError::CredentialIssuance);
+        }
+
+        // Decrypt the MAC of the tag.
+        let tag = amacs::Tag {
+            nonce: issuance.nonce,
+            mac: key.secret.decrypt(&issuance.encrypted_mac),
+        };
+
+        Ok(RenewalKeyCredential {
+            mac: tag,
+            identifier,
+            renewal_key: *renewal_key,
+        })
+    }
+
     /// Prove that this credential is valid and show proof of membership in a
     /// roster of signal group users.
     ///
@@ -130,7 +548,8 @@ impl SignalUser {
         };
         let roster_membership_proof = committed_values_equal::Proof::create(&mut roster_membership_transcript,
                                                                             roster_membership_publics,
-                                                                            roster_membership_secrets);
+                                                                            roster_membership_secrets,
+                                                                            rng);
 
         Ok(SignalCredentialPresentation {
             presentation: presentation,
@@ -138,6 +557,496 @@ impl SignalUser {
             roster_membership_proof: roster_membership_proof,
         })
     }
+
+    /// Prove that this credential is valid and that its phone number is in
+    /// some entry of a group `roster`, without revealing which.
+    ///
+    /// Unlike `SignalUser::show()`, the presentation does not contain our
+    /// `roster_entry_commitment`, and so presentations by the same member are
+    /// unlinkable to each other.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no credential, or if our
+    ///   `roster_entry_commitment` is not in the `roster`.
+    pub fn show_anonymously<R>(
+        &self,
+        rng: &mut R,
+        roster: &GroupRoster,
+        roster_entry_commitment: &RosterEntryCommitment,
+    ) -> Result<SignalCredentialAnonymousPresentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &SignalCredential = match self.user.credential {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        let index: usize = match roster.position(&roster_entry_commitment.commitment) {
+            Some(x) => x,
+            None    => return Err(CredentialError::MissingData),
+        };
+        let nonces = Nonces::new(rng, NUMBER_OF_ATTRIBUTES);
+        let presentation = self.user.show(&nonces, rng)?;
+
+        let A: &RistrettoPoint = &self.user.system_parameters.h;
+        let B: &RistrettoPoint = &self.user.system_parameters.g;
+
+        // Rerandomise our roster entry, C = m A + r B, into C' = m A + z B.
+        let z: Ephemeral = Ephemeral::new(rng);
+        let rerandomized_roster_entry = CommittedPhoneNumber(
+            pedersen::Commitment::to(&(&credential.attributes[0] * A), &z, B));
+
+        let mut transcript = Transcript::new(b"SIGNAL ANONYMOUS GROUP MEMBERSHIP");
+        let roster_membership_secrets = committed_values_equal::Secrets {
+            m0: &credential.attributes[0],
+            z0: (&nonces[0]).into(),
+            z1: (&z).into(),
+        };
+        let roster_membership_publics = committed_values_equal::Publics {
+            B: B,
+            A: A,
+            P: &presentation.rerandomized_nonce.clone(),
+            Cm0: &presentation.attributes_blinded[0].into(),
+            Cm1: &rerandomized_roster_entry.0.into(),
+        };
+        let roster_membership_proof = committed_values_equal::Proof::create(&mut transcript,
+                                                                            roster_membership_publics,
+                                                                            roster_membership_secrets,
+                                                                            rng);
+
+        // Each C_i - C' is a commitment to zero only for our entry, where it
+        // is (r - z) B.
+        let C_prime: RistrettoPoint = rerandomized_roster_entry.0.into();
+        let differences: Vec<RistrettoPoint> = roster.to_points().iter().map(|C| C - C_prime).collect();
+        let r: &Scalar = (&roster_entry_commitment.opening).into();
+        let z: &Scalar = (&z).into();
+        let roster_entry_proof = match one_out_of_many::Proof::create(&mut transcript, A, B, &differences,
+                                                                      index, &(r - z), rng) {
+            Ok(x)  => x,
+            Err(_) => return Err(CredentialError::MissingData),
+        };
+
+        Ok(SignalCredentialAnonymousPresentation {
+            rerandomized_roster_entry,
+            presentation,
+            roster_membership_proof,
+            roster_entry_proof,
+        })
+    }
+
+    /// Prove that this credential is valid and that its phone number is the
+    /// one in our `roster_entry`, which is encrypted to the group's
+    /// `public_key`.
+    ///
+    /// This is for groups with an `EncryptedGroupRoster`, which the group's
+    /// members may decrypt locally.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no credential.
+    pub fn show_encrypted<R>(
+        &self,
+        rng: &mut R,
+        roster_entry: &RosterEntryEncryption,
+        public_key: &elgamal::PublicKey,
+    ) -> Result<SignalCredentialEncryptedPresentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &SignalCredential = match self.user.credential {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        let nonces = Nonces::new(rng, NUMBER_OF_ATTRIBUTES);
+        let presentation = self.user.show(&nonces, rng)?;
+
+        let mut transcript = Transcript::new(b"SIGNAL ENCRYPTED GROUP MEMBERSHIP");
+        let roster_membership_secrets = committed_value_encrypted::Secrets {
+            m0: &credential.attributes[0],
+            z0: (&nonces[0]).into(),
+            e: (&roster_entry.nonce).into(),
+        };
+        let roster_membership_publics = committed_value_encrypted::Publics {
+            B: &self.user.system_parameters.g,
+            A: &self.user.system_parameters.h,
+            D: &(*public_key).into(),
+            P: &presentation.rerandomized_nonce.clone(),
+            Cm0: &presentation.attributes_blinded[0].into(),
+            E1: &roster_entry.encryption.0.commitment,
+            E2: &roster_entry.encryption.0.encryption,
+        };
+        let roster_membership_proof = committed_value_encrypted::Proof::create(&mut transcript,
+                                                                               roster_membership_publics,
+                                                                               roster_membership_secrets,
+                                                                               rng);
+
+        Ok(SignalCredentialEncryptedPresentation {
+            roster_entry: roster_entry.encryption,
+            presentation,
+            roster_membership_proof,
+        })
+    }
+
+    /// Request a `ProfileKeyCredential` on our identifier and our
+    /// `profile_key`, which is blinded by encrypting it to our elGamal `key`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no elGamal `key`.
+    pub fn request_profile_key_credential<R>(
+        &self,
+        profile_key: &ProfileKey,
+        rng: &mut R,
+    ) -> Result<ProfileKeyCredentialRequest, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let key: &elgamal::Keypair = match self.user.key {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        let mut transcript = Transcript::new(b"SIGNAL PROFILE KEY REQUEST");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let e: Ephemeral = Ephemeral::new(&mut csprng);
+        let encrypted_profile_key = profile_key.encrypt(&key.public, &e);
+
+        let secrets = encrypted_attribute::Secrets {
+            d: &key.secret.clone().into(),
+            e: (&e).into(),
+            m: &profile_key.to_scalar(),
+        };
+        let publics = encrypted_attribute::Publics {
+            B: &self.user.system_parameters.g,
+            D: &key.public.into(),
+            E1: &encrypted_profile_key.0.commitment,
+            E2: &encrypted_profile_key.0.encryption,
+        };
+        let proof = encrypted_attribute::Proof::create(&mut transcript, publics, secrets, &mut csprng);
+
+        Ok(ProfileKeyCredentialRequest {
+            identifier: self.identifier.clone(),
+            public_key: key.public,
+            encrypted_profile_key,
+            proof,
+        })
+    }
+
+    /// Check a `ProfileKeyCredentialIssuance` in response to our `request`,
+    /// from an issuer whose public key material for profile key credentials
+    /// is `profile_key_issuer_parameters`, and decrypt our credential.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no elGamal `key`.
+    /// * `CredentialError::NoIssuerParameters` if the
+    ///   `profile_key_issuer_parameters` are not for two attributes.
+    /// * `CredentialError::BadAttribute` if the `request` was not for our
+    ///   identifier and `profile_key`.
+    /// * `CredentialError::CredentialIssuance` if the proof of correct
+    ///   issuance couldn't be verified.
+    pub fn obtain_profile_key_credential(
+        &self,
+        profile_key: &ProfileKey,
+        request: &ProfileKeyCredentialRequest,
+        issuance: &ProfileKeyCredentialIssuance,
+        profile_key_issuer_parameters: &IssuerParameters,
+    ) -> Result<ProfileKeyCredential, CredentialError>
+    {
+        let key: &elgamal::Keypair = match self.user.key {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData),
+        };
+        if profile_key_issuer_parameters.Xn.len() != 2 {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+        if request.identifier != self.identifier || request.public_key != key.public ||
+            !request.encrypted_profile_key.is_encryption_of(profile_key, &key.secret)
+        {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let identifier: Scalar = self.identifier.to_scalar();
+        let P: &RistrettoPoint = &issuance.nonce;
+
+        let mut transcript = Transcript::new(b"SIGNAL PROFILE KEY ISSUANCE");
+        let publics = issuance_partially_blinded::Publics {
+            B: &self.user.system_parameters.g,
+            A: &self.user.system_parameters.h,
+            Cx0: &issuance.secret_key_commitment.into(),
+            X1: &profile_key_issuer_parameters.Xn[0],
+            X2: &profile_key_issuer_parameters.Xn[1],
+            P: P,
+            T0: &issuance.auxiliary_commitment,
+            T1: &issuance.auxiliary_commitment,
+            D: &key.public.into(),
+            E1: &request.encrypted_profile_key.0.commitment,
+            E2: &request.encrypted_profile_key.0.encryption,
+            M1: &(P * identifier),
+            EQ1: &issuance.encrypted_mac.commitment,
+            EQ2: &issuance.encrypted_mac.encryption,
+        };
+
+        if issuance.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::CredentialIssuance);
+        }
+
+        // Decrypt the MAC of the tag.
+        let tag = amacs::Tag {
+            nonce: *P,
+            mac: key.secret.decrypt(&issuance.encrypted_mac),
+        };
+
+        Ok(ProfileKeyCredential {
+            mac: tag,
+            identifier: identifier,
+            profile_key: *profile_key,
+        })
+    }
+
+    /// Prove that our profile key `credential` is valid, and that an
+    /// encryption of our profile key to a group's `public_key` is of the
+    /// profile key in the credential, without revealing our identifier.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerParameters` if the
+    ///   `profile_key_issuer_parameters` are not for two attributes.
+    pub fn show_profile_key<R>(
+        &self,
+        rng: &mut R,
+        credential: &ProfileKeyCredential,
+        public_key: &elgamal::PublicKey,
+        profile_key_issuer_parameters: &IssuerParameters,
+    ) -> Result<ProfileKeyCredentialPresentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if profile_key_issuer_parameters.Xn.len() != 2 {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+
+        let mut transcript = Transcript::new(b"SIGNAL PROFILE KEY PRESENTATION");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let A: &RistrettoPoint = &self.user.system_parameters.h;
+        let B: &RistrettoPoint = &self.user.system_parameters.g;
+        let X1: &RistrettoPoint = &profile_key_issuer_parameters.Xn[0];
+        let X2: &RistrettoPoint = &profile_key_issuer_parameters.Xn[1];
+
+        // Rerandomise the aMAC to prevent trivial linkages.
+        let r: Scalar = Scalar::random(&mut csprng);
+        let P: RistrettoPoint = credential.mac.nonce * r;
+        let Q: RistrettoPoint = credential.mac.mac * r;
+
+        let m1: Scalar = credential.identifier;
+        let m2: Scalar = credential.profile_key.to_scalar();
+        let z1: Ephemeral = Ephemeral::new(&mut csprng);
+        let z2: Ephemeral = Ephemeral::new(&mut csprng);
+        let zQ: Ephemeral = Ephemeral::new(&mut csprng);
+        let k: Ephemeral = Ephemeral::new(&mut csprng);
+
+        // Commit to the hidden attributes and the rerandomised aMAC, and
+        // encrypt the profile key to the group.
+        let identifier_commitment = pedersen::Commitment::to(&(P * m1), &z1, A);
+        let profile_key_commitment = pedersen::Commitment::to(&(P * m2), &z2, A);
+        let rerandomized_mac_commitment = pedersen::Commitment::to(&Q, &zQ, A);
+        let encrypted_profile_key = credential.profile_key.encrypt(public_key, &k);
+
+        // Calculate the error factor.
+        let V: RistrettoPoint = X1 * &z1 + X2 * &z2 - A * &zQ;
+        let minus_zQ = -zQ;
+
+        let secrets = valid_credential_with_encryption::Secrets {
+            m1: &m1,
+            m2: &m2,
+            z1: (&z1).into(),
+            z2: (&z2).into(),
+            minus_zQ: (&minus_zQ).into(),
+            k: (&k).into(),
+        };
+        let publics = valid_credential_with_encryption::Publics {
+            B: B,
+            A: A,
+            X1: X1,
+            X2: X2,
+            P: &P,
+            V: &V,
+            Cm1: &identifier_commitment.into(),
+            Cm2: &profile_key_commitment.into(),
+            D: &(*public_key).into(),
+            K1: &encrypted_profile_key.0.commitment,
+            K2: &encrypted_profile_key.0.encryption,
+        };
+        let proof = valid_credential_with_encryption::Proof::create(&mut transcript, publics, secrets, &mut csprng);
+
+        Ok(ProfileKeyCredentialPresentation {
+            rerandomized_nonce: P,
+            rerandomized_mac_commitment,
+            identifier_commitment,
+            profile_key_commitment,
+            encrypted_profile_key,
+            proof,
+        })
+    }
+
+    /// Check a `SignalRoleCredentialIssuance` from an issuer whose public key
+    /// material for role credentials is `role_issuer_parameters`, and that the
+    /// credential is for our identifier.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerParameters` if the `role_issuer_parameters`
+    ///   are not for two attributes.
+    /// * `CredentialError::BadAttribute` if the credential is for some other
+    ///   identifier.
+    /// * `CredentialError::CredentialIssuance` if the proof of correct
+    ///   issuance couldn't be verified.
+    pub fn obtain_role_finish(
+        &self,
+        issuance: &SignalRoleCredentialIssuance,
+        role_issuer_parameters: &IssuerParameters,
+    ) -> Result<SignalRoleCredential, CredentialError>
+    {
+        if role_issuer_parameters.Xn.len() != 2 {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+
+        let credential: &SignalRoleCredential = &issuance.credential;
+
+        if credential.identifier != self.identifier.to_scalar() {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let mut transcript = Transcript::new(b"SIGNAL ROLE ISSUANCE");
+        let P: &RistrettoPoint = &credential.mac.nonce;
+        let publics = issuance_revealed_role::Publics {
+            P: P,
+            Q: &credential.mac.mac,
+            Cx0: &issuance.secret_key_commitment.into(),
+            B: &self.user.system_parameters.g,
+            A: &self.user.system_parameters.h,
+            X1: &role_issuer_parameters.Xn[0],
+            X2: &role_issuer_parameters.Xn[1],
+            M1: &(P * credential.identifier),
+            M2: &(P * credential.role.to_scalar()),
+        };
+
+        if issuance.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::CredentialIssuance);
+        }
+
+        Ok(credential.clone())
+    }
+
+    /// Prove that our role `credential` is valid, that our role in the group
+    /// is at least the `minimum`, and that our identifier is committed to in
+    /// our `roster_entry_commitment`, without revealing either our identifier
+    /// or our exact role.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerParameters` if the `role_issuer_parameters`
+    ///   are not for two attributes.
+    /// * `CredentialError::BadAttribute` if our role is less than the
+    ///   `minimum`.
+    pub fn show_role<R>(
+        &self,
+        rng: &mut R,
+        credential: &SignalRoleCredential,
+        minimum: Role,
+        roster_entry_commitment: &RosterEntryCommitment,
+        role_issuer_parameters: &IssuerParameters,
+    ) -> Result<SignalRoleCredentialPresentation, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if role_issuer_parameters.Xn.len() != 2 {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+        if credential.role < minimum {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let mut transcript = Transcript::new(b"SIGNAL ROLE PRESENTATION");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let A: &RistrettoPoint = &self.user.system_parameters.h;
+        let B: &RistrettoPoint = &self.user.system_parameters.g;
+        let X1: &RistrettoPoint = &role_issuer_parameters.Xn[0];
+        let X2: &RistrettoPoint = &role_issuer_parameters.Xn[1];
+
+        // Rerandomise the aMAC to prevent trivial linkages.
+        let r: Scalar = Scalar::random(&mut csprng);
+        let P: RistrettoPoint = credential.mac.nonce * r;
+        let Q: RistrettoPoint = credential.mac.mac * r;
+
+        let m1: Scalar = credential.identifier;
+        let m2: Scalar = credential.role.to_scalar();
+        let z1: Ephemeral = Ephemeral::new(&mut csprng);
+        let z2: Ephemeral = Ephemeral::new(&mut csprng);
+        let zQ: Ephemeral = Ephemeral::new(&mut csprng);
+
+        // Commit to the hidden attributes and the rerandomised aMAC.
+        let identifier_commitment = pedersen::Commitment::to(&(P * m1), &z1, A);
+        let role_commitment = pedersen::Commitment::to(&(P * m2), &z2, A);
+        let rerandomized_mac_commitment = pedersen::Commitment::to(&Q, &zQ, A);
+
+        // Calculate the error factor.
+        let V: RistrettoPoint = X1 * &z1 + X2 * &z2 - A * &zQ;
+        let minus_zQ = -zQ;
+
+        let secrets = valid_role_credential::Secrets {
+            m1: &m1,
+            m2: &m2,
+            z1: (&z1).into(),
+            z2: (&z2).into(),
+            minus_zQ: (&minus_zQ).into(),
+        };
+        let publics = valid_role_credential::Publics {
+            B: B,
+            A: A,
+            X1: X1,
+            X2: X2,
+            P: &P,
+            V: &V,
+            Cm1: &identifier_commitment.into(),
+            Cm2: &role_commitment.into(),
+        };
+        let proof = valid_role_credential::Proof::create(&mut transcript, publics, secrets, &mut csprng);
+
+        let role_proof = RoleProof::create(&mut transcript, A, &P, &role_commitment.into(), minimum,
+                                           credential.role, (&z2).into(), &mut csprng)?;
+
+        let roster_membership_secrets = committed_values_equal::Secrets {
+            m0: &m1,
+            z0: (&z1).into(),
+            z1: (&roster_entry_commitment.opening).into(),
+        };
+        let roster_membership_publics = committed_values_equal::Publics {
+            B: B,
+            A: A,
+            P: &P,
+            Cm0: &identifier_commitment.into(),
+            Cm1: &roster_entry_commitment.commitment.0.into(),
+        };
+        let roster_membership_proof = committed_values_equal::Proof::create(&mut transcript,
+                                                                            roster_membership_publics,
+                                                                            roster_membership_secrets,
+                                                                            &mut csprng);
+
+        Ok(SignalRoleCredentialPresentation {
+            roster_entry_commitment: roster_entry_commitment.commitment,
+            rerandomized_nonce: P,
+            rerandomized_mac_commitment,
+            identifier_commitment,
+            role_commitment,
+            proof,
+            role_proof,
+            roster_membership_proof,
+        })
+    }
 }
 
 impl SignalUser {
@@ -258,6 +1167,9 @@ impl SignalUser {
 mod test {
     use super::*;
 
+    use aeonflux::issuer::Issuer;
+
+    use account_identifier::SIZEOF_UUID;
     use issuer::SignalIssuer;
 
     use rand::thread_rng;
@@ -285,4 +1197,23 @@ mod test {
 
         assert!(deserialized == alice);
     }
+
+    #[test]
+    fn signal_user_of_any_issuer_serialize_deserialize() {
+        let mut issuer_rng = thread_rng();
+
+        // Neither the identifier nor the user is of a fixed length.
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create_with_attributes(system_parameters, 3, 0, &mut issuer_rng).unwrap();
+        let alice: SignalUser = SignalUser {
+            identifier: AccountIdentifier::Uuid([7u8; SIZEOF_UUID]),
+            user: User::new(system_parameters, issuer.get_issuer_parameters(), None),
+        };
+
+        let serialized = alice.to_bytes();
+        let deserialized = SignalUser::from_bytes(&serialized).unwrap();
+
+        assert!(deserialized == alice);
+        assert!(SignalUser::from_bytes(&serialized[1..]).is_err());
+    }
 }
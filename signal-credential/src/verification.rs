@@ -0,0 +1,185 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Verification that a user owns the phone number they request a credential
+//! for, prior to a `SignalIssuer` issuing it.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use subtle::ConstantTimeEq;
+
+use account_identifier::AccountIdentifier;
+use errors::VerificationError;
+
+/// A service which checks that a user owns their `AccountIdentifier`, for
+/// example by having previously sent a code to a phone number via SMS or a
+/// voice call and then comparing it to the code the user supplies.
+///
+/// A `SignalIssuer` consults a `PhoneNumberVerifier` in
+/// `SignalIssuer::issue_verified()` and
+/// `SignalIssuer::issue_verified_and_rate_limited()` before issuing a
+/// credential.
+pub trait PhoneNumberVerifier {
+    /// Check that `code` proves ownership of the `identifier`.
+    ///
+    /// # Errors
+    ///
+    /// * `VerificationError::Unverified` if no code is pending for the
+    ///   `identifier` or the `code` is incorrect,
+    /// * `VerificationError::Expired` if the pending code has expired,
+    /// * `VerificationError::RateLimited` if too many attempts have been made
+    ///   to verify the `identifier`.
+    fn verify(&mut self, identifier: &AccountIdentifier, code: &[u8]) -> Result<(), VerificationError>;
+}
+
+/// A verification code which has been sent to a user but not yet checked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct PendingCode {
+    identifier: AccountIdentifier,
+    code: Vec<u8>,
+    expiry: u64,
+    failed_attempts: u32,
+}
+
+/// A local, in-memory `PhoneNumberVerifier` which records pending codes
+/// rather than sending them anywhere, for use in tests.
+///
+/// Time is measured by a logical clock, in seconds, which is only moved
+/// forward by calling `InMemoryPhoneNumberVerifier::advance()`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct InMemoryPhoneNumberVerifier {
+    pending: Vec<PendingCode>,
+    now: u64,
+    lifetime: u64,
+    max_attempts: u32,
+}
+
+impl InMemoryPhoneNumberVerifier {
+    /// Create a new verifier whose codes expire after `lifetime` seconds and
+    /// which permits `max_attempts` incorrect guesses per identifier.
+    pub fn new(lifetime: u64, max_attempts: u32) -> InMemoryPhoneNumberVerifier {
+        InMemoryPhoneNumberVerifier {
+            pending: Vec::new(),
+            now: 0,
+            lifetime,
+            max_attempts,
+        }
+    }
+
+    /// Record that `code` was sent to the owner of `identifier`.
+    ///
+    /// Any code previously pending for the `identifier` is replaced, but its
+    /// count of failed attempts is kept, so that requesting a new code does
+    /// not reset the rate limit.
+    pub fn add_pending(&mut self, identifier: &AccountIdentifier, code: &[u8]) {
+        let expiry: u64 = self.now.saturating_add(self.lifetime);
+
+        if let Some(entry) = self.pending.iter_mut().find(|x| &x.identifier == identifier) {
+            entry.code = code.to_vec();
+            entry.expiry = expiry;
+            return;
+        }
+        self.pending.push(PendingCode {
+            identifier: identifier.clone(),
+            code: code.to_vec(),
+            expiry,
+            failed_attempts: 0,
+        });
+    }
+
+    /// Determine whether a code is pending for the `identifier`.
+    pub fn is_pending(&self, identifier: &AccountIdentifier) -> bool {
+        self.pending.iter().any(|x| &x.identifier == identifier)
+    }
+
+    /// Move this verifier's clock forward by `seconds`.
+    pub fn advance(&mut self, seconds: u64) {
+        self.now = self.now.saturating_add(seconds);
+    }
+}
+
+impl PhoneNumberVerifier for InMemoryPhoneNumberVerifier {
+    fn verify(&mut self, identifier: &AccountIdentifier, code: &[u8]) -> Result<(), VerificationError> {
+        let index: usize = match self.pending.iter().position(|x| &x.identifier == identifier) {
+            Some(i) => i,
+            None    => return Err(VerificationError::Unverified),
+        };
+
+        if self.pending[index].failed_attempts >= self.max_attempts {
+            return Err(VerificationError::RateLimited);
+        }
+        if self.now >= self.pending[index].expiry {
+            self.pending.remove(index);
+            return Err(VerificationError::Expired);
+        }
+        if self.pending[index].code[..].ct_eq(code).unwrap_u8() == 1 {
+            self.pending.remove(index);
+            return Ok(());
+        }
+        self.pending[index].failed_attempts += 1;
+
+        Err(VerificationError::Unverified)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use account_identifier::ToAccountIdentifier;
+
+    const PHONE_NUMBER: &'static [u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+
+    #[test]
+    fn verify_pending_code() {
+        let identifier: AccountIdentifier = PHONE_NUMBER.to_account_identifier().unwrap();
+        let mut verifier = InMemoryPhoneNumberVerifier::new(600, 3);
+
+        assert_eq!(verifier.verify(&identifier, b"123456"), Err(VerificationError::Unverified));
+
+        verifier.add_pending(&identifier, b"123456");
+
+        assert_eq!(verifier.verify(&identifier, b"654321"), Err(VerificationError::Unverified));
+        assert!(verifier.verify(&identifier, b"123456").is_ok());
+        assert!(!verifier.is_pending(&identifier));
+
+        // Codes may only be used once.
+        assert_eq!(verifier.verify(&identifier, b"123456"), Err(VerificationError::Unverified));
+    }
+
+    #[test]
+    fn verify_expired_code() {
+        let identifier: AccountIdentifier = PHONE_NUMBER.to_account_identifier().unwrap();
+        let mut verifier = InMemoryPhoneNumberVerifier::new(600, 3);
+
+        verifier.add_pending(&identifier, b"123456");
+        verifier.advance(600);
+
+        assert_eq!(verifier.verify(&identifier, b"123456"), Err(VerificationError::Expired));
+    }
+
+    #[test]
+    fn verify_rate_limited() {
+        let identifier: AccountIdentifier = PHONE_NUMBER.to_account_identifier().unwrap();
+        let mut verifier = InMemoryPhoneNumberVerifier::new(600, 2);
+
+        verifier.add_pending(&identifier, b"123456");
+
+        assert_eq!(verifier.verify(&identifier, b"000000"), Err(VerificationError::Unverified));
+        assert_eq!(verifier.verify(&identifier, b"111111"), Err(VerificationError::Unverified));
+
+        // Requesting a new code doesn't reset the limit.
+        verifier.add_pending(&identifier, b"222222");
+
+        assert_eq!(verifier.verify(&identifier, b"222222"), Err(VerificationError::RateLimited));
+    }
+}
@@ -11,14 +11,29 @@ extern crate aeonflux;
 extern crate rand;
 extern crate signal_credential;
 
+use aeonflux::amacs;
+use aeonflux::elgamal;
+use aeonflux::errors::CredentialError;
 use aeonflux::issuer::IssuerParameters;
 use aeonflux::parameters::SystemParameters;
 use rand::thread_rng;
+use signal_credential::account_identifier::AccountIdentifier;
+use signal_credential::account_identifier::ToAccountIdentifier;
 use signal_credential::credential::*;
+use signal_credential::errors::IssuanceError;
+use signal_credential::errors::VerificationError;
 use signal_credential::issuer::*;
 use signal_credential::phone_number::CommittedPhoneNumber;
 use signal_credential::phone_number::RosterEntryCommitment;
+use signal_credential::phone_number::RosterEntryEncryption;
+use signal_credential::policy::*;
+use signal_credential::profile_key::*;
+use signal_credential::renewal::*;
+use signal_credential::role::*;
+use signal_credential::roster::EncryptedGroupRoster;
+use signal_credential::roster::GroupRoster;
 use signal_credential::user::SignalUser;
+use signal_credential::verification::*;
 
 #[test]
 fn credential_issuance_and_presentation() {
@@ -91,3 +106,644 @@ fn credential_issuance_and_presentation() {
     assert!(! roster_admins.contains(&server_copy_alice_roster_entry_commitment));
     assert!(roster_users.contains(&server_copy_alice_roster_entry_commitment));
 }
+
+#[test]
+fn credential_issuance_and_presentation_with_username() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+
+    let alice_username: AccountIdentifier = AccountIdentifier::username("alice").unwrap();
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                None,
+                                                &alice_username).unwrap();
+    let alice_issuance: SignalCredentialIssuance = issuer.issue(&alice_username, &mut issuer_rng).unwrap();
+
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    // The user must survive a round trip through its wire format.
+    assert!(SignalUser::from_bytes(&alice.to_bytes()).unwrap() == alice);
+
+    let alice_entry: RosterEntryCommitment = RosterEntryCommitment::create(&alice_username,
+                                                                           &system_parameters,
+                                                                           &mut alice_rng).unwrap();
+    let alice_presentation: SignalCredentialPresentation = alice.show(&mut alice_rng, &alice_entry).unwrap();
+    let verified_credential: VerifiedSignalCredential = issuer.verify(alice_presentation).unwrap();
+    let roster_entry: CommittedPhoneNumber = issuer.verify_roster_membership(&verified_credential).unwrap();
+
+    assert!(roster_entry == alice_entry.commitment);
+    assert!(alice_entry.open(&alice_username, &system_parameters).is_ok());
+    assert!(alice_entry.open(&AccountIdentifier::username("bob").unwrap(), &system_parameters).is_err());
+}
+
+#[test]
+fn credential_presentation_with_group_roster() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                None,
+                                                alice_phone_number_input).unwrap();
+    let alice_issuance: SignalCredentialIssuance = issuer.issue(alice_phone_number_input,
+                                                                &mut issuer_rng).unwrap();
+
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    let alice_entry: RosterEntryCommitment = RosterEntryCommitment::create(alice_phone_number_input,
+                                                                           &system_parameters,
+                                                                           &mut alice_rng).unwrap();
+    let mut roster: GroupRoster = GroupRoster::new();
+
+    roster.add(alice_entry.commitment);
+
+    let alice_presentation: SignalCredentialPresentation = alice.show(&mut alice_rng, &alice_entry).unwrap();
+
+    assert!(issuer.verify_membership(alice_presentation.clone(), &roster).is_ok());
+
+    // Once Alice is removed, they are no longer a member.
+    roster.remove(&alice_entry.commitment);
+
+    assert!(issuer.verify_membership(alice_presentation, &roster).is_err());
+}
+
+#[test]
+fn credential_presentation_with_anonymous_roster_membership() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+    let mut bob_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let bob_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 6, 6, 6, 6];
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                None,
+                                                alice_phone_number_input).unwrap();
+    let alice_issuance: SignalCredentialIssuance = issuer.issue(alice_phone_number_input,
+                                                                &mut issuer_rng).unwrap();
+
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    let alice_entry: RosterEntryCommitment = RosterEntryCommitment::create(alice_phone_number_input,
+                                                                           &system_parameters,
+                                                                           &mut alice_rng).unwrap();
+    let bob_entry: RosterEntryCommitment = RosterEntryCommitment::create(bob_phone_number_input,
+                                                                         &system_parameters,
+                                                                         &mut bob_rng).unwrap();
+    let mut roster: GroupRoster = GroupRoster::new();
+
+    roster.add(bob_entry.commitment);
+    roster.add(alice_entry.commitment);
+
+    let alice_presentation: SignalCredentialAnonymousPresentation =
+        alice.show_anonymously(&mut alice_rng, &roster, &alice_entry).unwrap();
+
+    // The presentation must not contain Alice's roster entry.
+    assert!(alice_presentation.rerandomized_roster_entry != alice_entry.commitment);
+
+    let deserialized = SignalCredentialAnonymousPresentation::from_bytes(&alice_presentation.to_bytes()).unwrap();
+
+    assert!(deserialized == alice_presentation);
+    assert!(issuer.verify_anonymous_membership(&deserialized, &roster).is_ok());
+
+    // Once Alice is removed, the proof no longer verifies.
+    roster.remove(&alice_entry.commitment);
+
+    assert!(issuer.verify_anonymous_membership(&alice_presentation, &roster).is_err());
+}
+
+#[test]
+fn role_credential_presentation_as_admin() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng)
+        .with_roles(&mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+    let role_issuer_parameters: IssuerParameters = issuer.get_role_issuer_parameters().unwrap();
+
+    // The issuer's role keypair must survive serialisation.
+    let deserialized_issuer: SignalIssuer = SignalIssuer::from_bytes(&issuer.to_bytes()).unwrap();
+
+    assert!(deserialized_issuer.get_role_issuer_parameters() == Some(role_issuer_parameters.clone()));
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let alice: SignalUser = SignalUser::new(system_parameters,
+                                            issuer_parameters.clone(),
+                                            None,
+                                            alice_phone_number_input).unwrap();
+    let alice_issuance: SignalRoleCredentialIssuance = issuer.issue_with_role(alice_phone_number_input,
+                                                                              Role::Admin,
+                                                                              &mut issuer_rng).unwrap();
+    let alice_issuance = SignalRoleCredentialIssuance::from_bytes(&alice_issuance.to_bytes()).unwrap();
+    let alice_credential: SignalRoleCredential = alice.obtain_role_finish(&alice_issuance,
+                                                                          &role_issuer_parameters).unwrap();
+    let alice_entry: RosterEntryCommitment = RosterEntryCommitment::create(alice_phone_number_input,
+                                                                           &system_parameters,
+                                                                           &mut alice_rng).unwrap();
+
+    // Alice, as an admin, can prove they are at least an admin.
+    let alice_presentation: SignalRoleCredentialPresentation =
+        alice.show_role(&mut alice_rng, &alice_credential, Role::Admin,
+                        &alice_entry, &role_issuer_parameters).unwrap();
+    let deserialized = SignalRoleCredentialPresentation::from_bytes(&alice_presentation.to_bytes()).unwrap();
+
+    assert!(deserialized == alice_presentation);
+
+    let roster_entry: CommittedPhoneNumber = issuer.verify_roster_membership_with_role(&deserialized,
+                                                                                      Role::Admin).unwrap();
+
+    assert!(roster_entry == alice_entry.commitment);
+
+    // The same presentation does not show that Alice is an owner.
+    assert!(issuer.verify_roster_membership_with_role(&alice_presentation, Role::Owner).is_err());
+
+    // Nor can Alice create a presentation claiming to be one.
+    assert!(alice.show_role(&mut alice_rng, &alice_credential, Role::Owner,
+                            &alice_entry, &role_issuer_parameters).is_err());
+}
+
+#[test]
+fn role_credential_issuance_with_wrong_keypair() {
+    let mut issuer_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let mut issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+
+    // The issuer's main keypair is for a single attribute, not two.
+    issuer.role_keypair = Some(issuer.issuer.keypair.clone());
+
+    let phone_number: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+
+    match issuer.issue_with_role(phone_number, Role::Admin, &mut issuer_rng) {
+        Err(CredentialError::WrongNumberOfAttributes) => (),
+        _ => panic!("a one-attribute role keypair was accepted"),
+    }
+}
+
+#[test]
+fn credential_presentation_with_encrypted_roster() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+
+    // The group's members share a key, to which the roster is encrypted.
+    let group_key: elgamal::Keypair = elgamal::Keypair::generate(&mut alice_rng);
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                None,
+                                                alice_phone_number_input).unwrap();
+    let alice_issuance: SignalCredentialIssuance = issuer.issue(alice_phone_number_input,
+                                                                &mut issuer_rng).unwrap();
+
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    let alice_entry: RosterEntryEncryption = RosterEntryEncryption::create(alice_phone_number_input,
+                                                                           &group_key.public,
+                                                                           &mut alice_rng).unwrap();
+    let mut roster: EncryptedGroupRoster = EncryptedGroupRoster::new(group_key.public);
+
+    roster.add(alice_entry.encryption);
+
+    let alice_presentation: SignalCredentialEncryptedPresentation =
+        alice.show_encrypted(&mut alice_rng, &alice_entry, &group_key.public).unwrap();
+    let deserialized = SignalCredentialEncryptedPresentation::from_bytes(&alice_presentation.to_bytes()).unwrap();
+
+    assert!(deserialized == alice_presentation);
+    assert!(issuer.verify_encrypted_membership(&deserialized, &roster).is_ok());
+
+    // Other members can find Alice in the roster.
+    assert!(roster.find(&alice.identifier, &group_key.secret) == Some(0));
+
+    // Alice can't claim an entry for someone else's phone number.
+    let bob_entry: RosterEntryEncryption = RosterEntryEncryption::create(&[1u8, 4, 1, 5, 5, 5, 5, 6, 6, 6, 6][..],
+                                                                         &group_key.public,
+                                                                         &mut alice_rng).unwrap();
+
+    roster.add(bob_entry.encryption);
+
+    let bad_presentation = alice.show_encrypted(&mut alice_rng, &bob_entry, &group_key.public).unwrap();
+
+    assert!(issuer.verify_encrypted_membership(&bad_presentation, &roster).is_err());
+}
+
+#[test]
+fn profile_key_credential_issuance_and_presentation() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng)
+        .with_roles(&mut issuer_rng)
+        .with_profile_keys(&mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+    let profile_key_issuer_parameters: IssuerParameters = issuer.get_profile_key_issuer_parameters().unwrap();
+
+    // Both of the issuer's optional keypairs must survive serialisation.
+    let deserialized_issuer: SignalIssuer = SignalIssuer::from_bytes(&issuer.to_bytes()).unwrap();
+
+    assert!(deserialized_issuer.get_role_issuer_parameters() == issuer.get_role_issuer_parameters());
+    assert!(deserialized_issuer.get_profile_key_issuer_parameters() == Some(profile_key_issuer_parameters.clone()));
+
+    // Alice needs an elGamal key in order to blind their profile key.
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let alice_key: elgamal::Keypair = elgamal::Keypair::generate(&mut alice_rng);
+    let alice: SignalUser = SignalUser::new(system_parameters,
+                                            issuer_parameters.clone(),
+                                            Some(alice_key),
+                                            alice_phone_number_input).unwrap();
+    let alice_profile_key: ProfileKey = ProfileKey([42u8; SIZEOF_PROFILE_KEY]);
+
+    let request: ProfileKeyCredentialRequest = alice.request_profile_key_credential(&alice_profile_key,
+                                                                                    &mut alice_rng).unwrap();
+    let request = ProfileKeyCredentialRequest::from_bytes(&request.to_bytes()).unwrap();
+    let issuance: ProfileKeyCredentialIssuance = issuer.issue_profile_key(&request, &mut issuer_rng).unwrap();
+    let issuance = ProfileKeyCredentialIssuance::from_bytes(&issuance.to_bytes()).unwrap();
+    let credential: ProfileKeyCredential = alice.obtain_profile_key_credential(&alice_profile_key,
+                                                                               &request,
+                                                                               &issuance,
+                                                                               &profile_key_issuer_parameters).unwrap();
+
+    assert!(ProfileKeyCredential::from_bytes(&credential.to_bytes()).unwrap() == credential);
+
+    // Alice shows the credential to a group, along with their profile key
+    // encrypted to the group's key.
+    let group_key: elgamal::Keypair = elgamal::Keypair::generate(&mut alice_rng);
+    let presentation: ProfileKeyCredentialPresentation =
+        alice.show_profile_key(&mut alice_rng, &credential, &group_key.public,
+                               &profile_key_issuer_parameters).unwrap();
+    let deserialized = ProfileKeyCredentialPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+    assert!(deserialized == presentation);
+
+    let encrypted_profile_key = issuer.verify_profile_key(&deserialized, &group_key.public).unwrap();
+
+    // Other group members can check Alice's profile key.
+    assert!(encrypted_profile_key.is_encryption_of(&alice_profile_key, &group_key.secret));
+
+    // The presentation doesn't verify for another group.
+    let other_group_key: elgamal::Keypair = elgamal::Keypair::generate(&mut alice_rng);
+
+    assert!(issuer.verify_profile_key(&presentation, &other_group_key.public).is_err());
+}
+
+#[test]
+fn credential_issuance_after_phone_number_verification() {
+    let mut issuer_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let alice_identifier: AccountIdentifier = alice_phone_number_input.to_account_identifier().unwrap();
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                None,
+                                                alice_phone_number_input.clone()).unwrap();
+
+    // The registration service texts Alice a code:
+    let mut verifier = InMemoryPhoneNumberVerifier::new(600, 3);
+
+    // Before Alice has been sent a code, issuance is refused:
+    let refused = issuer.issue_verified(&mut verifier, &alice_phone_number_input,
+                                        b"271828", &mut issuer_rng);
+    assert_eq!(refused.err(), Some(IssuanceError::Verification(VerificationError::Unverified)));
+
+    verifier.add_pending(&alice_identifier, b"271828");
+
+    // Mallory guesses wrong on Alice's behalf:
+    let refused = issuer.issue_verified(&mut verifier, &alice_phone_number_input,
+                                        b"000000", &mut issuer_rng);
+    assert_eq!(refused.err(), Some(IssuanceError::Verification(VerificationError::Unverified)));
+
+    // Alice enters the code they received:
+    let alice_issuance: SignalCredentialIssuance = issuer.issue_verified(&mut verifier,
+                                                                         &alice_phone_number_input,
+                                                                         b"271828",
+                                                                         &mut issuer_rng).unwrap();
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    // The code can't be replayed to obtain more credentials:
+    let refused = issuer.issue_verified(&mut verifier, &alice_phone_number_input,
+                                        b"271828", &mut issuer_rng);
+    assert!(refused.is_err());
+}
+
+#[test]
+fn credential_issuance_is_rate_limited() {
+    let mut issuer_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                None,
+                                                alice_phone_number_input.clone()).unwrap();
+
+    // Two credentials a day, at least an hour apart:
+    let policy: IssuancePolicy = IssuancePolicy::new(2, 86400, 3600);
+    let mut limiter = IssuanceLimiter::new(policy, InMemoryIssuanceStore::new());
+    let now: u64 = 1_540_000_000;
+
+    let alice_issuance: SignalCredentialIssuance = issuer.issue_rate_limited(&mut limiter,
+                                                                             &alice_phone_number_input,
+                                                                             now,
+                                                                             &mut issuer_rng).unwrap();
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    let refused = issuer.issue_rate_limited(&mut limiter, &alice_phone_number_input, now + 60, &mut issuer_rng);
+    assert_eq!(refused.err(), Some(IssuanceError::LimitExceeded{ retry_after: 3540 }));
+
+    assert!(issuer.issue_rate_limited(&mut limiter, &alice_phone_number_input, now + 3600, &mut issuer_rng).is_ok());
+
+    let refused = issuer.issue_rate_limited(&mut limiter, &alice_phone_number_input, now + 36000, &mut issuer_rng);
+    assert!(match refused { Err(IssuanceError::LimitExceeded{ .. }) => true, _ => false });
+}
+
+#[test]
+fn credential_issuance_is_verified_and_rate_limited() {
+    let mut issuer_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let alice_identifier: AccountIdentifier = alice_phone_number_input.to_account_identifier().unwrap();
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                None,
+                                                alice_phone_number_input.clone()).unwrap();
+
+    let mut verifier = InMemoryPhoneNumberVerifier::new(600, 3);
+    let policy: IssuancePolicy = IssuancePolicy::new(1, 86400, 3600);
+    let mut limiter = IssuanceLimiter::new(policy, InMemoryIssuanceStore::new());
+    let now: u64 = 1_540_000_000;
+
+    verifier.add_pending(&alice_identifier, b"271828");
+
+    // Mallory's wrong guesses don't use up Alice's allowance:
+    let refused = issuer.issue_verified_and_rate_limited(&mut verifier, &mut limiter,
+                                                         &alice_phone_number_input,
+                                                         b"000000", now, &mut issuer_rng);
+    assert_eq!(refused.err(), Some(IssuanceError::Verification(VerificationError::Unverified)));
+
+    let alice_issuance: SignalCredentialIssuance =
+        issuer.issue_verified_and_rate_limited(&mut verifier, &mut limiter,
+                                               &alice_phone_number_input,
+                                               b"271828", now, &mut issuer_rng).unwrap();
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    // Even with a freshly verified code, Alice can't exceed the policy:
+    verifier.add_pending(&alice_identifier, b"314159");
+
+    let refused = issuer.issue_verified_and_rate_limited(&mut verifier, &mut limiter,
+                                                         &alice_phone_number_input,
+                                                         b"314159", now + 60, &mut issuer_rng);
+    assert!(match refused { Err(IssuanceError::LimitExceeded{ .. }) => true, _ => false });
+}
+
+#[test]
+fn renewal_key_credential_issuance() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng)
+        .with_renewal_keys(&mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+    let renewal_key_issuer_parameters: IssuerParameters = issuer.get_renewal_key_issuer_parameters().unwrap();
+
+    // The renewal keypair must survive serialisation.
+    let deserialized_issuer: SignalIssuer = SignalIssuer::from_bytes(&issuer.to_bytes()).unwrap();
+
+    assert!(deserialized_issuer.get_renewal_key_issuer_parameters() == Some(renewal_key_issuer_parameters.clone()));
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let alice_key: elgamal::Keypair = elgamal::Keypair::generate(&mut alice_rng);
+    let alice: SignalUser = SignalUser::new(system_parameters,
+                                            issuer_parameters.clone(),
+                                            Some(alice_key),
+                                            alice_phone_number_input).unwrap();
+    let renewal_key: RenewalKey = RenewalKey::generate(&mut alice_rng);
+    let other_renewal_key: RenewalKey = RenewalKey::generate(&mut alice_rng);
+
+    let request: RenewalKeyCredentialRequest = alice.request_renewal_key_credential(&renewal_key,
+                                                                                   &mut alice_rng).unwrap();
+    let request = RenewalKeyCredentialRequest::from_bytes(&request.to_bytes()).unwrap();
+
+    // An issuer without renewal keys can't issue the credential.
+    let other_issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+
+    assert_eq!(other_issuer.issue_renewal_key(&request, &mut issuer_rng).err(), Some(CredentialError::NoIssuerKey));
+
+    let issuance: RenewalKeyCredentialIssuance = issuer.issue_renewal_key(&request, &mut issuer_rng).unwrap();
+    let issuance = RenewalKeyCredentialIssuance::from_bytes(&issuance.to_bytes()).unwrap();
+
+    // Alice can't be fooled into accepting a credential on some other renewal key.
+    assert!(alice.obtain_renewal_key_credential(&other_renewal_key, &request, &issuance,
+                                                &renewal_key_issuer_parameters).is_err());
+    // Nor one under some other key.
+    assert!(alice.obtain_renewal_key_credential(&renewal_key, &request, &issuance,
+                                                &issuer.get_issuer_parameters()).is_err());
+
+    let credential: RenewalKeyCredential = alice.obtain_renewal_key_credential(&renewal_key, &request,
+                                                                               &issuance,
+                                                                               &renewal_key_issuer_parameters).unwrap();
+
+    assert_eq!(RenewalKeyCredential::from_bytes(&credential.to_bytes()).unwrap(), credential);
+    assert_eq!(credential.renewal_key, renewal_key);
+
+    // The credential holds the issuer's MAC on Alice's identifier and renewal key.
+    let renewal_keypair = issuer.renewal_key_keypair.as_ref().unwrap();
+    let attributes = amacs::Message(vec![credential.identifier, credential.renewal_key.0], Vec::new());
+
+    assert!(renewal_keypair.secret.verify(&credential.mac, &attributes).is_ok());
+}
+
+#[test]
+fn credential_renewal_after_key_rotation() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let old_issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng)
+        .with_renewal_keys(&mut issuer_rng);
+    let old_issuer_parameters: IssuerParameters = old_issuer.issuer.keypair.public.clone();
+    let old_renewal_key_issuer_parameters: IssuerParameters =
+        old_issuer.get_renewal_key_issuer_parameters().unwrap();
+
+    // Alice needs an elGamal key in order to blind their identifier.
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let alice_key: elgamal::Keypair = elgamal::Keypair::generate(&mut alice_rng);
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                old_issuer_parameters.clone(),
+                                                Some(alice_key),
+                                                alice_phone_number_input).unwrap();
+    let alice_issuance: SignalCredentialIssuance = old_issuer.issue(&alice_phone_number_input,
+                                                                    &mut issuer_rng).unwrap();
+
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    // Alongside their credential, Alice obtains a credential on a renewal key.
+    let renewal_key: RenewalKey = RenewalKey::generate(&mut alice_rng);
+    let renewal_key_request = alice.request_renewal_key_credential(&renewal_key, &mut alice_rng).unwrap();
+    let renewal_key_issuance = old_issuer.issue_renewal_key(&renewal_key_request, &mut issuer_rng).unwrap();
+    let renewal_key_credential: RenewalKeyCredential =
+        alice.obtain_renewal_key_credential(&renewal_key, &renewal_key_request, &renewal_key_issuance,
+                                            &old_renewal_key_issuer_parameters).unwrap();
+
+    // The issuer rotates its keys.
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng)
+        .with_renewal_keys(&mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+    let renewal_key_issuer_parameters: IssuerParameters = issuer.get_renewal_key_issuer_parameters().unwrap();
+
+    let epoch: u64 = 17_824;
+    let request: SignalCredentialRenewalRequest = alice.request_renewal(&renewal_key_credential,
+                                                                        &old_renewal_key_issuer_parameters,
+                                                                        epoch, &mut alice_rng).unwrap();
+    let request = SignalCredentialRenewalRequest::from_bytes(&request.to_bytes()).unwrap();
+
+    // Alice's credentials weren't issued under the new keys.
+    assert!(issuer.renew(&request, &issuer, epoch, &mut issuer_rng).is_err());
+
+    // Nor was the request's renewal tag for another epoch.
+    assert!(issuer.renew(&request, &old_issuer, epoch + 1, &mut issuer_rng).is_err());
+
+    // Nor may Alice choose some other tag.
+    let mut forged: SignalCredentialRenewalRequest = request.clone();
+
+    forged.renewal_key.tag = RenewalKey::generate(&mut alice_rng).tag(epoch);
+
+    assert!(issuer.renew(&forged, &old_issuer, epoch, &mut issuer_rng).is_err());
+
+    // The tag can't be computed from Alice's identifier alone.
+    let alice_identifier: AccountIdentifier = alice_phone_number_input.to_account_identifier().unwrap();
+
+    assert!(request.renewal_key.tag != renewal_tag_generator(epoch) * alice_identifier.to_scalar());
+
+    let renewal: SignalCredentialRenewal = issuer.renew(&request, &old_issuer, epoch, &mut issuer_rng).unwrap();
+    let renewal = SignalCredentialRenewal::from_bytes(&renewal.to_bytes()).unwrap();
+
+    // The renewal doesn't check out against the old keys.
+    assert!(alice.obtain_renewal(&renewal_key_credential, &request, &renewal,
+                                 old_issuer_parameters.clone(), &renewal_key_issuer_parameters).is_err());
+    assert!(alice.obtain_renewal(&renewal_key_credential, &request, &renewal,
+                                 issuer_parameters.clone(), &old_renewal_key_issuer_parameters).is_err());
+
+    let renewed_renewal_key_credential: RenewalKeyCredential =
+        alice.obtain_renewal(&renewal_key_credential, &request, &renewal,
+                             issuer_parameters, &renewal_key_issuer_parameters).unwrap();
+
+    assert_eq!(renewed_renewal_key_credential.renewal_key, renewal_key);
+
+    // Alice can now show their renewed credential to the new issuer.
+    let alice_entry: RosterEntryCommitment = RosterEntryCommitment::create(&alice_phone_number_input,
+                                                                           &system_parameters,
+                                                                           &mut alice_rng).unwrap();
+    let alice_presentation: SignalCredentialPresentation = alice.show(&mut alice_rng, &alice_entry).unwrap();
+
+    assert!(old_issuer.verify(alice_presentation.clone()).is_err());
+
+    let verified_credential: VerifiedSignalCredential = issuer.verify(alice_presentation).unwrap();
+    let roster_entry: CommittedPhoneNumber = issuer.verify_roster_membership(&verified_credential).unwrap();
+
+    assert!(roster_entry == alice_entry.commitment);
+
+    // And renew again with the renewed renewal key credential.
+    let request: SignalCredentialRenewalRequest = alice.request_renewal(&renewed_renewal_key_credential,
+                                                                        &renewal_key_issuer_parameters,
+                                                                        epoch + 1, &mut alice_rng).unwrap();
+
+    assert!(issuer.renew(&request, &issuer, epoch + 1, &mut issuer_rng).is_ok());
+}
+
+#[test]
+fn credential_renewal_is_rate_limited() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng)
+        .with_renewal_keys(&mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+    let renewal_key_issuer_parameters: IssuerParameters = issuer.get_renewal_key_issuer_parameters().unwrap();
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let alice_key: elgamal::Keypair = elgamal::Keypair::generate(&mut alice_rng);
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                Some(alice_key),
+                                                alice_phone_number_input).unwrap();
+    let alice_issuance: SignalCredentialIssuance = issuer.issue(&alice_phone_number_input,
+                                                                &mut issuer_rng).unwrap();
+
+    alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+    let renewal_key: RenewalKey = RenewalKey::generate(&mut alice_rng);
+    let renewal_key_request = alice.request_renewal_key_credential(&renewal_key, &mut alice_rng).unwrap();
+    let renewal_key_issuance = issuer.issue_renewal_key(&renewal_key_request, &mut issuer_rng).unwrap();
+    let renewal_key_credential: RenewalKeyCredential =
+        alice.obtain_renewal_key_credential(&renewal_key, &renewal_key_request, &renewal_key_issuance,
+                                            &renewal_key_issuer_parameters).unwrap();
+
+    // One renewal per credential per day:
+    let mut limiter = RenewalLimiter::new(86400);
+    let now: u64 = 1_540_000_000;
+
+    let request: SignalCredentialRenewalRequest = alice.request_renewal(&renewal_key_credential,
+                                                                        &renewal_key_issuer_parameters,
+                                                                        limiter.epoch(now),
+                                                                        &mut alice_rng).unwrap();
+    let renewal: SignalCredentialRenewal = issuer.renew_rate_limited(&request, &issuer, &mut limiter,
+                                                                     now, &mut issuer_rng).unwrap();
+
+    // A fresh request in the same epoch carries the same tag, and is refused:
+    let second: SignalCredentialRenewalRequest = alice.request_renewal(&renewal_key_credential,
+                                                                       &renewal_key_issuer_parameters,
+                                                                       limiter.epoch(now),
+                                                                       &mut alice_rng).unwrap();
+
+    assert_eq!(request.renewal_key.tag, second.renewal_key.tag);
+
+    let refused = issuer.renew_rate_limited(&second, &issuer, &mut limiter, now + 60, &mut issuer_rng);
+    assert!(match refused { Err(IssuanceError::LimitExceeded{ .. }) => true, _ => false });
+
+    let renewal_key_credential: RenewalKeyCredential =
+        alice.obtain_renewal(&renewal_key_credential, &request, &renewal,
+                             issuer_parameters, &renewal_key_issuer_parameters).unwrap();
+
+    // In the next epoch, Alice's tag is unlinkable to the last one:
+    let later: u64 = now + 86400;
+    let third: SignalCredentialRenewalRequest = alice.request_renewal(&renewal_key_credential,
+                                                                      &renewal_key_issuer_parameters,
+                                                                      limiter.epoch(later),
+                                                                      &mut alice_rng).unwrap();
+
+    assert!(request.renewal_key.tag != third.renewal_key.tag);
+    assert!(issuer.renew_rate_limited(&third, &issuer, &mut limiter, later, &mut issuer_rng).is_ok());
+}
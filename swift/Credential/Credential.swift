@@ -10,6 +10,27 @@
 
 import Foundation
 
+/// Copy the bytes out of a `buf_t` returned by the Rust API, and release it.
+func takeBuffer(_ buffer: buf_t) -> [UInt8] {
+    defer { buffer_free(buffer) }
+
+    guard let ptr = buffer.ptr else { return [] }
+
+    return Array(UnsafeBufferPointer(start: ptr, count: Int(buffer.len)))
+}
+
+/// Call a function of the Rust API which writes its result to an out-parameter,
+/// returning the bytes if the call succeeded and `nil` otherwise.
+func takeResult(_ call: (UnsafeMutablePointer<buf_t>) -> status_t) -> [UInt8]? {
+    var buffer = buf_t(len: 0, ptr: nil)
+    let status = call(&buffer)
+    let bytes = takeBuffer(buffer)
+
+    guard status == status_t(STATUS_OK) else { return nil }
+
+    return bytes
+}
+
 class SystemParameters {
     var data = [UInt8](repeating: 0, count: Int(LENGTH_SYSTEM_PARAMETERS))
 
@@ -22,11 +43,9 @@ class SystemParameters {
     func create(seed: [UInt8]) -> Self? {
         guard seed.count == 32 else { return nil }
 
-        let buffer = system_parameters_create(seed)
+        guard let data = takeResult({ system_parameters_create(seed, $0) }) else { return nil }
 
-        self.data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        self.data = data
         return self
     }
 }
@@ -50,34 +69,39 @@ class RosterEntryCommitment {
         self.data = bytes
     }
 
+    /// Commit to a phone number, with an opening drawn from the system's entropy.
+    init?(phone_number: [UInt8], system_parameters: SystemParameters) {
+        guard let data = takeResult({ roster_entry_commitment_create(phone_number,
+                                                                     UInt64(phone_number.count),
+                                                                     &system_parameters.data,
+                                                                     UInt64(system_parameters.data.count),
+                                                                     nil, $0) }) else { return nil }
+        self.data = data
+    }
+
+    /// Commit to a phone number, with an opening from a `seed` which must never be reused.
     init?(withSeed seed: [UInt8],
           phone_number: [UInt8],
           system_parameters: SystemParameters) {
         guard seed.count == 32 else { return nil }
 
-        let buffer = roster_entry_commitment_create(phone_number,
-                                                    UInt64(phone_number.count),
-                                                    &system_parameters.data,
-                                                    UInt64(system_parameters.data.count),
-                                                    seed)
-        self.data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        guard let data = takeResult({ roster_entry_commitment_create(phone_number,
+                                                                     UInt64(phone_number.count),
+                                                                     &system_parameters.data,
+                                                                     UInt64(system_parameters.data.count),
+                                                                     seed, $0) }) else { return nil }
+        self.data = data
     }
 
     func open(phone_number: [UInt8], system_parameters: SystemParameters) -> Bool {
-        let buffer = roster_entry_commitment_open(&self.data,
+        let status = roster_entry_commitment_open(&self.data,
                                                   UInt64(self.data.count),
                                                   phone_number,
                                                   UInt64(phone_number.count),
                                                   &system_parameters.data,
                                                   UInt64(system_parameters.data.count));
 
-        if buffer.len == 1 {
-            return true
-        } else {
-            return false
-        }
+        return status == status_t(STATUS_OK)
     }
 }
 
@@ -125,61 +149,73 @@ class SignalIssuer {
     var keypair: AlgebraicMACKeypair
     var data = [UInt8](repeating: 0, count: Int(LENGTH_ISSUER))
 
+    /// Create an issuer, with keys drawn from the system's entropy.
+    init?(system_parameters: SystemParameters) {
+        guard let keypair_data = takeResult({ issuer_create(&system_parameters.data,
+                                                            UInt64(system_parameters.data.count),
+                                                            nil, $0) }) else { return nil }
+        self.keypair = AlgebraicMACKeypair(withBytes: keypair_data)!
+
+        guard let data = takeResult({ issuer_new(&system_parameters.data, UInt64(system_parameters.data.count),
+                                                 &self.keypair.data, UInt64(self.keypair.data.count), $0) })
+            else { return nil }
+        self.data = data
+    }
+
+    /// Create an issuer, with keys from a `seed` which must never be reused.
     init?(withSeed seed: [UInt8], system_parameters: SystemParameters) {
         guard seed.count == 32 else { return nil }
 
-        let keypair_buffer = issuer_create(&system_parameters.data,
-                                            UInt64(system_parameters.data.count), seed)
-        data = keypair_buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(keypair_buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(keypair_buffer.len)))
-        }
-        self.keypair = AlgebraicMACKeypair(withBytes: data)!
+        guard let keypair_data = takeResult({ issuer_create(&system_parameters.data,
+                                                            UInt64(system_parameters.data.count),
+                                                            seed, $0) }) else { return nil }
+        self.keypair = AlgebraicMACKeypair(withBytes: keypair_data)!
 
-        let buffer = issuer_new(&system_parameters.data, UInt64(system_parameters.data.count),
-                                &self.keypair.data, UInt64(self.keypair.data.count))
-        self.data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        guard let data = takeResult({ issuer_new(&system_parameters.data, UInt64(system_parameters.data.count),
+                                                 &self.keypair.data, UInt64(self.keypair.data.count), $0) })
+            else { return nil }
+        self.data = data
     }
 
     init(withKeypair keypair: AlgebraicMACKeypair, system_parameters: SystemParameters) {
-        let buffer = issuer_new(&system_parameters.data, UInt64(system_parameters.data.count),
-                                &keypair.data, UInt64(keypair.data.count))
-        self.data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        self.data = takeResult({ issuer_new(&system_parameters.data, UInt64(system_parameters.data.count),
+                                            &keypair.data, UInt64(keypair.data.count), $0) }) ?? []
         self.keypair = keypair
     }
 
     func get_parameters() -> IssuerParameters? {
-        let buffer = issuer_get_issuer_parameters(&self.data, UInt64(self.data.count))
-        let data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        guard let data = takeResult({ issuer_get_issuer_parameters(&self.data, UInt64(self.data.count), $0) })
+            else { return nil }
         let issuer_parameters = IssuerParameters(withBytes: data)
 
         return issuer_parameters
     }
 
+    func issue(phone_number: [UInt8]) -> CredentialIssuance? {
+        guard let data = takeResult({ issuer_issue(&self.data, UInt64(self.data.count),
+                                                   phone_number, UInt64(phone_number.count), nil, $0) })
+            else { return nil }
+        let issuance = CredentialIssuance(withBytes: data)
+
+        return issuance
+    }
+
+    /// As `issue(phone_number:)`, but with a `seed` which must never be reused.
     func issue(phone_number: [UInt8], seed: [UInt8]) -> CredentialIssuance? {
         guard seed.count == 32 else { return nil }
 
-        let buffer = issuer_issue(&self.data, UInt64(self.data.count),
-                                  phone_number, UInt64(phone_number.count), seed)
-        let data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        guard let data = takeResult({ issuer_issue(&self.data, UInt64(self.data.count),
+                                                   phone_number, UInt64(phone_number.count), seed, $0) })
+            else { return nil }
         let issuance = CredentialIssuance(withBytes: data)
 
         return issuance
     }
 
     func verify(presentation: CredentialPresentation) -> VerifiedCredential? {
-        let buffer = issuer_verify(&self.data, UInt64(self.data.count),
-                                   &presentation.data, UInt64(presentation.data.count))
-         let data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        guard let data = takeResult({ issuer_verify(&self.data, UInt64(self.data.count),
+                                                    &presentation.data, UInt64(presentation.data.count), $0) })
+            else { return nil }
         let verified = VerifiedCredential(withBytes: data)
 
         return verified
@@ -194,24 +230,33 @@ class User {
           system_parameters: SystemParameters,
           issuer_parameters: IssuerParameters,
           issuance: CredentialIssuance) {
-        let buffer = user_obtain_finish(phone_number, UInt64(phone_number.count),
-                                        &system_parameters.data, UInt64(system_parameters.data.count),
-                                        &issuer_parameters.data, UInt64(issuer_parameters.data.count),
-                                        &issuance.data, UInt64(issuance.data.count))
+        guard let data = takeResult({ user_obtain_finish(phone_number, UInt64(phone_number.count),
+                                                         &system_parameters.data, UInt64(system_parameters.data.count),
+                                                         &issuer_parameters.data, UInt64(issuer_parameters.data.count),
+                                                         &issuance.data, UInt64(issuance.data.count), $0) })
+            else { return nil }
 
-        self.data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        self.data = data
+    }
+
+    func show(roster_entry_commitment: RosterEntryCommitment) -> CredentialPresentation? {
+        guard let data = takeResult({ user_show(&self.data, UInt64(self.data.count),
+                                                &roster_entry_commitment.data,
+                                                UInt64(roster_entry_commitment.data.count), nil, $0) })
+            else { return nil }
+        let presentation = CredentialPresentation(withBytes: data)
+
+        return presentation
     }
 
+    /// As `show(roster_entry_commitment:)`, but with a `seed` which must never be reused.
     func show(roster_entry_commitment: RosterEntryCommitment, seed: [UInt8]) -> CredentialPresentation? {
         guard seed.count == 32 else { return nil }
 
-        let buffer = user_show(&self.data, UInt64(self.data.count),
-                               &roster_entry_commitment.data, UInt64(roster_entry_commitment.data.count), seed)
-        let data = buffer.ptr.withMemoryRebound(to: UInt8.self, capacity: Int(buffer.len)) {
-            Array(UnsafeBufferPointer(start: $0, count: Int(buffer.len)))
-        }
+        guard let data = takeResult({ user_show(&self.data, UInt64(self.data.count),
+                                                &roster_entry_commitment.data,
+                                                UInt64(roster_entry_commitment.data.count), seed, $0) })
+            else { return nil }
         let presentation = CredentialPresentation(withBytes: data)
 
         return presentation
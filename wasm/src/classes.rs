@@ -0,0 +1,286 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Javascript classes, for use via wasm-bindgen.
+//!
+//! Unlike the free functions in `js`, which take and return opaque bytes and
+//! deserialise them anew on every call, each class here holds a deserialised
+//! object, which lives in wasm memory until its `free()` method is called.
+//! Every class may be exported with `toBytes()` and imported with
+//! `fromBytes()`, in the same format used by the free functions and by the C
+//! API.
+//!
+//! Errors are thrown as in `js`, as an `Error` named `"CredentialError"`
+//! with a numeric `code`.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsValue;
+
+use signal_credential::bindings;
+use signal_credential::bindings::SignalRng;
+use signal_credential::credential;
+use signal_credential::elgamal;
+use signal_credential::issuer;
+use signal_credential::parameters;
+use signal_credential::phone_number;
+use signal_credential::status::Status;
+use signal_credential::user;
+
+use js::credential_error;
+
+/// Globally agreed upon parameters for the credential scheme.
+#[wasm_bindgen]
+pub struct SystemParameters {
+    parameters: parameters::SystemParameters,
+}
+
+#[wasm_bindgen]
+impl SystemParameters {
+    /// Create some `SystemParameters` by seeding a CSPRNG with 32 bytes.
+    pub fn create(seed: &[u8]) -> Result<SystemParameters, JsValue> {
+        let mut csprng: SignalRng = ok_or_throw!(bindings::csprng_from_seed(seed));
+
+        Ok(SystemParameters { parameters: parameters::SystemParameters::hunt_and_peck(&mut csprng) })
+    }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<SystemParameters, JsValue> {
+        Ok(SystemParameters { parameters: ok_or_throw!(parameters::SystemParameters::from_bytes(bytes)) })
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.parameters.to_bytes()
+    }
+}
+
+/// An issuer and verifier of credentials.
+#[wasm_bindgen]
+pub struct SignalIssuer {
+    issuer: issuer::SignalIssuer,
+}
+
+#[wasm_bindgen]
+impl SignalIssuer {
+    /// Create a brand-new issuer, with keys generated by seeding a CSPRNG
+    /// with 32 bytes.
+    pub fn create(system_parameters: &SystemParameters, seed: &[u8]) -> Result<SignalIssuer, JsValue> {
+        let mut csprng: SignalRng = ok_or_throw!(bindings::csprng_from_seed(seed));
+
+        Ok(SignalIssuer { issuer: issuer::SignalIssuer::create(system_parameters.parameters, &mut csprng) })
+    }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalIssuer, JsValue> {
+        Ok(SignalIssuer { issuer: ok_or_throw!(issuer::SignalIssuer::from_bytes(bytes)) })
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.issuer.to_bytes()
+    }
+
+    /// The public parameters of this issuer, which users need in order to
+    /// obtain credentials from it.
+    #[wasm_bindgen(getter, js_name = issuerParameters)]
+    pub fn issuer_parameters(&self) -> Vec<u8> {
+        self.issuer.get_issuer_parameters().to_bytes()
+    }
+
+    /// Issue a credential for a `phoneNumber`, returning the serialised
+    /// issuance to be given to the user.
+    ///
+    /// This neither verifies that the user owns the `phoneNumber` nor limits
+    /// how many credentials it may obtain, so it is unsafe to expose in a
+    /// deployment, as for `SignalIssuer::issue()`.
+    pub fn issue(&self, phone_number: &[u8], seed: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let mut csprng: SignalRng = ok_or_throw!(bindings::csprng_from_seed(seed));
+        let issuance = ok_or_throw!(self.issuer.issue(phone_number, &mut csprng));
+
+        Ok(issuance.to_bytes())
+    }
+
+    /// Verify a user's `presentation`, returning the serialised verified
+    /// credential.
+    pub fn verify(&self, presentation: &SignalCredentialPresentation) -> Result<Vec<u8>, JsValue> {
+        let verified = ok_or_throw!(self.issuer.verify(presentation.presentation.clone()));
+
+        Ok(verified.to_bytes())
+    }
+
+    /// Verify that a serialised verified credential is for the roster entry
+    /// it was presented with, returning the roster entry's commitment.
+    #[wasm_bindgen(js_name = verifyRosterMembership)]
+    pub fn verify_roster_membership(&self, verified_credential: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let verified = ok_or_throw!(credential::VerifiedSignalCredential::from_bytes(verified_credential));
+        let commitment = ok_or_throw!(self.issuer.verify_roster_membership(&verified));
+
+        Ok(commitment.to_bytes())
+    }
+}
+
+/// A user, who may obtain a credential and present it.
+#[wasm_bindgen]
+pub struct SignalUser {
+    user: user::SignalUser,
+}
+
+#[wasm_bindgen]
+impl SignalUser {
+    /// Create a user without a credential or an elGamal keypair.
+    pub fn create(
+        phone_number: &[u8],
+        system_parameters: &SystemParameters,
+        issuer_parameters: &[u8],
+    ) -> Result<SignalUser, JsValue>
+    {
+        let issuer_params = ok_or_throw!(issuer::IssuerParameters::from_bytes(issuer_parameters));
+        let user = ok_or_throw!(user::SignalUser::new(system_parameters.parameters, issuer_params,
+                                                      None, phone_number));
+
+        Ok(SignalUser { user })
+    }
+
+    /// Create a user without a credential, but with a serialised elGamal
+    /// `keypair`, as from `elgamal_keypair_create()`.
+    #[wasm_bindgen(js_name = createWithKeypair)]
+    pub fn create_with_keypair(
+        phone_number: &[u8],
+        system_parameters: &SystemParameters,
+        issuer_parameters: &[u8],
+        keypair: &[u8],
+    ) -> Result<SignalUser, JsValue>
+    {
+        let issuer_params = ok_or_throw!(issuer::IssuerParameters::from_bytes(issuer_parameters));
+        let key = ok_or_throw!(elgamal::Keypair::from_bytes(keypair));
+        let user = ok_or_throw!(user::SignalUser::new(system_parameters.parameters, issuer_params,
+                                                      Some(key), phone_number));
+
+        Ok(SignalUser { user })
+    }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalUser, JsValue> {
+        Ok(SignalUser { user: ok_or_throw!(user::SignalUser::from_bytes(bytes)) })
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.user.to_bytes()
+    }
+
+    /// Whether this user has obtained a credential.
+    #[wasm_bindgen(getter, js_name = hasCredential)]
+    pub fn has_credential(&self) -> bool {
+        self.user.user.credential.is_some()
+    }
+
+    /// Get the public half of this user's elGamal keypair, throwing a
+    /// `CredentialError` if they have none.
+    #[wasm_bindgen(js_name = publicKey)]
+    pub fn public_key(&self) -> Result<Vec<u8>, JsValue> {
+        match self.user.user.key {
+            Some(ref key) => Ok(key.public.to_bytes()),
+            None          => Err(credential_error(Status::MissingData)),
+        }
+    }
+
+    /// Check a serialised credential `issuance`, as from
+    /// `SignalIssuer.issue()`, and keep its credential.
+    pub fn obtain(&mut self, issuance: &[u8]) -> Result<(), JsValue> {
+        let issuance = ok_or_throw!(credential::SignalCredentialIssuance::from_bytes(issuance));
+
+        Ok(ok_or_throw!(self.user.obtain_finish(Some(&issuance))))
+    }
+
+    /// Present this user's credential, along with a proof that it is for
+    /// the same phone number as the `rosterEntryCommitment`.
+    pub fn show(
+        &self,
+        roster_entry_commitment: &RosterEntryCommitment,
+        seed: &[u8],
+    ) -> Result<SignalCredentialPresentation, JsValue>
+    {
+        let mut csprng: SignalRng = ok_or_throw!(bindings::csprng_from_seed(seed));
+        let presentation = ok_or_throw!(self.user.show(&mut csprng, &roster_entry_commitment.entry));
+
+        Ok(SignalCredentialPresentation { presentation })
+    }
+}
+
+/// A user's presentation of their credential to the issuer.
+#[wasm_bindgen]
+pub struct SignalCredentialPresentation {
+    presentation: credential::SignalCredentialPresentation,
+}
+
+#[wasm_bindgen]
+impl SignalCredentialPresentation {
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignalCredentialPresentation, JsValue> {
+        Ok(SignalCredentialPresentation {
+            presentation: ok_or_throw!(credential::SignalCredentialPresentation::from_bytes(bytes)),
+        })
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.presentation.to_bytes()
+    }
+}
+
+/// A commitment to a phone number in a group roster, along with its opening.
+#[wasm_bindgen]
+pub struct RosterEntryCommitment {
+    entry: phone_number::RosterEntryCommitment,
+}
+
+#[wasm_bindgen]
+impl RosterEntryCommitment {
+    /// Commit to a `phoneNumber`, with an opening created by seeding a
+    /// CSPRNG with 32 bytes.
+    pub fn create(
+        phone_number: &[u8],
+        system_parameters: &SystemParameters,
+        seed: &[u8],
+    ) -> Result<RosterEntryCommitment, JsValue>
+    {
+        let mut csprng: SignalRng = ok_or_throw!(bindings::csprng_from_seed(seed));
+        let entry = ok_or_throw!(phone_number::RosterEntryCommitment::create(phone_number,
+                                                                             &system_parameters.parameters,
+                                                                             &mut csprng));
+
+        Ok(RosterEntryCommitment { entry })
+    }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<RosterEntryCommitment, JsValue> {
+        Ok(RosterEntryCommitment { entry: ok_or_throw!(phone_number::RosterEntryCommitment::from_bytes(bytes)) })
+    }
+
+    /// Serialise the commitment *and* its opening, which should not be given
+    /// to the issuer.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entry.to_bytes()
+    }
+
+    /// The commitment without its opening, which is safe to give to the
+    /// issuer.
+    #[wasm_bindgen(getter)]
+    pub fn commitment(&self) -> Vec<u8> {
+        self.entry.commitment.to_bytes()
+    }
+
+    /// Check that this commitment opens to a `phoneNumber`, throwing a
+    /// `CredentialError` if it does not.
+    pub fn open(&self, phone_number: &[u8], system_parameters: &SystemParameters) -> Result<(), JsValue> {
+        Ok(ok_or_throw!(self.entry.open(phone_number, &system_parameters.parameters)))
+    }
+}
@@ -7,76 +7,90 @@
 // Authors:
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 
+//! Javascript API, for use via wasm-bindgen.
+//!
+//! Serialised objects are passed to and from Javascript as opaque values
+//! holding their bytes.  The work of each function is done by
+//! `signal_credential::bindings`, which the C API also wraps, so that both
+//! give identical results for identical inputs and seeds.
+//!
+//! For objects which are used repeatedly, the classes in `classes` avoid
+//! deserialising them on every call.
+
+use js_sys::Error;
+use js_sys::Reflect;
+
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue;
 
-pub use rand::ChaChaRng;
-pub use rand::SeedableRng;
+use signal_credential::bindings;
+use signal_credential::status::Status;
 
-use signal_credential::amacs::{self};
-use signal_credential::credential::SignalCredentialIssuance;
-use signal_credential::credential::SignalCredentialPresentation;
-use signal_credential::credential::VerifiedSignalCredential;
-use signal_credential::issuer::IssuerParameters;
-use signal_credential::issuer::SignalIssuer;
-use signal_credential::parameters::SystemParameters;
-use signal_credential::phone_number::RosterEntryCommitment;
-use signal_credential::user::SignalUser;
+/// Create the Javascript exception thrown for a failing `status`.
+///
+/// This is an `Error` whose `name` is `"CredentialError"`, whose `message`
+/// describes the `status`, and whose `code` is the numeric code of the
+/// `status`, which is the same code returned by the C API.
+pub fn credential_error(status: Status) -> JsValue {
+    let error: Error = Error::new(&status.to_string());
 
-pub type SignalRng = ChaChaRng;
+    error.set_name("CredentialError");
 
-#[macro_export]
-macro_rules! slice_to_array {
-    ($slice:ident, $length:expr) => {{
-        if $slice.len() != $length {
-            Err(())
-        } else {
-            let mut array: [u8; $length] = [0u8; $length];
-
-            // This will panic if the bytes.len() isn't equal to the array_length,
-            // hence the explicit double-checks on the lengths above.
-            array.copy_from_slice($slice);
-
-            Ok(array)
-        }
-    }}
+    // Setting a property on a freshly created `Error` cannot fail.
+    let _ = Reflect::set(&error, &JsValue::from("code"), &JsValue::from(status.code()));
+
+    error.into()
 }
 
 #[macro_export]
-macro_rules! ok_or_return {
+macro_rules! ok_or_throw {
     ($expr:expr) => {
         match $expr {
-            Ok(x)   => x,
-            Err(_x) => {
+            Ok(x)  => x,
+            Err(x) => {
                 #[cfg(feature = "std")]
-                println!("{:?}", _x);
-                return JsValue::from(0);
+                println!("{:?}", x);
+                return Err(credential_error(Status::from(x)));
             }
         }
-    }
-}
-
-#[macro_export]
-macro_rules! ok_or_false {
-    ($expr:expr) => {
+    };
+    ($expr:expr, $status:expr) => {
         match $expr {
             Ok(x)   => x,
-            Err(_x) => {
-                #[cfg(feature = "std")]
-                println!("{:?}", _x);
-                return false;
-            }
+            Err(_x) => return Err(credential_error($status)),
         }
-    }
+    };
 }
 
-#[macro_export]
-macro_rules! csprng_from_seed {
-    ($seed:ident) => {{
-        let mut seed_array: [u8; 32] = ok_or_return!(slice_to_array!($seed, 32));
+/// Get the bytes of a serialised object passed in from Javascript.
+fn bytes_from_js(value: JsValue) -> Result<Vec<u8>, JsValue> {
+    value.into_serde().map_err(|_| credential_error(Status::Serialisation))
+}
+
+/// Hand the bytes of a serialised object back to Javascript.
+fn bytes_to_js(bytes: Vec<u8>) -> Result<JsValue, JsValue> {
+    JsValue::from_serde(&bytes).map_err(|_| credential_error(Status::Serialisation))
+}
 
-        SignalRng::from_seed(seed_array)
-    }}
+/// Find the length of a kind of serialised object.
+///
+/// # Inputs
+///
+/// * `object` is the numeric code of a
+///   `signal_credential::sizes::SerializedObject`, e.g. `7` for a
+///   `SignalUser`.
+///
+/// # Returns
+///
+/// The length of the object, in bytes, once serialised.  This is computed by
+/// the library itself, and so is always correct for this version of it.
+///
+#[wasm_bindgen]
+pub fn length_of(
+    object: u32,
+) -> Result<u32, JsValue>
+{
+    Ok(ok_or_throw!(bindings::length_of(object)) as u32)
 }
 
 /// Create some globally-agreed upon `SystemParameters` by seeding a CSPRNG.
@@ -95,15 +109,9 @@ macro_rules! csprng_from_seed {
 #[wasm_bindgen]
 pub fn system_parameters_create(
     seed: &[u8],
-) -> JsValue
+) -> Result<JsValue, JsValue>
 {
-    if seed.len() != 32 {
-        return JsValue::from(0);
-    }
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
-    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut csprng);
-
-    ok_or_return!(JsValue::from_serde(&system_parameters))
+    bytes_to_js(ok_or_throw!(bindings::system_parameters_create(seed)))
 }
 
 /// Create a new credential issuer.
@@ -127,13 +135,11 @@ pub fn system_parameters_create(
 pub fn issuer_create(
     system_parameters: JsValue,
     seed: &[u8],
-) -> JsValue
+) -> Result<JsValue, JsValue>
 {
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
-    let system_params: SystemParameters = ok_or_return!(system_parameters.into_serde());
-    let issuer: SignalIssuer = SignalIssuer::create(system_params, &mut csprng);
+    let system_params: Vec<u8> = bytes_from_js(system_parameters)?;
 
-    ok_or_return!(JsValue::from_serde(&issuer.issuer.keypair))
+    bytes_to_js(ok_or_throw!(bindings::issuer_create(&system_params, seed)))
 }
 
 /// Get this credential issuer's parameters (a.k.a their public key material).
@@ -152,11 +158,11 @@ pub fn issuer_create(
 #[wasm_bindgen]
 pub fn issuer_get_issuer_parameters(
     issuer: JsValue,
-) -> JsValue
+) -> Result<JsValue, JsValue>
 {
-    let issuer: SignalIssuer = ok_or_return!(issuer.into_serde());
-    
-    ok_or_return!(JsValue::from_serde(&issuer.issuer.keypair.public))
+    let issuer: Vec<u8> = bytes_from_js(issuer)?;
+
+    bytes_to_js(ok_or_throw!(bindings::issuer_get_issuer_parameters(&issuer)))
 }
 
 /// Instantiate a previously generated credential issuer.
@@ -171,8 +177,7 @@ pub fn issuer_get_issuer_parameters(
 ///
 /// # Returns
 ///
-/// A `signal_credential::issuer::SignalIssuer` as a `JsValue`¹ if successful,
-/// otherwise a single byte set to `0`.
+/// A `signal_credential::issuer::SignalIssuer` as a `JsValue`¹.
 ///
 /// ¹ Which, by the way, you won't be able to do much of anything with since
 ///   it's internally serialised to literal bytes, so best don't touch it.
@@ -187,13 +192,12 @@ pub fn issuer_get_issuer_parameters(
 pub fn issuer_new(
     system_parameters: JsValue,
     keypair: JsValue,
-) -> JsValue
+) -> Result<JsValue, JsValue>
 {
-    let system_params: SystemParameters = ok_or_return!(system_parameters.into_serde());
-    let keys: amacs::Keypair = ok_or_return!(keypair.into_serde());
-    let issuer: SignalIssuer = SignalIssuer::new(system_params, keys);
+    let system_params: Vec<u8> = bytes_from_js(system_parameters)?;
+    let keys: Vec<u8> = bytes_from_js(keypair)?;
 
-    ok_or_return!(JsValue::from_serde(&issuer))
+    bytes_to_js(ok_or_throw!(bindings::issuer_new(&system_params, &keys)))
 }
 
 /// Issue a new credential to a user.
@@ -210,21 +214,18 @@ pub fn issuer_new(
 ///
 /// # Returns
 ///
-/// A `SignalCredentialIssuance` as a `JsValue` if the credential issuance was
-/// successful, otherwise a single byte set to `0`.
+/// A `SignalCredentialIssuance` as a `JsValue`.
 ///
 #[wasm_bindgen]
 pub fn issuer_issue(
     issuer: JsValue,
     phone_number: &[u8],
     seed: &[u8],
-) -> JsValue
+) -> Result<JsValue, JsValue>
 {
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
-    let issuer: SignalIssuer = ok_or_return!(issuer.into_serde());
-    let issuance: SignalCredentialIssuance = ok_or_return!(issuer.issue(&phone_number, &mut csprng));
+    let issuer: Vec<u8> = bytes_from_js(issuer)?;
 
-    ok_or_return!(JsValue::from_serde(&issuance))
+    bytes_to_js(ok_or_throw!(bindings::issuer_issue(&issuer, phone_number, seed)))
 }
 
 /// Check a `presentation` of a `SignalUser`'s credential.
@@ -237,19 +238,18 @@ pub fn issuer_issue(
 /// # Returns
 ///
 /// If successfully verified, returns a `VerifiedSignalCredential` as a
-/// `JsValue`.  Otherwise, returns a single byte set to `0`.
+/// `JsValue`.
 ///
 #[wasm_bindgen]
 pub fn issuer_verify(
     issuer: JsValue,
     presentation: JsValue,
-) -> JsValue
+) -> Result<JsValue, JsValue>
 {
-    let issuer: SignalIssuer = ok_or_return!(issuer.into_serde());
-    let presentation: SignalCredentialPresentation = ok_or_return!(presentation.into_serde());
-    let verified: VerifiedSignalCredential = ok_or_return!(issuer.verify(presentation));
+    let issuer: Vec<u8> = bytes_from_js(issuer)?;
+    let presentation: Vec<u8> = bytes_from_js(presentation)?;
 
-    ok_or_return!(JsValue::from_serde(&verified))
+    bytes_to_js(ok_or_throw!(bindings::issuer_verify(&issuer, &presentation)))
 }
 
 /// Check if a user is an owner in a Signal group.
@@ -263,19 +263,18 @@ pub fn issuer_verify(
 /// # Returns
 ///
 /// The roster entry commitment, if the user's credential has a committed value
-/// which matches the value in the roster entry commitment, `false` otherwise.
+/// which matches the value in the roster entry commitment.
 ///
 #[wasm_bindgen]
 pub fn issuer_verify_roster_membership(
     issuer: JsValue,
     verified_credential: JsValue,
-) -> JsValue
+) -> Result<JsValue, JsValue>
 {
-    let issuer: SignalIssuer = ok_or_return!(issuer.into_serde());
-    let verified: VerifiedSignalCredential = ok_or_return!(verified_credential.into_serde());
-    let roster_entry_commitment = ok_or_return!(issuer.verify_roster_membership(&verified));
+    let issuer: Vec<u8> = bytes_from_js(issuer)?;
+    let verified: Vec<u8> = bytes_from_js(verified_credential)?;
 
-    ok_or_return!(JsValue::from_serde(&roster_entry_commitment))
+    bytes_to_js(ok_or_throw!(bindings::issuer_verify_roster_membership(&issuer, &verified)))
 }
 
 /// Check the proof of correct issuance on a credential issuance and potentially
@@ -295,8 +294,7 @@ pub fn issuer_verify_roster_membership(
 ///
 /// # Returns
 ///
-/// The `SignalUser` as a `JsValue` if successful, otherwise a single byte set
-/// to `0`.
+/// The `SignalUser` as a `JsValue`.
 ///
 #[wasm_bindgen]
 pub fn user_obtain_finish(
@@ -304,17 +302,14 @@ pub fn user_obtain_finish(
     system_parameters: JsValue,
     issuer_parameters: JsValue,
     issuance: JsValue,
-) -> JsValue
+) -> Result<JsValue, JsValue>
 {
-    let system_params: SystemParameters = ok_or_return!(system_parameters.into_serde());
-    let issuer_params: IssuerParameters = ok_or_return!(issuer_parameters.into_serde());
-    let mut user: SignalUser = ok_or_return!(SignalUser::new(system_params, issuer_params,
-                                                             None, phone_number));
-    let issuance: SignalCredentialIssuance = ok_or_return!(issuance.into_serde());
-
-    ok_or_return!(user.obtain_finish(Some(&issuance)));
+    let system_params: Vec<u8> = bytes_from_js(system_parameters)?;
+    let issuer_params: Vec<u8> = bytes_from_js(issuer_parameters)?;
+    let issuance: Vec<u8> = bytes_from_js(issuance)?;
 
-    ok_or_return!(JsValue::from_serde(&user))
+    bytes_to_js(ok_or_throw!(bindings::user_obtain_finish(phone_number, &system_params,
+                                                          &issuer_params, &issuance)))
 }
 
 /// Present a user's credential to the issuer for verification.
@@ -336,12 +331,276 @@ pub fn user_show(
     user: JsValue,
     roster_entry_commitment: JsValue,
     seed: &[u8],
-) -> JsValue
+) -> Result<JsValue, JsValue>
+{
+    let user: Vec<u8> = bytes_from_js(user)?;
+    let entry: Vec<u8> = bytes_from_js(roster_entry_commitment)?;
+
+    bytes_to_js(ok_or_throw!(bindings::user_show(&user, &entry, seed)))
+}
+
+/// Create a new elGamal keypair, with which a user may later have credentials
+/// issued on encrypted attributes.
+///
+/// # Inputs
+///
+/// * `seed` must be a byte array with length 32, containing random bytes for
+///   seeding a CSPRNG.
+///
+/// # Returns
+///
+/// An `aeonflux::elgamal::Keypair` as a `JsValue`.
+///
+#[wasm_bindgen]
+pub fn elgamal_keypair_create(
+    seed: &[u8],
+) -> Result<JsValue, JsValue>
+{
+    bytes_to_js(ok_or_throw!(bindings::elgamal_keypair_create(seed)))
+}
+
+/// Get the public half of an elGamal keypair.
+///
+/// # Inputs
+///
+/// * `keypair` is an `aeonflux::elgamal::Keypair` as a `JsValue`, as may be
+///   obtained via `elgamal_keypair_create()`.
+///
+/// # Returns
+///
+/// An `aeonflux::elgamal::PublicKey` as a `JsValue`.
+///
+#[wasm_bindgen]
+pub fn elgamal_keypair_get_public_key(
+    keypair: JsValue,
+) -> Result<JsValue, JsValue>
+{
+    let keypair: Vec<u8> = bytes_from_js(keypair)?;
+
+    bytes_to_js(ok_or_throw!(bindings::elgamal_keypair_get_public_key(&keypair)))
+}
+
+/// Create a user who does not yet have a credential.
+///
+/// # Inputs
+///
+/// * `phone_number` is the `SignalUser`'s phone number as bytes, e.g.
+///   `[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4]`.
+/// * `system_parameters` are a globally agreed upon set of
+///   `aeonflux::parameters::SystemParameters`, which may be obtained via
+///   `system_parameters_create()`.
+/// * `issuer_parameters` is an `aeonflux::amacs::PublicKey` as a `JsValue`,
+///   which can be obtained by calling `issuer_get_issuer_parameters()`.
+/// * `keypair` is an `aeonflux::elgamal::Keypair` as a `JsValue`, or `null`
+///   or `undefined` for a user without one.
+///
+/// # Returns
+///
+/// The `SignalUser` as a `JsValue`.
+///
+#[wasm_bindgen]
+pub fn user_create(
+    phone_number: &[u8],
+    system_parameters: JsValue,
+    issuer_parameters: JsValue,
+    keypair: JsValue,
+) -> Result<JsValue, JsValue>
+{
+    let system_params: Vec<u8> = bytes_from_js(system_parameters)?;
+    let issuer_params: Vec<u8> = bytes_from_js(issuer_parameters)?;
+    let key: Option<Vec<u8>> = if keypair.is_null() || keypair.is_undefined() {
+        None
+    } else {
+        Some(bytes_from_js(keypair)?)
+    };
+
+    bytes_to_js(ok_or_throw!(bindings::user_create(phone_number, &system_params, &issuer_params,
+                                                   key.as_ref().map(|x| &x[..]))))
+}
+
+/// Check the proof of correct issuance on a credential issuance, and save the
+/// credential to a user created with `user_create()`.
+///
+/// # Inputs
+///
+/// * `user` a `SignalUser` as a `JsValue`.
+/// * `issuance` is a `SignalCredentialIssuance` as a `JsValue`, which is
+///   obtainable via `issuer_issue()`.
+///
+/// # Returns
+///
+/// The `SignalUser`, now holding the credential, as a `JsValue`.
+///
+#[wasm_bindgen]
+pub fn user_obtain(
+    user: JsValue,
+    issuance: JsValue,
+) -> Result<JsValue, JsValue>
+{
+    let user: Vec<u8> = bytes_from_js(user)?;
+    let issuance: Vec<u8> = bytes_from_js(issuance)?;
+
+    bytes_to_js(ok_or_throw!(bindings::user_obtain(&user, &issuance)))
+}
+
+/// Determine whether a user holds a credential.
+///
+/// # Inputs
+///
+/// * `user` a `SignalUser` as a `JsValue`.
+///
+/// # Returns
+///
+/// `true` if the user has obtained a credential, and `false` otherwise.
+///
+#[wasm_bindgen]
+pub fn user_has_credential(
+    user: JsValue,
+) -> Result<bool, JsValue>
+{
+    let user: Vec<u8> = bytes_from_js(user)?;
+
+    Ok(ok_or_throw!(bindings::user_has_credential(&user)))
+}
+
+/// Get the public half of a user's elGamal keypair.
+///
+/// # Inputs
+///
+/// * `user` a `SignalUser` as a `JsValue`.
+///
+/// # Returns
+///
+/// An `aeonflux::elgamal::PublicKey` as a `JsValue`.  Throws a
+/// `CredentialError` with the code of `Status::MissingData` if the user was
+/// created without a keypair.
+///
+#[wasm_bindgen]
+pub fn user_get_public_key(
+    user: JsValue,
+) -> Result<JsValue, JsValue>
+{
+    let user: Vec<u8> = bytes_from_js(user)?;
+
+    bytes_to_js(ok_or_throw!(bindings::user_get_public_key(&user)))
+}
+
+/// Export a user, for storage or for use with another language's API.
+///
+/// # Inputs
+///
+/// * `user` a `SignalUser` as a `JsValue`.
+///
+/// # Returns
+///
+/// The serialised `SignalUser` as a `Uint8Array`, in the same format used by
+/// the C API.
+///
+#[wasm_bindgen]
+pub fn user_export(
+    user: JsValue,
+) -> Result<Vec<u8>, JsValue>
+{
+    bytes_from_js(user)
+}
+
+/// Import a user previously exported with `user_export()`.
+///
+/// # Inputs
+///
+/// * `bytes` is a serialised `SignalUser`.
+///
+/// # Returns
+///
+/// The `SignalUser` as a `JsValue`.
+///
+#[wasm_bindgen]
+pub fn user_import(
+    bytes: &[u8],
+) -> Result<JsValue, JsValue>
+{
+    // Check that the bytes are a user before handing them back.
+    ok_or_throw!(bindings::user_has_credential(bytes));
+
+    bytes_to_js(bytes.to_vec())
+}
+
+/// Create a commitment to a phone number.
+///
+/// # Inputs
+///
+/// * `phone_number` is the `SignalUser`'s phone number as bytes, e.g.
+///   `[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4]`.
+/// * `system_parameters` are a globally agreed upon set of
+///   `aeonflux::parameters::SystemParameters`, which may be obtained via
+///   `system_parameters_create()`.
+/// * `seed` must be a byte array with length 32, containing random bytes for
+///   seeding a CSPRNG.
+///
+/// # Returns
+///
+/// A `RosterEntryCommitment` as a `JsValue`.  It contains the opening to the
+/// commitment, and so should *not* be given to the issuer.
+///
+#[wasm_bindgen]
+pub fn roster_entry_commitment_create(
+    phone_number: &[u8],
+    system_parameters: JsValue,
+    seed: &[u8],
+) -> Result<JsValue, JsValue>
+{
+    let system_params: Vec<u8> = bytes_from_js(system_parameters)?;
+
+    bytes_to_js(ok_or_throw!(bindings::roster_entry_commitment_create(phone_number, &system_params, seed)))
+}
+
+/// Remove the opening from a commitment to a phone number.
+///
+/// # Inputs
+///
+/// * `roster_entry_commitment` is a `RosterEntryCommitment` as a `JsValue`,
+///   as obtained from `roster_entry_commitment_create()`.
+///
+/// # Returns
+///
+/// The commitment without its opening, as a `JsValue`, which is safe to give
+/// to the issuer.
+///
+#[wasm_bindgen]
+pub fn roster_entry_commitment_remove_opening(
+    roster_entry_commitment: JsValue,
+) -> Result<JsValue, JsValue>
+{
+    let entry: Vec<u8> = bytes_from_js(roster_entry_commitment)?;
+
+    bytes_to_js(ok_or_throw!(bindings::roster_entry_commitment_remove_opening(&entry)))
+}
+
+/// Check that a commitment opens to a phone number.
+///
+/// # Inputs
+///
+/// * `roster_entry_commitment` is a `RosterEntryCommitment` as a `JsValue`,
+///   as obtained from `roster_entry_commitment_create()`.
+/// * `phone_number` is the phone number as bytes.
+/// * `system_parameters` are the `aeonflux::parameters::SystemParameters`
+///   used to create the commitment.
+///
+/// # Returns
+///
+/// Nothing if the commitment opens to the `phone_number`.  Otherwise, throws a
+/// `CredentialError`, whose code is that of `Status::InvalidPhoneNumber` if it
+/// is a commitment to some other phone number.
+///
+#[wasm_bindgen]
+pub fn roster_entry_commitment_open(
+    roster_entry_commitment: JsValue,
+    phone_number: &[u8],
+    system_parameters: JsValue,
+) -> Result<(), JsValue>
 {
-    let mut csprng: SignalRng = csprng_from_seed!(seed);
-    let user: SignalUser = ok_or_return!(user.into_serde());
-    let entry: RosterEntryCommitment = ok_or_return!(roster_entry_commitment.into_serde());
-    let presentation: SignalCredentialPresentation = ok_or_return!(user.show(&mut csprng, &entry));
+    let entry: Vec<u8> = bytes_from_js(roster_entry_commitment)?;
+    let system_params: Vec<u8> = bytes_from_js(system_parameters)?;
 
-    ok_or_return!(JsValue::from_serde(&presentation))
+    Ok(ok_or_throw!(bindings::roster_entry_commitment_open(&entry, phone_number, &system_params)))
 }
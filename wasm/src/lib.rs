@@ -12,8 +12,10 @@
 #![feature(custom_attribute)]
 // #![feature(proc_macro)] // Required for nightly rustc<1.29
 
-extern crate rand;
+extern crate js_sys;
 extern crate signal_credential;
 extern crate wasm_bindgen;
 
+#[macro_use]
 pub mod js;
+pub mod classes;
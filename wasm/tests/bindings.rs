@@ -0,0 +1,203 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Check that the C and Javascript APIs, which both wrap
+//! `signal_credential::bindings`, give identical results for the same inputs
+//! and seeds, and that each accepts the issuances and presentations of the
+//! other.  The C API's seeded functions are its `_deterministic` variants,
+//! since its usual ones refuse a seed they have already seen.
+//!
+//! Run with `make test`, which requires `wasm-bindgen-test-runner` and Node.
+
+#![cfg(target_arch = "wasm32")]
+
+extern crate credential;
+extern crate credential_ffi;
+extern crate js_sys;
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+
+use credential::js;
+use credential_ffi::c::buf_t;
+use credential_ffi::c::status_t;
+
+use js_sys::Reflect;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+/// The C API, including its deterministic variants.
+mod c {
+    pub use credential_ffi::c::*;
+    pub use credential_ffi::deterministic::*;
+}
+
+const SEED: [u8; 32] = [  0,  1,  2,  3,  4,  5,  6,  7,
+                          8,  9, 10, 11, 12, 13, 14, 15,
+                         16, 17, 18, 19, 20, 21, 22, 23,
+                         24, 25, 26, 27, 28, 29, 30, 31, ];
+const PHONE_NUMBER: &'static [u8] = &[ 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4 ];
+
+/// Call a C function with a fresh out-parameter appended to its arguments,
+/// check that it succeeded, and return a copy of the bytes it wrote.
+macro_rules! c_ok {
+    ($f:ident($($arg:expr),*)) => {{
+        let mut out: buf_t = buf_t { len: 0, ptr: ::std::ptr::null() };
+        let status: status_t = c::$f($($arg,)* &mut out);
+
+        assert!(status == 0, "{} returned status {}", stringify!($f), status);
+
+        let bytes: Vec<u8> = unsafe { ::std::slice::from_raw_parts(out.ptr, out.len as usize) }.to_vec();
+
+        c::buffer_free(out);
+        bytes
+    }}
+}
+
+/// Call a C function with a fresh out-parameter appended to its arguments,
+/// and return the status it failed with.
+macro_rules! c_err {
+    ($f:ident($($arg:expr),*)) => {{
+        let mut out: buf_t = buf_t { len: 0, ptr: ::std::ptr::null() };
+        let status: status_t = c::$f($($arg,)* &mut out);
+
+        assert!(status != 0, "{} unexpectedly succeeded", stringify!($f));
+        status
+    }}
+}
+
+fn from_js(value: JsValue) -> Vec<u8> {
+    value.into_serde().unwrap()
+}
+
+fn to_js(bytes: &[u8]) -> JsValue {
+    JsValue::from_serde(&bytes).unwrap()
+}
+
+/// Get the status code of an exception thrown by the Javascript API.
+fn js_code(error: JsValue) -> status_t {
+    Reflect::get(&error, &JsValue::from("code")).unwrap().as_f64().unwrap() as status_t
+}
+
+#[wasm_bindgen_test]
+fn system_parameters_create() {
+    let from_c = c_ok!(system_parameters_create(SEED.as_ptr()));
+    let from_js = from_js(js::system_parameters_create(&SEED).unwrap());
+
+    assert_eq!(from_c, from_js);
+}
+
+#[wasm_bindgen_test]
+fn full_protocol() {
+    let system_parameters = c_ok!(system_parameters_create(SEED.as_ptr()));
+
+    let keypair = c_ok!(issuer_create_deterministic(system_parameters.as_ptr(), system_parameters.len() as u64,
+                                                    SEED.as_ptr()));
+    assert_eq!(keypair, from_js(js::issuer_create(to_js(&system_parameters), &SEED).unwrap()));
+
+    let issuer = c_ok!(issuer_new(system_parameters.as_ptr(), system_parameters.len() as u64,
+                                  keypair.as_ptr(), keypair.len() as u64));
+    assert_eq!(issuer, from_js(js::issuer_new(to_js(&system_parameters), to_js(&keypair)).unwrap()));
+
+    let issuer_parameters = c_ok!(issuer_get_issuer_parameters(issuer.as_ptr(), issuer.len() as u64));
+    assert_eq!(issuer_parameters, from_js(js::issuer_get_issuer_parameters(to_js(&issuer)).unwrap()));
+
+    let issuance = c_ok!(issuer_issue_deterministic(issuer.as_ptr(), issuer.len() as u64,
+                                                    PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as u64,
+                                                    SEED.as_ptr()));
+    let js_issuance = from_js(js::issuer_issue(to_js(&issuer), PHONE_NUMBER, &SEED).unwrap());
+    assert_eq!(issuance, js_issuance);
+
+    let elgamal_keypair = c_ok!(elgamal_keypair_create_deterministic(SEED.as_ptr()));
+    assert_eq!(elgamal_keypair, from_js(js::elgamal_keypair_create(&SEED).unwrap()));
+
+    let public_key = c_ok!(elgamal_keypair_get_public_key(elgamal_keypair.as_ptr(), elgamal_keypair.len() as u64));
+    assert_eq!(public_key, from_js(js::elgamal_keypair_get_public_key(to_js(&elgamal_keypair)).unwrap()));
+
+    let user = c_ok!(user_create(PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as u64,
+                                 system_parameters.as_ptr(), system_parameters.len() as u64,
+                                 issuer_parameters.as_ptr(), issuer_parameters.len() as u64,
+                                 elgamal_keypair.as_ptr(), elgamal_keypair.len() as u64));
+    assert_eq!(user, from_js(js::user_create(PHONE_NUMBER, to_js(&system_parameters),
+                                             to_js(&issuer_parameters), to_js(&elgamal_keypair)).unwrap()));
+
+    let user_public_key = c_ok!(user_get_public_key(user.as_ptr(), user.len() as u64));
+    assert_eq!(user_public_key, from_js(js::user_get_public_key(to_js(&user)).unwrap()));
+
+    c_ok!(user_obtain(user.as_ptr(), user.len() as u64, js_issuance.as_ptr(), js_issuance.len() as u64));
+    js::user_obtain(to_js(&user), to_js(&js_issuance)).unwrap();
+
+    let user = c_ok!(user_obtain(user.as_ptr(), user.len() as u64, issuance.as_ptr(), issuance.len() as u64));
+    assert_eq!(user, from_js(js::user_obtain(to_js(&user), to_js(&issuance)).unwrap()));
+    assert_eq!(user, js::user_export(js::user_import(&user).unwrap()).unwrap());
+
+    let mut has_credential: u8 = 0;
+
+    assert_eq!(c::user_has_credential(user.as_ptr(), user.len() as u64, &mut has_credential), 0);
+    assert_eq!(has_credential == 1, js::user_has_credential(to_js(&user)).unwrap());
+
+    let entry = c_ok!(roster_entry_commitment_create_deterministic(PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as u64,
+                                                                   system_parameters.as_ptr(), system_parameters.len() as u64,
+                                                                   SEED.as_ptr()));
+    assert_eq!(entry, from_js(js::roster_entry_commitment_create(PHONE_NUMBER, to_js(&system_parameters),
+                                                                 &SEED).unwrap()));
+
+    let presentation = c_ok!(user_show_deterministic(user.as_ptr(), user.len() as u64,
+                                                     entry.as_ptr(), entry.len() as u64,
+                                                     SEED.as_ptr()));
+    let js_presentation = from_js(js::user_show(to_js(&user), to_js(&entry), &SEED).unwrap());
+    assert_eq!(presentation, js_presentation);
+
+    let js_verified = c_ok!(issuer_verify(issuer.as_ptr(), issuer.len() as u64,
+                                          js_presentation.as_ptr(), js_presentation.len() as u64));
+    js::issuer_verify(to_js(&issuer), to_js(&js_presentation)).unwrap();
+
+    let verified = c_ok!(issuer_verify(issuer.as_ptr(), issuer.len() as u64,
+                                       presentation.as_ptr(), presentation.len() as u64));
+    assert_eq!(verified, from_js(js::issuer_verify(to_js(&issuer), to_js(&presentation)).unwrap()));
+
+    let member = c_ok!(issuer_verify_roster_membership(issuer.as_ptr(), issuer.len() as u64,
+                                                       verified.as_ptr(), verified.len() as u64));
+    assert_eq!(member, from_js(js::issuer_verify_roster_membership(to_js(&issuer), to_js(&verified)).unwrap()));
+
+    // Either presentation proves membership of the same roster entry.
+    assert_eq!(member, c_ok!(issuer_verify_roster_membership(issuer.as_ptr(), issuer.len() as u64,
+                                                             js_verified.as_ptr(), js_verified.len() as u64)));
+
+    let commitment = c_ok!(roster_entry_commitment_remove_opening(entry.as_ptr(), entry.len() as u64));
+    assert_eq!(commitment, from_js(js::roster_entry_commitment_remove_opening(to_js(&entry)).unwrap()));
+    assert_eq!(member, commitment);
+}
+
+#[wasm_bindgen_test]
+fn errors_are_identical() {
+    let system_parameters = c_ok!(system_parameters_create(SEED.as_ptr()));
+    let keypair = c_ok!(issuer_create_deterministic(system_parameters.as_ptr(), system_parameters.len() as u64,
+                                                    SEED.as_ptr()));
+    let entry = c_ok!(roster_entry_commitment_create_deterministic(PHONE_NUMBER.as_ptr(), PHONE_NUMBER.len() as u64,
+                                                                   system_parameters.as_ptr(), system_parameters.len() as u64,
+                                                                   SEED.as_ptr()));
+    let other_number: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 4, 3, 2, 1];
+
+    // A keypair is not an issuer.
+    assert_eq!(c_err!(issuer_get_issuer_parameters(keypair.as_ptr(), keypair.len() as u64)),
+               js_code(js::issuer_get_issuer_parameters(to_js(&keypair)).unwrap_err()));
+
+    assert_eq!(c::roster_entry_commitment_open(entry.as_ptr(), entry.len() as u64,
+                                               other_number.as_ptr(), other_number.len() as u64,
+                                               system_parameters.as_ptr(), system_parameters.len() as u64),
+               js_code(js::roster_entry_commitment_open(to_js(&entry), other_number,
+                                                        to_js(&system_parameters)).unwrap_err()));
+
+    let mut c_length: u64 = 0;
+
+    assert_eq!(c::length_of(0, &mut c_length), js_code(js::length_of(0).unwrap_err()));
+    assert_eq!(c::length_of(7, &mut c_length), 0);
+    assert_eq!(c_length, js::length_of(7).unwrap() as u64);
+}
@@ -0,0 +1,125 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Tests for the Javascript classes, which run in Node under
+//! `wasm-bindgen-test`.
+//!
+//! Run with `make test`, which requires `wasm-bindgen-test-runner` and Node.
+
+#![cfg(target_arch = "wasm32")]
+
+extern crate credential;
+extern crate js_sys;
+extern crate signal_credential;
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+
+use credential::classes::RosterEntryCommitment;
+use credential::classes::SignalCredentialPresentation;
+use credential::classes::SignalIssuer;
+use credential::classes::SignalUser;
+use credential::classes::SystemParameters;
+use credential::js;
+
+use js_sys::Reflect;
+
+use signal_credential::status::Status;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+const SEED: [u8; 32] = [  0,  1,  2,  3,  4,  5,  6,  7,
+                          8,  9, 10, 11, 12, 13, 14, 15,
+                         16, 17, 18, 19, 20, 21, 22, 23,
+                         24, 25, 26, 27, 28, 29, 30, 31, ];
+const PHONE_NUMBER: &'static [u8] = &[ 1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4 ];
+
+fn from_js(value: JsValue) -> Vec<u8> {
+    value.into_serde().unwrap()
+}
+
+/// Get the status code of an exception thrown by the Javascript API.
+fn js_code(error: JsValue) -> u32 {
+    Reflect::get(&error, &JsValue::from("code")).unwrap().as_f64().unwrap() as u32
+}
+
+#[wasm_bindgen_test]
+fn system_parameters_round_trip() {
+    let system_parameters = SystemParameters::create(&SEED).unwrap();
+    let bytes = system_parameters.to_bytes();
+
+    assert_eq!(bytes, from_js(js::system_parameters_create(&SEED).unwrap()));
+    assert_eq!(bytes, SystemParameters::from_bytes(&bytes).unwrap().to_bytes());
+}
+
+#[wasm_bindgen_test]
+fn issuer_round_trip() {
+    let system_parameters = SystemParameters::create(&SEED).unwrap();
+    let issuer = SignalIssuer::create(&system_parameters, &SEED).unwrap();
+    let bytes = issuer.to_bytes();
+    let imported = SignalIssuer::from_bytes(&bytes).unwrap();
+
+    assert_eq!(bytes, imported.to_bytes());
+    assert_eq!(issuer.issuer_parameters(), imported.issuer_parameters());
+}
+
+#[wasm_bindgen_test]
+fn full_protocol() {
+    let system_parameters = SystemParameters::create(&SEED).unwrap();
+    let issuer = SignalIssuer::create(&system_parameters, &SEED).unwrap();
+    let issuance = issuer.issue(PHONE_NUMBER, &SEED).unwrap();
+    let mut user = SignalUser::create(PHONE_NUMBER, &system_parameters, &issuer.issuer_parameters()).unwrap();
+
+    assert!(!user.has_credential());
+
+    user.obtain(&issuance).unwrap();
+
+    assert!(user.has_credential());
+
+    let user = SignalUser::from_bytes(&user.to_bytes()).unwrap();
+    let entry = RosterEntryCommitment::create(PHONE_NUMBER, &system_parameters, &SEED).unwrap();
+
+    entry.open(PHONE_NUMBER, &system_parameters).unwrap();
+
+    let presentation = user.show(&entry, &SEED).unwrap();
+    let presentation = SignalCredentialPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+    let verified = issuer.verify(&presentation).unwrap();
+    let commitment = issuer.verify_roster_membership(&verified).unwrap();
+
+    assert_eq!(commitment, entry.commitment());
+}
+
+#[wasm_bindgen_test]
+fn user_with_keypair() {
+    let system_parameters = SystemParameters::create(&SEED).unwrap();
+    let issuer = SignalIssuer::create(&system_parameters, &SEED).unwrap();
+    let keypair = from_js(js::elgamal_keypair_create(&SEED).unwrap());
+    let user = SignalUser::create_with_keypair(PHONE_NUMBER, &system_parameters,
+                                               &issuer.issuer_parameters(), &keypair).unwrap();
+    let without = SignalUser::create(PHONE_NUMBER, &system_parameters, &issuer.issuer_parameters()).unwrap();
+
+    assert_eq!(user.public_key().unwrap(), &keypair[32..]);
+    assert_eq!(js_code(without.public_key().unwrap_err()), Status::MissingData.code());
+    assert_eq!(js_code(SignalUser::create_with_keypair(PHONE_NUMBER, &system_parameters,
+                                                       &issuer.issuer_parameters(),
+                                                       &keypair[1..]).err().unwrap()),
+               Status::WrongNumberOfBytes.code());
+}
+
+#[wasm_bindgen_test]
+fn errors() {
+    let system_parameters = SystemParameters::create(&SEED).unwrap();
+    let entry = RosterEntryCommitment::create(PHONE_NUMBER, &system_parameters, &SEED).unwrap();
+    let other_number: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 4, 3, 2, 1];
+
+    assert_eq!(js_code(SystemParameters::create(&SEED[1..]).err().unwrap()), Status::InvalidSeed.code());
+    assert_eq!(js_code(SignalIssuer::from_bytes(&[0u8; 3]).err().unwrap()), Status::WrongNumberOfBytes.code());
+    assert_eq!(js_code(entry.open(other_number, &system_parameters).unwrap_err()),
+               Status::InvalidPhoneNumber.code());
+}